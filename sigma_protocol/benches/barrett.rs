@@ -0,0 +1,51 @@
+//Сравнение редукции Барретта с делением % в возведении в степень
+//по модулю групп MODP: cargo bench --bench barrett
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use num_bigint::BigInt;
+use std::hint::black_box;
+
+use sigma_protocol::math::{self, Barrett};
+use sigma_protocol::modp;
+
+fn bench_reduce(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reduce");
+    for bits in [2048u32, 3072, 4096] {
+        let p = modp::params(bits).unwrap().p;
+        let barrett = Barrett::new(&p).unwrap();
+        let x = (&p - 1u32) * (&p - 2u32);
+        group.bench_with_input(BenchmarkId::new("rem", bits), &x, |b, x| {
+            b.iter(|| black_box(x) % &p)
+        });
+        group.bench_with_input(BenchmarkId::new("barrett", bits), &x, |b, x| {
+            b.iter(|| barrett.reduce(black_box(x)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_pow(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mod_pow");
+    group.sample_size(20);
+    for bits in [2048u32, 3072] {
+        let params = modp::params(bits).unwrap();
+        let barrett = Barrett::new(&params.p).unwrap();
+        //Показатель длины q, как у одноразовых ключей протокола
+        let exponent = &params.q - 12345u32;
+        let signed = BigInt::from(exponent.clone());
+        group.bench_with_input(BenchmarkId::new("rem", bits), &params.g, |b, g| {
+            b.iter(|| math::mod_pow_big(black_box(g), &signed, &params.p))
+        });
+        group.bench_with_input(BenchmarkId::new("barrett", bits), &params.g, |b, g| {
+            b.iter(|| barrett.pow(black_box(g), &exponent))
+        });
+        assert_eq!(
+            math::mod_pow_big(&params.g, &signed, &params.p),
+            Ok(barrett.pow(&params.g, &exponent))
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_reduce, bench_pow);
+criterion_main!(benches);
@@ -0,0 +1,122 @@
+use num_bigint::BigUint;
+
+use crate::batch;
+use crate::protocol::{Proof, PublicParams};
+
+//Агрегированная проверка набора доказательств одним уравнением
+//g^(Σ w_i a_i) * h^(Σ w_i b_i) == Π (u_t,i)^w_i * u_i^(c_i w_i) со
+//случайными весами. Это та же пакетная проверка, что в batch.rs
+//(короткие веса, мультиэкспонента, проверка подгруппы), и она
+//выполняется там, чтобы не держать две копии уравнения
+pub fn verify_aggregate(params: &PublicParams, proofs: &[Proof]) -> bool {
+    batch::verify_batch(params, proofs)
+}
+
+pub fn verify_aggregate_with_weights(
+    params: &PublicParams,
+    proofs: &[Proof],
+    weights: &[BigUint],
+) -> bool {
+    batch::verify_batch_with_weights(params, proofs, weights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_gen;
+    use crate::protocol::Key;
+    use num_traits::One;
+
+    // Безопасное простое p = 2q + 1 около 2^61, g и h - квадраты
+    fn params() -> PublicParams {
+        PublicParams {
+            p: BigUint::from(2305843009213699919u64),
+            q: BigUint::from(1152921504606849959u64),
+            g: BigUint::from(4u32),
+            h: BigUint::from(9u32),
+        }
+    }
+
+    fn make_proof(params: &PublicParams, schnorr: bool) -> Proof {
+        let order = params.order();
+        let mut secret = Key::random(&order);
+        let mut nonce = Key::random(&order);
+        if schnorr {
+            secret.beta = BigUint::ZERO;
+            nonce.beta = BigUint::ZERO;
+        }
+        let c = key_gen::random_biguint_mod(&order);
+        Proof {
+            u: params.compute_u(&secret.alpha, &secret.beta).unwrap(),
+            ut: params.compute_u(&nonce.alpha, &nonce.beta).unwrap(),
+            alpha: (&nonce.alpha + &secret.alpha * &c) % &order,
+            beta: (&nonce.beta + &secret.beta * &c) % &order,
+            c,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_accepts_valid_proofs() {
+        let params = params();
+        let mut proofs = Vec::new();
+        for i in 0..10 {
+            proofs.push(make_proof(&params, i % 2 == 0));
+        }
+        assert!(proofs.iter().all(|p| p.verify(&params)));
+        assert!(verify_aggregate(&params, &proofs));
+        assert!(verify_aggregate(&params, &[]));
+    }
+
+    #[test]
+    fn test_aggregate_rejects_tampered_proof() {
+        let params = params();
+        let mut proofs = Vec::new();
+        for _ in 0..5 {
+            proofs.push(make_proof(&params, false));
+        }
+        proofs[3].alpha += 1u32;
+        assert!(!proofs[3].verify(&params));
+        assert!(!verify_aggregate(&params, &proofs));
+    }
+
+    #[test]
+    fn test_aggregate_rejects_out_of_range() {
+        let params = params();
+        let mut proof = make_proof(&params, false);
+        proof.ut = BigUint::ZERO;
+        assert!(!verify_aggregate(&params, &[proof]));
+    }
+
+    #[test]
+    fn test_aggregate_rejects_negated_commitment() {
+        //-u_t вне подгруппы: при четном весе множитель -1 исчез бы
+        let params = params();
+        let mut proof = make_proof(&params, false);
+        proof.ut = &params.p - &proof.ut;
+        assert!(!proof.verify(&params));
+        let weights = [BigUint::from(2u32)];
+        assert!(!verify_aggregate_with_weights(
+            &params,
+            std::slice::from_ref(&proof),
+            &weights
+        ));
+        assert!(!verify_aggregate(&params, &[proof]));
+    }
+
+    #[test]
+    fn test_unit_weights_allow_cancellation() {
+        // При одинаковых весах ошибки в двух доказательствах могут взаимно погаситься
+        let params = params();
+        let mut a = make_proof(&params, true);
+        let mut b = make_proof(&params, true);
+        a.alpha += 1u32;
+        b.alpha = (&b.alpha + params.order() - 1u32) % params.order();
+        let ones = vec![BigUint::one(), BigUint::one()];
+        assert!(verify_aggregate_with_weights(
+            &params,
+            &[a.clone(), b.clone()],
+            &ones
+        ));
+        assert!(!verify_aggregate(&params, &[a, b]));
+    }
+}
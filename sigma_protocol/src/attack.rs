@@ -0,0 +1,142 @@
+//////////////////////////////////////////////////////////////
+// Атака на учебные параметры: ρ-метод Полларда раскладывает
+// p - 1 и показывает, что порядок подгруппы q мал. Тогда log_g u
+// находится перебором, и пара (log_g u, 0) - такой же секрет, как
+// у доказывающего: с ним проходит любая проверка. Шаги младенца
+// и великана (math::bsgs) сокращают перебор до sqrt(q), но для групп
+// MODP и этого слишком много, хотя разложение p - 1 = 2q мгновенно.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+use crate::group::{Group, Setting};
+use crate::key_gen;
+use crate::protocol::Key;
+
+//Раунды Миллера-Рабина для множителей
+const PRIME_ROUNDS: u8 = 16;
+//Константы c в x^2 + c и шаги на каждую до отказа
+const RHO_CONSTANTS: u32 = 16;
+const RHO_STEPS: u32 = 1 << 20;
+//Наибольшая длина порядка, при которой логарифм ищется перебором
+pub const MAX_LOG_BITS: u64 = 24;
+//То же для шагов младенца и великана: таблица на 2^20 элементов
+pub const MAX_BSGS_BITS: u64 = 40;
+
+//Нетривиальный делитель составного n ρ-методом Полларда с f(x) = x^2 + c;
+//None для простых n, n < 4 и если ни одна константа не сработала
+pub fn pollard_rho(n: &BigUint) -> Option<BigUint> {
+    if *n < BigUint::from(4u32) || key_gen::is_prime_miller_rabin(n, PRIME_ROUNDS) {
+        return None;
+    }
+    if n.is_even() {
+        return Some(BigUint::from(2u32));
+    }
+    for c in 1..=RHO_CONSTANTS {
+        let f = |x: &BigUint| (x * x + c) % n;
+        let mut x = BigUint::from(2u32);
+        let mut y = x.clone();
+        for _ in 0..RHO_STEPS {
+            x = f(&x);
+            y = f(&f(&y));
+            let diff = if x > y { &x - &y } else { &y - &x };
+            let d = diff.gcd(n);
+            //Цикл замкнулся без делителя: пробуем другую константу
+            if d == *n {
+                break;
+            }
+            if !d.is_one() {
+                return Some(d);
+            }
+        }
+    }
+    None
+}
+
+//Простые множители n по возрастанию с учетом кратности
+pub fn factorize(n: &BigUint) -> Option<Vec<BigUint>> {
+    if n.is_zero() {
+        return None;
+    }
+    let mut factors = Vec::new();
+    let mut stack = vec![n.clone()];
+    while let Some(m) = stack.pop() {
+        if m.is_one() {
+            continue;
+        }
+        if key_gen::is_prime_miller_rabin(&m, PRIME_ROUNDS) {
+            factors.push(m);
+            continue;
+        }
+        let d = pollard_rho(&m)?;
+        stack.push(&m / &d);
+        stack.push(d);
+    }
+    factors.sort();
+    Some(factors)
+}
+
+//log_base y перебором; None, если порядок длиннее MAX_LOG_BITS или логарифма нет
+pub fn discrete_log<G: Group>(group: &G, base: &G::Element, y: &G::Element) -> Option<BigUint> {
+    let order = group.order();
+    if order.bits() > MAX_LOG_BITS {
+        return None;
+    }
+    let mut acc = group.identity();
+    let mut x = BigUint::ZERO;
+    while x < order {
+        if acc == *y {
+            return Some(x);
+        }
+        acc = group.mul(&acc, base);
+        x += 1u32;
+    }
+    None
+}
+
+//Секрет (log_g u, 0) для открытого ключа u = g^alpha * h^beta
+pub fn recover_key<G: Group>(setting: &Setting<G>, u: &G::Element) -> Option<Key> {
+    discrete_log(&setting.group, &setting.g, u).map(|alpha| Key::new(alpha, BigUint::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modp;
+    use crate::protocol::{ProofKind, Prover, toy_params};
+
+    #[test]
+    fn test_factorize() {
+        let big = |n: u64| BigUint::from(n);
+        assert_eq!(factorize(&big(22)), Some(vec![big(2), big(11)]));
+        assert_eq!(factorize(&big(8051)), Some(vec![big(83), big(97)]));
+        assert_eq!(factorize(&big(1024)), Some(vec![big(2); 10]));
+        assert_eq!(factorize(&big(1)), Some(vec![]));
+        assert_eq!(factorize(&big(0)), None);
+        //Число Ферма F6 = 2^64 + 1
+        let f6 = (BigUint::one() << 64u32) + 1u32;
+        assert_eq!(
+            factorize(&f6),
+            Some(vec![big(274_177), big(67_280_421_310_721)])
+        );
+        assert_eq!(pollard_rho(&big(1_000_000_007)), None);
+    }
+
+    #[test]
+    fn test_recover_key_in_toy_group() {
+        let params = toy_params();
+        let setting = params.setting();
+        let secret = Key::new(BigUint::from(7u32), BigUint::from(5u32));
+        let prover = Prover::in_setting(setting.clone(), ProofKind::Okamoto, secret.clone());
+        let key = recover_key(&setting, prover.public_key()).unwrap();
+        assert_ne!(key, secret);
+        let forged = Prover::in_setting(setting, ProofKind::Okamoto, key);
+        assert_eq!(forged.public_key(), prover.public_key());
+
+        //Порядок MODP слишком велик для перебора
+        let modp = modp::params(2048).unwrap().setting();
+        assert_eq!(recover_key(&modp, &modp.h), None);
+    }
+}
@@ -0,0 +1,404 @@
+//////////////////////////////////////////////////////////////
+// Вход без пароля протоколом Шнорра: пользователь регистрирует
+// открытый ключ u = g^x, а при входе интерактивно доказывает
+// знание x. Сервер хранит только u, испытание выбирает сам, и
+// токен доступа выдается лишь после принятого ответа.
+//
+// Клиент: t = g^r -> сервер: случайное c -> клиент: z = r + c*x.
+// Сервер принимает, если g^z = t * u^c. Каждая сессия входа
+// допускает один ответ.
+//
+// Администраторы с их ключами задаются в конфигурации; только они
+// получают доступ к служебным эндпоинтам и регистрируют остальных
+// пользователей.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "server")]
+use crate::config::Address;
+use crate::group::{Group, Setting};
+use crate::message::decimal;
+use crate::schnorr::Schnorr;
+use crate::sigma::SigmaProtocol;
+#[cfg(feature = "server")]
+use crate::threshold;
+
+pub const REGISTER_PATH: &str = "/auth/register";
+pub const LOGIN_PATH: &str = "/auth/login";
+pub const RESPOND_PATH: &str = "/auth/respond";
+
+//Время жизни токена доступа
+pub const TOKEN_TTL: Duration = Duration::from_secs(3600);
+//Сколько сессия входа ждет ответа клиента
+pub const LOGIN_TTL: Duration = Duration::from_secs(60);
+//Предел открытых сессий входа: их создают и неаутентифицированные запросы
+pub const MAX_PENDING: usize = 1024;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RegisterRequest {
+    pub username: String,
+    #[serde(with = "decimal")]
+    pub public_key: BigUint,
+}
+
+//Первый ход входа: коммитмент t = g^r
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LoginRequest {
+    pub username: String,
+    #[serde(with = "decimal")]
+    pub t: BigUint,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LoginChallenge {
+    pub session: String,
+    #[serde(with = "decimal")]
+    pub c: BigUint,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LoginResponse {
+    pub session: String,
+    #[serde(with = "decimal")]
+    pub z: BigUint,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AccessToken {
+    pub token: String,
+    pub expires_in: u64,
+}
+
+//Открытая сессия входа до ответа клиента
+#[derive(Debug)]
+struct Pending {
+    username: String,
+    t: BigUint,
+    c: BigUint,
+    expires: Instant,
+}
+
+#[derive(Debug)]
+struct Grant {
+    username: String,
+    expires: Instant,
+}
+
+//////////////////////////////////////////////////////////////
+// Сервер: ключи пользователей, сессии входа и выданные токены
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug, Default)]
+pub struct Registry {
+    users: HashMap<String, BigUint>,
+    admins: HashSet<String>,
+    pending: HashMap<String, Pending>,
+    tokens: HashMap<String, Grant>,
+}
+
+fn random_id() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    //Ключ занятого имени не перезаписывается: иначе вход достался бы любому
+    pub fn register<G: Group>(
+        &mut self,
+        setting: &Setting<G>,
+        request: &RegisterRequest,
+    ) -> Result<(), String> {
+        if request.username.is_empty() {
+            return Err("Пустое имя пользователя".to_string());
+        }
+        if self.users.contains_key(&request.username) {
+            return Err(format!(
+                "Пользователь {} уже зарегистрирован",
+                request.username
+            ));
+        }
+        match setting.group.decode(&request.public_key) {
+            Some(u) if u != setting.group.identity() => {}
+            _ => return Err("Открытый ключ вне группы".to_string()),
+        }
+        self.users
+            .insert(request.username.clone(), request.public_key.clone());
+        Ok(())
+    }
+
+    //Администратор из конфигурации: регистрируется с правом доступа
+    //к служебным эндпоинтам
+    pub fn register_admin<G: Group>(
+        &mut self,
+        setting: &Setting<G>,
+        request: &RegisterRequest,
+    ) -> Result<(), String> {
+        self.register(setting, request)?;
+        self.admins.insert(request.username.clone());
+        Ok(())
+    }
+
+    pub fn is_admin(&self, username: &str) -> bool {
+        self.admins.contains(username)
+    }
+
+    //Запоминает коммитмент и отвечает испытанием c, выбранным сервером
+    pub fn login<G: Group>(
+        &mut self,
+        setting: &Setting<G>,
+        request: &LoginRequest,
+        c: BigUint,
+    ) -> Result<LoginChallenge, String> {
+        if !self.users.contains_key(&request.username) {
+            return Err(format!(
+                "Пользователь {} не зарегистрирован",
+                request.username
+            ));
+        }
+        if setting.group.decode(&request.t).is_none() {
+            return Err("Коммитмент вне группы".to_string());
+        }
+        let now = Instant::now();
+        self.pending.retain(|_, pending| pending.expires > now);
+        if self.pending.len() >= MAX_PENDING {
+            return Err("Слишком много открытых сессий входа".to_string());
+        }
+        let session = random_id();
+        self.pending.insert(
+            session.clone(),
+            Pending {
+                username: request.username.clone(),
+                t: request.t.clone(),
+                c: c.clone(),
+                expires: now + LOGIN_TTL,
+            },
+        );
+        Ok(LoginChallenge { session, c })
+    }
+
+    //Проверка g^z = t * u^c; сессия закрывается при любом исходе
+    pub fn respond<G: Group>(
+        &mut self,
+        setting: &Setting<G>,
+        response: &LoginResponse,
+    ) -> Result<AccessToken, String> {
+        let pending = self
+            .pending
+            .remove(&response.session)
+            .ok_or_else(|| format!("Нет открытой сессии входа {}", response.session))?;
+        if pending.expires <= Instant::now() {
+            return Err(format!("Сессия входа {} просрочена", response.session));
+        }
+        let group = &setting.group;
+        let u = group
+            .decode(&self.users[&pending.username])
+            .ok_or("Открытый ключ вне группы")?;
+        let t = group.decode(&pending.t).ok_or("Коммитмент вне группы")?;
+        let schnorr = Schnorr::in_setting(setting.clone());
+        if response.z >= setting.order() || !schnorr.verify(&u, &t, &pending.c, &response.z) {
+            return Err(format!(
+                "Пользователь {} не доказал знание ключа",
+                pending.username
+            ));
+        }
+        let token = random_id();
+        self.tokens.insert(
+            token.clone(),
+            Grant {
+                username: pending.username,
+                expires: Instant::now() + TOKEN_TTL,
+            },
+        );
+        Ok(AccessToken {
+            token,
+            expires_in: TOKEN_TTL.as_secs(),
+        })
+    }
+
+    //Имя владельца действующего токена; просроченный токен удаляется
+    pub fn authorize(&mut self, token: &str) -> Option<String> {
+        let grant = self.tokens.get(token)?;
+        if grant.expires <= Instant::now() {
+            self.tokens.remove(token);
+            return None;
+        }
+        Some(grant.username.clone())
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Клиент: вход на сервер по адресу
+//////////////////////////////////////////////////////////////
+
+#[cfg(feature = "server")]
+pub async fn login<G: Group>(
+    setting: &Setting<G>,
+    address: &Address,
+    username: &str,
+    x: &BigUint,
+) -> Result<AccessToken, String> {
+    let client = reqwest::Client::new();
+    let schnorr = Schnorr::in_setting(setting.clone());
+    let u = schnorr.statement(x);
+    let (r, t) = schnorr
+        .commit(&u, x)
+        .ok_or("Не удалось вычислить коммитмент")?;
+    let request = LoginRequest {
+        username: username.to_string(),
+        t: setting.group.encode(&t),
+    };
+    let challenge: LoginChallenge = threshold::post(&client, address, LOGIN_PATH, &request).await?;
+    let response = LoginResponse {
+        session: challenge.session,
+        z: schnorr.respond(x, r, &(challenge.c % setting.order())),
+    };
+    threshold::post(&client, address, RESPOND_PATH, &response).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::toy_params;
+    use crate::ristretto;
+
+    //Вход с ключом x на реестр; возвращает результат ответа
+    fn attempt<G: Group>(
+        registry: &mut Registry,
+        setting: &Setting<G>,
+        username: &str,
+        x: &BigUint,
+    ) -> Result<AccessToken, String> {
+        let schnorr = Schnorr::in_setting(setting.clone());
+        let (r, t) = schnorr.commit(&schnorr.statement(x), x).unwrap();
+        let request = LoginRequest {
+            username: username.to_string(),
+            t: setting.group.encode(&t),
+        };
+        let c = setting.group.random_scalar();
+        let challenge = registry.login(setting, &request, c)?;
+        let response = LoginResponse {
+            session: challenge.session,
+            z: schnorr.respond(x, r, &challenge.c),
+        };
+        registry.respond(setting, &response)
+    }
+
+    #[test]
+    fn test_login_issues_token() {
+        let setting = ristretto::setting();
+        let x = setting.group.random_scalar();
+        let public_key = setting.group.encode(&setting.group.exp(&setting.g, &x));
+        let mut registry = Registry::new();
+        let request = RegisterRequest {
+            username: "admin".to_string(),
+            public_key,
+        };
+        registry.register(&setting, &request).unwrap();
+        //Чужой ключ под занятым именем не принимается
+        assert!(registry.register(&setting, &request).is_err());
+
+        let token = attempt(&mut registry, &setting, "admin", &x).unwrap();
+        assert_eq!(registry.authorize(&token.token), Some("admin".to_string()));
+        assert_eq!(registry.authorize("forged"), None);
+
+        let wrong = &x + 1u32;
+        assert!(attempt(&mut registry, &setting, "admin", &wrong).is_err());
+        assert!(attempt(&mut registry, &setting, "guest", &x).is_err());
+    }
+
+    #[test]
+    fn test_session_single_use_and_expiry() {
+        let setting = toy_params().setting();
+        let mut registry = Registry::new();
+        //u = 4^3 = 18 mod 23
+        let request = RegisterRequest {
+            username: "admin".to_string(),
+            public_key: BigUint::from(18u32),
+        };
+        registry.register(&setting, &request).unwrap();
+        let zero = RegisterRequest {
+            username: "root".to_string(),
+            public_key: BigUint::from(1u32),
+        };
+        assert!(registry.register(&setting, &zero).is_err());
+
+        //t = 4^2 = 16, c = 5, z = 2 + 5*3 = 17 = 6 mod 11
+        let login = LoginRequest {
+            username: "admin".to_string(),
+            t: BigUint::from(16u32),
+        };
+        let challenge = registry
+            .login(&setting, &login, BigUint::from(5u32))
+            .unwrap();
+        let response = LoginResponse {
+            session: challenge.session,
+            z: BigUint::from(6u32),
+        };
+        let token = registry.respond(&setting, &response).unwrap();
+        //Повторный ответ в той же сессии отклоняется
+        assert!(registry.respond(&setting, &response).is_err());
+
+        registry.tokens.get_mut(&token.token).unwrap().expires = Instant::now();
+        assert_eq!(registry.authorize(&token.token), None);
+        assert!(registry.tokens.is_empty());
+
+        //Просроченная сессия входа не принимает ответ и удаляется
+        let challenge = registry
+            .login(&setting, &login, BigUint::from(5u32))
+            .unwrap();
+        registry
+            .pending
+            .get_mut(&challenge.session)
+            .unwrap()
+            .expires = Instant::now();
+        let response = LoginResponse {
+            session: challenge.session,
+            z: BigUint::from(6u32),
+        };
+        assert!(registry.respond(&setting, &response).is_err());
+        assert!(registry.pending.is_empty());
+    }
+
+    #[test]
+    fn test_admins_and_pending_bound() {
+        let setting = ristretto::setting();
+        let group = &setting.group;
+        let key = |x: &BigUint| group.encode(&group.exp(&setting.g, x));
+        let (x, y) = (group.random_scalar(), group.random_scalar());
+        let mut registry = Registry::new();
+        let admin = RegisterRequest {
+            username: "admin".to_string(),
+            public_key: key(&x),
+        };
+        let user = RegisterRequest {
+            username: "guest".to_string(),
+            public_key: key(&y),
+        };
+        registry.register_admin(&setting, &admin).unwrap();
+        registry.register(&setting, &user).unwrap();
+        assert!(registry.is_admin("admin"));
+        assert!(!registry.is_admin("guest"));
+
+        //Открытые сессии ограничены; просроченные освобождают место
+        let login = LoginRequest {
+            username: "guest".to_string(),
+            t: key(&x),
+        };
+        for _ in 0..MAX_PENDING {
+            registry.login(&setting, &login, BigUint::ZERO).unwrap();
+        }
+        assert!(registry.login(&setting, &login, BigUint::ZERO).is_err());
+        for pending in registry.pending.values_mut() {
+            pending.expires = Instant::now();
+        }
+        assert!(registry.login(&setting, &login, BigUint::ZERO).is_ok());
+        assert_eq!(registry.pending.len(), 1);
+    }
+}
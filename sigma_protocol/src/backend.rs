@@ -0,0 +1,357 @@
+//////////////////////////////////////////////////////////////
+// Бэкенд длинной арифметики: операции, на которые приходится
+// почти все время генерации простых чисел и пакетной проверки.
+// Остальной код работает с BigUint из num-bigint; бэкенд переводит
+// аргументы в свое представление один раз на операцию, а алгоритмы
+// (Миллер-Рабин, трюк Шамира, Штраус, Пиппенджер) написаны поверх
+// трейта один раз. С фичей gmp активен бэкенд на rug (GMP).
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use num_traits::One;
+#[cfg(feature = "gmp")]
+use rug::{Integer, integer::Order};
+
+pub trait Arithmetic {
+    //Представление чисел внутри бэкенда
+    type Int: Clone + PartialEq;
+
+    fn from_big(x: &BigUint) -> Self::Int;
+
+    fn to_big(x: &Self::Int) -> BigUint;
+
+    //1 mod modulus: ноль при модуле 1
+    fn one_mod(modulus: &Self::Int) -> Self::Int;
+
+    //base^exp mod modulus; modulus > 0
+    fn mod_pow(base: &Self::Int, exp: &Self::Int, modulus: &Self::Int) -> Self::Int;
+
+    fn mod_mul(a: &Self::Int, b: &Self::Int, modulus: &Self::Int) -> Self::Int;
+
+    fn bits(x: &Self::Int) -> u64;
+
+    fn bit(x: &Self::Int, i: u64) -> bool;
+}
+
+//num-bigint: для нечетного модуля modpow использует умножение Монтгомери
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NumBigint;
+
+impl Arithmetic for NumBigint {
+    type Int = BigUint;
+
+    fn from_big(x: &BigUint) -> BigUint {
+        x.clone()
+    }
+
+    fn to_big(x: &BigUint) -> BigUint {
+        x.clone()
+    }
+
+    fn one_mod(modulus: &BigUint) -> BigUint {
+        BigUint::one() % modulus
+    }
+
+    fn mod_pow(base: &BigUint, exp: &BigUint, modulus: &BigUint) -> BigUint {
+        base.modpow(exp, modulus)
+    }
+
+    fn mod_mul(a: &BigUint, b: &BigUint, modulus: &BigUint) -> BigUint {
+        a * b % modulus
+    }
+
+    fn bits(x: &BigUint) -> u64 {
+        x.bits()
+    }
+
+    fn bit(x: &BigUint, i: u64) -> bool {
+        x.bit(i)
+    }
+}
+
+//GMP через rug: числа переводятся словами по 32 бита
+#[cfg(feature = "gmp")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Gmp;
+
+#[cfg(feature = "gmp")]
+impl Arithmetic for Gmp {
+    type Int = Integer;
+
+    fn from_big(x: &BigUint) -> Integer {
+        Integer::from_digits(&x.to_u32_digits(), Order::Lsf)
+    }
+
+    fn to_big(x: &Integer) -> BigUint {
+        BigUint::new(x.to_digits::<u32>(Order::Lsf))
+    }
+
+    fn one_mod(modulus: &Integer) -> Integer {
+        Integer::from(1) % modulus
+    }
+
+    fn mod_pow(base: &Integer, exp: &Integer, modulus: &Integer) -> Integer {
+        //Обратный элемент нужен только для отрицательного показателя
+        Integer::from(
+            base.pow_mod_ref(exp, modulus)
+                .expect("показатель неотрицателен"),
+        )
+    }
+
+    fn mod_mul(a: &Integer, b: &Integer, modulus: &Integer) -> Integer {
+        Integer::from(a * b) % modulus
+    }
+
+    fn bits(x: &Integer) -> u64 {
+        x.significant_bits().into()
+    }
+
+    fn bit(x: &Integer, i: u64) -> bool {
+        //Длина чисел в GMP ограничена u32 бит: старшие биты нулевые
+        u32::try_from(i).is_ok_and(|i| x.get_bit(i))
+    }
+}
+
+#[cfg(not(feature = "gmp"))]
+type Active = NumBigint;
+#[cfg(feature = "gmp")]
+type Active = Gmp;
+
+pub fn mod_pow(base: &BigUint, exp: &BigUint, modulus: &BigUint) -> BigUint {
+    let [base, exp, modulus] = [base, exp, modulus].map(Active::from_big);
+    Active::to_big(&Active::mod_pow(&base, &exp, &modulus))
+}
+
+pub fn mod_mul(a: &BigUint, b: &BigUint, modulus: &BigUint) -> BigUint {
+    let [a, b, modulus] = [a, b, modulus].map(Active::from_big);
+    Active::to_big(&Active::mod_mul(&a, &b, &modulus))
+}
+
+//Основание a доказывает составность n, где n - 1 = t * 2^s
+pub fn is_witness(n: &BigUint, a: &BigUint, t: &BigUint, s: u32) -> bool {
+    witness::<Active>(n, a, t, s)
+}
+
+//g^a * h^b mod modulus; modulus > 0
+pub fn mod_multi_pow(
+    g: &BigUint,
+    a: &BigUint,
+    h: &BigUint,
+    b: &BigUint,
+    modulus: &BigUint,
+) -> BigUint {
+    shamir::<Active>(g, a, h, b, modulus)
+}
+
+//С этого числа оснований корзины выгоднее метода Штрауса
+const PIPPENGER_THRESHOLD: usize = 16;
+
+//Π bases_i^exps_i mod modulus; modulus > 0, длины равны
+pub fn multi_exp(bases: &[BigUint], exps: &[BigUint], modulus: &BigUint) -> BigUint {
+    multi_exp_with::<Active>(bases, exps, modulus)
+}
+
+fn multi_exp_with<A: Arithmetic>(
+    bases: &[BigUint],
+    exps: &[BigUint],
+    modulus: &BigUint,
+) -> BigUint {
+    let bases: Vec<A::Int> = bases.iter().map(|b| A::from_big(&(b % modulus))).collect();
+    let exps: Vec<A::Int> = exps.iter().map(A::from_big).collect();
+    let modulus = A::from_big(modulus);
+    let result = match bases.len() < PIPPENGER_THRESHOLD {
+        true => straus::<A>(&bases, &exps, &modulus),
+        false => pippenger::<A>(&bases, &exps, &modulus),
+    };
+    A::to_big(&result)
+}
+
+fn witness<A: Arithmetic>(n: &BigUint, a: &BigUint, t: &BigUint, s: u32) -> bool {
+    let one = A::from_big(&BigUint::one());
+    let n_1 = A::from_big(&(n - 1u32));
+    let n = A::from_big(n);
+    let mut x = A::mod_pow(&A::from_big(a), &A::from_big(t), &n);
+    if x == one || x == n_1 {
+        return false;
+    }
+    for _ in 0..s - 1 {
+        x = A::mod_mul(&x, &x, &n);
+        if x == one {
+            return true;
+        }
+        if x == n_1 {
+            return false;
+        }
+    }
+    true
+}
+
+//Один проход по битам: общие возведения в квадрат и умножение
+//на g, h или заранее вычисленное g*h
+fn shamir<A: Arithmetic>(
+    g: &BigUint,
+    a: &BigUint,
+    h: &BigUint,
+    b: &BigUint,
+    modulus: &BigUint,
+) -> BigUint {
+    let g = A::from_big(&(g % modulus));
+    let h = A::from_big(&(h % modulus));
+    let [a, b, modulus] = [a, b, modulus].map(A::from_big);
+    let gh = A::mod_mul(&g, &h, &modulus);
+    let mut result = A::one_mod(&modulus);
+    for i in (0..A::bits(&a).max(A::bits(&b))).rev() {
+        result = A::mod_mul(&result, &result, &modulus);
+        let factor = match (A::bit(&a, i), A::bit(&b, i)) {
+            (true, true) => &gh,
+            (true, false) => &g,
+            (false, true) => &h,
+            (false, false) => continue,
+        };
+        result = A::mod_mul(&result, factor, &modulus);
+    }
+    A::to_big(&result)
+}
+
+fn straus<A: Arithmetic>(bases: &[A::Int], exps: &[A::Int], modulus: &A::Int) -> A::Int {
+    let bits = exps.iter().map(A::bits).max().unwrap_or(0);
+    let mut result = A::one_mod(modulus);
+    for i in (0..bits).rev() {
+        result = A::mod_mul(&result, &result, modulus);
+        for (base, exp) in bases.iter().zip(exps) {
+            if A::bit(exp, i) {
+                result = A::mod_mul(&result, base, modulus);
+            }
+        }
+    }
+    result
+}
+
+fn pippenger<A: Arithmetic>(bases: &[A::Int], exps: &[A::Int], modulus: &A::Int) -> A::Int {
+    //Ширина окна порядка log2(N): корзин 2^c - 1, окон b/c
+    let c = (bases.len().ilog2() as u64).saturating_sub(2).max(2);
+    let bits = exps.iter().map(A::bits).max().unwrap_or(0);
+    let one = A::one_mod(modulus);
+    let mut result = one.clone();
+    for window in (0..bits.div_ceil(c)).rev() {
+        for _ in 0..c {
+            result = A::mod_mul(&result, &result, modulus);
+        }
+        let mut buckets = vec![one.clone(); (1 << c) - 1];
+        for (base, exp) in bases.iter().zip(exps) {
+            let digit = (0..c)
+                .filter(|j| A::bit(exp, window * c + j))
+                .fold(0usize, |d, j| d | 1 << j);
+            if digit != 0 {
+                buckets[digit - 1] = A::mod_mul(&buckets[digit - 1], base, modulus);
+            }
+        }
+        //Π bucket_d^d = Π_{d} (Π_{k >= d} bucket_k)
+        let mut running = one.clone();
+        let mut window_sum = one.clone();
+        for bucket in buckets.iter().rev() {
+            running = A::mod_mul(&running, bucket, modulus);
+            window_sum = A::mod_mul(&window_sum, &running, modulus);
+        }
+        result = A::mod_mul(&result, &window_sum, modulus);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math;
+    use num_bigint::BigInt;
+
+    //Проверки одинаковы для всех бэкендов
+    fn check_backend<A: Arithmetic>() {
+        let m = (BigUint::from(1u32) << 127u32) - 1u32;
+        let base = BigUint::from(0x1234_5678_9abc_def0u64);
+        let pow = |b: &BigUint, e: &BigUint, m: &BigUint| {
+            A::to_big(&A::mod_pow(
+                &A::from_big(b),
+                &A::from_big(e),
+                &A::from_big(m),
+            ))
+        };
+        for e in [0u64, 1, 2, 65_537, u64::MAX] {
+            let e = BigUint::from(e);
+            assert_eq!(
+                Ok(pow(&base, &e, &m)),
+                math::mod_pow_big(&base, &BigInt::from(e.clone()), &m)
+            );
+        }
+        //Четный модуль: без Монтгомери
+        let even = BigUint::from(1u32) << 64u32;
+        assert_eq!(
+            pow(&base, &BigUint::from(3u32), &even),
+            &base * &base * &base % &even
+        );
+        let [a, md] = [&base, &m].map(A::from_big);
+        assert_eq!(A::to_big(&A::mod_mul(&a, &a, &md)), &base * &base % &m);
+        assert_eq!(
+            A::to_big(&A::one_mod(&A::from_big(&BigUint::one()))),
+            BigUint::ZERO
+        );
+
+        //561 = 3 * 11 * 17 - число Кармайкла, 2 - свидетель; 2^7 * 5 + 1 = 641 простое
+        assert!(witness::<A>(
+            &BigUint::from(561u32),
+            &BigUint::from(2u32),
+            &BigUint::from(35u32),
+            4
+        ));
+        assert!(!witness::<A>(
+            &BigUint::from(641u32),
+            &BigUint::from(2u32),
+            &BigUint::from(5u32),
+            7
+        ));
+
+        let g = &m + 5u32;
+        let h = BigUint::from(3u32);
+        let (ea, eb) = (BigUint::from(u64::MAX), BigUint::from(12_345u32));
+        assert_eq!(
+            shamir::<A>(&g, &ea, &h, &eb, &m),
+            pow(&g, &ea, &m) * pow(&h, &eb, &m) % &m
+        );
+
+        //Основания больше модуля и нулевые показатели; оба метода для N корзин
+        for n in [3, PIPPENGER_THRESHOLD, 100] {
+            let bases: Vec<BigUint> = (0..n as u64)
+                .map(|i| BigUint::from(i * 7_919 + 3) * &m + i)
+                .collect();
+            let exps: Vec<BigUint> = (0..n as u64)
+                .map(|i| BigUint::from(i * 1_000_003 % 65_537).pow(3))
+                .collect();
+            let expected = bases
+                .iter()
+                .zip(&exps)
+                .fold(BigUint::one(), |acc, (b, e)| acc * pow(b, e, &m) % &m);
+            assert_eq!(multi_exp_with::<A>(&bases, &exps, &m), expected);
+            let reduced: Vec<A::Int> = bases.iter().map(|b| A::from_big(&(b % &m))).collect();
+            let exps: Vec<A::Int> = exps.iter().map(A::from_big).collect();
+            let md = A::from_big(&m);
+            assert_eq!(
+                A::to_big(&pippenger::<A>(&reduced, &exps, &md)),
+                A::to_big(&straus::<A>(&reduced, &exps, &md))
+            );
+        }
+    }
+
+    #[test]
+    fn test_num_bigint_backend() {
+        check_backend::<NumBigint>();
+    }
+
+    #[cfg(feature = "gmp")]
+    #[test]
+    fn test_gmp_backend() {
+        check_backend::<Gmp>();
+        //Перевод чисел длиннее одного слова и нуля
+        for x in [BigUint::ZERO, (BigUint::from(1u32) << 200u32) - 7u32] {
+            assert_eq!(Gmp::to_big(&Gmp::from_big(&x)), x);
+        }
+    }
+}
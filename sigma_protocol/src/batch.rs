@@ -0,0 +1,214 @@
+use num_bigint::BigUint;
+use num_traits::One;
+
+use crate::group::Group;
+use crate::key_gen;
+use crate::math;
+use crate::protocol::{Proof, PublicParams};
+
+//Разрядность случайных весов: вероятность пропустить неверное доказательство не выше 2^-64
+const WEIGHT_BITS: u64 = 64;
+
+//Пакетная проверка случайной линейной комбинацией:
+//Π (u_t,i)^w_i * u_i^(c_i w_i) * g^(-Σ w_i a_i) * h^(-Σ w_i b_i) == 1.
+//Короткие веса и мультиэкспонента (math::multi_exp, для больших пакетов -
+//метод Пиппенджера) делают проверку N доказательств заметно дешевле N
+//отдельных проверок.
+pub fn verify_batch(params: &PublicParams, proofs: &[Proof]) -> bool {
+    //В малых группах веса сравнимы с порядками элементов и ошибка может погаситься
+    if small_group(params) {
+        return proofs.iter().all(|proof| proof.verify(params));
+    }
+    let weights = random_weights(proofs.len());
+    verify_batch_with_weights(params, proofs, &weights)
+}
+
+fn small_group(params: &PublicParams) -> bool {
+    params.order().bits() <= WEIGHT_BITS
+}
+
+fn random_weights(n: usize) -> Vec<BigUint> {
+    let bound = BigUint::one() << WEIGHT_BITS;
+    let mut weights = Vec::with_capacity(n);
+    for _ in 0..n {
+        weights.push(key_gen::random_biguint_mod(&bound) + 1u32);
+    }
+    weights
+}
+
+pub fn verify_batch_with_weights(
+    params: &PublicParams,
+    proofs: &[Proof],
+    weights: &[BigUint],
+) -> bool {
+    if proofs.len() != weights.len() {
+        return false;
+    }
+    if !in_subgroup(params, proofs) {
+        return false;
+    }
+
+    let order = params.order();
+    let mut sum_alpha = BigUint::ZERO;
+    let mut sum_beta = BigUint::ZERO;
+    let mut exps = Vec::with_capacity(2 * proofs.len());
+    for (proof, w) in proofs.iter().zip(weights) {
+        sum_alpha = (sum_alpha + w * &proof.alpha) % &order;
+        sum_beta = (sum_beta + w * &proof.beta) % &order;
+        exps.push(w.clone());
+        exps.push(&proof.c * w % &order);
+    }
+    //Отрицательные показатели заменяются дополнением до порядка группы
+    let neg_alpha = (&order - sum_alpha) % &order;
+    let neg_beta = (&order - sum_beta) % &order;
+
+    let mut bases = Vec::with_capacity(exps.len() + 2);
+    for proof in proofs {
+        bases.push(proof.ut.clone());
+        bases.push(proof.u.clone());
+    }
+    bases.extend([params.g.clone(), params.h.clone()]);
+    exps.extend([neg_alpha, neg_beta]);
+
+    math::multi_exp(&bases, &exps, &params.p).is_some_and(|x| x.is_one())
+}
+
+//g, h и все u, u_t лежат в подгруппе порядка q. Без этого элемент малого
+//порядка гасится весом: p - u_t вместо u_t проходит при любом четном весе
+pub(crate) fn in_subgroup(params: &PublicParams, proofs: &[Proof]) -> bool {
+    let group = params.setting().group;
+    let generators = [&params.g, &params.h];
+    let elements = proofs.iter().flat_map(|proof| [&proof.u, &proof.ut]);
+    generators
+        .into_iter()
+        .chain(elements)
+        .all(|x| group.contains(x))
+}
+
+//Поиск неверных доказательств делением пакета пополам: при k ошибках
+//требуется порядка k*log(N) пакетных проверок вместо N отдельных
+pub fn find_invalid(params: &PublicParams, proofs: &[Proof]) -> Vec<usize> {
+    if small_group(params) {
+        return (0..proofs.len())
+            .filter(|&i| !proofs[i].verify(params))
+            .collect();
+    }
+    let mut invalid = Vec::new();
+    let mut pending = Vec::new();
+    pending.push(0..proofs.len());
+    while let Some(range) = pending.pop() {
+        match range.len() {
+            0 => {}
+            1 => {
+                if !proofs[range.start].verify(params) {
+                    invalid.push(range.start);
+                }
+            }
+            _ => {
+                if verify_batch(params, &proofs[range.clone()]) {
+                    continue;
+                }
+                let mid = range.start + range.len() / 2;
+                pending.push(mid..range.end);
+                pending.push(range.start..mid);
+            }
+        }
+    }
+    invalid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Key, Prover};
+
+    // Безопасное простое p = 2q + 1 = 2^127 + 8799, g и h - квадраты
+    fn params() -> PublicParams {
+        PublicParams {
+            p: (BigUint::one() << 127u32) + 8799u32,
+            q: (BigUint::one() << 126u32) + 4399u32,
+            g: BigUint::from(4u32),
+            h: BigUint::from(9u32),
+        }
+    }
+
+    fn proofs(n: usize) -> Vec<Proof> {
+        let mut proofs = Vec::with_capacity(n);
+        for _ in 0..n {
+            let prover = Prover::random(params()).unwrap();
+            proofs.push(prover.prove_non_interactive().unwrap());
+        }
+        proofs
+    }
+
+    #[test]
+    fn test_batch_accepts_valid_proofs() {
+        let proofs = proofs(20);
+        assert!(verify_batch(&params(), &proofs));
+        assert!(verify_batch(&params(), &[]));
+        assert!(find_invalid(&params(), &proofs).is_empty());
+    }
+
+    #[test]
+    fn test_batch_finds_tampered_proofs() {
+        let mut proofs = proofs(17);
+        proofs[3].alpha += 1u32;
+        proofs[16].ut = BigUint::ZERO;
+        assert!(!verify_batch(&params(), &proofs));
+        assert_eq!(find_invalid(&params(), &proofs), vec![3, 16]);
+    }
+
+    #[test]
+    fn test_batch_rejects_negated_commitment() {
+        //p - u_t = -u_t имеет множитель порядка 2: четный вес его погасил бы
+        let params = params();
+        let mut proofs = proofs(4);
+        proofs[1].ut = &params.p - &proofs[1].ut;
+        assert!(!proofs[1].verify(&params));
+        let even = vec![BigUint::from(2u32); proofs.len()];
+        assert!(!verify_batch_with_weights(&params, &proofs, &even));
+        assert!(!verify_batch(&params, &proofs));
+        assert_eq!(find_invalid(&params, &proofs), vec![1]);
+    }
+
+    #[test]
+    fn test_batch_matches_individual_check() {
+        let params = params();
+        let order = params.order();
+        let secret = Key::new(BigUint::from(11u32), BigUint::from(13u32));
+        let nonce = Key::new(BigUint::from(17u32), BigUint::from(19u32));
+        let c = BigUint::from(23u32);
+        let proof = Proof {
+            u: params.compute_u(&secret.alpha, &secret.beta).unwrap(),
+            ut: params.compute_u(&nonce.alpha, &nonce.beta).unwrap(),
+            alpha: (&nonce.alpha + &secret.alpha * &c) % &order,
+            beta: (&nonce.beta + &secret.beta * &c) % &order,
+            c,
+        };
+        assert!(proof.verify(&params));
+        let weights = [BigUint::from(5u32), BigUint::from(1u32)];
+        assert!(verify_batch_with_weights(
+            &params,
+            &[proof.clone(), proof],
+            &weights
+        ));
+    }
+
+    #[test]
+    fn test_small_group_checks_individually() {
+        let small = PublicParams {
+            p: BigUint::from(11u32),
+            q: BigUint::from(10u32),
+            g: BigUint::from(2u32),
+            h: BigUint::from(3u32),
+        };
+        let prover = Prover::random(small.clone()).unwrap();
+        let mut proofs = Vec::new();
+        for _ in 0..4 {
+            proofs.push(prover.prove_non_interactive().unwrap());
+        }
+        proofs[2].alpha = (&proofs[2].alpha + 1u32) % small.order();
+        assert!(!verify_batch(&small, &proofs));
+        assert_eq!(find_invalid(&small, &proofs), vec![2]);
+    }
+}
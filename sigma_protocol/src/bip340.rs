@@ -0,0 +1,317 @@
+//////////////////////////////////////////////////////////////
+// Подписи Шнорра BIP-340: неинтерактивный протокол Шнорра над
+// secp256k1, где испытание - хэш с тегом "BIP0340/challenge",
+// а точки передаются только x-координатой (y всегда четная).
+// Подписи совместимы с Bitcoin (Taproot).
+//////////////////////////////////////////////////////////////
+
+use k256::ProjectivePoint;
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+
+use crate::group::Group;
+use crate::math;
+use crate::protocol::{Proof, ProofKind, Verifier};
+use crate::secp256k1::{self, Secp256k1};
+
+pub const CHALLENGE_TAG: &str = "BIP0340/challenge";
+pub const AUX_TAG: &str = "BIP0340/aux";
+pub const NONCE_TAG: &str = "BIP0340/nonce";
+
+//SHA256(SHA256(tag) || SHA256(tag) || данные)
+pub fn tagged_hash(tag: &str, parts: &[&[u8]]) -> [u8; 32] {
+    let tag = Sha256::digest(tag.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(tag);
+    hasher.update(tag);
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+fn bytes32(x: &BigUint) -> [u8; 32] {
+    math::i2osp(x, 32)
+        .expect("значение короче 32 байт")
+        .try_into()
+        .unwrap()
+}
+
+//x-координата точки и четность y (по префиксу сжатой точки SEC1)
+fn x_only(point: &ProjectivePoint) -> ([u8; 32], bool) {
+    let encoded = Secp256k1.serialize(point);
+    let mut x = [0u8; 32];
+    x.copy_from_slice(&encoded[1..]);
+    (x, encoded[0] == 0x02)
+}
+
+//Точка с данной x-координатой и четной y; None, если x >= p или не на кривой
+fn lift_x(x: &[u8; 32]) -> Option<ProjectivePoint> {
+    let mut encoded = vec![0x02];
+    encoded.extend_from_slice(x);
+    Secp256k1.deserialize(&encoded)
+}
+
+//Испытание Фиата-Шамира: e = H_challenge(r || P || m) mod n
+pub fn challenge(r: &[u8; 32], public_key: &[u8; 32], message: &[u8]) -> BigUint {
+    let e = tagged_hash(CHALLENGE_TAG, &[r, public_key, message]);
+    BigUint::from_bytes_be(&e) % Secp256k1.order()
+}
+
+//Секретный ключ из [1, n)
+fn secret_scalar(secret: &[u8; 32]) -> Result<BigUint, String> {
+    let d = BigUint::from_bytes_be(secret);
+    if d == BigUint::ZERO || d >= Secp256k1.order() {
+        return Err("Секретный ключ должен лежать в [1, n)".to_string());
+    }
+    Ok(d)
+}
+
+//Публичный ключ: x-координата d*G
+pub fn public_key(secret: &[u8; 32]) -> Result<[u8; 32], String> {
+    let d = secret_scalar(secret)?;
+    Ok(x_only(&Secp256k1.exp(&ProjectivePoint::GENERATOR, &d)).0)
+}
+
+//Подпись (r, s): коммитмент R = k*G с четной y, ответ s = k + e*d;
+//одноразовый ключ k выводится из секрета, сообщения и aux по BIP-340
+pub fn sign(secret: &[u8; 32], message: &[u8], aux: &[u8; 32]) -> Result<[u8; 64], String> {
+    let group = Secp256k1;
+    let order = group.order();
+    let d = secret_scalar(secret)?;
+    let (px, even) = x_only(&group.exp(&ProjectivePoint::GENERATOR, &d));
+    let d = if even { d } else { &order - d };
+
+    let mut t = bytes32(&d);
+    for (t, a) in t.iter_mut().zip(tagged_hash(AUX_TAG, &[aux])) {
+        *t ^= a;
+    }
+    let k = BigUint::from_bytes_be(&tagged_hash(NONCE_TAG, &[&t, &px, message])) % &order;
+    if k == BigUint::ZERO {
+        return Err("Нулевой одноразовый ключ".to_string());
+    }
+    let (rx, even) = x_only(&group.exp(&ProjectivePoint::GENERATOR, &k));
+    let k = if even { k } else { &order - k };
+
+    let e = challenge(&rx, &px, message);
+    let s = (k + e * d) % &order;
+    let mut signature = [0u8; 64];
+    signature[..32].copy_from_slice(&rx);
+    signature[32..].copy_from_slice(&bytes32(&s));
+    if !verify(&px, message, &signature) {
+        return Err("Подпись не прошла проверку".to_string());
+    }
+    Ok(signature)
+}
+
+//Проверка подписи как доказательства Шнорра (u = P, u_t = R, c = e, a_z = s)
+pub fn verify(public_key: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+    let group = Secp256k1;
+    let mut rx = [0u8; 32];
+    rx.copy_from_slice(&signature[..32]);
+    let s = BigUint::from_bytes_be(&signature[32..]);
+    let (Some(p), Some(r)) = (lift_x(public_key), lift_x(&rx)) else {
+        return false;
+    };
+    if s >= group.order() {
+        return false;
+    }
+    let proof = Proof {
+        u: group.encode(&p),
+        ut: group.encode(&r),
+        c: challenge(&rx, public_key, message),
+        alpha: s,
+        beta: BigUint::ZERO,
+    };
+    Verifier::in_setting(secp256k1::setting(), ProofKind::Schnorr).verify(&proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::{from_hex, from_hex_array};
+
+    //Векторы из bip-0340/test-vectors.csv
+    const SIGN_VECTORS: [(&str, &str, &str, &str, &str); 4] = [
+        (
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA821525F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0",
+        ),
+        (
+            "B7E151628AED2A6ABF7158809CF4F3C762E7160F38B4DA56A784D9045190CFEF",
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "6896BD60EEAE296DB48A229FF71DFE071BDE413E6D43F917DC8DCF8C78DE33418906D11AC976ABCCB20B091292BFF4EA897EFCB639EA871CFA95F6DE339E4B0A",
+        ),
+        (
+            "C90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B14E5C9",
+            "DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8",
+            "C87AA53824B4D7AE2EB035A2B5BBBCCC080E76CDC6D1692C4B0B62D798E6D906",
+            "7E2D58D8B3BCDF1ABADEC7829054F90DDA9805AAB56C77333024B9D0A508B75C",
+            "5831AAEED7B44BB74E5EAB94BA9D4294C49BCF2A60728D8B4C200F50DD313C1BAB745879A5AD954A72C45A91C3A51D3C7ADEA98D82F8481E0E1E03674A6F3FB7",
+        ),
+        (
+            "0B432B2677937381AEF05BB02A66ECD012773062CF3FA2549E44F58ED2401710",
+            "25D1DFF95105F5253C4022F628A996AD3A0D95FBF21D468A1B33F8C160D8F517",
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+            "7EB0509757E246F19449885651611CB965ECC1A187DD51B64FDA1EDC9637D5EC97582B9CB13DB3933705B32BA982AF5AF25FD78881EBB32771FC5922EFC66EA3",
+        ),
+    ];
+
+    //Открытый ключ, сообщение, подпись, ожидаемый результат
+    const VERIFY_VECTORS: [(&str, &str, &str, bool); 11] = [
+        //4: подпись без вектора подписания
+        (
+            "D69C3509BB99E412E68B0FE8544E72837DFA30746D8BE2AA65975F29D22DC7B9",
+            "4DF3C3F68FCC83B27E9D42C90431A72499F17875C81A599B566C9889B9696703",
+            "00000000000000000000003B78CE563F89A0ED9414F5AA28AD0D96D6795F9C6376AFB1548AF603B3EB45C9F8207DEE1060CB71C04E80F593060B07D28308D7F4",
+            true,
+        ),
+        //5: открытый ключ не на кривой
+        (
+            "EEFDEA4CDB677750A420FEE807EACF21EB9898AE79B9768766E4FAA04A2D4A34",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E17776969E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            false,
+        ),
+        //6: R с нечетной y
+        (
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "FFF97BD5755EEEA420453A14355235D382F6472F8568A18B2F057A14602975563CC27944640AC607CD107AE10923D9EF7A73C643E166BE5EBEAFA34B1AC553E2",
+            false,
+        ),
+        //7: другое сообщение
+        (
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "1FA62E331EDBC21C394792D2AB1100A7B432B013DF3F6FF4F99FCB33E0E1515F28890B3EDB6E7189B630448B515CE4F8622A954CFE545735AAEA5134FCCDB2BD",
+            false,
+        ),
+        //8: s с обратным знаком
+        (
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769961764B3AA9B2FFCB6EF947B6887A226E8D7C93E00C5ED0C1834FF0D0C2E6DA6",
+            false,
+        ),
+        //9: sG - eP - бесконечно удаленная точка (x = 0)
+        (
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "0000000000000000000000000000000000000000000000000000000000000000123DDA8328AF9C23A94C1FEECFD123BA4FB73476F0D594DCB65C6425BD186051",
+            false,
+        ),
+        //10: sG - eP - бесконечно удаленная точка (x = 1)
+        (
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "00000000000000000000000000000000000000000000000000000000000000017615FBAF5AE28864013C099742DEADB4DBA87F11AC6754F93780D5A1837CF197",
+            false,
+        ),
+        //11: r не является x-координатой точки кривой
+        (
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "4A298DACAE57395A15D0795DDBFD1DCB564DA82B0F269BC70A74F8220429BA1D69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            false,
+        ),
+        //12: r равно p
+        (
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            false,
+        ),
+        //13: s равно n
+        (
+            "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
+            false,
+        ),
+        //14: x-координата ключа больше p
+        (
+            "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30",
+            "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E17776969E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            false,
+        ),
+    ];
+
+    //15-18: сообщения произвольной длины, один ключ и нулевой aux
+    const LENGTH_SECRET: &str = "0340034003400340034003400340034003400340034003400340034003400340";
+    const MESSAGE_LENGTH_VECTORS: [(&str, &str); 3] = [
+        (
+            "",
+            "71535DB165ECD9FBBC046E5FFAEA61186BB6AD436732FCCC25291A55895464CF6069CE26BF03466228F19A3A62DB8A649F2D560FAC652827D1AF0574E427AB63",
+        ),
+        (
+            "11",
+            "08A20A0AFEF64124649232E0693C583AB1B9934AE63B4C3511F3AE1134C6A303EA3173BFEA6683BD101FA5AA5DBC1996FE7CACFC5A577D33EC14564CEC2BACBF",
+        ),
+        (
+            "0102030405060708090A0B0C0D0E0F1011",
+            "5130F39A4059B43BC7CAC09A19ECE52B5D8699D1A71E3C52DA9AFDB6B50AC370C4A482B77BF960F8681540E25B6771ECE1E5A37FD80E5A51897C5566A97EA5A5",
+        ),
+    ];
+
+    const LONG_MESSAGE_SIGNATURE: &str = "403B12B0D8555A344175EA7EC746566303321E5DBFA8BE6F091635163ECA79A8585ED3E3170807E7C03B720FC54C7B23897FCBA0E9D0B4A06894CFD249F22367";
+
+    #[test]
+    fn test_sign_vectors() {
+        for (secret, public, aux, message, signature) in SIGN_VECTORS {
+            let secret = from_hex_array(secret).unwrap();
+            let public = from_hex_array(public).unwrap();
+            let aux = from_hex_array(aux).unwrap();
+            let message = from_hex(message).unwrap();
+            let expected: [u8; 64] = from_hex_array(signature).unwrap();
+
+            assert_eq!(public_key(&secret).unwrap(), public);
+            assert_eq!(sign(&secret, &message, &aux).unwrap(), expected);
+            assert!(verify(&public, &message, &expected));
+        }
+    }
+
+    #[test]
+    fn test_verify_vectors() {
+        for (i, (public, message, signature, valid)) in VERIFY_VECTORS.into_iter().enumerate() {
+            let public = from_hex_array(public).unwrap();
+            let message = from_hex(message).unwrap();
+            let signature = from_hex_array(signature).unwrap();
+            assert_eq!(verify(&public, &message, &signature), valid, "вектор {}", i);
+        }
+    }
+
+    #[test]
+    fn test_message_length_vectors() {
+        let secret = from_hex_array(LENGTH_SECRET).unwrap();
+        let public = public_key(&secret).unwrap();
+        let long = vec![0x99u8; 100];
+        let vectors = MESSAGE_LENGTH_VECTORS
+            .iter()
+            .map(|(message, signature)| (from_hex(message).unwrap(), *signature))
+            .chain([(long, LONG_MESSAGE_SIGNATURE)]);
+        for (message, signature) in vectors {
+            let expected: [u8; 64] = from_hex_array(signature).unwrap();
+            assert_eq!(sign(&secret, &message, &[0u8; 32]).unwrap(), expected);
+            assert!(verify(&public, &message, &expected));
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        assert!(public_key(&[0u8; 32]).is_err());
+        //Подпись не переносится на другое сообщение
+        let secret = [7u8; 32];
+        let signature = sign(&secret, b"message", &[0u8; 32]).unwrap();
+        let public = public_key(&secret).unwrap();
+        assert!(verify(&public, b"message", &signature));
+        assert!(!verify(&public, b"other", &signature));
+    }
+}
@@ -0,0 +1,332 @@
+//////////////////////////////////////////////////////////////
+// Слепая подпись Шнорра: сервер подписывает, не видя ни сообщения,
+// ни итоговой подписи (как банк, выпускающий электронные монеты).
+//
+// Сервер: R = g^k. Пользователь ослабляет связь со своей сессией
+// случайными alpha, beta: R' = R * g^alpha * y^beta,
+// c' = H(R', y, m), и отправляет c = c' + beta. Сервер отвечает
+// s = k + c*x, пользователь снимает ослепление: s' = s + alpha.
+// Подпись (R', s') проверяется как обычная: g^s' = R' * y^c'.
+//
+// Ограничение: при l одновременно открытых сессиях атака ROS дает
+// l + 1 подпись за l ответов сервера. Поэтому сессий открыто не
+// больше MAX_PENDING и каждая живет не дольше BLIND_TTL; полностью
+// атаку это не снимает, для этого нужна другая схема (например, Clause
+// Blind Schnorr).
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::fiat_shamir::absorb;
+use crate::group::{Group, Setting};
+use crate::key_gen;
+use crate::message::decimal;
+
+pub const COMMIT_PATH: &str = "/blind/commit";
+pub const SIGN_PATH: &str = "/blind/sign";
+
+//Домен хэша слепых подписей
+const BLIND_DOMAIN: &str = "sigma_protocol blind";
+
+//Сколько сессия ждет ослепленного испытания
+pub const BLIND_TTL: Duration = Duration::from_secs(30);
+//Предел одновременно открытых сессий: их создают неаутентифицированные
+//запросы, а параллельные сессии открывают путь атаке ROS
+pub const MAX_PENDING: usize = 8;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BlindCommitRequest {
+    pub session: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BlindCommitment {
+    #[serde(with = "decimal")]
+    pub public_key: BigUint,
+    #[serde(with = "decimal")]
+    pub r: BigUint,
+}
+
+//Ослепленное испытание c = c' + beta
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BlindSignRequest {
+    pub session: String,
+    #[serde(with = "decimal")]
+    pub c: BigUint,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BlindResponse {
+    #[serde(with = "decimal")]
+    pub s: BigUint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BlindSignature {
+    #[serde(with = "decimal")]
+    pub r: BigUint,
+    #[serde(with = "decimal")]
+    pub s: BigUint,
+}
+
+//c' = H(домен, группа, g, R', y, m) mod q
+fn challenge<G: Group>(setting: &Setting<G>, r: &BigUint, y: &BigUint, message: &[u8]) -> BigUint {
+    let group = &setting.group;
+    let mut hasher = Sha256::new();
+    absorb(&mut hasher, BLIND_DOMAIN.as_bytes());
+    absorb(&mut hasher, &group.describe());
+    absorb(&mut hasher, &group.serialize(&setting.g));
+    absorb(&mut hasher, &r.to_bytes_be());
+    absorb(&mut hasher, &y.to_bytes_be());
+    absorb(&mut hasher, message);
+    BigUint::from_bytes_be(&hasher.finalize()) % setting.order()
+}
+
+//////////////////////////////////////////////////////////////
+// Подписывающий: ключ x и одноразовые k открытых сессий
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+struct Pending {
+    k: BigUint,
+    expires: Instant,
+}
+
+#[derive(Debug)]
+pub struct BlindSigner {
+    x: BigUint,
+    pending: HashMap<String, Pending>,
+}
+
+impl BlindSigner {
+    pub fn new(x: BigUint) -> Self {
+        BlindSigner {
+            x,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn random(order: &BigUint) -> Self {
+        BlindSigner::new(key_gen::random_biguint_mod(order))
+    }
+
+    pub fn public_key<G: Group>(&self, setting: &Setting<G>) -> G::Element {
+        setting.group.exp(&setting.g, &self.x)
+    }
+
+    //R = g^k; k запоминается до подписи в этой сессии
+    pub fn commit<G: Group>(
+        &mut self,
+        setting: &Setting<G>,
+        request: &BlindCommitRequest,
+        k: BigUint,
+    ) -> Result<BlindCommitment, String> {
+        let now = Instant::now();
+        self.pending.retain(|_, pending| pending.expires > now);
+        if self.pending.contains_key(&request.session) {
+            return Err(format!("Сессия {} уже открыта", request.session));
+        }
+        if self.pending.len() >= MAX_PENDING {
+            return Err("Слишком много открытых сессий подписи".to_string());
+        }
+        let group = &setting.group;
+        let commitment = BlindCommitment {
+            public_key: group.encode(&self.public_key(setting)),
+            r: group.encode(&group.exp(&setting.g, &k)),
+        };
+        self.pending.insert(
+            request.session.clone(),
+            Pending {
+                k,
+                expires: now + BLIND_TTL,
+            },
+        );
+        Ok(commitment)
+    }
+
+    //s = k + c*x; повторная подпись с тем же k раскрыла бы x
+    pub fn sign(
+        &mut self,
+        request: &BlindSignRequest,
+        order: &BigUint,
+    ) -> Result<BlindResponse, String> {
+        let pending = self
+            .pending
+            .remove(&request.session)
+            .ok_or_else(|| format!("Нет открытой сессии {}", request.session))?;
+        if pending.expires <= Instant::now() {
+            return Err(format!("Сессия {} просрочена", request.session));
+        }
+        Ok(BlindResponse {
+            s: (pending.k + &request.c % order * &self.x) % order,
+        })
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Пользователь: ослепление испытания и снятие ослепления ответа
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct Blinding {
+    alpha: BigUint,
+    //Открытые значения сервера и ослепленные R', c'
+    public_key: BigUint,
+    r: BigUint,
+    c: BigUint,
+    blinded_r: BigUint,
+    blinded_c: BigUint,
+}
+
+impl Blinding {
+    //Ослепленное испытание для отправки серверу
+    pub fn challenge(&self) -> &BigUint {
+        &self.c
+    }
+
+    //R' и c', которых сервер не видит
+    pub fn blinded(&self) -> (&BigUint, &BigUint) {
+        (&self.blinded_r, &self.blinded_c)
+    }
+}
+
+pub fn blind<G: Group>(
+    setting: &Setting<G>,
+    commitment: &BlindCommitment,
+    message: &[u8],
+) -> Result<Blinding, String> {
+    let group = &setting.group;
+    let order = setting.order();
+    let y = group
+        .decode(&commitment.public_key)
+        .ok_or("Открытый ключ сервера вне группы")?;
+    let r = group.decode(&commitment.r).ok_or("R вне группы")?;
+    let alpha = group.random_scalar();
+    let beta = group.random_scalar();
+    let blinded = group.mul(
+        &r,
+        &group.mul(&group.exp(&setting.g, &alpha), &group.exp(&y, &beta)),
+    );
+    let blinded_r = group.encode(&blinded);
+    let blinded_c = challenge(setting, &blinded_r, &commitment.public_key, message);
+    Ok(Blinding {
+        alpha,
+        public_key: commitment.public_key.clone(),
+        r: commitment.r.clone(),
+        c: (&blinded_c + beta) % &order,
+        blinded_r,
+        blinded_c,
+    })
+}
+
+//Проверка ответа сервера g^s = R * y^c и s' = s + alpha
+pub fn unblind<G: Group>(
+    setting: &Setting<G>,
+    blinding: &Blinding,
+    response: &BlindResponse,
+) -> Result<BlindSignature, String> {
+    let group = &setting.group;
+    let (Some(y), Some(r)) = (
+        group.decode(&blinding.public_key),
+        group.decode(&blinding.r),
+    ) else {
+        return Err("Значения сервера вне группы".to_string());
+    };
+    if group.exp(&setting.g, &response.s) != group.mul(&r, &group.exp(&y, &blinding.c)) {
+        return Err("Ответ сервера не прошел проверку".to_string());
+    }
+    Ok(BlindSignature {
+        r: blinding.blinded_r.clone(),
+        s: (&response.s + &blinding.alpha) % setting.order(),
+    })
+}
+
+//g^s' == R' * y^c'
+pub fn verify<G: Group>(
+    setting: &Setting<G>,
+    public_key: &BigUint,
+    message: &[u8],
+    signature: &BlindSignature,
+) -> bool {
+    let group = &setting.group;
+    let (Some(y), Some(r)) = (group.decode(public_key), group.decode(&signature.r)) else {
+        return false;
+    };
+    if signature.s >= setting.order() {
+        return false;
+    }
+    let c = challenge(setting, &signature.r, public_key, message);
+    group.exp(&setting.g, &signature.s) == group.mul(&r, &group.exp(&y, &c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ristretto;
+
+    #[test]
+    fn test_blind_signature() {
+        let setting = ristretto::setting();
+        let order = setting.order();
+        let mut signer = BlindSigner::random(&order);
+        let session = BlindCommitRequest {
+            session: "coin".to_string(),
+        };
+        let k = setting.group.random_scalar();
+        let commitment = signer.commit(&setting, &session, k.clone()).unwrap();
+        assert!(signer.commit(&setting, &session, k).is_err());
+
+        let blinding = blind(&setting, &commitment, b"coin #1").unwrap();
+        let request = BlindSignRequest {
+            session: "coin".to_string(),
+            c: blinding.challenge().clone(),
+        };
+        let response = signer.sign(&request, &order).unwrap();
+        assert!(signer.sign(&request, &order).is_err());
+        let signature = unblind(&setting, &blinding, &response).unwrap();
+
+        let y = commitment.public_key.clone();
+        assert!(verify(&setting, &y, b"coin #1", &signature));
+        assert!(!verify(&setting, &y, b"coin #2", &signature));
+        //Сервер не видел ни R', ни c'
+        assert_ne!(signature.r, commitment.r);
+        assert_ne!(blinding.blinded().1, blinding.challenge());
+
+        let forged = BlindResponse {
+            s: (&response.s + 1u32) % &order,
+        };
+        assert!(unblind(&setting, &blinding, &forged).is_err());
+    }
+
+    #[test]
+    fn test_pending_bound() {
+        let setting = ristretto::setting();
+        let mut signer = BlindSigner::random(&setting.order());
+        let request = |i: usize| BlindCommitRequest {
+            session: format!("coin {i}"),
+        };
+        for i in 0..MAX_PENDING {
+            let k = setting.group.random_scalar();
+            signer.commit(&setting, &request(i), k).unwrap();
+        }
+        let k = setting.group.random_scalar();
+        assert!(signer.commit(&setting, &request(MAX_PENDING), k).is_err());
+
+        //Просроченные сессии не подписываются и освобождают место
+        for pending in signer.pending.values_mut() {
+            pending.expires = Instant::now();
+        }
+        let sign = BlindSignRequest {
+            session: "coin 0".to_string(),
+            c: BigUint::from(1u32),
+        };
+        assert!(signer.sign(&sign, &setting.order()).is_err());
+        let k = setting.group.random_scalar();
+        signer.commit(&setting, &request(MAX_PENDING), k).unwrap();
+        assert_eq!(signer.pending.len(), 1);
+    }
+}
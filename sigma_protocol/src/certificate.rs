@@ -0,0 +1,394 @@
+//////////////////////////////////////////////////////////////
+// Доказуемо простые числа с сертификатом Поклингтона. Если
+// n - 1 = F * R, F > sqrt(n), и для некоторого a выполнено
+// a^(n-1) = 1 mod n и gcd(a^((n-1)/r) - 1, n) = 1 для каждого
+// простого r | F, то n простое. Здесь F - одно простое q со своим
+// сертификатом; цепочка заканчивается числами до 64 бит, простота
+// которых проверяется детерминированным тестом Миллера-Рабина.
+// Сертификат Пратта требует полного разложения n - 1 = П r_i^e_i
+// с сертификатами всех r_i и свидетеля a порядка ровно n - 1:
+// a^(n-1) = 1 и a^((n-1)/r_i) != 1. Кандидаты для него строятся как
+// n = 2Rq + 1 с малым R, которое раскладывается ρ-методом Полларда.
+// Проверка сертификата не зависит от случайности генератора.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::{BigInt, BigUint, RandBigInt};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use serde::{Deserialize, Serialize};
+
+use crate::attack;
+use crate::key_gen;
+use crate::math;
+use crate::message::decimal;
+
+//Числа не длиннее SMALL_BITS - листья сертификата
+const SMALL_BITS: u64 = 64;
+//Основания, перебираемые в поисках свидетеля Поклингтона
+const WITNESS_TRIES: u32 = 64;
+//Случайные раунды Миллера-Рабина перед поиском свидетеля
+const PRIME_ROUNDS: u8 = 8;
+//Кандидатов на один делитель: при малых длинах R пробегает мало значений,
+//и без смены делителя поиск мог бы не закончиться
+const CANDIDATES_PER_FACTOR: u32 = 4096;
+//Длина множителя R в n - 1 = 2Rq для сертификата Пратта
+const PRATT_COFACTOR_BITS: u64 = 48;
+
+//Вид сертификата при генерации
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum CertificateKind {
+    #[default]
+    Pocklington,
+    Pratt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PrimeCertificate {
+    #[serde(with = "decimal")]
+    pub n: BigUint,
+    pub proof: PrimeProof,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PrimeProof {
+    //n < 2^64: детерминированный тест Миллера-Рабина
+    Small,
+    //Свидетель a и сертификат простого делителя q числа n - 1, q > sqrt(n)
+    Pocklington {
+        #[serde(with = "decimal")]
+        witness: BigUint,
+        factor: Box<PrimeCertificate>,
+    },
+    //Свидетель порядка n - 1 и сертификаты всех простых делителей n - 1
+    Pratt {
+        #[serde(with = "decimal")]
+        witness: BigUint,
+        factors: Vec<PrimeCertificate>,
+    },
+}
+
+fn pow(base: &BigUint, exp: &BigUint, n: &BigUint) -> BigUint {
+    math::mod_pow_big(base, &BigInt::from(exp.clone()), n).unwrap_or_default()
+}
+
+//Условия Поклингтона для n с простым делителем q числа n - 1 и основанием a
+fn pocklington_holds(n: &BigUint, q: &BigUint, a: &BigUint) -> bool {
+    let n_1 = n - 1u32;
+    if !(&n_1 % q).is_zero() || q * q <= *n {
+        return false;
+    }
+    pow(a, &n_1, n).is_one() && (pow(a, &(&n_1 / q), n) + &n_1).gcd(n).is_one()
+}
+
+//Условия Пратта: простые primes исчерпывают n - 1, a имеет порядок n - 1
+fn pratt_holds(n: &BigUint, primes: &[&BigUint], a: &BigUint) -> bool {
+    if *n <= BigUint::from(2u32) {
+        return false;
+    }
+    let n_1 = n - 1u32;
+    let mut rest = n_1.clone();
+    for &r in primes {
+        if *r <= BigUint::one() {
+            return false;
+        }
+        while (&rest % r).is_zero() {
+            rest /= r;
+        }
+    }
+    rest.is_one()
+        && pow(a, &n_1, n).is_one()
+        && primes.iter().all(|&r| !pow(a, &(&n_1 / r), n).is_one())
+}
+
+impl PrimeCertificate {
+    //Проверка всей цепочки сертификатов
+    pub fn verify(&self) -> Result<(), String> {
+        let n = &self.n;
+        match &self.proof {
+            PrimeProof::Small => {
+                if n.bits() > SMALL_BITS || !key_gen::is_prime_miller_rabin(n, 0) {
+                    return Err(format!("{} не является малым простым", n));
+                }
+            }
+            PrimeProof::Pocklington { witness, factor } => {
+                factor.verify()?;
+                if *n <= BigUint::from(2u32) || !pocklington_holds(n, &factor.n, witness) {
+                    return Err(format!(
+                        "Условия Поклингтона для {} с основанием {} не выполнены",
+                        n, witness
+                    ));
+                }
+            }
+            PrimeProof::Pratt { witness, factors } => {
+                for factor in factors {
+                    factor.verify()?;
+                }
+                let primes: Vec<&BigUint> = factors.iter().map(|f| &f.n).collect();
+                if !pratt_holds(n, &primes, witness) {
+                    return Err(format!(
+                        "Условия Пратта для {} с основанием {} не выполнены",
+                        n, witness
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    //Наибольший доказанный простой делитель n - 1, если он есть
+    pub fn factor(&self) -> Option<&PrimeCertificate> {
+        match &self.proof {
+            PrimeProof::Small => None,
+            PrimeProof::Pocklington { factor, .. } => Some(factor),
+            PrimeProof::Pratt { factors, .. } => factors.iter().max_by(|a, b| a.n.cmp(&b.n)),
+        }
+    }
+}
+
+//Независимая проверка сертификата из файла параметров
+pub fn verify_certificate(certificate: &PrimeCertificate) -> Result<(), String> {
+    certificate.verify()
+}
+
+//Поиск свидетеля для n с известным простым делителем q числа n - 1
+fn pocklington(n: &BigUint, factor: &PrimeCertificate) -> Option<PrimeCertificate> {
+    (2..2 + WITNESS_TRIES)
+        .map(BigUint::from)
+        .find(|a| pocklington_holds(n, &factor.n, a))
+        .map(|witness| PrimeCertificate {
+            n: n.clone(),
+            proof: PrimeProof::Pocklington {
+                witness,
+                factor: Box::new(factor.clone()),
+            },
+        })
+}
+
+//Сертификат Пратта: n - 1 = q * R, где R раскладывается ρ-методом
+//на простые не длиннее SMALL_BITS
+fn pratt(n: &BigUint, factor: &PrimeCertificate) -> Option<PrimeCertificate> {
+    let n_1 = n - 1u32;
+    if !(&n_1 % &factor.n).is_zero() {
+        return None;
+    }
+    let mut small = attack::factorize(&(&n_1 / &factor.n))?;
+    small.dedup();
+    let mut factors = vec![factor.clone()];
+    for r in small.into_iter().filter(|r| *r != factor.n) {
+        if r.bits() > SMALL_BITS {
+            return None;
+        }
+        factors.push(PrimeCertificate {
+            n: r,
+            proof: PrimeProof::Small,
+        });
+    }
+    let primes: Vec<&BigUint> = factors.iter().map(|f| &f.n).collect();
+    let witness = (2..2 + WITNESS_TRIES)
+        .map(BigUint::from)
+        .find(|a| pratt_holds(n, &primes, a))?;
+    Some(PrimeCertificate {
+        n: n.clone(),
+        proof: PrimeProof::Pratt { witness, factors },
+    })
+}
+
+fn certify(
+    n: &BigUint,
+    factor: &PrimeCertificate,
+    kind: CertificateKind,
+) -> Option<PrimeCertificate> {
+    match kind {
+        CertificateKind::Pocklington => pocklington(n, factor),
+        CertificateKind::Pratt => pratt(n, factor),
+    }
+}
+
+//Случайное n = 2Rq + 1 длиной ровно bits бит
+fn candidate(bits: u64, q: &BigUint) -> BigUint {
+    let step = q * 2u32;
+    let lo = ((BigUint::one() << (bits - 1)) - 1u32).div_ceil(&step);
+    let hi = ((BigUint::one() << bits) - 2u32) / &step;
+    let r = rand::thread_rng().gen_biguint_range(&lo, &(hi + 1u32));
+    r * step + 1u32
+}
+
+//Длина простого делителя q: для Поклингтона q^2 > n, для Пратта
+//остаток R должен легко раскладываться
+fn factor_bits(bits: u64, kind: CertificateKind) -> u64 {
+    match kind {
+        CertificateKind::Pocklington => bits / 2 + 2,
+        CertificateKind::Pratt => bits.saturating_sub(PRATT_COFACTOR_BITS).max(2),
+    }
+}
+
+//Простое длиной ровно bits бит (bits >= 2) вместе с сертификатом
+pub fn gen_provable_prime(bits: u64, kind: CertificateKind) -> PrimeCertificate {
+    if bits <= SMALL_BITS {
+        let mut rng = rand::thread_rng();
+        loop {
+            let mut n = rng.gen_biguint(bits);
+            n.set_bit(bits - 1, true);
+            if bits > 2 {
+                n.set_bit(0, true);
+            }
+            if key_gen::is_prime_miller_rabin(&n, 0) {
+                return PrimeCertificate {
+                    n,
+                    proof: PrimeProof::Small,
+                };
+            }
+        }
+    }
+    loop {
+        let factor = gen_provable_prime(factor_bits(bits, kind), kind);
+        for _ in 0..CANDIDATES_PER_FACTOR {
+            let n = candidate(bits, &factor.n);
+            if !key_gen::has_small_factor(&n)
+                && key_gen::is_prime_miller_rabin(&n, PRIME_ROUNDS)
+                && let Some(certificate) = certify(&n, &factor, kind)
+            {
+                return certificate;
+            }
+        }
+    }
+}
+
+//Сертификат p = 2q + 1 для доказанного простого q, если p простое
+fn certify_safe(q: &PrimeCertificate, kind: CertificateKind) -> Option<PrimeCertificate> {
+    let p: BigUint = &q.n * 2u32 + 1u32;
+    if !key_gen::is_prime_miller_rabin(&p, PRIME_ROUNDS) {
+        return None;
+    }
+    certify(&p, q, kind)
+}
+
+//Безопасное простое p = 2q + 1 длиной bits (bits >= 8): сертификат p
+//опирается на сертификат q, так что factor() дает доказанное q
+pub fn gen_provable_safe_prime(bits: u64, kind: CertificateKind) -> PrimeCertificate {
+    //Малое q доказывается напрямую; подбирать его через делитель q - 1
+    //нельзя - при малых длинах подходящего делителя может не найтись
+    if bits - 1 <= SMALL_BITS {
+        loop {
+            if let Some(p) = certify_safe(&gen_provable_prime(bits - 1, kind), kind) {
+                return p;
+            }
+        }
+    }
+    loop {
+        let inner = gen_provable_prime(factor_bits(bits - 1, kind), kind);
+        for _ in 0..CANDIDATES_PER_FACTOR {
+            let q = candidate(bits - 1, &inner.n);
+            //При q = 1 (mod 3) число p делится на 3
+            if &q % 3u32 == BigUint::one()
+                || key_gen::has_small_factor(&q)
+                || !key_gen::is_prime_miller_rabin(&q, PRIME_ROUNDS)
+            {
+                continue;
+            }
+            if let Some(q) = certify(&q, &inner, kind)
+                && let Some(p) = certify_safe(&q, kind)
+            {
+                return p;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provable_prime_certificate() {
+        for bits in [16u64, 64, 65, 200] {
+            let certificate = gen_provable_prime(bits, CertificateKind::Pocklington);
+            assert_eq!(certificate.n.bits(), bits);
+            assert_eq!(certificate.verify(), Ok(()));
+        }
+        let certificate = gen_provable_prime(300, CertificateKind::Pocklington);
+        //Цепочка проходит через сертификаты меньших простых
+        assert!(
+            certificate
+                .factor()
+                .and_then(PrimeCertificate::factor)
+                .is_some()
+        );
+        let json = serde_json::to_string(&certificate).unwrap();
+        let parsed: PrimeCertificate = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, certificate);
+        assert_eq!(parsed.verify(), Ok(()));
+
+        //Подмена числа или свидетеля ломает проверку
+        let mut forged = certificate.clone();
+        forged.n += 2u32;
+        assert!(forged.verify().is_err());
+        let small = PrimeCertificate {
+            n: BigUint::from(561u32),
+            proof: PrimeProof::Small,
+        };
+        assert!(small.verify().is_err());
+        let wrong_witness = PrimeCertificate {
+            n: BigUint::from(23u32),
+            proof: PrimeProof::Pocklington {
+                witness: BigUint::from(22u32),
+                factor: Box::new(PrimeCertificate {
+                    n: BigUint::from(11u32),
+                    proof: PrimeProof::Small,
+                }),
+            },
+        };
+        assert!(wrong_witness.verify().is_err());
+    }
+
+    #[test]
+    fn test_provable_safe_prime() {
+        for bits in [8u64, 128] {
+            let p = gen_provable_safe_prime(bits, CertificateKind::Pocklington);
+            assert_eq!(p.n.bits(), bits);
+            assert_eq!(p.verify(), Ok(()));
+            let q = p.factor().unwrap();
+            assert_eq!(p.n, &q.n * 2u32 + 1u32);
+        }
+    }
+
+    #[test]
+    fn test_pratt_certificate() {
+        for bits in [16u64, 65, 200] {
+            let certificate = gen_provable_prime(bits, CertificateKind::Pratt);
+            assert_eq!(certificate.n.bits(), bits);
+            assert_eq!(verify_certificate(&certificate), Ok(()));
+        }
+        let p = gen_provable_safe_prime(128, CertificateKind::Pratt);
+        assert!(matches!(p.proof, PrimeProof::Pratt { .. }));
+        assert_eq!(verify_certificate(&p), Ok(()));
+        let q = p.factor().unwrap();
+        assert_eq!(p.n, &q.n * 2u32 + 1u32);
+        assert!(matches!(q.proof, PrimeProof::Pratt { .. }));
+
+        //Без одного из делителей n - 1 разложение неполное
+        let json = serde_json::to_string(&p).unwrap();
+        let mut partial: PrimeCertificate = serde_json::from_str(&json).unwrap();
+        if let PrimeProof::Pratt { factors, .. } = &mut partial.proof {
+            factors.retain(|f| f.n != BigUint::from(2u32));
+        }
+        assert!(verify_certificate(&partial).is_err());
+        //23 - 1 = 2 * 11, но 22 = -1 имеет порядок 2, а не 22
+        let wrong = PrimeCertificate {
+            n: BigUint::from(23u32),
+            proof: PrimeProof::Pratt {
+                witness: BigUint::from(22u32),
+                factors: [2u32, 11]
+                    .map(|r| PrimeCertificate {
+                        n: BigUint::from(r),
+                        proof: PrimeProof::Small,
+                    })
+                    .to_vec(),
+            },
+        };
+        assert!(verify_certificate(&wrong).is_err());
+    }
+}
@@ -0,0 +1,331 @@
+//////////////////////////////////////////////////////////////
+// Источники испытания проверяющего. Кроме локальной случайности
+// испытание можно брать из публичного маяка случайности (drand):
+// c = H(домен, раунд, случайность раунда, u, u_t) mod |C|.
+// Случайность раунда публикуется маяком и подписывается, поэтому
+// любой может пересчитать c по номеру раунда из стенограммы и
+// убедиться, что проверяющий его не подбирал. Раунд должен быть
+// опубликован после коммитмента, иначе доказывающий знал бы c заранее
+// и мог бы перебирать u_t, поэтому маяк ждет следующего раунда.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+#[cfg(feature = "server")]
+use std::time::Duration;
+
+use crate::encoding;
+use crate::fiat_shamir::absorb;
+use crate::key_gen;
+use crate::math;
+use crate::message::ProtocolMessage;
+
+//Публичный маяк drand по умолчанию
+pub const DRAND_URL: &str = "https://api.drand.sh";
+
+//Домены хэшей: испытание из маяка и коммитмент испытания
+const BEACON_DOMAIN: &str = "sigma_protocol beacon challenge";
+const COMMIT_DOMAIN: &str = "sigma_protocol challenge commitment";
+//Длина соли коммитмента испытания в битах
+const SALT_BITS: u32 = 256;
+//Опрос маяка в ожидании следующего раунда; период drand до 30 с
+#[cfg(feature = "server")]
+const BEACON_POLL: Duration = Duration::from_secs(1);
+#[cfg(feature = "server")]
+const BEACON_WAIT: Duration = Duration::from_secs(90);
+
+//Выданное испытание; для маяка - и раунд, из которого оно получено
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issued {
+    pub c: BigUint,
+    pub round: Option<BeaconRound>,
+}
+
+pub trait ChallengeProvider {
+    //Испытание из [0, space) для коммитмента (u, u_t)
+    fn issue(
+        &self,
+        space: &BigUint,
+        u: &BigUint,
+        ut: &BigUint,
+    ) -> impl Future<Output = Result<Issued, String>> + Send;
+}
+
+//Локальная случайность проверяющего
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomChallenge;
+
+impl ChallengeProvider for RandomChallenge {
+    async fn issue(&self, space: &BigUint, _u: &BigUint, _ut: &BigUint) -> Result<Issued, String> {
+        Ok(Issued {
+            c: key_gen::random_biguint_mod(space),
+            round: None,
+        })
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Маяк случайности drand
+//////////////////////////////////////////////////////////////
+
+//Раунд маяка: номер и опубликованная случайность в hex
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BeaconRound {
+    pub round: u64,
+    pub randomness: String,
+}
+
+impl BeaconRound {
+    fn randomness_bytes(&self) -> Result<Vec<u8>, String> {
+        let hex = &self.randomness;
+        if hex.is_empty() {
+            return Err(format!("Некорректная случайность раунда {}", self.round));
+        }
+        encoding::from_hex(hex)
+            .map_err(|_| format!("Некорректная случайность раунда {}", self.round))
+    }
+
+    //c = H(домен, раунд, случайность, u, u_t) mod space
+    pub fn challenge(&self, space: &BigUint, u: &BigUint, ut: &BigUint) -> Result<BigUint, String> {
+        let mut hasher = Sha256::new();
+        absorb(&mut hasher, BEACON_DOMAIN.as_bytes());
+        absorb(&mut hasher, &self.round.to_be_bytes());
+        absorb(&mut hasher, &self.randomness_bytes()?);
+        absorb(&mut hasher, &u.to_bytes_be());
+        absorb(&mut hasher, &ut.to_bytes_be());
+        Ok(BigUint::from_bytes_be(&hasher.finalize()) % space)
+    }
+}
+
+#[cfg(feature = "server")]
+#[derive(Debug, Clone)]
+pub struct Beacon {
+    url: String,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "server")]
+impl Beacon {
+    pub fn new(url: &str) -> Self {
+        Beacon {
+            url: url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn fetch(&self, path: &str) -> Result<BeaconRound, String> {
+        let url = format!("{}{}", self.url, path);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| format!("Маяк {} недоступен: {}", url, e))?;
+        if !response.status().is_success() {
+            return Err(format!("{}: HTTP {}", url, response.status()));
+        }
+        response.json().await.map_err(|e| e.to_string())
+    }
+
+    //Последний опубликованный раунд
+    pub async fn latest(&self) -> Result<BeaconRound, String> {
+        self.fetch("/public/latest").await
+    }
+
+    pub async fn round(&self, round: u64) -> Result<BeaconRound, String> {
+        self.fetch(&format!("/public/{}", round)).await
+    }
+
+    //Первый раунд, опубликованный после вызова: будущий раунд маяк
+    //отдает с ошибкой, пока его не опубликует
+    pub async fn next(&self) -> Result<BeaconRound, String> {
+        let next = self.latest().await?.round + 1;
+        let deadline = tokio::time::Instant::now() + BEACON_WAIT;
+        loop {
+            match self.round(next).await {
+                Ok(round) if round.round == next => return Ok(round),
+                Ok(round) => {
+                    return Err(format!("Маяк вернул раунд {} вместо {}", round.round, next));
+                }
+                Err(e) if tokio::time::Instant::now() >= deadline => {
+                    return Err(format!("Раунд {} не опубликован: {}", next, e));
+                }
+                Err(_) => tokio::time::sleep(BEACON_POLL).await,
+            }
+        }
+    }
+
+    //Проверка испытания из стенограммы по раунду, запрошенному у маяка заново
+    pub async fn audit(
+        &self,
+        round: u64,
+        space: &BigUint,
+        u: &BigUint,
+        ut: &BigUint,
+        c: &BigUint,
+    ) -> Result<bool, String> {
+        Ok(self.round(round).await?.challenge(space, u, ut)? == *c)
+    }
+}
+
+#[cfg(feature = "server")]
+impl ChallengeProvider for Beacon {
+    //Коммитмент уже получен: испытание из раунда, опубликованного после него
+    async fn issue(&self, space: &BigUint, u: &BigUint, ut: &BigUint) -> Result<Issued, String> {
+        let round = self.next().await?;
+        Ok(Issued {
+            c: round.challenge(space, u, ut)?,
+            round: Some(round),
+        })
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Испытание с предварительной фиксацией: проверяющий публикует
+// H(s, c) до коммитмента доказывающего и раскрывает c и соль s
+// после него. Доказывающий сверяет раскрытие с коммитментом,
+// поэтому проверяющий не может выбрать c в зависимости от u_t.
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SealedChallenge {
+    pub c: BigUint,
+    pub salt: BigUint,
+}
+
+//H(домен, s, c); соль скрывает c от перебора малого пространства испытаний
+pub fn challenge_digest(c: &BigUint, salt: &BigUint) -> BigUint {
+    let mut hasher = Sha256::new();
+    absorb(&mut hasher, COMMIT_DOMAIN.as_bytes());
+    absorb(&mut hasher, &salt.to_bytes_be());
+    absorb(&mut hasher, &c.to_bytes_be());
+    BigUint::from_bytes_be(&hasher.finalize())
+}
+
+//Проверка доказывающим раскрытого испытания
+pub fn open_challenge(digest: &BigUint, c: &BigUint, salt: &BigUint) -> bool {
+    math::ct_eq(&challenge_digest(c, salt), digest)
+}
+
+impl SealedChallenge {
+    pub fn seal(c: BigUint) -> Self {
+        let salt = key_gen::random_biguint_mod(&(BigUint::from(1u32) << SALT_BITS));
+        SealedChallenge { c, salt }
+    }
+
+    pub fn digest(&self) -> BigUint {
+        challenge_digest(&self.c, &self.salt)
+    }
+
+    pub fn commitment(&self) -> ProtocolMessage {
+        ProtocolMessage::ChallengeCommitment {
+            digest: self.digest(),
+        }
+    }
+
+    pub fn reveal(&self) -> ProtocolMessage {
+        ProtocolMessage::ChallengeReveal {
+            c: self.c.clone(),
+            salt: self.salt.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "server")]
+    use axum::{Json, Router, extract::Path, http::StatusCode, routing::get};
+    #[cfg(feature = "server")]
+    use std::sync::Arc;
+    #[cfg(feature = "server")]
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn round(round: u64) -> BeaconRound {
+        BeaconRound {
+            round,
+            randomness: format!("{:064x}", round * 0x9e37_79b9),
+        }
+    }
+
+    #[test]
+    fn test_beacon_challenge() {
+        let space = BigUint::from(11u32);
+        let (u, ut) = (BigUint::from(6u32), BigUint::from(8u32));
+        let c = round(7).challenge(&space, &u, &ut).unwrap();
+        assert!(c < space);
+        assert_eq!(round(7).challenge(&space, &u, &ut).unwrap(), c);
+
+        //Испытание привязано к коммитменту и раунду
+        let big = BigUint::from(1u32) << 128;
+        let c = round(7).challenge(&big, &u, &ut).unwrap();
+        assert_ne!(round(8).challenge(&big, &u, &ut).unwrap(), c);
+        assert_ne!(round(7).challenge(&big, &ut, &u).unwrap(), c);
+
+        let broken = BeaconRound {
+            round: 7,
+            randomness: "xyz".to_string(),
+        };
+        assert!(broken.challenge(&space, &u, &ut).is_err());
+        let json = r#"{"round":7,"randomness":"ab01","signature":"00"}"#;
+        let parsed: BeaconRound = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.randomness_bytes().unwrap(), [0xab, 0x01]);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_beacon_issue_and_audit() {
+        //Маяк с раундами 1..=5, последний - 5; раунд 6 публикуется
+        //после первого запроса к нему
+        let published = Arc::new(AtomicBool::new(false));
+        let app = Router::new()
+            .route("/public/latest", get(|| async { Json(round(5)) }))
+            .route(
+                "/public/{round}",
+                get(|Path(n): Path<u64>| async move {
+                    match n <= 5 || published.swap(true, Ordering::Relaxed) {
+                        true => Ok(Json(round(n))),
+                        false => Err(StatusCode::NOT_FOUND),
+                    }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let beacon = Beacon::new(&format!("http://{}/", addr));
+        let space = BigUint::from(1u32) << 64;
+        let (u, ut) = (BigUint::from(6u32), BigUint::from(8u32));
+        //Уже опубликованный раунд 5 доказывающий мог знать до коммитмента
+        let issued = beacon.issue(&space, &u, &ut).await.unwrap();
+        assert_eq!(issued.round, Some(round(6)));
+        assert!(beacon.audit(6, &space, &u, &ut, &issued.c).await.unwrap());
+        assert!(!beacon.audit(5, &space, &u, &ut, &issued.c).await.unwrap());
+
+        let local = RandomChallenge.issue(&space, &u, &ut).await.unwrap();
+        assert!(local.c < space && local.round.is_none());
+        assert!(Beacon::new("http://127.0.0.1:1").latest().await.is_err());
+    }
+
+    #[test]
+    fn test_sealed_challenge_opens() {
+        let sealed = SealedChallenge::seal(BigUint::from(1u32));
+        let digest = sealed.digest();
+        assert!(open_challenge(&digest, &sealed.c, &sealed.salt));
+        //Подменить испытание или соль после фиксации нельзя
+        assert!(!open_challenge(&digest, &BigUint::from(0u32), &sealed.salt));
+        assert!(!open_challenge(&digest, &sealed.c, &(&sealed.salt + 1u32)));
+        //Соль случайна: одинаковые испытания дают разные коммитменты
+        let other = SealedChallenge::seal(BigUint::from(1u32));
+        assert_ne!(other.digest(), digest);
+        assert_eq!(
+            sealed.reveal(),
+            ProtocolMessage::ChallengeReveal {
+                c: sealed.c.clone(),
+                salt: sealed.salt.clone()
+            }
+        );
+    }
+}
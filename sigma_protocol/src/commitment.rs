@@ -0,0 +1,251 @@
+use num_bigint::{BigUint, ToBigInt};
+
+use crate::key_gen;
+use crate::math;
+
+//Пара образующих для коммитмента Педерсена g^value * h^blinding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generators {
+    pub g: BigUint,
+    pub h: BigUint,
+}
+
+impl Generators {
+    pub fn new(g: BigUint, h: BigUint) -> Self {
+        Generators { g, h }
+    }
+}
+
+//Вычисление g^a * h^b mod modulus
+fn pow2(gens: &Generators, a: &BigUint, b: &BigUint, modulus: &BigUint) -> Option<BigUint> {
+    math::mod_multi_pow(&gens.g, a, &gens.h, b, modulus)
+}
+
+pub fn commit(
+    gens: &Generators,
+    value: &BigUint,
+    blinding: &BigUint,
+    modulus: &BigUint,
+) -> Option<BigUint> {
+    pow2(gens, value, blinding, modulus)
+}
+
+//Раскрытие коммитмента: значение и ослепляющий множитель
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opening {
+    pub value: BigUint,
+    pub blinding: BigUint,
+}
+
+impl Opening {
+    pub fn new(value: BigUint, blinding: BigUint) -> Self {
+        Opening { value, blinding }
+    }
+
+    pub fn random(value: BigUint, order: &BigUint) -> Self {
+        Opening::new(value, key_gen::random_biguint_mod(order))
+    }
+
+    //Раскрытие произведения коммитментов: (v1 + v2, r1 + r2)
+    pub fn add(&self, other: &Opening, order: &BigUint) -> Opening {
+        Opening {
+            value: (&self.value + &other.value) % order,
+            blinding: (&self.blinding + &other.blinding) % order,
+        }
+    }
+}
+
+//Проверка, что commitment = g^value * h^blinding
+pub fn open(gens: &Generators, commitment: &BigUint, opening: &Opening, modulus: &BigUint) -> bool {
+    *commitment > BigUint::ZERO
+        && commitment < modulus
+        && commit(gens, &opening.value, &opening.blinding, modulus).as_ref() == Some(commitment)
+}
+
+//Гомоморфное сложение: C1 * C2 = g^(v1+v2) * h^(r1+r2)
+pub fn add(c1: &BigUint, c2: &BigUint, modulus: &BigUint) -> BigUint {
+    c1 * c2 % modulus
+}
+
+//////////////////////////////////////////////////////////////
+// Доказательство равенства значений двух коммитментов
+// C1 = g1^x h1^r1, C2 = g2^x h2^r2
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct EqualityStatement {
+    pub gens1: Generators,
+    pub gens2: Generators,
+    pub c1: BigUint,
+    pub c2: BigUint,
+}
+
+#[derive(Debug, Clone)]
+pub struct EqualityWitness {
+    pub value: BigUint,
+    pub r1: BigUint,
+    pub r2: BigUint,
+}
+
+#[derive(Debug, Clone)]
+pub struct EqualityNonce {
+    t: BigUint,
+    s1: BigUint,
+    s2: BigUint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EqualityCommitment {
+    pub t1: BigUint,
+    pub t2: BigUint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EqualityResponse {
+    pub z: BigUint,
+    pub z1: BigUint,
+    pub z2: BigUint,
+}
+
+//Первый шаг доказывающего: T1 = g1^t h1^s1, T2 = g2^t h2^s2
+pub fn equality_commit(
+    statement: &EqualityStatement,
+    modulus: &BigUint,
+    order: &BigUint,
+) -> Option<(EqualityNonce, EqualityCommitment)> {
+    let nonce = EqualityNonce {
+        t: key_gen::random_biguint_mod(order),
+        s1: key_gen::random_biguint_mod(order),
+        s2: key_gen::random_biguint_mod(order),
+    };
+    let commitment = EqualityCommitment {
+        t1: pow2(&statement.gens1, &nonce.t, &nonce.s1, modulus)?,
+        t2: pow2(&statement.gens2, &nonce.t, &nonce.s2, modulus)?,
+    };
+    Some((nonce, commitment))
+}
+
+//Ответ на испытание c: z = t + c*x, z1 = s1 + c*r1, z2 = s2 + c*r2 (mod order)
+pub fn equality_respond(
+    witness: &EqualityWitness,
+    nonce: &EqualityNonce,
+    c: &BigUint,
+    order: &BigUint,
+) -> EqualityResponse {
+    EqualityResponse {
+        z: (&nonce.t + &witness.value * c) % order,
+        z1: (&nonce.s1 + &witness.r1 * c) % order,
+        z2: (&nonce.s2 + &witness.r2 * c) % order,
+    }
+}
+
+//Проверка: g1^z h1^z1 == T1 * C1^c и g2^z h2^z2 == T2 * C2^c
+pub fn equality_verify(
+    statement: &EqualityStatement,
+    commitment: &EqualityCommitment,
+    c: &BigUint,
+    response: &EqualityResponse,
+    modulus: &BigUint,
+) -> bool {
+    let check = |gens: &Generators, com: &BigUint, t: &BigUint, z_blind: &BigUint| {
+        let left = pow2(gens, &response.z, z_blind, modulus)?;
+        let right = t * math::mod_pow_big(com, &c.to_bigint()?, modulus).ok()? % modulus;
+        Some(left == right)
+    };
+    check(
+        &statement.gens1,
+        &statement.c1,
+        &commitment.t1,
+        &response.z1,
+    )
+    .unwrap_or(false)
+        && check(
+            &statement.gens2,
+            &statement.c2,
+            &commitment.t2,
+            &response.z2,
+        )
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Подгруппа порядка 11 в Z_23^*
+    fn params() -> (BigUint, BigUint, Generators, Generators) {
+        let p = BigUint::from(23u32);
+        let q = BigUint::from(11u32);
+        let gens1 = Generators::new(BigUint::from(4u32), BigUint::from(9u32));
+        let gens2 = Generators::new(BigUint::from(2u32), BigUint::from(3u32));
+        (p, q, gens1, gens2)
+    }
+
+    #[test]
+    fn test_commit_homomorphic_exponents() {
+        let (p, _, gens, _) = params();
+        let c = commit(&gens, &BigUint::from(3u32), &BigUint::from(5u32), &p).unwrap();
+        let expected = BigUint::from(4u32).pow(3) * BigUint::from(9u32).pow(5) % &p;
+        assert_eq!(c, expected);
+    }
+
+    #[test]
+    fn test_open_and_add() {
+        let (p, q, gens, _) = params();
+        let a = Opening::new(BigUint::from(3u32), BigUint::from(5u32));
+        let b = Opening::new(BigUint::from(9u32), BigUint::from(8u32));
+        let ca = commit(&gens, &a.value, &a.blinding, &p).unwrap();
+        let cb = commit(&gens, &b.value, &b.blinding, &p).unwrap();
+        assert!(open(&gens, &ca, &a, &p));
+        assert!(!open(&gens, &ca, &b, &p));
+
+        //Сумма раскрывается суммой значений и ослепляющих множителей по модулю порядка
+        let sum = add(&ca, &cb, &p);
+        assert!(open(&gens, &sum, &a.add(&b, &q), &p));
+        assert_eq!(a.add(&b, &q).value, BigUint::from(1u32));
+    }
+
+    #[test]
+    fn test_equality_proof_accepts_same_value() {
+        let (p, q, gens1, gens2) = params();
+        let witness = EqualityWitness {
+            value: BigUint::from(7u32),
+            r1: BigUint::from(3u32),
+            r2: BigUint::from(10u32),
+        };
+        let statement = EqualityStatement {
+            c1: commit(&gens1, &witness.value, &witness.r1, &p).unwrap(),
+            c2: commit(&gens2, &witness.value, &witness.r2, &p).unwrap(),
+            gens1,
+            gens2,
+        };
+
+        for ch in 0u32..11 {
+            let (nonce, com) = equality_commit(&statement, &p, &q).unwrap();
+            let c = BigUint::from(ch);
+            let resp = equality_respond(&witness, &nonce, &c, &q);
+            assert!(equality_verify(&statement, &com, &c, &resp, &p));
+        }
+    }
+
+    #[test]
+    fn test_equality_proof_rejects_different_values() {
+        let (p, q, gens1, _) = params();
+        let witness = EqualityWitness {
+            value: BigUint::from(7u32),
+            r1: BigUint::from(3u32),
+            r2: BigUint::from(3u32),
+        };
+        let statement = EqualityStatement {
+            c1: commit(&gens1, &witness.value, &witness.r1, &p).unwrap(),
+            c2: commit(&gens1, &BigUint::from(8u32), &witness.r2, &p).unwrap(),
+            gens2: gens1.clone(),
+            gens1,
+        };
+
+        let (nonce, com) = equality_commit(&statement, &p, &q).unwrap();
+        let c = BigUint::from(5u32);
+        let resp = equality_respond(&witness, &nonce, &c, &q);
+        assert!(!equality_verify(&statement, &com, &c, &resp, &p));
+    }
+}
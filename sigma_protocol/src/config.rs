@@ -1,11 +1,204 @@
+#[cfg(feature = "server")]
+use clap::ValueEnum;
+use num_bigint::BigUint;
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::time::Duration;
+
+use crate::auth::RegisterRequest;
+use crate::certificate::CertificateKind;
+use crate::challenge;
+use crate::consensus;
+use crate::health::RngCheck;
+use crate::key_gen::{self, PrimeKind};
+use crate::nonce::NonceMode;
+use crate::oracle::HashKind;
+use crate::protocol::ProofKind;
+use crate::strength;
+use crate::threshold::Share;
+
+//Длина p, генерируемого при запуске, если prime_bits не задана: около
+//112 бит стойкости по NIST SP 800-57
+pub const DEFAULT_PRIME_BITS: u64 = 2048;
+//Сколько внешний доказывающий может молчать между шагами протокола
+pub const DEFAULT_SESSION_TIMEOUT_SECS: u64 = 60;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
     name: String,
     address: Address,
     second_server: Address,
+    //Все остальные серверы для протоколов с несколькими участниками;
+    //по умолчанию только second_server
+    #[serde(default)]
+    peers: Vec<Address>,
+    #[serde(default)]
+    retry: RetryPolicy,
+    #[serde(default)]
+    codec: Codec,
+    #[serde(default)]
+    challenge: ChallengeMode,
+    //Пространство испытаний [0, 2^challenge_bits); по умолчанию [0, q)
+    challenge_bits: Option<u32>,
+    //Адрес маяка случайности для challenge: beacon; по умолчанию drand
+    beacon: Option<String>,
+    //Проверяющий фиксирует испытание хэшем с солью до коммитмента u_t
+    //и раскрывает после; с испытанием из хэша u_t несовместимо
+    #[serde(default)]
+    challenge_commitment: bool,
+    //Доказываемое утверждение: okamoto или schnorr
+    #[serde(default)]
+    protocol: ProofKind,
+    //Группа, в которой выполняется протокол
+    #[serde(default)]
+    group: GroupKind,
+    //Длина безопасного простого p для генерации параметров при запуске;
+    //по умолчанию DEFAULT_PRIME_BITS. Параметры у каждого процесса свои,
+    //поэтому сторонам в разных процессах нужны общая группа или params_file
+    prime_bits: Option<u64>,
+    //Учебные параметры (подгруппа порядка 11 в Z_23^*) и фиксированное
+    //испытание - только для демонстрации; с prime_bits и params_file
+    //несовместимы
+    #[serde(default)]
+    toy_params: bool,
+    //Порог стойкости параметров в битах, ниже которого сервер
+    //предупреждает; по умолчанию 112
+    min_security_bits: Option<u32>,
+    //Раунды Миллера-Рабина при поиске простых; по умолчанию 8
+    mr_rounds: Option<u8>,
+    //Предел времени поиска простого при запуске и в /start/primes, в
+    //секундах; без него поиск идет до результата
+    prime_timeout_secs: Option<u64>,
+    //Файл параметров группы от gen-params вместо генерации при запуске;
+    //с prime_bits несовместим
+    params_file: Option<String>,
+    //Пул параметров, заранее сгенерированных в фоне для /start/primes
+    params_pool: Option<PoolConfig>,
+    //Длина секретных показателей доказывающего в битах; по умолчанию
+    //секрет равномерен по всей группе
+    secret_bits: Option<u64>,
+    //Генерировать p с сертификатом Поклингтона вместо теста Миллера-Рабина
+    #[serde(default)]
+    provable_primes: bool,
+    //Вид сертификата простоты: pocklington или pratt
+    #[serde(default)]
+    certificate: CertificateKind,
+    //Простые множители модуля FFS: safe или strong (Гордон)
+    #[serde(default)]
+    ffs_primes: PrimeKind,
+    //Реакция на проваленную самопроверку генератора при запуске:
+    //refuse (не запускаться) или warn
+    #[serde(default)]
+    rng_check: RngCheck,
+    //Одноразовые ключи доказывающего: random или deterministic (RFC 6979)
+    #[serde(default)]
+    nonces: NonceMode,
+    //Хэш-функция испытаний Фиата-Шамира: sha256, sha3_256 или blake3
+    #[serde(default)]
+    hash: HashKind,
+    //Число независимых раундов в одной сессии
+    #[serde(default = "default_rounds")]
+    rounds: u32,
+    //Предел ожидания следующего шага внешнего доказывающего, в секундах
+    session_timeout_secs: Option<u64>,
+    //Файл долговременного ключа доказывающего; создается при первом запуске.
+    //Без него ключ новый в каждой сессии
+    key_file: Option<String>,
+    //Новый key_file шифруется паролем; пароль берется из переменной
+    //SIGMA_KEY_PASSWORD или запрашивается при запуске
+    #[serde(default)]
+    encrypt_key: bool,
+    //Соль в hex: ключ доказывающего выводится из парольной фразы
+    //(SIGMA_KEY_PASSPHRASE или запрос при запуске) вместо key_file
+    key_salt: Option<String>,
+    //Файл для журнала событий в формате JSON Lines
+    event_log: Option<String>,
+    //Доля секрета порогового доказывающего
+    threshold: Option<ThresholdConfig>,
+    //Проверяющие для подтверждения доказательства кворумом
+    #[serde(default)]
+    verifiers: Vec<Address>,
+    //Число подтверждений; по умолчанию большинство проверяющих
+    quorum: Option<usize>,
+    //Серверы, подписывающие совместно с этим по схеме MuSig
+    #[serde(default)]
+    cosigners: Vec<Address>,
+    //Администраторы и их открытые ключи u = g^x: только они входят в
+    //служебные эндпоинты и регистрируют других пользователей
+    #[serde(default)]
+    admins: Vec<RegisterRequest>,
+}
+
+//Сколько наборов параметров держать в запасе и длина их p
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct PoolConfig {
+    pub size: usize,
+    pub bits: u64,
+}
+
+//Доля секрета, порог t и адреса остальных участников
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ThresholdConfig {
+    pub share: Share,
+    pub threshold: usize,
+    #[serde(default)]
+    pub peers: Vec<Address>,
+}
+
+//Формат сообщений между серверами
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Codec {
+    Json,
+    #[default]
+    Binary,
+}
+
+//Способ получения испытания в демонстрации
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChallengeMode {
+    //Испытание проверяющего в демонстрации: фиксированное C с
+    //--toy-params, иначе случайное
+    #[default]
+    Interactive,
+    //Случайное испытание из [0, q)
+    Random,
+    //Испытание - хэш параметров и коммитмента (Фиат-Шамир)
+    FiatShamir,
+    //Испытание из публичного маяка случайности; раунд попадает в стенограмму
+    Beacon,
+}
+
+//Группа протокола: вычеты по модулю q из параметров сервера,
+//стандартная группа MODP (RFC 3526) или кривая
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "server", derive(ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum GroupKind {
+    #[default]
+    Modular,
+    Modp2048,
+    Modp3072,
+    Modp4096,
+    Ristretto255,
+    Secp256k1,
+}
+
+impl GroupKind {
+    //Длина модуля для групп MODP
+    pub fn modp_bits(self) -> Option<u32> {
+        match self {
+            GroupKind::Modp2048 => Some(2048),
+            GroupKind::Modp3072 => Some(3072),
+            GroupKind::Modp4096 => Some(4096),
+            _ => None,
+        }
+    }
+}
+
+fn default_rounds() -> u32 {
+    1
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -14,15 +207,221 @@ pub struct Address {
     port: Option<u32>,
 }
 
+//Параметры повторной отправки сообщений второму серверу
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            base_delay_ms: 200,
+            max_delay_ms: 5000,
+        }
+    }
+}
+
+//Длина не меньше min бит
+pub fn check_bits(name: &str, bits: u64, min: u64) -> Result<(), String> {
+    match bits < min {
+        true => Err(format!("{} = {}: нужно не меньше {} бит", name, bits, min)),
+        false => Ok(()),
+    }
+}
+
 impl Config {
     pub fn load(path: &str) -> Result<Self, std::io::Error> {
         let json_content = fs::read_to_string(path)?;
         let config: Config = serde_json::from_str(&json_content)?;
+        config
+            .validate()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
         Ok(config)
     }
+    //Длины из командной строки вместо указанных в файле
+    //Флаг --toy-params заменяет параметры из конфигурации учебными
+    pub fn with_toy_params(mut self, toy: bool) -> Self {
+        if toy {
+            self.toy_params = true;
+            self.prime_bits = None;
+            self.params_file = None;
+        }
+        self
+    }
+    pub fn with_bits(
+        mut self,
+        prime_bits: Option<u64>,
+        secret_bits: Option<u64>,
+    ) -> Result<Self, String> {
+        //Длина из командной строки важнее файла параметров конфигурации
+        if prime_bits.is_some() {
+            self.params_file = None;
+        }
+        self.prime_bits = prime_bits.or(self.prime_bits);
+        self.secret_bits = secret_bits.or(self.secret_bits);
+        self.validate()?;
+        Ok(self)
+    }
+    fn validate(&self) -> Result<(), String> {
+        if self.prime_bits.is_some() && self.params_file.is_some() {
+            return Err("prime_bits и params_file взаимоисключающие".to_string());
+        }
+        if self.toy_params && (self.prime_bits.is_some() || self.params_file.is_some()) {
+            return Err("toy_params несовместим с prime_bits и params_file".to_string());
+        }
+        if let Some(bits) = self.prime_bits {
+            check_bits("prime_bits", bits, key_gen::MIN_PRIME_BITS)?;
+        }
+        if let Some(rounds) = self.mr_rounds
+            && !(1..=key_gen::MAX_MR_ROUNDS).contains(&rounds)
+        {
+            return Err(format!(
+                "mr_rounds = {}: допустимо от 1 до {}",
+                rounds,
+                key_gen::MAX_MR_ROUNDS
+            ));
+        }
+        if let Some(pool) = self.params_pool {
+            check_bits("params_pool.bits", pool.bits, key_gen::MIN_PRIME_BITS)?;
+            if pool.size == 0 {
+                return Err("params_pool.size должен быть больше 0".to_string());
+            }
+        }
+        if let Some(bits) = self.secret_bits {
+            check_bits("secret_bits", bits, key_gen::MIN_SECRET_BITS)?;
+        }
+        if self.session_timeout_secs == Some(0) {
+            return Err("session_timeout_secs должен быть больше 0".to_string());
+        }
+        Ok(())
+    }
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
     pub fn get_address(&self) -> String {
         self.address.get()
     }
+    pub fn get_second_server(&self) -> &Address {
+        &self.second_server
+    }
+    pub fn get_own_address(&self) -> &Address {
+        &self.address
+    }
+    pub fn get_peers(&self) -> Vec<Address> {
+        match self.peers.is_empty() {
+            true => vec![self.second_server.clone()],
+            false => self.peers.clone(),
+        }
+    }
+    pub fn get_retry(&self) -> &RetryPolicy {
+        &self.retry
+    }
+    pub fn get_codec(&self) -> Codec {
+        self.codec
+    }
+    pub fn get_challenge(&self) -> ChallengeMode {
+        self.challenge
+    }
+    pub fn get_challenge_space(&self) -> Option<BigUint> {
+        self.challenge_bits.map(|bits| BigUint::from(1u32) << bits)
+    }
+    pub fn get_challenge_commitment(&self) -> bool {
+        self.challenge_commitment
+    }
+    pub fn get_beacon(&self) -> &str {
+        self.beacon.as_deref().unwrap_or(challenge::DRAND_URL)
+    }
+    pub fn get_protocol(&self) -> ProofKind {
+        self.protocol
+    }
+    pub fn get_group(&self) -> GroupKind {
+        self.group
+    }
+    pub fn get_rng_check(&self) -> RngCheck {
+        self.rng_check
+    }
+    pub fn get_nonces(&self) -> NonceMode {
+        self.nonces
+    }
+    pub fn get_hash(&self) -> HashKind {
+        self.hash
+    }
+    pub fn get_rounds(&self) -> u32 {
+        self.rounds.max(1)
+    }
+    pub fn get_session_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.session_timeout_secs
+                .unwrap_or(DEFAULT_SESSION_TIMEOUT_SECS),
+        )
+    }
+    pub fn get_prime_bits(&self) -> u64 {
+        self.prime_bits.unwrap_or(DEFAULT_PRIME_BITS)
+    }
+    pub fn get_toy_params(&self) -> bool {
+        self.toy_params
+    }
+    pub fn get_min_security_bits(&self) -> u32 {
+        self.min_security_bits
+            .unwrap_or(strength::DEFAULT_MIN_SECURITY_BITS)
+    }
+    pub fn get_mr_rounds(&self) -> u8 {
+        self.mr_rounds.unwrap_or(key_gen::DEFAULT_MR_ROUNDS)
+    }
+    pub fn get_prime_timeout(&self) -> Option<Duration> {
+        self.prime_timeout_secs.map(Duration::from_secs)
+    }
+    pub fn get_params_file(&self) -> Option<&str> {
+        self.params_file.as_deref()
+    }
+    pub fn get_params_pool(&self) -> Option<PoolConfig> {
+        self.params_pool
+    }
+    pub fn get_secret_bits(&self) -> Option<u64> {
+        self.secret_bits
+    }
+    pub fn get_provable_primes(&self) -> bool {
+        self.provable_primes
+    }
+    pub fn get_certificate(&self) -> CertificateKind {
+        self.certificate
+    }
+    pub fn get_ffs_primes(&self) -> PrimeKind {
+        self.ffs_primes
+    }
+    pub fn get_key_file(&self) -> Option<&str> {
+        self.key_file.as_deref()
+    }
+    pub fn get_encrypt_key(&self) -> bool {
+        self.encrypt_key
+    }
+    pub fn get_key_salt(&self) -> Option<&str> {
+        self.key_salt.as_deref()
+    }
+    pub fn get_event_log(&self) -> Option<&str> {
+        self.event_log.as_deref()
+    }
+    pub fn get_threshold(&self) -> Option<&ThresholdConfig> {
+        self.threshold.as_ref()
+    }
+    pub fn get_verifiers(&self) -> &[Address] {
+        &self.verifiers
+    }
+    pub fn get_quorum(&self) -> usize {
+        self.quorum
+            .unwrap_or_else(|| consensus::majority(self.verifiers.len()))
+    }
+    pub fn get_cosigners(&self) -> &[Address] {
+        &self.cosigners
+    }
+    pub fn get_admins(&self) -> &[RegisterRequest] {
+        &self.admins
+    }
 }
 
 impl Address {
@@ -0,0 +1,122 @@
+//////////////////////////////////////////////////////////////
+// Проверка доказательства несколькими серверами: доказательство
+// принимается, только если его подтвердил кворум проверяющих.
+//////////////////////////////////////////////////////////////
+
+#[cfg(feature = "server")]
+use futures_util::future::join_all;
+#[cfg(feature = "server")]
+use serde::Deserialize;
+
+#[cfg(feature = "server")]
+use crate::config::Address;
+#[cfg(feature = "server")]
+use crate::protocol::Proof;
+
+pub const VERIFY_PATH: &str = "/verify/fiat_shamir";
+
+//Решение одного проверяющего; None - сервер не ответил
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vote {
+    pub verifier: String,
+    pub accepted: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decision {
+    pub votes: Vec<Vote>,
+    pub quorum: usize,
+    pub accepted: bool,
+}
+
+#[cfg(feature = "server")]
+#[derive(Deserialize)]
+struct VerifyReport {
+    valid: bool,
+}
+
+//Кворум по умолчанию - большинство
+pub fn majority(verifiers: usize) -> usize {
+    verifiers / 2 + 1
+}
+
+//Итог по голосам: не ответившие серверы считаются отказавшими
+pub fn decide(votes: Vec<Vote>, quorum: usize) -> Decision {
+    let approvals = votes.iter().filter(|v| v.accepted == Some(true)).count();
+    Decision {
+        accepted: quorum > 0 && approvals >= quorum,
+        votes,
+        quorum,
+    }
+}
+
+#[cfg(feature = "server")]
+async fn ask(client: &reqwest::Client, verifier: &str, proof: &Proof) -> Result<bool, String> {
+    let url = format!("http://{}{}", verifier, VERIFY_PATH);
+    let response = client
+        .post(&url)
+        .json(proof)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    let report: VerifyReport = response.json().await.map_err(|e| e.to_string())?;
+    Ok(report.valid)
+}
+
+//Доказательство рассылается всем проверяющим одновременно
+#[cfg(feature = "server")]
+pub async fn verify(
+    client: &reqwest::Client,
+    verifiers: &[Address],
+    quorum: usize,
+    proof: &Proof,
+) -> Decision {
+    let votes = join_all(verifiers.iter().map(|address| async move {
+        let verifier = address.get();
+        let accepted = ask(client, &verifier, proof)
+            .await
+            .inspect_err(|e| tracing::warn!("Проверяющий {} не ответил: {}", verifier, e))
+            .ok();
+        Vote { verifier, accepted }
+    }))
+    .await;
+    decide(votes, quorum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vote(accepted: Option<bool>) -> Vote {
+        Vote {
+            verifier: "v".to_string(),
+            accepted,
+        }
+    }
+
+    #[test]
+    fn test_quorum() {
+        assert_eq!(majority(3), 2);
+        assert_eq!(majority(4), 3);
+        let votes = vec![vote(Some(true)), vote(None), vote(Some(true))];
+        assert!(decide(votes.clone(), 2).accepted);
+        assert!(!decide(votes, 3).accepted);
+        assert!(!decide(vec![vote(Some(false)), vote(Some(true))], 2).accepted);
+        assert!(!decide(Vec::new(), 0).accepted);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_unreachable_verifiers_reject() {
+        let verifiers: Vec<Address> = serde_json::from_str(r#"[{"port":1},{"port":2}]"#).unwrap();
+        let proof: Proof =
+            serde_json::from_str(r#"{"u":"1","ut":"1","c":"1","alpha":"1"}"#).unwrap();
+        let decision = verify(&reqwest::Client::new(), &verifiers, 1, &proof).await;
+        assert!(!decision.accepted);
+        assert_eq!(decision.votes.len(), 2);
+        assert!(decision.votes.iter().all(|v| v.accepted.is_none()));
+    }
+}
@@ -0,0 +1,498 @@
+//////////////////////////////////////////////////////////////
+// Анонимное удостоверение с выборочным раскрытием. Сервер выдает
+// коммитмент Педерсена к атрибутам C = h^r * П g_i^a_i и подписывает
+// его. Владелец предъявляет C с подписью, раскрывает часть атрибутов
+// и доказывает, что знает остальные, а один скрытый атрибут лежит
+// в открытом множестве значений. Доказательство - дизъюнкция
+// (по значениям множества) доказательств представления C, сделанная
+// неинтерактивной по Фиату-Шамиру.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::fiat_shamir::absorb;
+use crate::group::{Group, Setting};
+use crate::message::{decimal, decimal_vec};
+
+//Домены хэшей: вывод образующих атрибутов, подпись и предъявление
+const BASE_DOMAIN: &str = "sigma_protocol credential base";
+const SIGN_DOMAIN: &str = "sigma_protocol credential signature";
+const PROOF_DOMAIN: &str = "sigma_protocol credential proof";
+
+//Образующие g_1..g_n, логарифмы которых никому не известны
+pub fn bases<G: Group>(setting: &Setting<G>, count: usize) -> Vec<G::Element> {
+    let group = &setting.group;
+    (0..count as u32)
+        .map(|i| {
+            (0u32..)
+                .map(|counter| {
+                    let mut seed = BASE_DOMAIN.as_bytes().to_vec();
+                    seed.extend(group.describe());
+                    seed.extend(i.to_be_bytes());
+                    seed.extend(counter.to_be_bytes());
+                    group.hash_to_element(&seed)
+                })
+                .find(|b| group.contains(b) && *b != group.identity())
+                .expect("хэш в группу с бесконечным счетчиком")
+        })
+        .collect()
+}
+
+//П bases_i^exponents_i
+fn multi_exp<G: Group>(group: &G, bases: &[G::Element], exponents: &[BigUint]) -> G::Element {
+    bases
+        .iter()
+        .zip(exponents)
+        .fold(group.identity(), |acc, (b, e)| {
+            group.mul(&acc, &group.exp(b, e))
+        })
+}
+
+//-x mod q
+fn negate(x: &BigUint, order: &BigUint) -> BigUint {
+    (order - x % order) % order
+}
+
+//Подпись Шнорра сервера на коммитменте
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssuerSignature {
+    #[serde(with = "decimal")]
+    pub r: BigUint,
+    #[serde(with = "decimal")]
+    pub s: BigUint,
+}
+
+//Удостоверение у владельца: атрибуты, ослепляющий множитель и подпись
+#[derive(Debug, Clone)]
+pub struct Credential {
+    pub attributes: Vec<BigUint>,
+    pub blinding: BigUint,
+    pub commitment: BigUint,
+    pub signature: IssuerSignature,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Disclosed {
+    pub index: usize,
+    #[serde(with = "decimal")]
+    pub value: BigUint,
+}
+
+//Утверждение "атрибут index принимает одно из значений values"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SetPredicate {
+    pub index: usize,
+    #[serde(with = "decimal_vec")]
+    pub values: Vec<BigUint>,
+}
+
+//Ветвь дизъюнкции: испытание и ответы для скрытых атрибутов и r
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Branch {
+    #[serde(with = "decimal")]
+    pub c: BigUint,
+    #[serde(with = "decimal_vec")]
+    pub responses: Vec<BigUint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Presentation {
+    #[serde(with = "decimal")]
+    pub commitment: BigUint,
+    pub signature: IssuerSignature,
+    pub disclosed: Vec<Disclosed>,
+    pub predicate: SetPredicate,
+    pub branches: Vec<Branch>,
+}
+
+//////////////////////////////////////////////////////////////
+// Выдача удостоверения
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct Issuer<G: Group> {
+    setting: Setting<G>,
+    bases: Vec<G::Element>,
+    x: BigUint,
+}
+
+impl<G: Group> Issuer<G> {
+    pub fn random(setting: Setting<G>, attributes: usize) -> Self {
+        let x = setting.group.random_scalar();
+        Issuer {
+            bases: bases(&setting, attributes),
+            setting,
+            x,
+        }
+    }
+
+    pub fn public_key(&self) -> G::Element {
+        self.setting.group.exp(&self.setting.g, &self.x)
+    }
+
+    //C = h^r * П g_i^a_i и подпись на C
+    pub fn issue(&self, attributes: Vec<BigUint>) -> Result<Credential, String> {
+        if attributes.len() != self.bases.len() {
+            return Err(format!("Ожидалось атрибутов: {}", self.bases.len()));
+        }
+        let group = &self.setting.group;
+        let order = self.setting.order();
+        let attributes: Vec<BigUint> = attributes.iter().map(|a| a % &order).collect();
+        let blinding = group.random_scalar();
+        let c = group.mul(
+            &group.exp(&self.setting.h, &blinding),
+            &multi_exp(group, &self.bases, &attributes),
+        );
+        let commitment = group.encode(&c);
+
+        let k = group.random_scalar();
+        let r = group.encode(&group.exp(&self.setting.g, &k));
+        let y = group.encode(&self.public_key());
+        let e = signature_challenge(&self.setting, &r, &y, &commitment);
+        let signature = IssuerSignature {
+            s: (k + e * &self.x) % &order,
+            r,
+        };
+        Ok(Credential {
+            attributes,
+            blinding,
+            commitment,
+            signature,
+        })
+    }
+}
+
+fn signature_challenge<G: Group>(
+    setting: &Setting<G>,
+    r: &BigUint,
+    y: &BigUint,
+    commitment: &BigUint,
+) -> BigUint {
+    let mut hasher = Sha256::new();
+    absorb(&mut hasher, SIGN_DOMAIN.as_bytes());
+    absorb(&mut hasher, &setting.group.describe());
+    for x in [r, y, commitment] {
+        absorb(&mut hasher, &x.to_bytes_be());
+    }
+    BigUint::from_bytes_be(&hasher.finalize()) % setting.order()
+}
+
+//g^s == R * y^e
+fn signature_valid<G: Group>(
+    setting: &Setting<G>,
+    y: &BigUint,
+    commitment: &BigUint,
+    signature: &IssuerSignature,
+) -> bool {
+    let group = &setting.group;
+    let (Some(public), Some(r)) = (group.decode(y), group.decode(&signature.r)) else {
+        return false;
+    };
+    let e = signature_challenge(setting, &signature.r, y, commitment);
+    group.exp(&setting.g, &signature.s) == group.mul(&r, &group.exp(&public, &e))
+}
+
+//////////////////////////////////////////////////////////////
+// Предъявление и проверка
+//////////////////////////////////////////////////////////////
+
+//Номера скрытых атрибутов без атрибута предиката
+fn hidden(count: usize, disclosed: &[Disclosed], predicate: usize) -> Vec<usize> {
+    (0..count)
+        .filter(|i| *i != predicate && disclosed.iter().all(|d| d.index != *i))
+        .collect()
+}
+
+//Открытые данные одной проверки: образующие скрытых частей и X_v для
+//каждого значения предиката, X_v = C * П g_d^-a_d * g_j^-v
+struct Statement<G: Group> {
+    bases: Vec<G::Element>,
+    targets: Vec<G::Element>,
+}
+
+fn statement<G: Group>(
+    setting: &Setting<G>,
+    count: usize,
+    commitment: &G::Element,
+    disclosed: &[Disclosed],
+    predicate: &SetPredicate,
+) -> Result<Statement<G>, String> {
+    let group = &setting.group;
+    let order = setting.order();
+    let all = bases(setting, count);
+    if predicate.index >= count || predicate.values.is_empty() {
+        return Err("Неверный предикат".to_string());
+    }
+    let mut seen = vec![false; count];
+    for d in disclosed {
+        if d.index >= count || d.index == predicate.index || seen[d.index] {
+            return Err(format!(
+                "Неверный номер раскрываемого атрибута: {}",
+                d.index
+            ));
+        }
+        seen[d.index] = true;
+    }
+    let open = disclosed.iter().fold(commitment.clone(), |acc, d| {
+        group.mul(&acc, &group.exp(&all[d.index], &negate(&d.value, &order)))
+    });
+    let targets = predicate
+        .values
+        .iter()
+        .map(|v| group.mul(&open, &group.exp(&all[predicate.index], &negate(v, &order))))
+        .collect();
+    let mut bases: Vec<G::Element> = hidden(count, disclosed, predicate.index)
+        .into_iter()
+        .map(|i| all[i].clone())
+        .collect();
+    bases.push(setting.h.clone());
+    Ok(Statement { bases, targets })
+}
+
+fn proof_challenge<G: Group>(
+    setting: &Setting<G>,
+    issuer: &BigUint,
+    presentation: &Presentation,
+    commitments: &[G::Element],
+) -> BigUint {
+    let group = &setting.group;
+    let mut hasher = Sha256::new();
+    absorb(&mut hasher, PROOF_DOMAIN.as_bytes());
+    absorb(&mut hasher, &group.describe());
+    absorb(&mut hasher, &issuer.to_bytes_be());
+    absorb(&mut hasher, &presentation.commitment.to_bytes_be());
+    for d in &presentation.disclosed {
+        absorb(&mut hasher, &(d.index as u64).to_be_bytes());
+        absorb(&mut hasher, &d.value.to_bytes_be());
+    }
+    absorb(
+        &mut hasher,
+        &(presentation.predicate.index as u64).to_be_bytes(),
+    );
+    for v in &presentation.predicate.values {
+        absorb(&mut hasher, &v.to_bytes_be());
+    }
+    for t in commitments {
+        absorb(&mut hasher, &group.serialize(t));
+    }
+    BigUint::from_bytes_be(&hasher.finalize()) % setting.order()
+}
+
+//T = П bases^z * X^-c
+fn simulated<G: Group>(
+    setting: &Setting<G>,
+    bases: &[G::Element],
+    target: &G::Element,
+    branch: &Branch,
+) -> G::Element {
+    let group = &setting.group;
+    group.mul(
+        &multi_exp(group, bases, &branch.responses),
+        &group.exp(target, &negate(&branch.c, &setting.order())),
+    )
+}
+
+//Раскрыть атрибуты disclose и доказать, что атрибут predicate.index
+//входит в predicate.values
+pub fn present<G: Group>(
+    setting: &Setting<G>,
+    issuer: &G::Element,
+    credential: &Credential,
+    disclose: &[usize],
+    predicate: SetPredicate,
+) -> Result<Presentation, String> {
+    let group = &setting.group;
+    let order = setting.order();
+    let count = credential.attributes.len();
+    let commitment = group
+        .decode(&credential.commitment)
+        .ok_or("Коммитмент вне группы")?;
+    let disclosed: Vec<Disclosed> = disclose
+        .iter()
+        .map(|&index| {
+            credential
+                .attributes
+                .get(index)
+                .map(|value| Disclosed {
+                    index,
+                    value: value.clone(),
+                })
+                .ok_or(format!("Нет атрибута {}", index))
+        })
+        .collect::<Result<_, _>>()?;
+    let Statement { bases, targets } =
+        statement(setting, count, &commitment, &disclosed, &predicate)?;
+    let actual = &credential.attributes[predicate.index];
+    let real = predicate
+        .values
+        .iter()
+        .position(|v| v % &order == *actual)
+        .ok_or("Атрибут не удовлетворяет предикату")?;
+
+    //Свидетель: скрытые атрибуты и r
+    let mut witness: Vec<BigUint> = hidden(count, &disclosed, predicate.index)
+        .into_iter()
+        .map(|i| credential.attributes[i].clone())
+        .collect();
+    witness.push(credential.blinding.clone());
+
+    let mut nonces = Vec::with_capacity(witness.len());
+    for _ in 0..witness.len() {
+        nonces.push(group.random_scalar());
+    }
+    let mut branches = Vec::with_capacity(targets.len());
+    let mut commitments = Vec::with_capacity(targets.len());
+    for (v, target) in targets.iter().enumerate() {
+        if v == real {
+            branches.push(Branch {
+                c: BigUint::ZERO,
+                responses: vec![],
+            });
+            commitments.push(multi_exp(group, &bases, &nonces));
+            continue;
+        }
+        let mut responses = Vec::with_capacity(bases.len());
+        for _ in 0..bases.len() {
+            responses.push(group.random_scalar());
+        }
+        let branch = Branch {
+            c: group.random_scalar(),
+            responses,
+        };
+        commitments.push(simulated(setting, &bases, target, &branch));
+        branches.push(branch);
+    }
+
+    let mut presentation = Presentation {
+        commitment: credential.commitment.clone(),
+        signature: credential.signature.clone(),
+        disclosed,
+        predicate,
+        branches: vec![],
+    };
+    let c = proof_challenge(setting, &group.encode(issuer), &presentation, &commitments);
+    let others = branches.iter().fold(BigUint::ZERO, |acc, b| acc + &b.c) % &order;
+    let c_real = (c + &order - others) % &order;
+    branches[real].responses = nonces
+        .into_iter()
+        .zip(&witness)
+        .map(|(n, w)| (n + w * &c_real) % &order)
+        .collect();
+    branches[real].c = c_real;
+    presentation.branches = branches;
+    Ok(presentation)
+}
+
+//Подпись сервера на C и дизъюнкция с суммой испытаний, равной хэшу
+pub fn verify<G: Group>(
+    setting: &Setting<G>,
+    issuer: &G::Element,
+    count: usize,
+    presentation: &Presentation,
+) -> bool {
+    let group = &setting.group;
+    let order = setting.order();
+    let y = group.encode(issuer);
+    if !signature_valid(
+        setting,
+        &y,
+        &presentation.commitment,
+        &presentation.signature,
+    ) {
+        return false;
+    }
+    let Some(commitment) = group.decode(&presentation.commitment) else {
+        return false;
+    };
+    let Ok(Statement { bases, targets }) = statement(
+        setting,
+        count,
+        &commitment,
+        &presentation.disclosed,
+        &presentation.predicate,
+    ) else {
+        return false;
+    };
+    let branches = &presentation.branches;
+    if branches.len() != targets.len() || branches.iter().any(|b| b.responses.len() != bases.len())
+    {
+        return false;
+    }
+    let commitments: Vec<G::Element> = branches
+        .iter()
+        .zip(&targets)
+        .map(|(branch, target)| simulated(setting, &bases, target, branch))
+        .collect();
+    let sum = branches.iter().fold(BigUint::ZERO, |acc, b| acc + &b.c) % &order;
+    sum == proof_challenge(setting, &y, presentation, &commitments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ristretto;
+
+    fn n(x: u32) -> BigUint {
+        BigUint::from(x)
+    }
+
+    fn adults() -> SetPredicate {
+        SetPredicate {
+            index: 0,
+            values: (18..=30).map(n).collect(),
+        }
+    }
+
+    #[test]
+    fn test_selective_disclosure() {
+        let setting = ristretto::setting();
+        let issuer = Issuer::random(setting.clone(), 4);
+        let y = issuer.public_key();
+        let credential = issuer.issue(vec![n(27), n(643), n(123456), n(2)]).unwrap();
+
+        let presentation = present(&setting, &y, &credential, &[1], adults()).unwrap();
+        assert!(verify(&setting, &y, 4, &presentation));
+        assert_eq!(
+            presentation.disclosed,
+            [Disclosed {
+                index: 1,
+                value: n(643)
+            }]
+        );
+        let json = serde_json::to_string(&presentation).unwrap();
+        assert!(!json.contains("123456"));
+        let restored: Presentation = serde_json::from_str(&json).unwrap();
+        assert!(verify(&setting, &y, 4, &restored));
+
+        //Подмена раскрытого значения, предиката или ключа сервера
+        let mut forged = presentation.clone();
+        forged.disclosed[0].value = n(840);
+        assert!(!verify(&setting, &y, 4, &forged));
+        let mut forged = presentation.clone();
+        forged.predicate.values.pop();
+        assert!(!verify(&setting, &y, 4, &forged));
+        let other = Issuer::random(setting.clone(), 4).public_key();
+        assert!(!verify(&setting, &other, 4, &presentation));
+    }
+
+    #[test]
+    fn test_predicate_must_hold() {
+        let setting = ristretto::setting();
+        let issuer = Issuer::random(setting.clone(), 3);
+        let y = issuer.public_key();
+        let credential = issuer.issue(vec![n(16), n(1), n(2)]).unwrap();
+        assert!(present(&setting, &y, &credential, &[], adults()).is_err());
+        //Атрибут предиката нельзя одновременно раскрыть
+        let young = SetPredicate {
+            index: 0,
+            values: vec![n(15), n(16)],
+        };
+        assert!(present(&setting, &y, &credential, &[0], young.clone()).is_err());
+        let presentation = present(&setting, &y, &credential, &[], young).unwrap();
+        assert!(verify(&setting, &y, 3, &presentation));
+        assert!(!verify(&setting, &y, 4, &presentation));
+        assert!(issuer.issue(vec![n(1)]).is_err());
+    }
+}
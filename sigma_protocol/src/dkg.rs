@@ -0,0 +1,521 @@
+//////////////////////////////////////////////////////////////
+// Распределенная генерация ключа по Педерсену: каждый из n серверов
+// раздает свой случайный секрет a_i,0 проверяемым разделением
+// Фельдмана. Доля сервера j - сумма x_j = Σ f_i(j) полученных долей,
+// общий открытый ключ Y = П C_i,0 = g^(Σ a_i,0). Секрет Σ a_i,0
+// не известен никому, но любые t долей вычисляют его в показателе.
+//
+// Раунды: коммитменты многочленов -> рассылка всех коммитментов ->
+// доли f_i(j) напрямую серверу j (координатор их не видит) ->
+// итоговые доли и ключи проверки g^x_j.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+#[cfg(feature = "server")]
+use std::sync::{Arc, Mutex};
+
+use crate::config::Address;
+use crate::feldman::{self, FeldmanShare};
+use crate::group::{Group, Setting};
+use crate::math;
+use crate::message::{decimal, decimal_vec};
+use crate::threshold;
+
+pub const COMMIT_PATH: &str = "/dkg/commit";
+pub const COMMITMENTS_PATH: &str = "/dkg/commitments";
+pub const DISTRIBUTE_PATH: &str = "/dkg/distribute";
+pub const SHARE_PATH: &str = "/dkg/share";
+pub const FINISH_PATH: &str = "/dkg/finish";
+
+//Номер сервера в сессии, порог и число участников
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CommitRequest {
+    pub session: String,
+    pub index: u32,
+    pub threshold: usize,
+    pub parties: usize,
+}
+
+//Коммитменты C_i,k = g^a_i,k многочлена участника index
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PartyCommitments {
+    pub index: u32,
+    #[serde(with = "decimal_vec")]
+    pub commitments: Vec<BigUint>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CommitmentsRequest {
+    pub session: String,
+    pub parties: Vec<PartyCommitments>,
+}
+
+//Адреса участников: roster[j - 1] получает долю f_i(j)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DistributeRequest {
+    pub session: String,
+    pub roster: Vec<Address>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ShareRequest {
+    pub session: String,
+    pub from: u32,
+    pub share: FeldmanShare,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FinishRequest {
+    pub session: String,
+}
+
+//Общий ключ и ключ проверки доли g^x_j
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DkgResult {
+    pub index: u32,
+    #[serde(with = "decimal")]
+    pub public_key: BigUint,
+    #[serde(with = "decimal")]
+    pub verification_key: BigUint,
+}
+
+//Y = П g^x_j^l_j по ключам проверки t участников
+pub fn combine<G: Group>(setting: &Setting<G>, keys: &[(u32, BigUint)]) -> Option<G::Element> {
+    let group = &setting.group;
+    let order = setting.order();
+    let indices: Vec<u32> = keys.iter().map(|(index, _)| *index).collect();
+    keys.iter().try_fold(group.identity(), |acc, (index, key)| {
+        let l = threshold::lagrange(*index, &indices, &order)?;
+        Some(group.mul(&acc, &group.exp(&group.decode(key)?, &l)))
+    })
+}
+
+//////////////////////////////////////////////////////////////
+// Участник: свой многочлен и полученные доли каждой сессии
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+struct Session {
+    index: u32,
+    threshold: usize,
+    parties: usize,
+    coefficients: Vec<BigUint>,
+    //Коммитменты всех участников по порядку номеров
+    commitments: Option<Vec<Vec<BigUint>>>,
+    received: BTreeMap<u32, BigUint>,
+}
+
+#[derive(Debug, Default)]
+pub struct DkgParty {
+    sessions: HashMap<String, Session>,
+}
+
+impl DkgParty {
+    pub fn new() -> Self {
+        DkgParty::default()
+    }
+
+    fn session(&mut self, session: &str) -> Result<&mut Session, String> {
+        self.sessions
+            .get_mut(session)
+            .ok_or_else(|| format!("Нет сессии генерации ключа {}", session))
+    }
+
+    //Первый раунд: многочлен степени t - 1 и коммитменты его коэффициентов
+    pub fn commit<G: Group>(
+        &mut self,
+        setting: &Setting<G>,
+        request: &CommitRequest,
+        coefficients: Vec<BigUint>,
+    ) -> Result<PartyCommitments, String> {
+        if self.sessions.contains_key(&request.session) {
+            return Err(format!("Сессия {} уже открыта", request.session));
+        }
+        if request.index == 0 || request.index as usize > request.parties {
+            return Err(format!(
+                "Номер {} вне диапазона 1..={}",
+                request.index, request.parties
+            ));
+        }
+        if coefficients.len() != request.threshold {
+            return Err("Число коэффициентов не равно порогу".to_string());
+        }
+        let dealing = feldman::deal_with(setting, &coefficients, request.parties)?;
+        self.sessions.insert(
+            request.session.clone(),
+            Session {
+                index: request.index,
+                threshold: request.threshold,
+                parties: request.parties,
+                coefficients,
+                commitments: None,
+                received: BTreeMap::new(),
+            },
+        );
+        Ok(PartyCommitments {
+            index: request.index,
+            commitments: dealing.commitments,
+        })
+    }
+
+    //Второй раунд: коммитменты всех участников, по которым проверяются доли
+    pub fn accept_commitments<G: Group>(
+        &mut self,
+        setting: &Setting<G>,
+        request: &CommitmentsRequest,
+    ) -> Result<(), String> {
+        let session = self.session(&request.session)?;
+        if session.commitments.is_some() {
+            return Err("Коммитменты уже получены".to_string());
+        }
+        let mut parties = request.parties.clone();
+        parties.sort_by_key(|p| p.index);
+        let indices: Vec<u32> = parties.iter().map(|p| p.index).collect();
+        if indices != (1..=session.parties as u32).collect::<Vec<_>>() {
+            return Err("Нужны коммитменты каждого участника ровно по одному".to_string());
+        }
+        if parties
+            .iter()
+            .any(|p| p.commitments.len() != session.threshold)
+        {
+            return Err("Число коммитментов участника не равно порогу".to_string());
+        }
+        let group = &setting.group;
+        let own: Vec<BigUint> = session
+            .coefficients
+            .iter()
+            .map(|a| group.encode(&group.exp(&setting.g, a)))
+            .collect();
+        if parties[session.index as usize - 1].commitments != own {
+            return Err("Собственные коммитменты участника подменены".to_string());
+        }
+        session.commitments = Some(parties.into_iter().map(|p| p.commitments).collect());
+        Ok(())
+    }
+
+    //Третий раунд: доли f_i(j) для всех участников, включая себя
+    pub fn shares(
+        &mut self,
+        session: &str,
+        order: &BigUint,
+    ) -> Result<(u32, Vec<FeldmanShare>), String> {
+        let session = self.session(session)?;
+        if session.commitments.is_none() {
+            return Err("Доли рассылаются после получения всех коммитментов".to_string());
+        }
+        let shares = (1..=session.parties as u32)
+            .map(|index| FeldmanShare {
+                index,
+                value: math::eval_polynomial(&session.coefficients, &BigUint::from(index), order),
+            })
+            .collect();
+        Ok((session.index, shares))
+    }
+
+    //Доля от участника from проверяется по его коммитментам
+    pub fn receive<G: Group>(
+        &mut self,
+        setting: &Setting<G>,
+        request: &ShareRequest,
+    ) -> Result<(), String> {
+        let session = self.session(&request.session)?;
+        let commitments = session
+            .commitments
+            .as_ref()
+            .ok_or("Коммитменты участников еще не получены")?;
+        if request.share.index != session.index {
+            return Err(format!(
+                "Доля предназначена участнику {}",
+                request.share.index
+            ));
+        }
+        let dealer = commitments
+            .get((request.from as usize).wrapping_sub(1))
+            .ok_or_else(|| format!("Нет участника {}", request.from))?;
+        if session.received.contains_key(&request.from) {
+            return Err(format!("Доля от участника {} уже получена", request.from));
+        }
+        if !feldman::verify_share(setting, dealer, &request.share) {
+            return Err(format!(
+                "Доля от участника {} не совпадает с его коммитментами",
+                request.from
+            ));
+        }
+        session
+            .received
+            .insert(request.from, request.share.value.clone());
+        Ok(())
+    }
+
+    //Итог: x_j = Σ f_i(j), Y = П C_i,0
+    pub fn finish<G: Group>(
+        &mut self,
+        setting: &Setting<G>,
+        request: &FinishRequest,
+    ) -> Result<DkgResult, String> {
+        let session = self.session(&request.session)?;
+        let commitments = session
+            .commitments
+            .as_ref()
+            .ok_or("Коммитменты участников еще не получены")?;
+        if session.received.len() != session.parties {
+            return Err(format!(
+                "Получено {} долей из {}",
+                session.received.len(),
+                session.parties
+            ));
+        }
+        let group = &setting.group;
+        let order = setting.order();
+        let x = session
+            .received
+            .values()
+            .fold(BigUint::ZERO, |acc, s| (acc + s) % &order);
+        let mut y = group.identity();
+        for c in commitments {
+            let c0 = group.decode(&c[0]).ok_or("Коммитмент вне группы")?;
+            y = group.mul(&y, &c0);
+        }
+        Ok(DkgResult {
+            index: session.index,
+            public_key: group.encode(&y),
+            verification_key: group.encode(&group.exp(&setting.g, &x)),
+        })
+    }
+}
+
+//Рассылка долей участника по адресам из списка
+#[cfg(feature = "server")]
+pub async fn distribute(
+    party: &Arc<Mutex<DkgParty>>,
+    order: &BigUint,
+    request: &DistributeRequest,
+) -> Result<(), String> {
+    let (from, shares) = party.lock().unwrap().shares(&request.session, order)?;
+    let client = reqwest::Client::new();
+    for share in shares {
+        let address = request
+            .roster
+            .get(share.index as usize - 1)
+            .ok_or_else(|| format!("Нет адреса участника {}", share.index))?;
+        let body = ShareRequest {
+            session: request.session.clone(),
+            from,
+            share,
+        };
+        threshold::post::<_, ()>(&client, address, SHARE_PATH, &body).await?;
+    }
+    Ok(())
+}
+
+//Участник в этом процессе или на другом сервере
+#[cfg(feature = "server")]
+#[derive(Debug, Clone)]
+pub enum Participant {
+    Local(Arc<Mutex<DkgParty>>),
+    Remote {
+        address: Address,
+        client: reqwest::Client,
+    },
+}
+
+#[cfg(feature = "server")]
+impl Participant {
+    pub fn remote(address: Address) -> Self {
+        Participant::Remote {
+            address,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn commit<G: Group>(
+        &self,
+        setting: &Setting<G>,
+        request: &CommitRequest,
+    ) -> Result<PartyCommitments, String> {
+        match self {
+            Participant::Local(party) => {
+                let mut coefficients = Vec::with_capacity(request.threshold);
+                for _ in 0..request.threshold {
+                    coefficients.push(setting.group.random_scalar());
+                }
+                party.lock().unwrap().commit(setting, request, coefficients)
+            }
+            Participant::Remote { address, client } => {
+                threshold::post(client, address, COMMIT_PATH, request).await
+            }
+        }
+    }
+
+    pub async fn accept_commitments<G: Group>(
+        &self,
+        setting: &Setting<G>,
+        request: &CommitmentsRequest,
+    ) -> Result<(), String> {
+        match self {
+            Participant::Local(party) => party.lock().unwrap().accept_commitments(setting, request),
+            Participant::Remote { address, client } => {
+                threshold::post(client, address, COMMITMENTS_PATH, request).await
+            }
+        }
+    }
+
+    pub async fn distribute(
+        &self,
+        order: &BigUint,
+        request: &DistributeRequest,
+    ) -> Result<(), String> {
+        match self {
+            Participant::Local(party) => distribute(party, order, request).await,
+            Participant::Remote { address, client } => {
+                threshold::post(client, address, DISTRIBUTE_PATH, request).await
+            }
+        }
+    }
+
+    pub async fn finish<G: Group>(
+        &self,
+        setting: &Setting<G>,
+        request: &FinishRequest,
+    ) -> Result<DkgResult, String> {
+        match self {
+            Participant::Local(party) => party.lock().unwrap().finish(setting, request),
+            Participant::Remote { address, client } => {
+                threshold::post(client, address, FINISH_PATH, request).await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::toy_params;
+    use crate::ristretto;
+
+    //Раунды без сети: доли передаются напрямую
+    fn run<G: Group>(
+        setting: &Setting<G>,
+        parties: &mut [DkgParty],
+        threshold: usize,
+    ) -> (Vec<DkgResult>, BigUint) {
+        let n = parties.len();
+        let mut commitments = Vec::new();
+        let mut secret = BigUint::ZERO;
+        for (i, party) in parties.iter_mut().enumerate() {
+            let request = CommitRequest {
+                session: "s".to_string(),
+                index: i as u32 + 1,
+                threshold,
+                parties: n,
+            };
+            let mut coefficients = Vec::new();
+            for _ in 0..threshold {
+                coefficients.push(setting.group.random_scalar());
+            }
+            secret = (secret + &coefficients[0]) % setting.order();
+            commitments.push(party.commit(setting, &request, coefficients).unwrap());
+        }
+        let request = CommitmentsRequest {
+            session: "s".to_string(),
+            parties: commitments,
+        };
+        for party in parties.iter_mut() {
+            party.accept_commitments(setting, &request).unwrap();
+        }
+        for i in 0..n {
+            let (from, shares) = parties[i].shares("s", &setting.order()).unwrap();
+            for share in shares {
+                let to = share.index as usize - 1;
+                let request = ShareRequest {
+                    session: "s".to_string(),
+                    from,
+                    share,
+                };
+                parties[to].receive(setting, &request).unwrap();
+            }
+        }
+        let finish = FinishRequest {
+            session: "s".to_string(),
+        };
+        let results = parties
+            .iter_mut()
+            .map(|party| party.finish(setting, &finish).unwrap())
+            .collect();
+        (results, secret)
+    }
+
+    #[test]
+    fn test_joint_key() {
+        let setting = ristretto::setting();
+        let mut parties: Vec<DkgParty> = (0..4).map(|_| DkgParty::new()).collect();
+        let (results, secret) = run(&setting, &mut parties, 3);
+        let y = setting
+            .group
+            .encode(&setting.group.exp(&setting.g, &secret));
+        assert!(results.iter().all(|r| r.public_key == y));
+
+        //Любые три ключа проверки дают Y в показателе, двух мало
+        let keys: Vec<(u32, BigUint)> = results
+            .iter()
+            .map(|r| (r.index, r.verification_key.clone()))
+            .collect();
+        let combined = combine(&setting, &keys[1..]).unwrap();
+        assert_eq!(setting.group.encode(&combined), y);
+        let combined = combine(&setting, &keys[..2]).unwrap();
+        assert_ne!(setting.group.encode(&combined), y);
+    }
+
+    #[test]
+    fn test_rounds_enforced() {
+        let setting = toy_params().setting();
+        let mut party = DkgParty::new();
+        let request = CommitRequest {
+            session: "s".to_string(),
+            index: 1,
+            threshold: 2,
+            parties: 2,
+        };
+        let coefficients = vec![BigUint::from(3u32), BigUint::from(5u32)];
+        let own = party
+            .commit(&setting, &request, coefficients.clone())
+            .unwrap();
+        assert!(party.commit(&setting, &request, coefficients).is_err());
+        //Доли не раздаются до получения всех коммитментов
+        assert!(party.shares("s", &setting.order()).is_err());
+
+        let other = PartyCommitments {
+            index: 2,
+            commitments: vec![BigUint::from(16u32), BigUint::from(2u32)],
+        };
+        let partial = CommitmentsRequest {
+            session: "s".to_string(),
+            parties: vec![own.clone()],
+        };
+        assert!(party.accept_commitments(&setting, &partial).is_err());
+        let all = CommitmentsRequest {
+            session: "s".to_string(),
+            parties: vec![other, own],
+        };
+        party.accept_commitments(&setting, &all).unwrap();
+
+        //Коммитменты (16, 2) = (g^2, g^6): f_2(z) = 2 + 6z, верная доля f_2(1) = 8
+        let share = |value: u32| ShareRequest {
+            session: "s".to_string(),
+            from: 2,
+            share: FeldmanShare {
+                index: 1,
+                value: BigUint::from(value),
+            },
+        };
+        assert!(party.receive(&setting, &share(7)).is_err());
+        party.receive(&setting, &share(8)).unwrap();
+        assert!(party.receive(&setting, &share(8)).is_err());
+        let finish = FinishRequest {
+            session: "s".to_string(),
+        };
+        assert!(party.finish(&setting, &finish).is_err());
+    }
+}
@@ -0,0 +1,356 @@
+//////////////////////////////////////////////////////////////
+// Протокол Чаума-Педерсена (DLEQ): доказательство того, что
+// u1 = g^x и u2 = h^x имеют один и тот же дискретный логарифм x.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::{BigInt, BigUint, ToBigInt};
+
+use crate::key_gen;
+use crate::math::{self, MathError};
+use crate::message::ProtocolMessage;
+use crate::protocol::PublicParams;
+use crate::sigma::SigmaProtocol;
+#[cfg(feature = "server")]
+use crate::{protocol::fail, timing::Timeline, transport::Transport};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DleqStatement {
+    pub u1: BigUint,
+    pub u2: BigUint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DleqCommitment {
+    pub t1: BigUint,
+    pub t2: BigUint,
+}
+
+#[derive(Debug, Clone)]
+pub struct Dleq {
+    params: PublicParams,
+}
+
+impl Dleq {
+    pub fn new(params: PublicParams) -> Self {
+        Dleq { params }
+    }
+
+    pub fn params(&self) -> &PublicParams {
+        &self.params
+    }
+
+    //(g^x, h^x)
+    fn pair(&self, x: &BigUint) -> Result<(BigUint, BigUint), MathError> {
+        let x = BigInt::from(x.clone());
+        Ok((
+            math::mod_pow_big(&self.params.g, &x, &self.params.p)?,
+            math::mod_pow_big(&self.params.h, &x, &self.params.p)?,
+        ))
+    }
+
+    pub fn statement(&self, x: &BigUint) -> Result<DleqStatement, MathError> {
+        let (u1, u2) = self.pair(x)?;
+        Ok(DleqStatement { u1, u2 })
+    }
+
+    fn in_range(&self, x: &BigUint) -> bool {
+        *x > BigUint::ZERO && *x < self.params.p
+    }
+
+    //base^z == t * u^c
+    fn check(&self, base: &BigUint, u: &BigUint, t: &BigUint, c: &BigUint, z: &BigUint) -> bool {
+        let p = &self.params.p;
+        let (Some(c), Some(z)) = (c.to_bigint(), z.to_bigint()) else {
+            return false;
+        };
+        let left = math::mod_pow_big(base, &z, p);
+        let right = math::mod_pow_big(u, &c, p).map(|uc| t * uc % p);
+        left.is_ok() && left == right
+    }
+}
+
+impl SigmaProtocol for Dleq {
+    type Statement = DleqStatement;
+    type Witness = BigUint;
+    type Nonce = BigUint;
+    type Commitment = DleqCommitment;
+    type Challenge = BigUint;
+    type Response = BigUint;
+
+    fn commit(
+        &self,
+        _statement: &DleqStatement,
+        _witness: &BigUint,
+    ) -> Option<(BigUint, DleqCommitment)> {
+        let r = key_gen::random_biguint_mod(&self.params.order());
+        let (t1, t2) = self.pair(&r).ok()?;
+        Some((r, DleqCommitment { t1, t2 }))
+    }
+
+    fn challenge(&self) -> BigUint {
+        key_gen::random_biguint_mod(&self.params.order())
+    }
+
+    //z = r + x*c
+    fn respond(&self, x: &BigUint, r: BigUint, c: &BigUint) -> BigUint {
+        (r + x * c) % self.params.order()
+    }
+
+    //g^z == t1 * u1^c и h^z == t2 * u2^c
+    fn verify(
+        &self,
+        statement: &DleqStatement,
+        commitment: &DleqCommitment,
+        c: &BigUint,
+        z: &BigUint,
+    ) -> bool {
+        let elements = [&statement.u1, &statement.u2, &commitment.t1, &commitment.t2];
+        if !elements.iter().all(|x| self.in_range(x)) {
+            return false;
+        }
+        self.check(&self.params.g, &statement.u1, &commitment.t1, c, z)
+            && self.check(&self.params.h, &statement.u2, &commitment.t2, c, z)
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Стороны протокола поверх транспорта
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct DleqProver {
+    protocol: Dleq,
+    x: BigUint,
+    statement: DleqStatement,
+}
+
+impl DleqProver {
+    pub fn new(params: PublicParams, x: BigUint) -> Result<Self, MathError> {
+        let protocol = Dleq::new(params);
+        let statement = protocol.statement(&x)?;
+        Ok(DleqProver {
+            protocol,
+            x,
+            statement,
+        })
+    }
+
+    pub fn random(params: PublicParams) -> Result<Self, MathError> {
+        let x = key_gen::random_biguint_mod(&params.order());
+        DleqProver::new(params, x)
+    }
+
+    pub fn statement(&self) -> &DleqStatement {
+        &self.statement
+    }
+
+    pub fn secret(&self) -> &BigUint {
+        &self.x
+    }
+
+    //Одноразовый r и сообщение с коммитментом
+    pub fn commit(&self) -> Option<(BigUint, ProtocolMessage)> {
+        let (r, commitment) = self.protocol.commit(&self.statement, &self.x)?;
+        let message = ProtocolMessage::DleqCommitment {
+            u1: self.statement.u1.clone(),
+            u2: self.statement.u2.clone(),
+            t1: commitment.t1,
+            t2: commitment.t2,
+        };
+        Some((r, message))
+    }
+
+    pub fn respond(&self, r: BigUint, c: &BigUint) -> ProtocolMessage {
+        ProtocolMessage::DleqResponse {
+            z: self.protocol.respond(&self.x, r, c),
+        }
+    }
+
+    #[cfg(feature = "server")]
+    pub async fn run<T: Transport>(
+        &self,
+        transport: &mut T,
+        timeline: &mut Timeline,
+    ) -> Result<bool, String> {
+        timeline.start_compute();
+        let (r, commitment) = self.commit().ok_or("Ошибка вычисления t1, t2")?;
+        timeline.stop_compute();
+        transport.send(&commitment).await?;
+        timeline.mark("commitment");
+
+        let c = match transport.receive().await? {
+            ProtocolMessage::Challenge { c } => c,
+            ProtocolMessage::Abort { reason } => return Err(reason),
+            other => {
+                let reason = format!("Ожидалось испытание, получено: {}", other.describe());
+                return Err(fail(transport, reason).await);
+            }
+        };
+        timeline.mark("challenge");
+
+        timeline.start_compute();
+        let response = self.respond(r, &c);
+        timeline.stop_compute();
+        transport.send(&response).await?;
+        timeline.mark("response");
+
+        let result = match transport.receive().await? {
+            ProtocolMessage::Verdict { accepted } => Ok(accepted),
+            ProtocolMessage::Abort { reason } => Err(reason),
+            other => Err(format!("Ожидался вердикт, получено: {}", other.describe())),
+        };
+        timeline.mark("verdict");
+        result
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DleqVerifier {
+    protocol: Dleq,
+}
+
+impl DleqVerifier {
+    pub fn new(params: PublicParams) -> Self {
+        DleqVerifier {
+            protocol: Dleq::new(params),
+        }
+    }
+
+    pub fn challenge(&self) -> BigUint {
+        self.protocol.challenge()
+    }
+
+    //Разбор коммитмента из сообщения
+    pub fn receive_commitment(
+        message: ProtocolMessage,
+    ) -> Result<(DleqStatement, DleqCommitment), String> {
+        match message {
+            ProtocolMessage::DleqCommitment { u1, u2, t1, t2 } => {
+                Ok((DleqStatement { u1, u2 }, DleqCommitment { t1, t2 }))
+            }
+            other => Err(format!(
+                "Ожидался коммитмент DLEQ, получено: {}",
+                other.describe()
+            )),
+        }
+    }
+
+    pub fn verify(
+        &self,
+        statement: &DleqStatement,
+        commitment: &DleqCommitment,
+        c: &BigUint,
+        message: ProtocolMessage,
+    ) -> Result<bool, String> {
+        match message {
+            ProtocolMessage::DleqResponse { z } => {
+                Ok(self.protocol.verify(statement, commitment, c, &z))
+            }
+            other => Err(format!(
+                "Ожидался ответ DLEQ, получено: {}",
+                other.describe()
+            )),
+        }
+    }
+
+    #[cfg(feature = "server")]
+    pub async fn run<T: Transport>(
+        &self,
+        transport: &mut T,
+        timeline: &mut Timeline,
+    ) -> Result<bool, String> {
+        let (statement, commitment) = match transport.receive().await? {
+            ProtocolMessage::Abort { reason } => return Err(reason),
+            message => match DleqVerifier::receive_commitment(message) {
+                Ok(parts) => parts,
+                Err(e) => return Err(fail(transport, e).await),
+            },
+        };
+        timeline.mark("commitment");
+
+        timeline.start_compute();
+        let c = self.challenge();
+        timeline.stop_compute();
+        transport
+            .send(&ProtocolMessage::Challenge { c: c.clone() })
+            .await?;
+        timeline.mark("challenge");
+
+        let accepted = match transport.receive().await? {
+            ProtocolMessage::Abort { reason } => return Err(reason),
+            message => {
+                timeline.start_compute();
+                let accepted = self.verify(&statement, &commitment, &c, message);
+                timeline.stop_compute();
+                match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => return Err(fail(transport, e).await),
+                }
+            }
+        };
+        timeline.mark("response");
+
+        transport
+            .send(&ProtocolMessage::Verdict { accepted })
+            .await?;
+        timeline.mark("verdict");
+        Ok(accepted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::toy_params;
+    #[cfg(feature = "server")]
+    use crate::transport::ChannelTransport;
+
+    #[test]
+    fn test_dleq_equation() {
+        let protocol = Dleq::new(toy_params());
+        let x = BigUint::from(7u32);
+        let statement = protocol.statement(&x).unwrap();
+        let r = BigUint::from(3u32);
+        let (t1, t2) = protocol.pair(&r).unwrap();
+        let commitment = DleqCommitment { t1, t2 };
+        for c in 0..11u32 {
+            let c = BigUint::from(c);
+            let z = protocol.respond(&x, r.clone(), &c);
+            assert!(protocol.verify(&statement, &commitment, &c, &z));
+        }
+    }
+
+    #[test]
+    fn test_different_logs_rejected() {
+        let protocol = Dleq::new(toy_params());
+        //u1 = g^7, u2 = h^8
+        let u1 = protocol.statement(&BigUint::from(7u32)).unwrap().u1;
+        let u2 = protocol.statement(&BigUint::from(8u32)).unwrap().u2;
+        let statement = DleqStatement { u1, u2 };
+        let r = BigUint::from(3u32);
+        let (t1, t2) = protocol.pair(&r).unwrap();
+        let commitment = DleqCommitment { t1, t2 };
+        let c = BigUint::from(4u32);
+        for x in [7u32, 8] {
+            let z = protocol.respond(&BigUint::from(x), r.clone(), &c);
+            assert!(!protocol.verify(&statement, &commitment, &c, &z));
+        }
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_dleq_over_transport() {
+        let (mut prover_side, mut verifier_side) = ChannelTransport::pair();
+        let prover = DleqProver::random(toy_params()).unwrap();
+        let verifier = DleqVerifier::new(toy_params());
+        let handle =
+            tokio::spawn(
+                async move { verifier.run(&mut verifier_side, &mut Timeline::new()).await },
+            );
+        assert_eq!(
+            prover.run(&mut prover_side, &mut Timeline::new()).await,
+            Ok(true)
+        );
+        assert_eq!(handle.await.unwrap(), Ok(true));
+    }
+}
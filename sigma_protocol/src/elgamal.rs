@@ -0,0 +1,273 @@
+//////////////////////////////////////////////////////////////
+// Экспоненциальное шифрование Эль-Гамаля в группе протокола:
+// (c1, c2) = (g^r, g^m * y^r), где y = g^x - открытый ключ.
+// Доказательство знания открытого текста: отправитель знает пару
+// (m, r), не раскрывая ее; это сигма-протокол с двумя основаниями
+// g и y, как у Окамото.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+
+use crate::group::{Group, ModGroup, Setting};
+use crate::protocol::PublicParams;
+use crate::sigma::SigmaProtocol;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ciphertext<E> {
+    pub c1: E,
+    pub c2: E,
+}
+
+//Открытый текст и случайность шифрования
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plaintext {
+    pub m: BigUint,
+    pub r: BigUint,
+}
+
+#[derive(Debug, Clone)]
+pub struct ElGamal<G: Group = ModGroup> {
+    setting: Setting<G>,
+}
+
+impl ElGamal {
+    pub fn new(params: PublicParams) -> Self {
+        ElGamal::in_setting(params.setting())
+    }
+}
+
+impl<G: Group> ElGamal<G> {
+    pub fn in_setting(setting: Setting<G>) -> Self {
+        ElGamal { setting }
+    }
+
+    pub fn setting(&self) -> &Setting<G> {
+        &self.setting
+    }
+
+    //y = g^x
+    pub fn public_key(&self, x: &BigUint) -> G::Element {
+        self.setting.group.exp(&self.setting.g, x)
+    }
+
+    //Пара (x, y) со случайным секретом
+    pub fn keygen(&self) -> (BigUint, G::Element) {
+        let x = self.setting.group.random_scalar();
+        let y = self.public_key(&x);
+        (x, y)
+    }
+
+    //(g^r, g^m * y^r)
+    pub fn encrypt_with(&self, y: &G::Element, plaintext: &Plaintext) -> Ciphertext<G::Element> {
+        let group = &self.setting.group;
+        Ciphertext {
+            c1: group.exp(&self.setting.g, &plaintext.r),
+            c2: group.mul(
+                &group.exp(&self.setting.g, &plaintext.m),
+                &group.exp(y, &plaintext.r),
+            ),
+        }
+    }
+
+    pub fn encrypt(&self, y: &G::Element, m: &BigUint) -> (Ciphertext<G::Element>, Plaintext) {
+        let plaintext = Plaintext {
+            m: m % self.setting.order(),
+            r: self.setting.group.random_scalar(),
+        };
+        (self.encrypt_with(y, &plaintext), plaintext)
+    }
+
+    //g^m = c2 * c1^-x
+    pub fn decrypt(&self, x: &BigUint, ciphertext: &Ciphertext<G::Element>) -> G::Element {
+        let order = self.setting.order();
+        let group = &self.setting.group;
+        let mask = group.exp(&ciphertext.c1, &((&order - x % &order) % &order));
+        group.mul(&ciphertext.c2, &mask)
+    }
+
+    //m перебором до bound: экспоненциальный вариант годится для малых чисел
+    pub fn decrypt_small(
+        &self,
+        x: &BigUint,
+        ciphertext: &Ciphertext<G::Element>,
+        bound: u64,
+    ) -> Option<BigUint> {
+        let target = self.decrypt(x, ciphertext);
+        let group = &self.setting.group;
+        let mut current = group.identity();
+        for m in 0..bound {
+            if current == target {
+                return Some(BigUint::from(m));
+            }
+            current = group.mul(&current, &self.setting.g);
+        }
+        None
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Доказательство знания открытого текста: знаю (m, r) такие, что
+// c1 = g^r и c2 = g^m * y^r
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct PlaintextKnowledge<G: Group = ModGroup> {
+    elgamal: ElGamal<G>,
+    y: G::Element,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaintextResponse {
+    pub zm: BigUint,
+    pub zr: BigUint,
+}
+
+impl<G: Group> PlaintextKnowledge<G> {
+    pub fn new(elgamal: ElGamal<G>, y: G::Element) -> Self {
+        PlaintextKnowledge { elgamal, y }
+    }
+
+    pub fn public_key(&self) -> &G::Element {
+        &self.y
+    }
+
+    //Обе стороны проверки для журнала: (g^zr, c1^c * t1) и (g^zm * y^zr, c2^c * t2)
+    pub fn sides(
+        &self,
+        ciphertext: &Ciphertext<G::Element>,
+        t: &Ciphertext<G::Element>,
+        c: &BigUint,
+        z: &PlaintextResponse,
+    ) -> (Ciphertext<G::Element>, Ciphertext<G::Element>) {
+        let group = &self.elgamal.setting.group;
+        let left = self.elgamal.encrypt_with(
+            &self.y,
+            &Plaintext {
+                m: z.zm.clone(),
+                r: z.zr.clone(),
+            },
+        );
+        let right = Ciphertext {
+            c1: group.mul(&t.c1, &group.exp(&ciphertext.c1, c)),
+            c2: group.mul(&t.c2, &group.exp(&ciphertext.c2, c)),
+        };
+        (left, right)
+    }
+}
+
+impl<G: Group> SigmaProtocol for PlaintextKnowledge<G> {
+    type Statement = Ciphertext<G::Element>;
+    type Witness = Plaintext;
+    type Nonce = Plaintext;
+    type Commitment = Ciphertext<G::Element>;
+    type Challenge = BigUint;
+    type Response = PlaintextResponse;
+
+    //Коммитмент - шифрование случайного текста a случайностью b
+    fn commit(
+        &self,
+        _ciphertext: &Ciphertext<G::Element>,
+        _plaintext: &Plaintext,
+    ) -> Option<(Plaintext, Ciphertext<G::Element>)> {
+        let group = &self.elgamal.setting.group;
+        let nonce = Plaintext {
+            m: group.random_scalar(),
+            r: group.random_scalar(),
+        };
+        let t = self.elgamal.encrypt_with(&self.y, &nonce);
+        Some((nonce, t))
+    }
+
+    fn challenge(&self) -> BigUint {
+        self.elgamal.setting.group.random_scalar()
+    }
+
+    //zm = a + m*c, zr = b + r*c
+    fn respond(&self, plaintext: &Plaintext, nonce: Plaintext, c: &BigUint) -> PlaintextResponse {
+        let order = self.elgamal.setting.order();
+        PlaintextResponse {
+            zm: (nonce.m + &plaintext.m * c) % &order,
+            zr: (nonce.r + &plaintext.r * c) % &order,
+        }
+    }
+
+    //Enc(zm; zr) == t * (c1, c2)^c покомпонентно
+    fn verify(
+        &self,
+        ciphertext: &Ciphertext<G::Element>,
+        t: &Ciphertext<G::Element>,
+        c: &BigUint,
+        z: &PlaintextResponse,
+    ) -> bool {
+        let group = &self.elgamal.setting.group;
+        let elements = [&self.y, &ciphertext.c1, &ciphertext.c2, &t.c1, &t.c2];
+        if !elements.iter().all(|e| group.contains(e)) {
+            return false;
+        }
+        let (left, right) = self.sides(ciphertext, t, c, z);
+        left == right
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::toy_params;
+    use crate::ristretto;
+    use crate::sigma;
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let elgamal = ElGamal::new(toy_params());
+        let (x, y) = elgamal.keygen();
+        for m in 0..11u32 {
+            let (ciphertext, _) = elgamal.encrypt(&y, &BigUint::from(m));
+            assert_eq!(
+                elgamal.decrypt(&x, &ciphertext),
+                elgamal.public_key(&BigUint::from(m))
+            );
+            assert_eq!(
+                elgamal.decrypt_small(&x, &ciphertext, 11),
+                Some(BigUint::from(m))
+            );
+        }
+
+        let elgamal = ElGamal::in_setting(ristretto::setting());
+        let (x, y) = elgamal.keygen();
+        let (ciphertext, _) = elgamal.encrypt(&y, &BigUint::from(42u32));
+        assert_eq!(
+            elgamal.decrypt_small(&x, &ciphertext, 100),
+            Some(BigUint::from(42u32))
+        );
+        assert_eq!(elgamal.decrypt_small(&x, &ciphertext, 42), None);
+    }
+
+    #[test]
+    fn test_plaintext_knowledge() {
+        let elgamal = ElGamal::new(toy_params());
+        let (_, y) = elgamal.keygen();
+        let (ciphertext, plaintext) = elgamal.encrypt(&y, &BigUint::from(7u32));
+        let protocol = PlaintextKnowledge::new(elgamal.clone(), y.clone());
+        assert_eq!(
+            sigma::execute(&protocol, &ciphertext, &plaintext),
+            Some(true)
+        );
+
+        //Неверный открытый текст не проходит проверку
+        let (nonce, t) = protocol.commit(&ciphertext, &plaintext).unwrap();
+        let c = BigUint::from(3u32);
+        let wrong = Plaintext {
+            m: BigUint::from(8u32),
+            ..plaintext.clone()
+        };
+        let z = protocol.respond(&wrong, nonce.clone(), &c);
+        assert!(!protocol.verify(&ciphertext, &t, &c, &z));
+        let z = protocol.respond(&plaintext, nonce, &c);
+        assert!(protocol.verify(&ciphertext, &t, &c, &z));
+        let outside = Ciphertext {
+            c1: BigUint::ZERO,
+            ..ciphertext
+        };
+        assert!(!protocol.verify(&outside, &t, &c, &z));
+    }
+}
@@ -0,0 +1,238 @@
+//////////////////////////////////////////////////////////////
+// Большие числа в JSON строками: шестнадцатеричной (HexBig) или
+// base64 от байтов в порядке big-endian (B64Big). Разбор строгий:
+// без префиксов, пробелов и лишних символов, а длина ограничена,
+// чтобы запрос не заставлял сервер разбирать гигантские числа.
+//
+// Здесь же hex для байтовых строк (соли, ключи, подписи, отпечатки).
+//////////////////////////////////////////////////////////////
+
+use std::fmt;
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+//Наибольшая длина числа в байтах: с запасом для модулей в 4096 бит
+pub const MAX_BYTES: usize = 1024;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+//Число строчными шестнадцатеричными цифрами без ведущих нулей
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HexBig(pub BigUint);
+
+//Число в base64 (RFC 4648, с дополнением) от минимальной записи в байтах
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct B64Big(pub BigUint);
+
+impl HexBig {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        if s.is_empty() {
+            return Err("Пустая шестнадцатеричная строка".to_string());
+        }
+        if s.len() > 2 * MAX_BYTES {
+            return Err(format!("Число длиннее {} байт", MAX_BYTES));
+        }
+        if let Some(c) = s.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(format!(
+                "Недопустимый символ {:?} в шестнадцатеричной строке",
+                c
+            ));
+        }
+        BigUint::parse_bytes(s.as_bytes(), 16)
+            .map(HexBig)
+            .ok_or_else(|| format!("Некорректная шестнадцатеричная строка {}", s))
+    }
+}
+
+impl fmt::Display for HexBig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:x}", self.0)
+    }
+}
+
+impl B64Big {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        if s.is_empty() || !s.len().is_multiple_of(4) {
+            return Err("Длина строки base64 должна быть положительной и кратной 4".to_string());
+        }
+        if s.len() / 4 * 3 > MAX_BYTES + 2 {
+            return Err(format!("Число длиннее {} байт", MAX_BYTES));
+        }
+        let bytes = s.as_bytes();
+        let padding = bytes.iter().rev().take_while(|&&b| b == b'=').count();
+        if padding > 2 {
+            return Err("Лишнее дополнение в строке base64".to_string());
+        }
+        let mut out = Vec::with_capacity(bytes.len() / 4 * 3);
+        for (i, chunk) in bytes.chunks(4).enumerate() {
+            let last = i == bytes.len() / 4 - 1;
+            let pad = if last { padding } else { 0 };
+            let mut acc = 0u32;
+            for &b in &chunk[..4 - pad] {
+                let digit = BASE64_ALPHABET
+                    .iter()
+                    .position(|&a| a == b)
+                    .ok_or_else(|| {
+                        format!("Недопустимый символ {:?} в строке base64", b as char)
+                    })?;
+                acc = acc << 6 | digit as u32;
+            }
+            acc <<= 6 * pad;
+            let decoded = acc.to_be_bytes();
+            //Отброшенные дополнением биты должны быть нулевыми
+            if decoded[4 - pad..].iter().any(|&b| b != 0) {
+                return Err("Неканоническая строка base64".to_string());
+            }
+            out.extend_from_slice(&decoded[1..4 - pad]);
+        }
+        if out.len() > MAX_BYTES {
+            return Err(format!("Число длиннее {} байт", MAX_BYTES));
+        }
+        Ok(B64Big(BigUint::from_bytes_be(&out)))
+    }
+}
+
+impl fmt::Display for B64Big {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = self.0.to_bytes_be();
+        let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+        for chunk in bytes.chunks(3) {
+            let mut block = [0u8; 3];
+            block[..chunk.len()].copy_from_slice(chunk);
+            let acc = u32::from_be_bytes([0, block[0], block[1], block[2]]);
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    out.push(BASE64_ALPHABET[(acc >> (18 - 6 * i) & 0x3f) as usize] as char);
+                } else {
+                    out.push('=');
+                }
+            }
+        }
+        f.write_str(&out)
+    }
+}
+
+//Байты строчными шестнадцатеричными цифрами, по две на байт
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+//Байты из hex четной длины в любом регистре; префикс 0x допускается
+pub fn from_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let hex = hex.trim_start_matches("0x");
+    if !hex.len().is_multiple_of(2) {
+        return Err("Нечетная длина hex".to_string());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+                .ok_or_else(|| "Некорректный hex".to_string())
+        })
+        .collect()
+}
+
+//hex фиксированной длины (ключ, aux, подпись)
+pub fn from_hex_array<const N: usize>(hex: &str) -> Result<[u8; N], String> {
+    from_hex(hex)?
+        .try_into()
+        .map_err(|_| format!("Ожидалось байт: {}", N))
+}
+
+//Общие преобразования и serde для обеих оберток
+macro_rules! impl_string_big {
+    ($name:ident) => {
+        impl From<BigUint> for $name {
+            fn from(value: BigUint) -> Self {
+                $name(value)
+            }
+        }
+
+        impl From<$name> for BigUint {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                $name::parse(&s).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+impl_string_big!(HexBig);
+impl_string_big!(B64Big);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_hex() {
+        assert_eq!(to_hex(&[0x00, 0xab, 0x7f]), "00ab7f");
+        assert_eq!(from_hex("00AB7f").unwrap(), vec![0x00, 0xab, 0x7f]);
+        assert_eq!(from_hex("0x01").unwrap(), vec![1]);
+        assert_eq!(from_hex("").unwrap(), Vec::<u8>::new());
+        assert!(from_hex("0g").is_err());
+        assert!(from_hex("abc").is_err());
+        assert!(from_hex("жж").is_err());
+        assert_eq!(from_hex_array::<2>("0102").unwrap(), [1, 2]);
+        assert!(from_hex_array::<32>("00").is_err());
+    }
+
+    #[test]
+    fn test_hex_round_trip_and_strictness() {
+        let x = HexBig(BigUint::from(0xdead_beefu32));
+        let json = serde_json::to_string(&x).unwrap();
+        assert_eq!(json, "\"deadbeef\"");
+        assert_eq!(serde_json::from_str::<HexBig>(&json).unwrap(), x);
+        assert_eq!(HexBig::parse("0").unwrap().to_string(), "0");
+        assert_eq!(
+            HexBig::parse("00FF").unwrap(),
+            HexBig(BigUint::from(255u32))
+        );
+        for bad in ["", "0x10", " 10", "1g", "-1"] {
+            assert!(HexBig::parse(bad).is_err(), "{:?}", bad);
+        }
+        assert!(HexBig::parse(&"f".repeat(2 * MAX_BYTES + 1)).is_err());
+    }
+
+    #[test]
+    fn test_base64_round_trip_and_strictness() {
+        //Векторы RFC 4648: "f", "fo", "foo", "foob"
+        for (bytes, text) in [
+            (&b"f"[..], "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+        ] {
+            let x = B64Big(BigUint::from_bytes_be(bytes));
+            assert_eq!(x.to_string(), text);
+            assert_eq!(B64Big::parse(text).unwrap(), x);
+        }
+        assert_eq!(B64Big::default().to_string(), "AA==");
+        let json = serde_json::to_string(&B64Big(BigUint::from(1u32) << 100u32)).unwrap();
+        assert_eq!(
+            serde_json::from_str::<B64Big>(&json).unwrap().0,
+            BigUint::from(1u32) << 100u32
+        );
+        for bad in [
+            "", "Zg=", "Zg", "Zh==", "Z===", "Zg==Zg==", "Zm9v\n", "Zm-v",
+        ] {
+            assert!(B64Big::parse(bad).is_err(), "{:?}", bad);
+        }
+        assert!(B64Big::parse(&"A".repeat(4 * (MAX_BYTES / 3 + 2))).is_err());
+    }
+}
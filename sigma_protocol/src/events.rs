@@ -0,0 +1,1381 @@
+use axum::extract::ws::{Message as AxumMessage, WebSocket};
+use axum::response::sse::Event;
+use futures_util::SinkExt;
+use num_bigint::BigUint;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{broadcast, mpsc};
+use tracing::warn;
+
+use crate::credential::Presentation;
+use crate::graph::{Graph, Permutation};
+use crate::message::{ProtocolMessage, decimal, decimal_vec};
+use crate::ring::RingSignature;
+use crate::timing::StepTiming;
+
+//События протокола, публикуемые в журнал
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProtocolEvent {
+    ParamsAnnounced {
+        #[serde(with = "decimal")]
+        p: BigUint,
+        #[serde(with = "decimal")]
+        q: BigUint,
+        #[serde(with = "decimal")]
+        g: BigUint,
+        #[serde(with = "decimal")]
+        h: BigUint,
+    },
+    //Ход генерации безопасного простого: expected - ожидаемое число
+    //проверенных кандидатов, по нему можно рисовать полосу прогресса
+    //Параметры группы слабее порога стойкости или имеют слабую форму
+    WeakParams {
+        security_bits: u32,
+        warnings: Vec<String>,
+    },
+    PrimeProgress {
+        bits: u64,
+        windows: u64,
+        tested: u64,
+        mr_rounds: u64,
+        expected: f64,
+        elapsed_ms: u64,
+    },
+    Commitment {
+        #[serde(with = "decimal")]
+        u: BigUint,
+        #[serde(with = "decimal")]
+        ut: BigUint,
+    },
+    SecretDisclosed {
+        #[serde(with = "decimal")]
+        alpha: BigUint,
+        #[serde(with = "decimal")]
+        beta: BigUint,
+    },
+    NonceDisclosed {
+        #[serde(with = "decimal")]
+        alpha: BigUint,
+        #[serde(with = "decimal")]
+        beta: BigUint,
+    },
+    Challenge {
+        #[serde(with = "decimal")]
+        c: BigUint,
+    },
+    //Испытание, зафиксированное до коммитмента, и его раскрытие
+    ChallengeCommitted {
+        #[serde(with = "decimal")]
+        digest: BigUint,
+    },
+    ChallengeRevealed {
+        #[serde(with = "decimal")]
+        c: BigUint,
+        #[serde(with = "decimal")]
+        salt: BigUint,
+        opened: bool,
+    },
+    //Раунд маяка случайности, из которого получено испытание
+    BeaconRound {
+        round: u64,
+        randomness: String,
+    },
+    Response {
+        #[serde(with = "decimal")]
+        alpha: BigUint,
+        #[serde(with = "decimal")]
+        beta: BigUint,
+    },
+    Computed {
+        name: String,
+        #[serde(with = "decimal")]
+        value: BigUint,
+    },
+    Verdict {
+        #[serde(with = "decimal")]
+        uz: BigUint,
+        #[serde(with = "decimal")]
+        utuc: BigUint,
+        accepted: bool,
+    },
+    DleqCommitment {
+        #[serde(with = "decimal")]
+        u1: BigUint,
+        #[serde(with = "decimal")]
+        u2: BigUint,
+        #[serde(with = "decimal")]
+        t1: BigUint,
+        #[serde(with = "decimal")]
+        t2: BigUint,
+    },
+    DleqResponse {
+        #[serde(with = "decimal")]
+        z: BigUint,
+    },
+    DleqVerdict {
+        accepted: bool,
+    },
+    Error {
+        reason: String,
+    },
+    PeerMessage {
+        from: String,
+        message: ProtocolMessage,
+    },
+    //Событие из журнала второго сервера в раздельном режиме
+    PeerEvent {
+        from: String,
+        narration: String,
+    },
+    PeerRetry {
+        attempt: u32,
+        attempts: u32,
+        error: String,
+        delay_ms: u64,
+    },
+    PeerFailed {
+        error: String,
+    },
+    RemoteVerdict {
+        accepted: bool,
+    },
+    RemoteError {
+        reason: String,
+    },
+    Timings {
+        steps: Vec<StepTiming>,
+    },
+    Round {
+        round: u32,
+        rounds: u32,
+    },
+    Soundness {
+        rounds: u32,
+        bits: f64,
+    },
+    Lagged {
+        skipped: u64,
+    },
+    //Решение одного из проверяющих кворума; None - сервер не ответил
+    VerifierVerdict {
+        verifier: String,
+        accepted: Option<bool>,
+    },
+    Consensus {
+        approvals: usize,
+        verifiers: usize,
+        quorum: usize,
+        accepted: bool,
+    },
+    //Шаги симулятора, идущие рядом с честным прогоном
+    SimulatorChoice {
+        #[serde(with = "decimal")]
+        c: BigUint,
+        #[serde(with = "decimal")]
+        alpha: BigUint,
+        #[serde(with = "decimal")]
+        beta: BigUint,
+    },
+    SimulatorCommitment {
+        #[serde(with = "decimal")]
+        ut: BigUint,
+        accepted: bool,
+    },
+    //Экстрактор отматывает доказывающего к коммитменту
+    Rewind,
+    Extracted {
+        #[serde(with = "decimal")]
+        alpha: BigUint,
+        #[serde(with = "decimal")]
+        beta: BigUint,
+        //Совпадает ли с секретом доказывающего
+        matches: bool,
+    },
+    //Протокол Фейге-Фиата-Шамира
+    FfsKeys {
+        #[serde(with = "decimal")]
+        n: BigUint,
+        #[serde(with = "decimal_vec")]
+        keys: Vec<BigUint>,
+    },
+    FfsCommitment {
+        #[serde(with = "decimal")]
+        x: BigUint,
+    },
+    FfsChallenge {
+        bits: Vec<bool>,
+    },
+    FfsResponse {
+        #[serde(with = "decimal")]
+        y: BigUint,
+    },
+    //Шифрование Эль-Гамаля и доказательство знания открытого текста
+    ElGamalKey {
+        #[serde(with = "decimal")]
+        y: BigUint,
+    },
+    ElGamalCiphertext {
+        #[serde(with = "decimal")]
+        c1: BigUint,
+        #[serde(with = "decimal")]
+        c2: BigUint,
+    },
+    ElGamalCommitment {
+        #[serde(with = "decimal")]
+        t1: BigUint,
+        #[serde(with = "decimal")]
+        t2: BigUint,
+    },
+    ElGamalResponse {
+        #[serde(with = "decimal")]
+        zm: BigUint,
+        #[serde(with = "decimal")]
+        zr: BigUint,
+    },
+    ElGamalVerdict {
+        accepted: bool,
+    },
+    ElGamalDecrypted {
+        #[serde(with = "decimal")]
+        m: BigUint,
+    },
+    //Анонимное удостоверение: атрибуты видит только сервер
+    CredentialAttributes {
+        names: Vec<String>,
+        #[serde(with = "decimal_vec")]
+        values: Vec<BigUint>,
+    },
+    CredentialIssued {
+        #[serde(with = "decimal")]
+        commitment: BigUint,
+    },
+    CredentialPresented {
+        presentation: Presentation,
+    },
+    CredentialVerdict {
+        accepted: bool,
+    },
+    //Распределенная генерация ключа
+    DkgStarted {
+        parties: usize,
+        threshold: usize,
+    },
+    DkgCommitments {
+        index: u32,
+        #[serde(with = "decimal_vec")]
+        commitments: Vec<BigUint>,
+    },
+    DkgShareReceived {
+        from: u32,
+        to: u32,
+        valid: bool,
+    },
+    DkgShareKey {
+        index: u32,
+        #[serde(with = "decimal")]
+        verification_key: BigUint,
+    },
+    DkgVerdict {
+        #[serde(with = "decimal")]
+        public_key: BigUint,
+        accepted: bool,
+    },
+    //Обмен ключами: свои ключи, ключи второго сервера и ключ сессии
+    HandshakeSent {
+        #[serde(with = "decimal")]
+        static_key: BigUint,
+        #[serde(with = "decimal")]
+        ephemeral: BigUint,
+    },
+    HandshakeReceived {
+        #[serde(with = "decimal")]
+        static_key: BigUint,
+        #[serde(with = "decimal")]
+        ephemeral: BigUint,
+        valid: bool,
+    },
+    HandshakeEstablished {
+        fingerprint: String,
+    },
+    //Совместная подпись MuSig: ключи, хэши R_i, R_i и частичные подписи
+    MuSigKeys {
+        #[serde(with = "decimal_vec")]
+        keys: Vec<BigUint>,
+        #[serde(with = "decimal")]
+        aggregate: BigUint,
+    },
+    MuSigCommitments {
+        #[serde(with = "decimal_vec")]
+        commitments: Vec<BigUint>,
+    },
+    MuSigNonces {
+        #[serde(with = "decimal_vec")]
+        nonces: Vec<BigUint>,
+    },
+    MuSigPartials {
+        #[serde(with = "decimal_vec")]
+        partials: Vec<BigUint>,
+    },
+    MuSigSignature {
+        message: String,
+        #[serde(with = "decimal")]
+        r: BigUint,
+        #[serde(with = "decimal")]
+        s: BigUint,
+    },
+    MuSigVerdict {
+        accepted: bool,
+    },
+    //Аргумент скалярного произведения: P = g^a * h^b * u^<a, b>
+    IpaStatement {
+        n: usize,
+        #[serde(with = "decimal")]
+        p: BigUint,
+    },
+    IpaRound {
+        round: usize,
+        #[serde(with = "decimal")]
+        l: BigUint,
+        #[serde(with = "decimal")]
+        r: BigUint,
+    },
+    IpaFinal {
+        #[serde(with = "decimal")]
+        a: BigUint,
+        #[serde(with = "decimal")]
+        b: BigUint,
+    },
+    IpaVerdict {
+        accepted: bool,
+        size: usize,
+        naive: usize,
+    },
+    //Слепая подпись Шнорра: сервер видит только R, c и s
+    BlindCommitment {
+        session: String,
+        #[serde(with = "decimal")]
+        r: BigUint,
+    },
+    BlindChallenge {
+        session: String,
+        #[serde(with = "decimal")]
+        c: BigUint,
+    },
+    BlindResponse {
+        session: String,
+        #[serde(with = "decimal")]
+        s: BigUint,
+    },
+    BlindSignature {
+        #[serde(with = "decimal")]
+        r: BigUint,
+        #[serde(with = "decimal")]
+        c: BigUint,
+        #[serde(with = "decimal")]
+        s: BigUint,
+    },
+    BlindVerdict {
+        accepted: bool,
+    },
+    //Кольцевая подпись: номер подписавшего виден только серверу
+    RingSigner {
+        index: usize,
+        size: usize,
+    },
+    RingSigned {
+        message: String,
+        signature: RingSignature,
+    },
+    RingVerdict {
+        accepted: bool,
+    },
+    //Протокол изоморфизма графов
+    GiGraphs {
+        g0: Graph,
+        g1: Graph,
+    },
+    GiCommitment {
+        h: Graph,
+    },
+    GiChallenge {
+        bit: bool,
+    },
+    GiResponse {
+        permutation: Permutation,
+    },
+    GiVerdict {
+        accepted: bool,
+    },
+    //Промежуточный итог попыток обмана без секрета
+    Forgery {
+        attempts: u64,
+        accepted: u64,
+        rate: f64,
+        expected: f64,
+    },
+    //Атака на учебные параметры: разложение p - 1
+    Factored {
+        #[serde(with = "decimal")]
+        n: BigUint,
+        #[serde(with = "decimal_vec")]
+        factors: Vec<BigUint>,
+    },
+    //Порядок подгруппы слишком велик для перебора логарифма
+    AttackInfeasible {
+        bits: u64,
+    },
+    //Шаги великана в поиске log_g u
+    BsgsProgress {
+        step: u64,
+        steps: u64,
+    },
+    //Секрет, восстановленный по открытому ключу, и проверка доказательства с ним
+    Attacked {
+        #[serde(with = "decimal")]
+        alpha: BigUint,
+        #[serde(with = "decimal")]
+        beta: BigUint,
+        accepted: bool,
+    },
+}
+
+//Отступ для многострочных фрагментов журнала
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("\t   {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+//Событие с полями для программ и текстом для человека
+#[derive(Serialize)]
+struct NarratedEvent<'a> {
+    #[serde(flatten)]
+    event: &'a ProtocolEvent,
+    narration: String,
+}
+
+impl ProtocolEvent {
+    //JSON события вместе с полем narration
+    pub fn to_narrated_json(&self) -> Result<String, String> {
+        serde_json::to_string(&NarratedEvent {
+            event: self,
+            narration: self.narration(),
+        })
+        .map_err(|e| e.to_string())
+    }
+
+    //Текст для человека
+    pub fn narration(&self) -> String {
+        match self {
+            ProtocolEvent::ParamsAnnounced { p, q, g, h } => format!(
+                "Сервер: Правила сервера: \n\t p = {} \n\t q = {} \n\t g = {} \n\t h = {}",
+                p, q, g, h
+            ),
+            ProtocolEvent::WeakParams {
+                security_bits,
+                warnings,
+            } => format!(
+                "Сервер: Внимание! Параметры группы небезопасны (стойкость около {} бит): \n\t {}",
+                security_bits,
+                warnings.join(" \n\t ")
+            ),
+            ProtocolEvent::PrimeProgress {
+                bits,
+                windows,
+                tested,
+                mr_rounds,
+                expected,
+                elapsed_ms,
+            } => format!(
+                "Сервер: Поиск безопасного простого длиной {} бит: {} окон решета, проверено {} кандидатов (ожидается около {:.0}), {} раундов Миллера-Рабина за {:.1} с",
+                bits,
+                windows,
+                tested,
+                expected,
+                mr_rounds,
+                *elapsed_ms as f64 / 1000.0
+            ),
+            ProtocolEvent::Commitment { u, ut } => format!(
+                "Павел: Привет, я Павел! И я знаю секретный ключ! \n\t Вот мой публичный ключ(u): {} \n\t И дополнительный ключ для доказательства (u_t): {}",
+                u, ut
+            ),
+            ProtocolEvent::SecretDisclosed { alpha, beta } => format!(
+                "Сервер: Виктор не получит следующее сообщение: \n\t Секретный ключ Павла: ({}, {})",
+                alpha, beta
+            ),
+            ProtocolEvent::NonceDisclosed { alpha, beta } => format!(
+                "Сервер: Виктор не получит следующее сообщение: \n\t Дополнительный ключ: ({}, {})",
+                alpha, beta
+            ),
+            ProtocolEvent::Challenge { c } => format!(
+                "Виктор: Привет, я Виктор. Докажи что ты знаешь секретный ключ, твое испытание: {}",
+                c
+            ),
+            ProtocolEvent::ChallengeCommitted { digest } => format!(
+                "Виктор: Я уже выбрал испытание, но покажу его после твоего u_t \n\t Коммитмент испытания H(s, c) = {}",
+                digest
+            ),
+            ProtocolEvent::ChallengeRevealed {
+                c,
+                salt,
+                opened: true,
+            } => format!(
+                "Виктор: Твое испытание: {}, соль s = {} \n\t Павел: H(s, c) совпадает с коммитментом, испытание не подобрано под u_t",
+                c, salt
+            ),
+            ProtocolEvent::ChallengeRevealed {
+                c, opened: false, ..
+            } => format!(
+                "Павел: ❌ Испытание {} не совпадает с коммитментом Виктора!",
+                c
+            ),
+            ProtocolEvent::BeaconRound { round, randomness } => format!(
+                "Виктор: Испытание беру из публичного маяка, раунд {} \n\t Случайность раунда: {} \n\t c = H(раунд, случайность, u, u_t) - любой может пересчитать",
+                round, randomness
+            ),
+            ProtocolEvent::Response { alpha, beta } => format!(
+                "Павел: Я успешно вычислил \n\t a_z = {} \n\t b_z = {}",
+                alpha, beta
+            ),
+            ProtocolEvent::Computed { name, value } => {
+                format!("Виктор: Я успешно вычислил {} = {}", name, value)
+            }
+            ProtocolEvent::Verdict {
+                uz,
+                utuc,
+                accepted: true,
+            } => format!(
+                "Виктор: {} = {} \n\t Павел, вы знаете секретный ключ!",
+                uz, utuc
+            ),
+            ProtocolEvent::Verdict {
+                uz,
+                utuc,
+                accepted: false,
+            } => format!(
+                "Виктор: {} != {} \n\t Павел, вы не знаете секретный ключ!",
+                uz, utuc
+            ),
+            ProtocolEvent::DleqCommitment { u1, u2, t1, t2 } => format!(
+                "Павел: Привет, я Павел! Логарифмы u1 = {} по основанию g и u2 = {} по основанию h совпадают! \n\t Мой коммитмент: t1 = {}, t2 = {}",
+                u1, u2, t1, t2
+            ),
+            ProtocolEvent::DleqResponse { z } => {
+                format!("Павел: Я успешно вычислил \n\t z = {}", z)
+            }
+            ProtocolEvent::DleqVerdict { accepted: true } => {
+                "Виктор: g^z = t1 * u1^c и h^z = t2 * u2^c \n\t Павел, логарифмы совпадают!"
+                    .to_string()
+            }
+            ProtocolEvent::DleqVerdict { accepted: false } => {
+                "Виктор: Равенства не выполняются \n\t Павел, вы не доказали совпадение логарифмов!"
+                    .to_string()
+            }
+            ProtocolEvent::Error { reason } => {
+                format!("Задача завершена с ошибкой! {}", reason)
+            }
+            ProtocolEvent::PeerMessage { from, message } => {
+                format!("{}: {}", from, message.describe())
+            }
+            ProtocolEvent::PeerEvent { from, narration } => format!("[{}] {}", from, narration),
+            ProtocolEvent::PeerRetry {
+                attempt,
+                attempts,
+                error,
+                delay_ms,
+            } => format!(
+                "Сервер: ⚠️ Второй сервер недоступен (попытка {}/{}): {}. Повтор через {} мс",
+                attempt, attempts, error, delay_ms
+            ),
+            ProtocolEvent::PeerFailed { error } => format!("Сервер: ❌ {}", error),
+            ProtocolEvent::RemoteVerdict { accepted: true } => {
+                "Виктор: Доказывающий по WebSocket знает секретный ключ!".to_string()
+            }
+            ProtocolEvent::RemoteVerdict { accepted: false } => {
+                "Виктор: Доказывающий по WebSocket не знает секретный ключ!".to_string()
+            }
+            ProtocolEvent::RemoteError { reason } => {
+                format!("Виктор: Ошибка протокола по WebSocket: {}", reason)
+            }
+            ProtocolEvent::Timings { steps } => {
+                let mut text = "Сервер: Время шагов протокола:".to_string();
+                for step in steps {
+                    text += &format!(
+                        "\n\t {} (+{:.1} мс): вычисления {:.3} мс, задержка {:.1} мс",
+                        step.step, step.at_ms, step.compute_ms, step.delay_ms
+                    );
+                }
+                text
+            }
+            ProtocolEvent::Round { round, rounds } => {
+                format!("Сервер: Раунд {} из {}", round, rounds)
+            }
+            ProtocolEvent::Soundness { rounds, bits } if *bits == 0.0 => format!(
+                "Сервер: Раундов: {}. Испытание фиксировано, поэтому повторение не снижает вероятность обмана",
+                rounds
+            ),
+            ProtocolEvent::Soundness { rounds, bits } => format!(
+                "Сервер: Раундов: {}. Вероятность успешного обмана не выше 2^-{:.1}",
+                rounds, bits
+            ),
+            ProtocolEvent::Lagged { skipped } => {
+                format!("⚠️ Пропущено {} сообщений", skipped)
+            }
+            ProtocolEvent::VerifierVerdict {
+                verifier,
+                accepted: Some(true),
+            } => format!("Проверяющий {}: доказательство принято", verifier),
+            ProtocolEvent::VerifierVerdict {
+                verifier,
+                accepted: Some(false),
+            } => format!("Проверяющий {}: доказательство отвергнуто", verifier),
+            ProtocolEvent::VerifierVerdict {
+                verifier,
+                accepted: None,
+            } => format!("Проверяющий {}: ⚠️ не ответил", verifier),
+            ProtocolEvent::Consensus {
+                approvals,
+                verifiers,
+                quorum,
+                accepted,
+            } => format!(
+                "Сервер: Подтвердили {} из {} проверяющих при кворуме {}: доказательство {}",
+                approvals,
+                verifiers,
+                quorum,
+                if *accepted {
+                    "принято"
+                } else {
+                    "отвергнуто"
+                }
+            ),
+            ProtocolEvent::SimulatorChoice { c, alpha, beta } => format!(
+                "Симулятор: Я не знаю секретного ключа, поэтому сначала выбираю испытание и ответ: \n\t c = {} \n\t a_z = {} \n\t b_z = {}",
+                c, alpha, beta
+            ),
+            ProtocolEvent::SimulatorCommitment { ut, accepted: true } => format!(
+                "Симулятор: Вычисляю коммитмент обратно: u_t = g^a_z * h^b_z * u^-c = {} \n\t Проверка Виктора пройдена, хотя секрет мне неизвестен: стенограмма ничего не раскрывает о секрете",
+                ut
+            ),
+            ProtocolEvent::SimulatorCommitment {
+                ut,
+                accepted: false,
+            } => format!(
+                "Симулятор: Вычисляю коммитмент обратно: u_t = {} \n\t Проверка Виктора не пройдена",
+                ut
+            ),
+            ProtocolEvent::Rewind => {
+                "Экстрактор: Отматываю Павла к моменту после коммитмента и задаю другое испытание"
+                    .to_string()
+            }
+            ProtocolEvent::Extracted {
+                alpha,
+                beta,
+                matches: true,
+            } => format!(
+                "Экстрактор: Из двух ответов на один коммитмент \n\t alpha = (a_z1 - a_z2) / (c1 - c2) = {} \n\t beta = (b_z1 - b_z2) / (c1 - c2) = {} \n\t Это секретный ключ Павла: кто отвечает на два испытания, тот знает секрет",
+                alpha, beta
+            ),
+            ProtocolEvent::Extracted {
+                alpha,
+                beta,
+                matches: false,
+            } => format!(
+                "Экстрактор: Получено ({}, {}), но это не секретный ключ Павла",
+                alpha, beta
+            ),
+            ProtocolEvent::FfsKeys { n, keys } => {
+                let mut text = format!(
+                    "Сервер: Протокол Фейге-Фиата-Шамира, модуль n = {} \n\t Открытые ключи Павла v_i = s_i^2 mod n:",
+                    n
+                );
+                for (i, v) in keys.iter().enumerate() {
+                    text += &format!("\n\t v_{} = {}", i + 1, v);
+                }
+                text
+            }
+            ProtocolEvent::FfsCommitment { x } => {
+                format!("Павел: Мой коммитмент x = r^2 mod n = {}", x)
+            }
+            ProtocolEvent::FfsChallenge { bits } => format!(
+                "Виктор: Твое испытание - биты a = {}",
+                bits.iter()
+                    .map(|b| if *b { "1" } else { "0" })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            ProtocolEvent::FfsResponse { y } => {
+                format!("Павел: Мой ответ y = r * П s_i^a_i mod n = {}", y)
+            }
+            ProtocolEvent::ElGamalKey { y } => format!(
+                "Виктор: Мой открытый ключ Эль-Гамаля y = g^x = {}",
+                y
+            ),
+            ProtocolEvent::ElGamalCiphertext { c1, c2 } => format!(
+                "Павел: Шифрую число m для Виктора: \n\t c1 = g^r = {} \n\t c2 = g^m * y^r = {}",
+                c1, c2
+            ),
+            ProtocolEvent::ElGamalCommitment { t1, t2 } => format!(
+                "Павел: Я знаю m и r! Мой коммитмент - шифрование случайных (a, b): \n\t t1 = g^b = {} \n\t t2 = g^a * y^b = {}",
+                t1, t2
+            ),
+            ProtocolEvent::ElGamalResponse { zm, zr } => format!(
+                "Павел: Я успешно вычислил \n\t z_m = a + m*c = {} \n\t z_r = b + r*c = {}",
+                zm, zr
+            ),
+            ProtocolEvent::ElGamalVerdict { accepted: true } => {
+                "Виктор: g^z_r = t1 * c1^c и g^z_m * y^z_r = t2 * c2^c \n\t Павел, вы знаете открытый текст!"
+                    .to_string()
+            }
+            ProtocolEvent::ElGamalVerdict { accepted: false } => {
+                "Виктор: Равенства не выполняются \n\t Павел, вы не доказали знание открытого текста!"
+                    .to_string()
+            }
+            ProtocolEvent::ElGamalDecrypted { m } => {
+                format!("Виктор: Расшифровал своим ключом x: g^m = c2 / c1^x, m = {}", m)
+            }
+            ProtocolEvent::CredentialAttributes { names, values } => {
+                let mut text =
+                    "Сервер: Виктор не получит следующее сообщение: \n\t Атрибуты Павла:".to_string();
+                for (i, (name, value)) in names.iter().zip(values).enumerate() {
+                    text += &format!("\n\t a_{} ({}) = {}", i + 1, name, value);
+                }
+                text
+            }
+            ProtocolEvent::CredentialIssued { commitment } => format!(
+                "Сервер: Выдаю Павлу подписанное удостоверение C = h^r * П g_i^a_i = {}",
+                commitment
+            ),
+            ProtocolEvent::CredentialPresented { presentation } => {
+                let mut text = format!(
+                    "Павел: Предъявляю удостоверение C = {}",
+                    presentation.commitment
+                );
+                for d in &presentation.disclosed {
+                    text += &format!("\n\t Раскрываю a_{} = {}", d.index + 1, d.value);
+                }
+                text += &format!(
+                    "\n\t Доказываю, не раскрывая: a_{} ∈ {{{}}} \n\t Остальные атрибуты скрыты; ветвей дизъюнкции: {}",
+                    presentation.predicate.index + 1,
+                    presentation
+                        .predicate
+                        .values
+                        .iter()
+                        .map(|v| v.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    presentation.branches.len()
+                );
+                text
+            }
+            ProtocolEvent::CredentialVerdict { accepted: true } => {
+                "Виктор: Подпись сервера на C верна, сумма испытаний ветвей равна хэшу \n\t Павел, удостоверение принято!".to_string()
+            }
+            ProtocolEvent::CredentialVerdict { accepted: false } => {
+                "Виктор: Проверка не пройдена \n\t Павел, удостоверение отвергнуто!".to_string()
+            }
+            ProtocolEvent::DkgStarted { parties, threshold } => format!(
+                "Сервер: Генерация общего ключа: участников {}, порог {} \n\t Каждый раздает свой секрет, общий секрет не узнает никто",
+                parties, threshold
+            ),
+            ProtocolEvent::DkgCommitments { index, commitments } => {
+                let mut text = format!("Участник {}: Коммитменты моего многочлена:", index);
+                for (k, c) in commitments.iter().enumerate() {
+                    text += &format!("\n\t C_{},{} = g^a_{},{} = {}", index, k, index, k, c);
+                }
+                text
+            }
+            ProtocolEvent::DkgShareReceived {
+                from,
+                to,
+                valid: true,
+            } => format!(
+                "Участник {}: Доля от участника {} совпадает с его коммитментами",
+                to, from
+            ),
+            ProtocolEvent::DkgShareReceived {
+                from,
+                to,
+                valid: false,
+            } => format!(
+                "Участник {}: ❌ Доля от участника {} не прошла проверку!",
+                to, from
+            ),
+            ProtocolEvent::DkgShareKey {
+                index,
+                verification_key,
+            } => format!(
+                "Участник {}: Моя доля x_{} = Σ f_i({}) \n\t Ключ проверки g^x_{} = {}",
+                index, index, index, index, verification_key
+            ),
+            ProtocolEvent::DkgVerdict {
+                public_key,
+                accepted: true,
+            } => format!(
+                "Сервер: Общий ключ Y = П C_i,0 = {} \n\t Ключи проверки порога участников дают тот же Y",
+                public_key
+            ),
+            ProtocolEvent::DkgVerdict {
+                public_key,
+                accepted: false,
+            } => format!(
+                "Сервер: ❌ Участники получили несогласованный ключ Y = {}",
+                public_key
+            ),
+            ProtocolEvent::HandshakeSent {
+                static_key,
+                ephemeral,
+            } => format!(
+                "Сервер: Мой постоянный ключ Y = g^x = {} \n\t Одноразовый ключ E = g^e = {} \n\t Доказываю знание x, испытание - хэш всех ключей обмена",
+                static_key, ephemeral
+            ),
+            ProtocolEvent::HandshakeReceived {
+                static_key,
+                ephemeral,
+                valid: true,
+            } => format!(
+                "Сервер: Второй сервер доказал знание ключа Y' = {} \n\t Его одноразовый ключ E' = {}",
+                static_key, ephemeral
+            ),
+            ProtocolEvent::HandshakeReceived {
+                static_key,
+                valid: false,
+                ..
+            } => format!(
+                "Сервер: ❌ Второй сервер не доказал знание ключа Y' = {}",
+                static_key
+            ),
+            ProtocolEvent::HandshakeEstablished { fingerprint } => format!(
+                "Сервер: Общий ключ сессии из E'^e, отпечаток {} \n\t Дальнейшие сообщения второму серверу шифруются",
+                fingerprint
+            ),
+            ProtocolEvent::MuSigKeys { keys, aggregate } => {
+                let mut text = "Сервер: Ключи участников совместной подписи:".to_string();
+                for (i, key) in keys.iter().enumerate() {
+                    text += &format!("\n\t X_{} = {}", i + 1, key);
+                }
+                text + &format!("\n\t Агрегированный ключ X = П X_i^H(L, X_i) = {}", aggregate)
+            }
+            ProtocolEvent::MuSigCommitments { commitments } => {
+                let mut text = "Участники: Наши хэши t_i = H(R_i), сами R_i пока скрыты:".to_string();
+                for (i, t) in commitments.iter().enumerate() {
+                    text += &format!("\n\t t_{} = {}", i + 1, t);
+                }
+                text
+            }
+            ProtocolEvent::MuSigNonces { nonces } => {
+                let mut text = "Участники: Получив все хэши, раскрываем R_i = g^r_i:".to_string();
+                for (i, r) in nonces.iter().enumerate() {
+                    text += &format!("\n\t R_{} = {}", i + 1, r);
+                }
+                text
+            }
+            ProtocolEvent::MuSigPartials { partials } => {
+                let mut text = "Участники: Частичные подписи s_i = r_i + c * a_i * x_i:".to_string();
+                for (i, s) in partials.iter().enumerate() {
+                    text += &format!("\n\t s_{} = {}", i + 1, s);
+                }
+                text
+            }
+            ProtocolEvent::MuSigSignature { message, r, s } => format!(
+                "Сервер: Совместная подпись сообщения \"{}\": \n\t R = П R_i = {} \n\t s = Σ s_i = {}",
+                message, r, s
+            ),
+            ProtocolEvent::MuSigVerdict { accepted: true } => {
+                "Виктор: g^s = R * X^c \n\t Подпись верна для одного агрегированного ключа!".to_string()
+            }
+            ProtocolEvent::MuSigVerdict { accepted: false } => {
+                "Виктор: g^s != R * X^c \n\t Совместная подпись неверна!".to_string()
+            }
+            ProtocolEvent::IpaStatement { n, p } => format!(
+                "Павел: Я знаю векторы a, b длины {} такие, что \n\t P = g^a * h^b * u^<a, b> = {}",
+                n, p
+            ),
+            ProtocolEvent::IpaRound { round, l, r } => format!(
+                "Павел: Раунд {}: сворачиваю векторы вдвое \n\t L = {} \n\t R = {}",
+                round, l, r
+            ),
+            ProtocolEvent::IpaFinal { a, b } => format!(
+                "Павел: Остались числа \n\t a = {} \n\t b = {}",
+                a, b
+            ),
+            ProtocolEvent::IpaVerdict {
+                accepted: true,
+                size,
+                naive,
+            } => format!(
+                "Виктор: Свернутое P = g^a * h^b * u^(a*b) \n\t Павел, доказательство принято! Передано значений: {} вместо {}",
+                size, naive
+            ),
+            ProtocolEvent::IpaVerdict {
+                accepted: false, ..
+            } => "Виктор: Свернутое P не совпадает \n\t Павел, доказательство отвергнуто!".to_string(),
+            ProtocolEvent::BlindCommitment { session, r } => format!(
+                "Виктор: Открываю сессию слепой подписи {}: R = g^k = {}",
+                session, r
+            ),
+            ProtocolEvent::BlindChallenge { session, c } => format!(
+                "Павел: Ослепленное испытание для сессии {}: c = c' + beta = {}",
+                session, c
+            ),
+            ProtocolEvent::BlindResponse { session, s } => format!(
+                "Виктор: Подписываю вслепую в сессии {}: s = k + c*x = {}",
+                session, s
+            ),
+            ProtocolEvent::BlindSignature { r, c, s } => format!(
+                "Павел: Снимаю ослепление: \n\t R' = R * g^alpha * y^beta = {} \n\t c' = H(R', y, m) = {} \n\t s' = s + alpha = {} \n\t Виктор не видел ни R', ни c', ни s'",
+                r, c, s
+            ),
+            ProtocolEvent::BlindVerdict { accepted: true } => {
+                "Виктор: g^s' = R' * y^c' \n\t Подпись верна, но связать ее с сессией подписи нельзя".to_string()
+            }
+            ProtocolEvent::BlindVerdict { accepted: false } => {
+                "Виктор: g^s' != R' * y^c' \n\t Подпись неверна!".to_string()
+            }
+            ProtocolEvent::RingSigner { index, size } => format!(
+                "Сервер: Виктор не получит следующее сообщение: \n\t Подписывает участник {} из {}",
+                index + 1,
+                size
+            ),
+            ProtocolEvent::RingSigned { message, signature } => {
+                let mut text = format!(
+                    "Павел: Подпись сообщения \"{}\" от имени кольца:",
+                    message
+                );
+                for (i, ((y, c), z)) in signature
+                    .ring
+                    .iter()
+                    .zip(&signature.challenges)
+                    .zip(&signature.responses)
+                    .enumerate()
+                {
+                    text += &format!("\n\t y_{} = {} \n\t   c_{} = {}, z_{} = {}", i + 1, y, i + 1, c, i + 1, z);
+                }
+                text
+            }
+            ProtocolEvent::RingVerdict { accepted: true } => {
+                "Виктор: c_1 + ... + c_N = H(кольцо, сообщение, g^z_i * y_i^-c_i) \n\t Подпись верна, но кто из кольца ее поставил, неизвестно".to_string()
+            }
+            ProtocolEvent::RingVerdict { accepted: false } => {
+                "Виктор: Сумма испытаний не совпадает с хэшем \n\t Подпись неверна!".to_string()
+            }
+            ProtocolEvent::GiGraphs { g0, g1 } => format!(
+                "Сервер: Изоморфизм графов на {} вершинах. Павел знает перестановку pi: G0 -> G1 \n\t G0: \n{} \n\t G1: \n{}",
+                g0.vertices(),
+                indent(&g0.render()),
+                indent(&g1.render())
+            ),
+            ProtocolEvent::GiCommitment { h } => format!(
+                "Павел: Мой коммитмент - случайная копия H = sigma(G1): \n{}",
+                indent(&h.render())
+            ),
+            ProtocolEvent::GiChallenge { bit } => format!(
+                "Виктор: Твое испытание b = {}: покажи изоморфизм G{} -> H",
+                *bit as u8, *bit as u8
+            ),
+            ProtocolEvent::GiResponse { permutation } => format!(
+                "Павел: Перестановка rho = [{}]",
+                permutation
+                    .as_slice()
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            ProtocolEvent::GiVerdict { accepted: true } => {
+                "Виктор: rho(G_b) = H \n\t Павел, вы знаете изоморфизм!".to_string()
+            }
+            ProtocolEvent::GiVerdict { accepted: false } => {
+                "Виктор: rho(G_b) != H \n\t Павел, вы не знаете изоморфизм!".to_string()
+            }
+            ProtocolEvent::Forgery {
+                attempts,
+                accepted,
+                rate,
+                expected,
+            } => format!(
+                "Обманщик: Принято {} из {} попыток ({:.4}%), теоретически 1/|C| = {:.4}%",
+                accepted,
+                attempts,
+                rate * 100.0,
+                expected * 100.0
+            ),
+            ProtocolEvent::Factored { n, factors } => format!(
+                "Атакующий: ρ-метод Полларда: p - 1 = {} = {} \n\t Наибольший простой множитель задает порядок подгруппы",
+                n,
+                factors
+                    .iter()
+                    .map(|f| f.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" * ")
+            ),
+            ProtocolEvent::AttackInfeasible { bits } => format!(
+                "Атакующий: Порядок подгруппы q длиной {} бит: перебор логарифма невозможен, параметры стойкие",
+                bits
+            ),
+            ProtocolEvent::BsgsProgress { step, steps } => format!(
+                "Атакующий: Шаги младенца и великана: {} из {} шагов великана",
+                step, steps
+            ),
+            ProtocolEvent::Attacked {
+                alpha,
+                beta,
+                accepted: true,
+            } => format!(
+                "Атакующий: Нашел log_g u и секрет \n\t alpha = {} \n\t beta = {} \n\t Виктор принял мое доказательство: учебные параметры не дают никакой защиты",
+                alpha, beta
+            ),
+            ProtocolEvent::Attacked {
+                alpha,
+                beta,
+                accepted: false,
+            } => format!(
+                "Атакующий: Секрет ({}, {}) найден, но доказательство отвергнуто",
+                alpha, beta
+            ),
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Шина событий
+//////////////////////////////////////////////////////////////
+
+//Событие с номером: номера растут на единицу в пределах шины
+#[derive(Debug, Clone)]
+pub struct Numbered {
+    pub id: u64,
+    pub event: ProtocolEvent,
+}
+
+//Последние события шины для переподключившихся клиентов
+#[derive(Debug)]
+struct History {
+    next_id: u64,
+    events: VecDeque<Numbered>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<Numbered>,
+    history: Arc<Mutex<History>>,
+    capacity: usize,
+    background: usize,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        let history = History {
+            next_id: 1,
+            events: VecDeque::with_capacity(capacity),
+        };
+        EventBus {
+            tx,
+            history: Arc::new(Mutex::new(history)),
+            capacity,
+            background: 0,
+        }
+    }
+
+    //Номер присваивается и событие рассылается под одной блокировкой,
+    //поэтому resume не теряет и не повторяет события
+    pub fn publish(&self, event: ProtocolEvent) {
+        let mut history = self.history.lock().unwrap();
+        let numbered = Numbered {
+            id: history.next_id,
+            event,
+        };
+        history.next_id += 1;
+        if history.events.len() == self.capacity {
+            history.events.pop_front();
+        }
+        history.events.push_back(numbered.clone());
+        let _ = self
+            .tx
+            .send(numbered)
+            .inspect_err(|e| warn!("Error log stream: {}", e));
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Numbered> {
+        self.tx.subscribe()
+    }
+
+    //Перекачка в приемник всех событий после last_id: сначала из буфера,
+    //затем новых. Вытесненные из буфера события заменяются уведомлением Lagged
+    pub fn resume<S: EventSink + 'static>(
+        &self,
+        last_id: u64,
+        mut sink: S,
+    ) -> impl Future<Output = ()> + Send + 'static {
+        let (rx, backlog, skipped) = {
+            let history = self.history.lock().unwrap();
+            let rx = self.tx.subscribe();
+            let backlog: Vec<Numbered> = history
+                .events
+                .iter()
+                .filter(|numbered| numbered.id > last_id)
+                .cloned()
+                .collect();
+            let first = backlog.first().map_or(history.next_id, |n| n.id);
+            (rx, backlog, first.saturating_sub(last_id + 1))
+        };
+        async move {
+            if skipped > 0 && sink.emit(&ProtocolEvent::Lagged { skipped }).await.is_err() {
+                return;
+            }
+            for numbered in backlog {
+                if sink
+                    .emit_numbered(numbered.id, &numbered.event)
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+            pump(rx, sink).await
+        }
+    }
+
+    //Фоновый приемник (файл и т.п.), не считается подключенным клиентом
+    pub fn attach<S: EventSink + 'static>(&mut self, sink: S) {
+        self.background += 1;
+        tokio::spawn(pump(self.subscribe(), sink));
+    }
+
+    //Число подключенных клиентов (SSE, WebSocket)
+    pub fn live_subscribers(&self) -> usize {
+        self.tx.receiver_count().saturating_sub(self.background)
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Приемники событий: здесь события превращаются в текст
+//////////////////////////////////////////////////////////////
+
+pub trait EventSink: Send {
+    fn emit(&mut self, event: &ProtocolEvent) -> impl Future<Output = Result<(), String>> + Send;
+
+    //Событие с номером шины; номер нужен только приемникам с переподключением
+    fn emit_numbered(
+        &mut self,
+        _id: u64,
+        event: &ProtocolEvent,
+    ) -> impl Future<Output = Result<(), String>> + Send {
+        self.emit(event)
+    }
+}
+
+//Перекачка событий из шины в приемник, пока приемник принимает
+pub async fn pump<S: EventSink>(mut rx: broadcast::Receiver<Numbered>, mut sink: S) {
+    loop {
+        let sent = match rx.recv().await {
+            Ok(numbered) => sink.emit_numbered(numbered.id, &numbered.event).await,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                sink.emit(&ProtocolEvent::Lagged { skipped }).await
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        if sent.is_err() {
+            return;
+        }
+    }
+}
+
+pub struct NullSink;
+
+impl EventSink for NullSink {
+    async fn emit(&mut self, _event: &ProtocolEvent) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+//Server-Sent Events: поток ответа питается из канала
+pub struct SseSink {
+    tx: mpsc::Sender<Result<Event, Infallible>>,
+}
+
+impl SseSink {
+    pub fn new(tx: mpsc::Sender<Result<Event, Infallible>>) -> Self {
+        SseSink { tx }
+    }
+}
+
+impl SseSink {
+    async fn send(&mut self, sse: Event) -> Result<(), String> {
+        self.tx
+            .send(Ok(sse))
+            .await
+            .map_err(|_| "Клиент SSE отключился".to_string())
+    }
+
+    //Ход поиска простого уходит отдельным событием для индикатора,
+    //остальное - в общий поток; данные всегда JSON с narration
+    fn event(event: &ProtocolEvent) -> Result<Event, String> {
+        let data = event.to_narrated_json()?;
+        Ok(match event {
+            ProtocolEvent::PrimeProgress { .. } => {
+                Event::default().event("prime_progress").data(data)
+            }
+            _ => Event::default().data(data),
+        })
+    }
+}
+
+impl EventSink for SseSink {
+    async fn emit(&mut self, event: &ProtocolEvent) -> Result<(), String> {
+        let sse = Self::event(event)?;
+        self.send(sse).await
+    }
+
+    //Номер уходит в id события: браузер вернет его в Last-Event-ID
+    async fn emit_numbered(&mut self, id: u64, event: &ProtocolEvent) -> Result<(), String> {
+        let sse = Self::event(event)?.id(id.to_string());
+        self.send(sse).await
+    }
+}
+
+impl EventSink for WebSocket {
+    async fn emit(&mut self, event: &ProtocolEvent) -> Result<(), String> {
+        SinkExt::send(self, AxumMessage::Text(event.narration().into()))
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+//Журнал в файле: одно JSON-событие с меткой времени на строку
+pub struct FileSink {
+    file: tokio::fs::File,
+}
+
+impl FileSink {
+    pub async fn open(path: &str) -> Result<Self, std::io::Error> {
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        Ok(FileSink { file })
+    }
+}
+
+#[derive(Serialize)]
+struct FileRecord<'a> {
+    time: String,
+    #[serde(flatten)]
+    event: &'a ProtocolEvent,
+}
+
+impl EventSink for FileSink {
+    async fn emit(&mut self, event: &ProtocolEvent) -> Result<(), String> {
+        let record = FileRecord {
+            time: chrono::Local::now().to_rfc3339(),
+            event,
+        };
+        let mut line = serde_json::to_string(&record).map_err(|e| e.to_string())?;
+        line.push('\n');
+        self.file
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct VecSink(mpsc::UnboundedSender<String>);
+
+    impl EventSink for VecSink {
+        async fn emit(&mut self, event: &ProtocolEvent) -> Result<(), String> {
+            self.0.send(event.narration()).map_err(|e| e.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bus_delivers_to_sinks() {
+        let mut bus = EventBus::new(16);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        bus.attach(VecSink(tx));
+        bus.attach(NullSink);
+        assert_eq!(bus.live_subscribers(), 0);
+
+        let _live = bus.subscribe();
+        assert_eq!(bus.live_subscribers(), 1);
+
+        bus.publish(ProtocolEvent::Challenge {
+            c: BigUint::from(4u32),
+        });
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            "Виктор: Привет, я Виктор. Докажи что ты знаешь секретный ключ, твое испытание: 4"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_last_id() {
+        let bus = EventBus::new(2);
+        for round in 1..=3 {
+            bus.publish(ProtocolEvent::Round { round, rounds: 3 });
+        }
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(bus.resume(0, VecSink(tx)));
+        //Первое событие вытеснено из буфера емкостью 2
+        assert_eq!(
+            rx.recv().await.unwrap(),
+            ProtocolEvent::Lagged { skipped: 1 }.narration()
+        );
+        assert_eq!(rx.recv().await.unwrap(), "Сервер: Раунд 2 из 3");
+        assert_eq!(rx.recv().await.unwrap(), "Сервер: Раунд 3 из 3");
+
+        bus.publish(ProtocolEvent::Round {
+            round: 4,
+            rounds: 4,
+        });
+        assert_eq!(rx.recv().await.unwrap(), "Сервер: Раунд 4 из 4");
+    }
+
+    #[tokio::test]
+    async fn test_resume_without_gap() {
+        let bus = EventBus::new(4);
+        for round in 1..=3 {
+            bus.publish(ProtocolEvent::Round { round, rounds: 3 });
+        }
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(bus.resume(2, VecSink(tx)));
+        assert_eq!(rx.recv().await.unwrap(), "Сервер: Раунд 3 из 3");
+    }
+
+    #[test]
+    fn test_event_json() {
+        let event = ProtocolEvent::Verdict {
+            uz: BigUint::from(3u32),
+            utuc: BigUint::from(3u32),
+            accepted: true,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"verdict","uz":"3","utuc":"3","accepted":true}"#
+        );
+    }
+
+    #[test]
+    fn test_narrated_json() {
+        let event = ProtocolEvent::Round {
+            round: 2,
+            rounds: 3,
+        };
+        let json: serde_json::Value =
+            serde_json::from_str(&event.to_narrated_json().unwrap()).unwrap();
+        assert_eq!(json["event"], "round");
+        assert_eq!(json["round"], 2);
+        assert_eq!(json["narration"], "Сервер: Раунд 2 из 3");
+    }
+}
@@ -0,0 +1,125 @@
+//////////////////////////////////////////////////////////////
+// Экстрактор знания (специальная корректность): доказывающего
+// отматывают к коммитменту и задают второе испытание. Из двух
+// ответов на один коммитмент секрет вычисляется однозначно:
+// alpha = (a_z1 - a_z2) / (c1 - c2), beta = (b_z1 - b_z2) / (c1 - c2).
+//////////////////////////////////////////////////////////////
+
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+
+use crate::group::Group;
+use crate::modint::ModInt;
+use crate::protocol::{Key, Proof, Prover, Verifier};
+
+//Сколько раз пробовать получить второе испытание, отличное от первого
+const CHALLENGE_ATTEMPTS: usize = 64;
+
+//Два принятых доказательства с общим коммитментом и извлеченный секрет
+#[derive(Debug, Clone)]
+pub struct Extraction {
+    pub first: Proof,
+    pub second: Proof,
+    pub witness: Key,
+}
+
+//Секрет по двум ответам на один коммитмент с разными испытаниями
+pub fn extract(order: &BigUint, first: &Proof, second: &Proof) -> Result<Key, String> {
+    if first.u != second.u || first.ut != second.ut {
+        return Err("Доказательства относятся к разным коммитментам".to_string());
+    }
+    let c1 = ModInt::new(first.c.clone(), Arc::new(order.clone()));
+    let inverse = (&c1 - &second.c)
+        .inverse()
+        .map_err(|_| "Испытания должны различаться по модулю q")?;
+    let diff = |a: &BigUint, b: &BigUint| ((c1.lift(a.clone()) - b) * &inverse).into_value();
+    Ok(Key::new(
+        diff(&first.alpha, &second.alpha),
+        diff(&first.beta, &second.beta),
+    ))
+}
+
+//Второе испытание, отличное от первого
+pub fn second_challenge<G: Group>(
+    verifier: &Verifier<G>,
+    first: &BigUint,
+) -> Result<BigUint, String> {
+    let order = verifier.setting().order();
+    for _ in 0..CHALLENGE_ATTEMPTS {
+        let c = verifier.challenge();
+        if c.clone() % &order != first % &order {
+            return Ok(c);
+        }
+    }
+    Err("Не удалось получить второе испытание: пространство испытаний слишком мало".to_string())
+}
+
+//Полный прогон экстрактора над доказывающим
+pub fn rewind<G: Group>(prover: &Prover<G>, verifier: &Verifier<G>) -> Result<Extraction, String> {
+    let commitment = prover.commit().ok_or("Ошибка вычисления u_t")?;
+    let snapshot = commitment.rewind_for_extraction();
+    let c1 = verifier.challenge();
+    let first = prover.respond(commitment, &c1);
+    let c2 = second_challenge(verifier, &c1)?;
+    let second = prover.respond(snapshot, &c2);
+    if !verifier.verify(&first) || !verifier.verify(&second) {
+        return Err("Доказывающий не ответил на оба испытания".to_string());
+    }
+    let witness = extract(&verifier.setting().order(), &first, &second)?;
+    Ok(Extraction {
+        first,
+        second,
+        witness,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{ProofKind, toy_params};
+
+    #[test]
+    fn test_extracts_witness() {
+        let secret = Key::new(BigUint::from(5u32), BigUint::from(2u32));
+        let prover = Prover::new(toy_params(), secret.clone()).unwrap();
+        let extraction = rewind(&prover, &Verifier::new(toy_params())).unwrap();
+        assert_eq!(extraction.witness, secret);
+        assert_eq!(extraction.first.ut, extraction.second.ut);
+        assert_ne!(extraction.first.c, extraction.second.c);
+
+        let prover = Prover::schnorr(toy_params(), BigUint::from(7u32)).unwrap();
+        let verifier = Verifier::with_kind(toy_params(), ProofKind::Schnorr);
+        let extraction = rewind(&prover, &verifier).unwrap();
+        assert_eq!(
+            extraction.witness,
+            Key::new(BigUint::from(7u32), BigUint::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_extract_rejects_unrelated_proofs() {
+        let prover = Prover::new(
+            toy_params(),
+            Key::new(BigUint::from(5u32), BigUint::from(2u32)),
+        )
+        .unwrap();
+        let commitment = prover.commit().unwrap();
+        let snapshot = commitment.rewind_for_extraction();
+        let first = prover.respond(commitment, &BigUint::from(3u32));
+        //Одинаковые по модулю q испытания ничего не дают
+        let same = prover.respond(snapshot, &BigUint::from(14u32));
+        assert!(extract(&BigUint::from(11u32), &first, &same).is_err());
+
+        let other = Proof {
+            ut: first.ut.clone() * 4u32 % 23u32,
+            c: BigUint::from(4u32),
+            ..first.clone()
+        };
+        assert!(extract(&BigUint::from(11u32), &first, &other).is_err());
+
+        //Из одного испытания второе не выбрать
+        let verifier = Verifier::new(toy_params()).with_challenge_space(BigUint::from(1u32));
+        assert!(rewind(&prover, &verifier).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//////////////////////////////////////////////////////////////
+// Проверяемое разделение секрета Фельдмана: раздающий делит x
+// по схеме Шамира многочленом f(z) = x + a_1 z + ... + a_(t-1) z^(t-1)
+// и публикует коммитменты C_j = g^a_j (C_0 = g^x - открытый ключ).
+// Участник i проверяет свою долю s_i = f(i) без доверия раздающему:
+// g^s_i = П C_j^(i^j). Любые t долей восстанавливают x.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+use crate::group::{Group, Setting};
+use crate::key_gen;
+use crate::math;
+use crate::message::{decimal, decimal_vec};
+use crate::threshold;
+
+//Доля участника index: f(index)
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FeldmanShare {
+    pub index: u32,
+    #[serde(with = "decimal")]
+    pub value: BigUint,
+}
+
+//Открытые коммитменты коэффициентов и доли для рассылки участникам
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Dealing {
+    #[serde(with = "decimal_vec")]
+    pub commitments: Vec<BigUint>,
+    pub shares: Vec<FeldmanShare>,
+}
+
+//Коммитменты многочлена с заданными коэффициентами и доли участников 1..=parties
+pub fn deal_with<G: Group>(
+    setting: &Setting<G>,
+    coefficients: &[BigUint],
+    parties: usize,
+) -> Result<Dealing, String> {
+    let order = setting.order();
+    let threshold = coefficients.len();
+    if threshold == 0 || threshold > parties {
+        return Err(format!(
+            "Порог {} должен быть от 1 до числа участников {}",
+            threshold, parties
+        ));
+    }
+    if BigUint::from(parties) >= order {
+        return Err("Участников больше, чем ненулевых точек в Z_q".to_string());
+    }
+    let group = &setting.group;
+    let commitments = coefficients
+        .iter()
+        .map(|a| group.encode(&group.exp(&setting.g, a)))
+        .collect();
+    let shares = (1..=parties as u32)
+        .map(|index| FeldmanShare {
+            index,
+            value: math::eval_polynomial(coefficients, &BigUint::from(index), &order),
+        })
+        .collect();
+    Ok(Dealing {
+        commitments,
+        shares,
+    })
+}
+
+//Разделение секрета: любые threshold из parties долей восстанавливают его
+pub fn deal<G: Group>(
+    setting: &Setting<G>,
+    secret: &BigUint,
+    threshold: usize,
+    parties: usize,
+) -> Result<Dealing, String> {
+    let order = setting.order();
+    let mut coefficients = vec![secret % &order];
+    for _ in 1..threshold {
+        coefficients.push(key_gen::random_biguint_mod(&order));
+    }
+    deal_with(setting, &coefficients, parties)
+}
+
+//П C_j^(index^j) - ожидаемое g^f(index)
+pub fn expected_share<G: Group>(
+    setting: &Setting<G>,
+    commitments: &[BigUint],
+    index: u32,
+) -> Option<G::Element> {
+    let group = &setting.group;
+    let order = setting.order();
+    let x = BigUint::from(index);
+    let mut power = BigUint::from(1u32);
+    let mut expected = group.identity();
+    for c in commitments {
+        expected = group.mul(&expected, &group.exp(&group.decode(c)?, &power));
+        power = power * &x % &order;
+    }
+    Some(expected)
+}
+
+//Проверка доли участником: g^s_i == П C_j^(i^j)
+pub fn verify_share<G: Group>(
+    setting: &Setting<G>,
+    commitments: &[BigUint],
+    share: &FeldmanShare,
+) -> bool {
+    if commitments.is_empty() || share.index == 0 || share.value >= setting.order() {
+        return false;
+    }
+    match expected_share(setting, commitments, share.index) {
+        Some(expected) => setting.group.exp(&setting.g, &share.value) == expected,
+        None => false,
+    }
+}
+
+//Открытый ключ разделенного секрета: C_0 = g^x
+pub fn public_key(commitments: &[BigUint]) -> Option<&BigUint> {
+    commitments.first()
+}
+
+//Интерполяция Лагранжа в нуле; None при повторяющихся номерах
+pub fn reconstruct(shares: &[FeldmanShare], order: &BigUint) -> Option<BigUint> {
+    let indices: Vec<u32> = shares.iter().map(|s| s.index).collect();
+    let mut secret = BigUint::ZERO;
+    for share in shares {
+        let l = threshold::lagrange(share.index, &indices, order)?;
+        secret = (secret + l * &share.value) % order;
+    }
+    Some(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::toy_params;
+    use crate::ristretto;
+
+    #[test]
+    fn test_deal_verify_reconstruct() {
+        let setting = ristretto::setting();
+        let order = setting.order();
+        let secret = setting.group.random_scalar();
+        let dealing = deal(&setting, &secret, 3, 5).unwrap();
+        assert_eq!(dealing.commitments.len(), 3);
+        let y = setting
+            .group
+            .encode(&setting.group.exp(&setting.g, &secret));
+        assert_eq!(public_key(&dealing.commitments), Some(&y));
+        for share in &dealing.shares {
+            assert!(verify_share(&setting, &dealing.commitments, share));
+        }
+        assert_eq!(
+            reconstruct(&dealing.shares[..3], &order),
+            Some(secret.clone())
+        );
+        assert_eq!(
+            reconstruct(&dealing.shares[2..], &order),
+            Some(secret.clone())
+        );
+        //Двух долей недостаточно
+        assert_ne!(reconstruct(&dealing.shares[..2], &order), Some(secret));
+    }
+
+    #[test]
+    fn test_cheating_dealer_detected() {
+        let setting = toy_params().setting();
+        let coefficients = [BigUint::from(7u32), BigUint::from(3u32)];
+        let mut dealing = deal_with(&setting, &coefficients, 3).unwrap();
+        //f(2) = 7 + 3*2 = 13 = 2 mod 11
+        assert_eq!(dealing.shares[1].value, BigUint::from(2u32));
+        assert!(verify_share(
+            &setting,
+            &dealing.commitments,
+            &dealing.shares[1]
+        ));
+
+        dealing.shares[1].value = BigUint::from(3u32);
+        assert!(!verify_share(
+            &setting,
+            &dealing.commitments,
+            &dealing.shares[1]
+        ));
+        let zero = FeldmanShare {
+            index: 0,
+            value: BigUint::from(7u32),
+        };
+        assert!(!verify_share(&setting, &dealing.commitments, &zero));
+        assert!(deal_with(&setting, &coefficients, 1).is_err());
+        assert!(deal_with(&setting, &coefficients, 11).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+//////////////////////////////////////////////////////////////
+// Протокол идентификации Фейге-Фиата-Шамира (1988): доказательство
+// знания квадратных корней s_1..s_k из открытых v_i = s_i^2 mod n,
+// где n - произведение двух простых, разложение которого неизвестно.
+// Испытание - k бит, поэтому обманщик угадывает его с вероятностью 2^-k
+// в каждом раунде.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::{BigUint, RandBigInt};
+use num_integer::Integer;
+
+use crate::key_gen::{self, PrimeKind};
+use crate::sigma::SigmaProtocol;
+
+//Модуль n = p * q из двух безопасных простых (числа Блюма)
+//или двух сильных простых Гордона
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FfsParams {
+    pub n: BigUint,
+}
+
+impl FfsParams {
+    //Разложение n сразу забывается: его знание позволило бы извлекать корни
+    pub fn generate(bits: u64, kind: PrimeKind) -> Self {
+        let p = key_gen::gen_modulus_prime(bits / 2, kind);
+        let q = loop {
+            let candidate = key_gen::gen_modulus_prime(bits - bits / 2, kind);
+            if candidate != p {
+                break candidate;
+            }
+        };
+        FfsParams { n: p * q }
+    }
+
+    //Случайный обратимый элемент из [1, n)
+    pub fn random_unit(&self) -> BigUint {
+        let mut rng = rand::thread_rng();
+        loop {
+            let x = rng.gen_biguint_below(&self.n);
+            if x > BigUint::ZERO && x.gcd(&self.n) == BigUint::from(1u32) {
+                return x;
+            }
+        }
+    }
+
+    fn in_range(&self, x: &BigUint) -> bool {
+        *x > BigUint::ZERO && *x < self.n
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Ffs {
+    params: FfsParams,
+    //Число секретов k и длина испытания в битах
+    secrets: usize,
+}
+
+impl Ffs {
+    pub fn new(params: FfsParams, secrets: usize) -> Self {
+        Ffs { params, secrets }
+    }
+
+    pub fn params(&self) -> &FfsParams {
+        &self.params
+    }
+
+    pub fn secrets(&self) -> usize {
+        self.secrets
+    }
+
+    //v_i = s_i^2 mod n
+    pub fn statement(&self, witness: &[BigUint]) -> Vec<BigUint> {
+        witness
+            .iter()
+            .map(|s| s.modpow(&BigUint::from(2u32), &self.params.n))
+            .collect()
+    }
+
+    //Обе стороны проверки: y^2 и x * П v_i^a_i
+    pub fn sides(
+        &self,
+        keys: &[BigUint],
+        x: &BigUint,
+        bits: &[bool],
+        y: &BigUint,
+    ) -> (BigUint, BigUint) {
+        let n = &self.params.n;
+        let left = y.modpow(&BigUint::from(2u32), n);
+        let right = keys
+            .iter()
+            .zip(bits)
+            .filter(|(_, bit)| **bit)
+            .fold(x % n, |acc, (v, _)| acc * v % n);
+        (left, right)
+    }
+}
+
+impl SigmaProtocol for Ffs {
+    type Statement = Vec<BigUint>;
+    type Witness = Vec<BigUint>;
+    type Nonce = BigUint;
+    type Commitment = BigUint;
+    type Challenge = Vec<bool>;
+    type Response = BigUint;
+
+    //x = r^2 mod n
+    fn commit(
+        &self,
+        _statement: &Vec<BigUint>,
+        _witness: &Vec<BigUint>,
+    ) -> Option<(BigUint, BigUint)> {
+        let r = self.params.random_unit();
+        let x = r.modpow(&BigUint::from(2u32), &self.params.n);
+        Some((r, x))
+    }
+
+    fn challenge(&self) -> Vec<bool> {
+        (0..self.secrets).map(|_| rand::random()).collect()
+    }
+
+    //y = r * П s_i^a_i mod n
+    fn respond(&self, witness: &Vec<BigUint>, r: BigUint, bits: &Vec<bool>) -> BigUint {
+        let n = &self.params.n;
+        witness
+            .iter()
+            .zip(bits)
+            .filter(|(_, bit)| **bit)
+            .fold(r % n, |acc, (s, _)| acc * s % n)
+    }
+
+    //y^2 == x * П v_i^a_i mod n
+    fn verify(&self, keys: &Vec<BigUint>, x: &BigUint, bits: &Vec<bool>, y: &BigUint) -> bool {
+        if keys.len() != self.secrets || bits.len() != self.secrets {
+            return false;
+        }
+        let elements = keys.iter().chain([x, y]);
+        if !elements.into_iter().all(|e| self.params.in_range(e)) {
+            return false;
+        }
+        let (left, right) = self.sides(keys, x, bits, y);
+        left == right
+    }
+}
+
+//Доказывающий с k секретами и открытыми ключами
+#[derive(Debug, Clone)]
+pub struct FfsProver {
+    protocol: Ffs,
+    secrets: Vec<BigUint>,
+    keys: Vec<BigUint>,
+}
+
+impl FfsProver {
+    pub fn new(protocol: Ffs, secrets: Vec<BigUint>) -> Option<Self> {
+        if secrets.len() != protocol.secrets() {
+            return None;
+        }
+        let keys = protocol.statement(&secrets);
+        Some(FfsProver {
+            protocol,
+            secrets,
+            keys,
+        })
+    }
+
+    pub fn random(protocol: Ffs) -> Self {
+        let secrets = (0..protocol.secrets())
+            .map(|_| protocol.params().random_unit())
+            .collect();
+        FfsProver::new(protocol, secrets).expect("число секретов совпадает")
+    }
+
+    pub fn keys(&self) -> &[BigUint] {
+        &self.keys
+    }
+
+    pub fn secrets(&self) -> &[BigUint] {
+        &self.secrets
+    }
+
+    //Одноразовый r и коммитмент x
+    pub fn commit(&self) -> (BigUint, BigUint) {
+        self.protocol
+            .commit(&self.keys, &self.secrets)
+            .expect("коммитмент FFS всегда вычисляется")
+    }
+
+    pub fn respond(&self, r: BigUint, bits: &Vec<bool>) -> BigUint {
+        self.protocol.respond(&self.secrets, r, bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sigma;
+
+    //n = 23 * 47, оба безопасные простые
+    fn protocol() -> Ffs {
+        Ffs::new(
+            FfsParams {
+                n: BigUint::from(23u32 * 47),
+            },
+            3,
+        )
+    }
+
+    #[test]
+    fn test_honest_prover_accepted() {
+        let prover = FfsProver::random(protocol());
+        let protocol = protocol();
+        for _ in 0..16 {
+            let (r, x) = prover.commit();
+            let bits = protocol.challenge();
+            let y = prover.respond(r, &bits);
+            assert!(protocol.verify(&prover.keys().to_vec(), &x, &bits, &y));
+        }
+        let witness = prover.secrets().to_vec();
+        assert_eq!(
+            sigma::execute(&protocol, &prover.keys().to_vec(), &witness),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_wrong_root_rejected() {
+        let protocol = protocol();
+        let prover = FfsProver::random(protocol.clone());
+        let (r, x) = prover.commit();
+        let bits = vec![true, false, true];
+        let mut y = prover.respond(r, &bits);
+        y = y * 2u32 % &protocol.params().n;
+        assert!(!protocol.verify(&prover.keys().to_vec(), &x, &bits, &y));
+        //Без единичных бит проверяется только x = r^2
+        let (r, x) = prover.commit();
+        let zero = vec![false; 3];
+        assert!(protocol.verify(&prover.keys().to_vec(), &x, &zero, &r));
+        assert!(!protocol.verify(&prover.keys().to_vec(), &x, &vec![false; 2], &r));
+        assert!(!protocol.verify(&prover.keys().to_vec(), &BigUint::ZERO, &zero, &r));
+    }
+
+    #[test]
+    fn test_generated_modulus() {
+        let params = FfsParams::generate(64, PrimeKind::Safe);
+        assert!(params.n.bits() >= 62);
+        let strong = FfsParams::generate(64, PrimeKind::Strong);
+        assert!(strong.n.bits() >= 63);
+        let unit = params.random_unit();
+        assert_eq!(unit.gcd(&params.n), BigUint::from(1u32));
+        assert!(FfsProver::new(Ffs::new(params, 2), vec![BigUint::from(3u32)]).is_none());
+    }
+}
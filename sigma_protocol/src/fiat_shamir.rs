@@ -0,0 +1,237 @@
+//////////////////////////////////////////////////////////////
+// Неинтерактивный режим (эвристика Фиата-Шамира): испытание
+// вычисляется как хэш от параметров и коммитмента вместо
+// случайного выбора проверяющим.
+//
+// Стенограмма по образцу merlin: все сообщения протокола
+// поглощаются с метками, испытания выжимаются из накопленного
+// состояния и сами поглощаются обратно. Доказательства, выполненные
+// в одной стенограмме, связаны со всем контекстом и друг с другом:
+// испытание второго зависит от первого.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+
+use crate::encoding;
+use crate::group::{Group, Setting};
+use crate::oracle::{HashKind, Oracle, RandomOracle};
+use crate::protocol::PublicParams;
+
+//Каждое значение кодируется длиной (u32 BE) и байтами, чтобы склейка была однозначной
+pub(crate) fn absorb<H: RandomOracle>(hasher: &mut H, bytes: &[u8]) {
+    hasher.input(&(bytes.len() as u32).to_be_bytes());
+    hasher.input(bytes);
+}
+
+//Домен всех стенограмм и метка одиночного доказательства знания
+const TRANSCRIPT_DOMAIN: &str = "sigma_protocol transcript v1";
+pub const PROOF_LABEL: &[u8] = b"sigma_protocol proof";
+const PARAMS_LABEL: &[u8] = b"sigma_protocol params";
+//Байтов на испытание: вдвое больше q, чтобы смещение mod q было пренебрежимо
+const CHALLENGE_BYTES: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct ProofTranscript {
+    hasher: Oracle,
+}
+
+impl ProofTranscript {
+    //Новая стенограмма; метка отделяет разные протоколы друг от друга
+    pub fn new(label: &[u8]) -> Self {
+        ProofTranscript::with_hash(label, HashKind::default())
+    }
+
+    pub fn with_hash(label: &[u8], hash: HashKind) -> Self {
+        let mut hasher = hash.oracle();
+        absorb(&mut hasher, TRANSCRIPT_DOMAIN.as_bytes());
+        absorb(&mut hasher, label);
+        ProofTranscript { hasher }
+    }
+
+    pub fn append_message(&mut self, label: &[u8], message: &[u8]) {
+        absorb(&mut self.hasher, label);
+        absorb(&mut self.hasher, message);
+    }
+
+    pub fn append_biguint(&mut self, label: &[u8], value: &BigUint) {
+        self.append_message(label, &value.to_bytes_be());
+    }
+
+    //Описание группы и образующие
+    pub fn append_setting<G: Group>(&mut self, setting: &Setting<G>) {
+        let group = &setting.group;
+        self.append_message(b"group", &group.describe());
+        self.append_biguint(b"g", &group.encode(&setting.g));
+        self.append_biguint(b"h", &group.encode(&setting.h));
+    }
+
+    //Отпечаток накопленного состояния, например для вывода одноразовых ключей
+    pub fn state_digest(&self) -> [u8; 32] {
+        self.hasher.clone().output()
+    }
+
+    //Выжимание: блоки H(состояние, метка, счетчик); результат поглощается
+    //обратно, так что следующее испытание зависит от предыдущего
+    pub fn challenge_bytes(&mut self, label: &[u8], out: &mut [u8]) {
+        let mut fork = self.hasher.clone();
+        absorb(&mut fork, b"challenge");
+        absorb(&mut fork, label);
+        absorb(&mut fork, &(out.len() as u32).to_be_bytes());
+        for (counter, chunk) in out.chunks_mut(32).enumerate() {
+            let mut block = fork.clone();
+            block.input(&(counter as u32).to_be_bytes());
+            chunk.copy_from_slice(&block.output()[..chunk.len()]);
+        }
+        self.append_message(label, out);
+    }
+
+    pub fn challenge_scalar(&mut self, label: &[u8], order: &BigUint) -> BigUint {
+        let mut bytes = [0u8; CHALLENGE_BYTES];
+        self.challenge_bytes(label, &mut bytes);
+        BigUint::from_bytes_be(&bytes) % order
+    }
+
+    //Испытание доказательства знания: параметры, u и u_t
+    pub fn proof_challenge<G: Group>(
+        &mut self,
+        setting: &Setting<G>,
+        u: &BigUint,
+        ut: &BigUint,
+    ) -> BigUint {
+        self.append_setting(setting);
+        self.append_biguint(b"u", u);
+        self.append_biguint(b"ut", ut);
+        self.challenge_scalar(b"c", &setting.order())
+    }
+}
+
+//c = H(p, g, h, u, u_t) mod q
+pub fn challenge(params: &PublicParams, u: &BigUint, ut: &BigUint) -> BigUint {
+    challenge_in(&params.setting(), u, ut)
+}
+
+//То же для произвольной группы: вместо q хэшируется описание группы
+pub fn challenge_in<G: Group>(setting: &Setting<G>, u: &BigUint, ut: &BigUint) -> BigUint {
+    challenge_with(HashKind::default(), setting, u, ut)
+}
+
+//Испытание с хэш-функцией из конфигурации
+pub fn challenge_with<G: Group>(
+    hash: HashKind,
+    setting: &Setting<G>,
+    u: &BigUint,
+    ut: &BigUint,
+) -> BigUint {
+    ProofTranscript::with_hash(PROOF_LABEL, hash).proof_challenge(setting, u, ut)
+}
+
+//Отпечаток группы и образующих в hex: по нему клиент ссылается на
+//параметры, в которых построено доказательство
+pub fn params_fingerprint<G: Group>(setting: &Setting<G>) -> String {
+    let mut transcript = ProofTranscript::new(PARAMS_LABEL);
+    transcript.append_setting(setting);
+    encoding::to_hex(&transcript.state_digest())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Key, ProofKind, Prover, Verifier, toy_params};
+    use crate::ristretto;
+
+    #[test]
+    fn test_challenge_deterministic() {
+        let params = toy_params();
+        let (u, ut) = (BigUint::from(3u32), BigUint::from(8u32));
+        let c = challenge(&params, &u, &ut);
+        assert_eq!(c, challenge(&params, &u, &ut));
+        assert!(c < params.order());
+    }
+
+    #[test]
+    fn test_non_interactive_proof() {
+        let prover = Prover::new(
+            toy_params(),
+            Key::new(BigUint::from(5u32), BigUint::from(2u32)),
+        )
+        .unwrap();
+        let verifier = Verifier::new(toy_params());
+
+        let proof = prover.prove_non_interactive().unwrap();
+        assert!(verifier.verify_non_interactive(&proof));
+
+        //Испытание, не совпадающее с хэшем, отвергается даже при верном уравнении
+        let mut forged = proof.clone();
+        forged.c = (&proof.c + 1u32) % toy_params().order();
+        forged.alpha = (&proof.alpha + 5u32) % toy_params().order();
+        forged.beta = (&proof.beta + 2u32) % toy_params().order();
+        assert!(verifier.verify(&forged));
+        assert!(!verifier.verify_non_interactive(&forged));
+    }
+
+    #[test]
+    fn test_params_fingerprint() {
+        let setting = toy_params().setting();
+        assert_eq!(params_fingerprint(&setting).len(), 64);
+        assert_eq!(params_fingerprint(&setting), params_fingerprint(&setting));
+        let other = PublicParams {
+            h: BigUint::from(3u32),
+            ..toy_params()
+        };
+        assert_ne!(
+            params_fingerprint(&setting),
+            params_fingerprint(&other.setting())
+        );
+    }
+
+    #[test]
+    fn test_transcript_binds_context() {
+        let mut a = ProofTranscript::new(b"test");
+        let mut b = ProofTranscript::new(b"test");
+        a.append_message(b"m", b"ab");
+        b.append_message(b"ma", b"b");
+        let order = BigUint::from(1u32) << 200;
+        //Метки и длины однозначно разделяют сообщения
+        assert_ne!(
+            a.clone().challenge_scalar(b"c", &order),
+            b.challenge_scalar(b"c", &order)
+        );
+        //Повторное выжимание дает новое испытание
+        let first = a.challenge_scalar(b"c", &order);
+        assert_ne!(a.challenge_scalar(b"c", &order), first);
+
+        //Два доказательства в одной стенограмме проверяются только в том же контексте
+        let setting = ristretto::setting();
+        let secret = |a: u32, b: u32| Key::new(BigUint::from(a), BigUint::from(b));
+        let prover = Prover::in_setting(setting.clone(), ProofKind::Okamoto, secret(5, 2));
+        let other = Prover::in_setting(setting.clone(), ProofKind::Okamoto, secret(3, 7));
+        let verifier = Verifier::in_setting(setting, ProofKind::Okamoto);
+        let mut transcript = ProofTranscript::new(b"conjunction");
+        transcript.append_message(b"context", b"session 1");
+        let mut proving = transcript.clone();
+        let first = prover.prove_in(&mut proving).unwrap();
+        let second = other.prove_in(&mut proving).unwrap();
+
+        let mut verifying = transcript.clone();
+        assert!(verifier.verify_in(&first, &mut verifying));
+        assert!(verifier.verify_in(&second, &mut verifying));
+        assert_eq!(verifying.state_digest(), proving.state_digest());
+        //Без первого доказательства, в другом контексте или отдельно - отказ
+        assert!(!verifier.verify_in(&second, &mut transcript.clone()));
+        let mut foreign = ProofTranscript::new(b"conjunction");
+        foreign.append_message(b"context", b"session 2");
+        assert!(!verifier.verify_in(&first, &mut foreign));
+        assert!(!verifier.verify_non_interactive(&first));
+
+        //Испытание зависит от выбранной хэш-функции
+        let blake = prover.clone().with_hash(HashKind::Blake3);
+        let proof = blake.prove_non_interactive().unwrap();
+        assert!(
+            verifier
+                .clone()
+                .with_hash(HashKind::Blake3)
+                .verify_non_interactive(&proof)
+        );
+        assert!(!verifier.verify_non_interactive(&proof));
+    }
+}
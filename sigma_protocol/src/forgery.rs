@@ -0,0 +1,85 @@
+//////////////////////////////////////////////////////////////
+// Обманщик без секрета: угадывает испытание заранее и строит
+// коммитмент под догадку, как симулятор. Проверку проходит,
+// только если догадка совпала, то есть с вероятностью 1/|C|.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use serde::Serialize;
+
+use crate::group::Group;
+use crate::message::decimal;
+use crate::protocol::{Proof, Verifier, soundness_bits};
+
+//Итоги серии попыток обмана
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ForgeryStats {
+    pub attempts: u64,
+    pub accepted: u64,
+    //Доля принятых попыток
+    pub rate: f64,
+    //Теоретическая вероятность 1/|C|
+    pub expected: f64,
+    #[serde(with = "decimal")]
+    pub space: BigUint,
+}
+
+impl ForgeryStats {
+    pub fn new(space: BigUint) -> Self {
+        ForgeryStats {
+            expected: (-soundness_bits(&space, 1)).exp2(),
+            space,
+            ..ForgeryStats::default()
+        }
+    }
+
+    pub fn record(&mut self, accepted: bool) {
+        self.attempts += 1;
+        self.accepted += accepted as u64;
+        self.rate = self.accepted as f64 / self.attempts as f64;
+    }
+}
+
+//Одна попытка: коммитмент под угаданное испытание, затем ответ
+//на настоящее испытание проверяющего
+pub fn attempt<G: Group>(verifier: &Verifier<G>, u: &G::Element) -> bool {
+    let guess = verifier.challenge();
+    let prepared = verifier.simulate_for(u, guess);
+    let c = verifier.challenge();
+    verifier.verify(&Proof { c, ..prepared })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{ProofKind, toy_params};
+
+    // Подгруппа порядка 11 в Z_23^*
+    fn verifier() -> Verifier {
+        Verifier::new(toy_params())
+    }
+
+    #[test]
+    fn test_acceptance_rate_matches_space() {
+        let u = BigUint::from(6u32);
+        //Испытание из двух значений: обманщик угадывает примерно в половине случаев
+        let verifier = verifier().with_challenge_space(BigUint::from(2u32));
+        let mut stats = ForgeryStats::new(verifier.challenge_space().clone());
+        for _ in 0..400 {
+            stats.record(attempt(&verifier, &u));
+        }
+        assert_eq!(stats.expected, 0.5);
+        assert!(stats.rate > 0.35 && stats.rate < 0.65, "{}", stats.rate);
+
+        //Из одного значения испытание известно заранее
+        let verifier = verifier.with_challenge_space(BigUint::ZERO);
+        assert_eq!(verifier.challenge_space(), &BigUint::from(1u32));
+        assert!(attempt(&verifier, &u));
+
+        //Пространство не превышает q
+        let verifier = Verifier::with_kind(verifier.setting().params(), ProofKind::Schnorr)
+            .with_challenge_space(BigUint::from(1000u32));
+        assert_eq!(verifier.challenge_space(), &BigUint::from(11u32));
+        assert!((ForgeryStats::new(BigUint::from(11u32)).expected - 1.0 / 11.0).abs() < 1e-12);
+    }
+}
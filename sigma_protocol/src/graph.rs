@@ -0,0 +1,166 @@
+//////////////////////////////////////////////////////////////
+// Небольшие неориентированные графы и перестановки вершин
+// для протокола изоморфизма графов.
+//////////////////////////////////////////////////////////////
+
+use std::collections::BTreeSet;
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+//Перестановка вершин: вершина i переходит в map[i]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Permutation(Vec<usize>);
+
+impl Permutation {
+    pub fn identity(n: usize) -> Self {
+        Permutation((0..n).collect())
+    }
+
+    pub fn random(n: usize) -> Self {
+        let mut map: Vec<usize> = (0..n).collect();
+        map.shuffle(&mut rand::thread_rng());
+        Permutation(map)
+    }
+
+    //Проверка, что map - биекция на [0, n)
+    pub fn from_vec(map: Vec<usize>) -> Option<Self> {
+        let mut seen = vec![false; map.len()];
+        for &v in &map {
+            if v >= map.len() || seen[v] {
+                return None;
+            }
+            seen[v] = true;
+        }
+        Some(Permutation(map))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn apply(&self, v: usize) -> usize {
+        self.0[v]
+    }
+
+    //Сначала self, затем other: v -> other(self(v))
+    pub fn then(&self, other: &Permutation) -> Permutation {
+        Permutation(self.0.iter().map(|&v| other.apply(v)).collect())
+    }
+
+    pub fn inverse(&self) -> Permutation {
+        let mut map = vec![0; self.0.len()];
+        for (i, &v) in self.0.iter().enumerate() {
+            map[v] = i;
+        }
+        Permutation(map)
+    }
+
+    pub fn as_slice(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+//Граф на вершинах 0..vertices; ребро хранится как (меньшая, большая)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Graph {
+    vertices: usize,
+    edges: BTreeSet<(usize, usize)>,
+}
+
+impl Graph {
+    //Петли и ребра за пределами графа отбрасываются
+    pub fn new(vertices: usize, edges: impl IntoIterator<Item = (usize, usize)>) -> Self {
+        let edges = edges
+            .into_iter()
+            .filter(|&(a, b)| a != b && a < vertices && b < vertices)
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        Graph { vertices, edges }
+    }
+
+    //Случайный граф G(n, p)
+    pub fn random(vertices: usize, density: f64) -> Self {
+        let mut rng = rand::thread_rng();
+        let edges = (0..vertices)
+            .flat_map(|a| (a + 1..vertices).map(move |b| (a, b)))
+            .filter(|_| rng.gen_bool(density.clamp(0.0, 1.0)))
+            .collect::<Vec<_>>();
+        Graph::new(vertices, edges)
+    }
+
+    pub fn vertices(&self) -> usize {
+        self.vertices
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = &(usize, usize)> {
+        self.edges.iter()
+    }
+
+    pub fn has_edge(&self, a: usize, b: usize) -> bool {
+        self.edges.contains(&(a.min(b), a.max(b)))
+    }
+
+    //Переименование вершин; None, если размеры не совпадают
+    pub fn permute(&self, permutation: &Permutation) -> Option<Graph> {
+        if permutation.len() != self.vertices {
+            return None;
+        }
+        Some(Graph::new(
+            self.vertices,
+            self.edges
+                .iter()
+                .map(|&(a, b)| (permutation.apply(a), permutation.apply(b))),
+        ))
+    }
+
+    //Матрица смежности построчно для журнала
+    pub fn render(&self) -> String {
+        (0..self.vertices)
+            .map(|a| {
+                (0..self.vertices)
+                    .map(|b| if self.has_edge(a, b) { "1" } else { "·" })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permutation_algebra() {
+        let p = Permutation::from_vec(vec![2, 0, 1]).unwrap();
+        let q = Permutation::from_vec(vec![1, 2, 0]).unwrap();
+        assert_eq!(p.then(&p.inverse()), Permutation::identity(3));
+        assert_eq!(p.then(&q).as_slice(), &[0, 1, 2]);
+        assert!(Permutation::from_vec(vec![0, 0, 1]).is_none());
+        assert!(Permutation::from_vec(vec![0, 3, 1]).is_none());
+        let r = Permutation::random(8);
+        assert!(Permutation::from_vec(r.as_slice().to_vec()).is_some());
+    }
+
+    #[test]
+    fn test_permute_graph() {
+        //Путь 0-1-2 переходит в путь 2-0-1
+        let path = Graph::new(3, [(0, 1), (2, 1), (1, 1), (0, 5)]);
+        assert_eq!(path.edges().count(), 2);
+        let p = Permutation::from_vec(vec![2, 0, 1]).unwrap();
+        let image = path.permute(&p).unwrap();
+        assert!(image.has_edge(2, 0) && image.has_edge(0, 1));
+        assert!(!image.has_edge(2, 1));
+        assert_eq!(image.permute(&p.inverse()).unwrap(), path);
+        assert!(path.permute(&Permutation::identity(4)).is_none());
+        assert_eq!(path.render(), "· 1 ·\n1 · 1\n· 1 ·");
+    }
+}
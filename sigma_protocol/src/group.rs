@@ -0,0 +1,369 @@
+//////////////////////////////////////////////////////////////
+// Абстракция группы, в которой работает протокол. Скаляры
+// (показатели степени) - числа по модулю порядка группы,
+// элементы группы задаются реализацией.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::{BigUint, ToBigInt};
+use num_traits::One;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::key_gen::{self, SecureRng};
+use crate::math::{self, FixedBase};
+use crate::modint::ModInt;
+use crate::oracle::HashKind;
+
+//Домен вывода второй образующей: h = hash_to_element(H_DOMAIN, группа, g, i)
+pub const H_DOMAIN: &str = "sigma_protocol h";
+
+pub trait Group: Clone + Debug + Send + Sync {
+    type Element: Clone + PartialEq + Debug + Send + Sync;
+
+    fn identity(&self) -> Self::Element;
+
+    fn mul(&self, a: &Self::Element, b: &Self::Element) -> Self::Element;
+
+    fn exp(&self, base: &Self::Element, k: &BigUint) -> Self::Element;
+
+    //x^a * y^b; реализации могут считать обе степени за один проход
+    fn exp2(
+        &self,
+        x: &Self::Element,
+        a: &BigUint,
+        y: &Self::Element,
+        b: &BigUint,
+    ) -> Self::Element {
+        self.mul(&self.exp(x, a), &self.exp(y, b))
+    }
+
+    //Порядок группы: по нему приводятся скаляры
+    fn order(&self) -> BigUint;
+
+    //Модуль, по которому ведутся вычисления (для кривых - модуль поля)
+    fn modulus(&self) -> BigUint;
+
+    //Проверка, что значение - допустимый элемент группы
+    fn contains(&self, element: &Self::Element) -> bool;
+
+    fn serialize(&self, element: &Self::Element) -> Vec<u8>;
+
+    fn deserialize(&self, bytes: &[u8]) -> Option<Self::Element>;
+
+    //Длина serialize в байтах, одинаковая для всех элементов
+    fn element_len(&self) -> usize;
+
+    //Однозначное описание группы для хэширования
+    fn describe(&self) -> Vec<u8>;
+
+    //Детерминированное отображение байтов в элемент группы, при котором
+    //логарифм результата по любой образующей никому не известен
+    fn hash_to_element(&self, data: &[u8]) -> Self::Element;
+
+    fn random_scalar(&self) -> BigUint {
+        key_gen::random_biguint_mod(&self.order())
+    }
+
+    fn random_scalar_with<R: SecureRng>(&self, rng: &mut R) -> BigUint {
+        key_gen::random_biguint_mod_with(rng, &self.order())
+    }
+
+    //Элемент в виде числа для сообщений протокола
+    fn encode(&self, element: &Self::Element) -> BigUint {
+        BigUint::from_bytes_be(&self.serialize(element))
+    }
+
+    //Число дополняется ведущими нулями до element_len байт
+    fn decode(&self, value: &BigUint) -> Option<Self::Element> {
+        let bytes = math::i2osp(value, self.element_len()).ok()?;
+        self.deserialize(&bytes)
+            .filter(|element| self.contains(element))
+    }
+}
+
+//Группа вместе с образующими g и h
+#[derive(Debug, Clone)]
+pub struct Setting<G: Group> {
+    pub group: G,
+    pub g: G::Element,
+    pub h: G::Element,
+}
+
+impl<G: Group> Setting<G> {
+    pub fn new(group: G, g: G::Element, h: G::Element) -> Self {
+        Setting { group, g, h }
+    }
+
+    //Образующая h выводится из g хэшированием в группу ("nothing up my sleeve")
+    pub fn derive(group: G, g: G::Element) -> Self {
+        let h = derive_h(&group, &g);
+        Setting { group, g, h }
+    }
+
+    //Проверка, что h получена из g процедурой derive_h
+    pub fn h_is_derived(&self) -> bool {
+        derive_h(&self.group, &self.g) == self.h
+    }
+
+    //g^a * h^b
+    pub fn compute_u(&self, a: &BigUint, b: &BigUint) -> G::Element {
+        self.group.exp2(&self.g, a, &self.h, b)
+    }
+
+    pub fn order(&self) -> BigUint {
+        self.group.order()
+    }
+
+    //Показатель как вычет по модулю порядка группы
+    pub fn scalar(&self, value: BigUint) -> ModInt {
+        ModInt::new(value, Arc::new(self.order()))
+    }
+}
+
+//Первый по счетчику i хэш H_DOMAIN || группа || g || i, дающий элемент,
+//отличный от единицы и от самой g
+pub fn derive_h<G: Group>(group: &G, g: &G::Element) -> G::Element {
+    let mut seed = H_DOMAIN.as_bytes().to_vec();
+    for part in [group.describe(), group.serialize(g)] {
+        seed.extend((part.len() as u64).to_be_bytes());
+        seed.extend(part);
+    }
+    (0u32..)
+        .map(|i| group.hash_to_element(&[seed.as_slice(), &i.to_be_bytes()].concat()))
+        .find(|h| group.contains(h) && *h != group.identity() && h != g)
+        .expect("хэш в группу с бесконечным счетчиком")
+}
+
+//////////////////////////////////////////////////////////////
+// Мультипликативная группа вычетов по модулю
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct ModGroup {
+    modulus: BigUint,
+    order: BigUint,
+    //Хэш-функция отображения байтов в элемент группы
+    hash: HashKind,
+    //Таблицы степеней фиксированных оснований (обычно g и h)
+    fixed: Arc<Vec<FixedBase>>,
+}
+
+//Таблицы - лишь ускорение и на равенство групп не влияют
+impl PartialEq for ModGroup {
+    fn eq(&self, other: &Self) -> bool {
+        self.modulus == other.modulus && self.order == other.order && self.hash == other.hash
+    }
+}
+
+impl Eq for ModGroup {}
+
+impl ModGroup {
+    //modulus > 1; order - порядок группы (кратен порядкам образующих)
+    pub fn new(modulus: BigUint, order: BigUint) -> Self {
+        assert!(
+            modulus > BigUint::one(),
+            "модуль группы должен быть больше 1"
+        );
+        ModGroup {
+            modulus,
+            order,
+            hash: HashKind::default(),
+            fixed: Arc::default(),
+        }
+    }
+
+    pub fn with_hash(mut self, hash: HashKind) -> Self {
+        self.hash = hash;
+        self
+    }
+
+    //Таблицы для показателей длины порядка группы
+    pub fn with_fixed_bases(mut self, bases: &[BigUint]) -> Self {
+        let bits = self.order.bits();
+        let tables = bases
+            .iter()
+            .filter_map(|base| FixedBase::new(base, &self.modulus, bits))
+            .collect();
+        self.fixed = Arc::new(tables);
+        self
+    }
+}
+
+impl Setting<ModGroup> {
+    //Предвычисление степеней g и h: compute_u больше не возводит с нуля
+    pub fn precompute(mut self) -> Self {
+        self.group = self
+            .group
+            .with_fixed_bases(&[self.g.clone(), self.h.clone()]);
+        self
+    }
+}
+
+impl Group for ModGroup {
+    type Element = BigUint;
+
+    fn identity(&self) -> BigUint {
+        BigUint::one()
+    }
+
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b % &self.modulus
+    }
+
+    fn exp(&self, base: &BigUint, k: &BigUint) -> BigUint {
+        if let Some(fixed) = self.fixed.iter().find(|fixed| fixed.base() == base) {
+            return fixed.pow(k);
+        }
+        //Показатель неотрицателен, обращение не нужно, а модуль больше 1
+        //по построению группы: ошибка здесь - нарушение инварианта
+        let k = k.to_bigint().expect("BigUint всегда представим как BigInt");
+        math::mod_pow_big(base, &k, &self.modulus).expect("модуль группы больше 1")
+    }
+
+    //С таблицами обоих оснований квадраты не нужны вовсе, иначе трюк Шамира
+    fn exp2(&self, x: &BigUint, a: &BigUint, y: &BigUint, b: &BigUint) -> BigUint {
+        let fixed = |base: &BigUint| self.fixed.iter().any(|fixed| fixed.base() == base);
+        if fixed(x) && fixed(y) {
+            return self.mul(&self.exp(x, a), &self.exp(y, b));
+        }
+        math::mod_multi_pow(x, a, y, b, &self.modulus).expect("модуль группы больше 1")
+    }
+
+    fn order(&self) -> BigUint {
+        self.order.clone()
+    }
+
+    fn modulus(&self) -> BigUint {
+        self.modulus.clone()
+    }
+
+    //Элементы лежат в [1, modulus) и в подгруппе порядка order. При
+    //безопасном простом modulus = 2*order + 1 эта подгруппа - квадратичные
+    //вычеты, и вместо возведения в степень order достаточно символа Лежандра
+    fn contains(&self, element: &BigUint) -> bool {
+        if *element == BigUint::ZERO || *element >= self.modulus {
+            return false;
+        }
+        match self.modulus == &self.order * 2u32 + 1u32 {
+            true => math::jacobi(element, &self.modulus) == Some(1),
+            false => self.exp(element, &self.order) == self.identity(),
+        }
+    }
+
+    //Ровно element_len байт; значения вне группы длиннее модуля
+    //и записываются как есть
+    fn serialize(&self, element: &BigUint) -> Vec<u8> {
+        math::i2osp(element, self.element_len()).unwrap_or_else(|_| element.to_bytes_be())
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Option<BigUint> {
+        (bytes.len() == self.element_len()).then(|| math::os2ip(bytes))
+    }
+
+    fn element_len(&self) -> usize {
+        math::byte_len(&self.modulus)
+    }
+
+    fn describe(&self) -> Vec<u8> {
+        self.modulus.to_bytes_be()
+    }
+
+    //Хэш, растянутый до длины модуля с запасом в 64 бита, возводится
+    //в степень (modulus - 1) / order и попадает в подгруппу порядка order
+    fn hash_to_element(&self, data: &[u8]) -> BigUint {
+        let len = (self.modulus.bits() + 64).div_ceil(256) as usize * 32;
+        let bytes = self.hash.expand(data, len);
+        let x = BigUint::from_bytes_be(&bytes) % &self.modulus;
+        let cofactor = (&self.modulus - 1u32) / &self.order;
+        self.exp(&x, &cofactor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Подгруппа порядка 11 в Z_23^*
+    fn setting() -> Setting<ModGroup> {
+        Setting::new(
+            ModGroup::new(BigUint::from(23u32), BigUint::from(22u32)),
+            BigUint::from(4u32),
+            BigUint::from(9u32),
+        )
+    }
+
+    #[test]
+    fn test_mod_group_arithmetic() {
+        let s = setting();
+        let group = &s.group;
+        assert_eq!(group.exp(&s.g, &BigUint::from(11u32)), group.identity());
+        assert_eq!(
+            group.mul(&s.g, &group.exp(&s.g, &BigUint::from(10u32))),
+            group.identity()
+        );
+        assert_eq!(
+            s.compute_u(&BigUint::from(3u32), &BigUint::from(5u32)),
+            BigUint::from(4u32).pow(3) * BigUint::from(9u32).pow(5) % 23u32
+        );
+    }
+
+    #[test]
+    fn test_precomputed_setting() {
+        let s = setting();
+        let fast = s.clone().precompute();
+        assert_eq!(fast.group, s.group);
+        for (a, b) in [(0u32, 0u32), (3, 5), (21, 21), (100, 7)] {
+            let (a, b) = (BigUint::from(a), BigUint::from(b));
+            assert_eq!(fast.compute_u(&a, &b), s.compute_u(&a, &b));
+        }
+        //Основания без таблиц возводятся как обычно
+        assert_eq!(
+            fast.group.exp(&BigUint::from(5u32), &BigUint::from(3u32)),
+            BigUint::from(10u32)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "модуль группы должен быть больше 1")]
+    fn test_mod_group_rejects_trivial_modulus() {
+        ModGroup::new(BigUint::one(), BigUint::one());
+    }
+
+    #[test]
+    fn test_mod_group_encoding() {
+        let group = setting().group;
+        let x = BigUint::from(17u32);
+        assert_eq!(group.decode(&group.encode(&x)), Some(x));
+        assert_eq!(group.decode(&BigUint::ZERO), None);
+        assert_eq!(group.decode(&BigUint::from(23u32)), None);
+    }
+
+    #[test]
+    fn test_mod_group_contains_only_subgroup() {
+        //23 = 2*11 + 1: подгруппа порядка 11 - квадратичные вычеты
+        let group = ModGroup::new(BigUint::from(23u32), BigUint::from(11u32));
+        assert!(group.contains(&BigUint::from(4u32)));
+        assert!(group.contains(&group.identity()));
+        //-1 и 5 - невычеты, их порядок делится на 2
+        assert!(!group.contains(&BigUint::from(22u32)));
+        assert!(!group.contains(&BigUint::from(5u32)));
+        assert_eq!(group.decode(&BigUint::from(22u32)), None);
+        //Без безопасного простого проверяется x^order == 1: порядок 3 в Z_7^*
+        let group = ModGroup::new(BigUint::from(7u32), BigUint::from(3u32));
+        assert!(group.contains(&BigUint::from(2u32)));
+        assert!(!group.contains(&BigUint::from(3u32)));
+    }
+
+    #[test]
+    fn test_derived_h() {
+        let group = ModGroup::new(BigUint::from(23u32), BigUint::from(11u32));
+        let s = Setting::derive(group.clone(), BigUint::from(4u32));
+        assert!(s.h_is_derived());
+        assert_ne!(s.h, s.g);
+        assert_eq!(group.exp(&s.h, &BigUint::from(11u32)), group.identity());
+        //Вывод детерминирован и зависит от g
+        assert_eq!(s.h, BigUint::from(3u32));
+        assert_eq!(derive_h(&group, &BigUint::from(9u32)), BigUint::from(16u32));
+        assert!(!Setting::new(group, BigUint::from(4u32), BigUint::from(9u32)).h_is_derived());
+    }
+}
@@ -0,0 +1,303 @@
+//////////////////////////////////////////////////////////////
+// Обмен ключами Диффи-Хеллмана между двумя серверами с проверкой
+// подлинности сигма-протоколом. У каждого сервера постоянный ключ
+// Y = g^x; на сессию он выбирает одноразовый E = g^e и доказывает
+// знание x протоколом Шнорра, испытание которого - хэш от всех
+// значений обмена (Фиат-Шамир). Доказательство привязано к этой
+// сессии и не может быть перенесено в другую.
+//
+// Общий секрет E_B^e_A = E_A^e_B превращается в ключ сессии через
+// HKDF-SHA256; дальнейшие сообщения шифруются ChaCha20-Poly1305.
+// Постоянный ключ собеседника нужно сверить с известным заранее:
+// доказательство подтверждает владение ключом, а не чей он.
+//////////////////////////////////////////////////////////////
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key as CipherKey, Nonce};
+use hkdf::Hkdf;
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+use crate::encoding;
+use crate::fiat_shamir::absorb;
+use crate::group::{Group, Setting};
+use crate::message::decimal;
+use crate::schnorr::Schnorr;
+use crate::sigma::SigmaProtocol;
+#[cfg(feature = "server")]
+use crate::{config::Address, threshold};
+
+pub const HANDSHAKE_PATH: &str = "/handshake";
+
+//Домены хэшей: испытания доказательств и вывод ключа сессии
+const PROOF_DOMAIN: &str = "sigma_protocol handshake proof";
+const KEY_INFO: &[u8] = b"sigma_protocol handshake key";
+const NONCE_LEN: usize = 12;
+
+//Неинтерактивное доказательство Шнорра: t = g^r, z = r + c*x
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyProof {
+    #[serde(with = "decimal")]
+    pub t: BigUint,
+    #[serde(with = "decimal")]
+    pub z: BigUint,
+}
+
+//Постоянный и одноразовый ключи стороны с доказательством знания x
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hello {
+    #[serde(with = "decimal")]
+    pub static_key: BigUint,
+    #[serde(with = "decimal")]
+    pub ephemeral: BigUint,
+    pub proof: KeyProof,
+}
+
+//c = H(домен, группа, значения обмена до доказательства, t) mod q
+fn challenge<G: Group>(setting: &Setting<G>, context: &[&BigUint], t: &BigUint) -> BigUint {
+    let mut hasher = Sha256::new();
+    absorb(&mut hasher, PROOF_DOMAIN.as_bytes());
+    absorb(&mut hasher, &setting.group.describe());
+    for value in context.iter().chain([&t]) {
+        absorb(&mut hasher, &value.to_bytes_be());
+    }
+    BigUint::from_bytes_be(&hasher.finalize()) % setting.order()
+}
+
+fn prove_key<G: Group>(setting: &Setting<G>, x: &BigUint, context: &[&BigUint]) -> KeyProof {
+    let schnorr = Schnorr::in_setting(setting.clone());
+    let y = schnorr.statement(x);
+    let (r, t) = schnorr
+        .commit(&y, x)
+        .expect("коммитмент Шнорра всегда вычисляется");
+    let t = setting.group.encode(&t);
+    let c = challenge(setting, context, &t);
+    KeyProof {
+        z: schnorr.respond(x, r, &c),
+        t,
+    }
+}
+
+fn verify_key<G: Group>(
+    setting: &Setting<G>,
+    y: &BigUint,
+    context: &[&BigUint],
+    proof: &KeyProof,
+) -> bool {
+    let group = &setting.group;
+    let (Some(y), Some(t)) = (group.decode(y), group.decode(&proof.t)) else {
+        return false;
+    };
+    if proof.z >= setting.order() {
+        return false;
+    }
+    let c = challenge(setting, context, &proof.t);
+    Schnorr::in_setting(setting.clone()).verify(&y, &t, &c, &proof.z)
+}
+
+//////////////////////////////////////////////////////////////
+// Ключ сессии: шифрование с проверкой целостности
+//////////////////////////////////////////////////////////////
+
+#[derive(Clone)]
+pub struct SessionKey {
+    key: [u8; 32],
+}
+
+//Сам ключ в журнал не попадает
+impl fmt::Debug for SessionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SessionKey({})", self.fingerprint())
+    }
+}
+
+impl SessionKey {
+    //HKDF от общего секрета; соль - хэш всех значений обмена
+    fn derive(shared: &[u8], transcript: &[&BigUint]) -> Self {
+        let mut hasher = Sha256::new();
+        for value in transcript {
+            absorb(&mut hasher, &value.to_bytes_be());
+        }
+        let salt = hasher.finalize();
+        let mut key = [0u8; 32];
+        Hkdf::<Sha256>::new(Some(&salt), shared)
+            .expand(KEY_INFO, &mut key)
+            .expect("32 байта - допустимая длина HKDF-SHA256");
+        SessionKey { key }
+    }
+
+    //Первые байты SHA-256 ключа: стороны сверяют их в журнале
+    pub fn fingerprint(&self) -> String {
+        encoding::to_hex(&Sha256::digest(self.key)[..8])
+    }
+
+    //Случайный nonce и шифртекст с тегом
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let cipher = ChaCha20Poly1305::new(CipherKey::from_slice(&self.key));
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let mut out = nonce.to_vec();
+        out.extend(
+            cipher
+                .encrypt(Nonce::from_slice(&nonce), plaintext)
+                .map_err(|e| e.to_string())?,
+        );
+        Ok(out)
+    }
+
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, String> {
+        if sealed.len() < NONCE_LEN {
+            return Err("Зашифрованное сообщение короче nonce".to_string());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        ChaCha20Poly1305::new(CipherKey::from_slice(&self.key))
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Сообщение не прошло проверку целостности".to_string())
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Стороны обмена
+//////////////////////////////////////////////////////////////
+
+//Инициатор между первым сообщением и ответом
+#[derive(Debug, Clone)]
+pub struct Initiator<G: Group> {
+    setting: Setting<G>,
+    e: BigUint,
+    hello: Hello,
+}
+
+impl<G: Group> Initiator<G> {
+    //Y_A, E_A и доказательство знания x_A, привязанное к E_A
+    pub fn start(setting: Setting<G>, x: &BigUint) -> Self {
+        let group = &setting.group;
+        let e = group.random_scalar();
+        let static_key = group.encode(&group.exp(&setting.g, x));
+        let ephemeral = group.encode(&group.exp(&setting.g, &e));
+        let proof = prove_key(&setting, x, &[&static_key, &ephemeral]);
+        Initiator {
+            hello: Hello {
+                static_key,
+                ephemeral,
+                proof,
+            },
+            setting,
+            e,
+        }
+    }
+
+    pub fn hello(&self) -> &Hello {
+        &self.hello
+    }
+
+    //Проверка ответа и ключ сессии из E_B^e_A
+    pub fn finish(&self, reply: &Hello) -> Result<SessionKey, String> {
+        let setting = &self.setting;
+        let transcript = [
+            &self.hello.static_key,
+            &self.hello.ephemeral,
+            &reply.static_key,
+            &reply.ephemeral,
+        ];
+        if !verify_key(setting, &reply.static_key, &transcript, &reply.proof) {
+            return Err("Отвечающий не доказал знание своего ключа".to_string());
+        }
+        let group = &setting.group;
+        let e_b = group
+            .decode(&reply.ephemeral)
+            .ok_or("Одноразовый ключ отвечающего вне группы")?;
+        let shared = group.serialize(&group.exp(&e_b, &self.e));
+        Ok(SessionKey::derive(&shared, &transcript))
+    }
+
+    //Обмен с сервером по адресу: первое сообщение, ответ и ключ сессии
+    #[cfg(feature = "server")]
+    pub async fn connect(&self, address: &Address) -> Result<(Hello, SessionKey), String> {
+        let client = reqwest::Client::new();
+        let reply: Hello = threshold::post(&client, address, HANDSHAKE_PATH, &self.hello).await?;
+        let key = self.finish(&reply)?;
+        Ok((reply, key))
+    }
+}
+
+//Отвечающий: проверка инициатора, свой ответ и ключ сессии из E_A^e_B
+pub fn respond<G: Group>(
+    setting: &Setting<G>,
+    x: &BigUint,
+    hello: &Hello,
+) -> Result<(Hello, SessionKey), String> {
+    if !verify_key(
+        setting,
+        &hello.static_key,
+        &[&hello.static_key, &hello.ephemeral],
+        &hello.proof,
+    ) {
+        return Err("Инициатор не доказал знание своего ключа".to_string());
+    }
+    let group = &setting.group;
+    let e_a = group
+        .decode(&hello.ephemeral)
+        .ok_or("Одноразовый ключ инициатора вне группы")?;
+    let e = group.random_scalar();
+    let static_key = group.encode(&group.exp(&setting.g, x));
+    let ephemeral = group.encode(&group.exp(&setting.g, &e));
+    let transcript = [&hello.static_key, &hello.ephemeral, &static_key, &ephemeral];
+    let proof = prove_key(setting, x, &transcript);
+    let shared = group.serialize(&group.exp(&e_a, &e));
+    let key = SessionKey::derive(&shared, &transcript);
+    let reply = Hello {
+        static_key,
+        ephemeral,
+        proof,
+    };
+    Ok((reply, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ristretto;
+
+    #[test]
+    fn test_handshake_agrees_on_key() {
+        let setting = ristretto::setting();
+        let x_a = setting.group.random_scalar();
+        let x_b = setting.group.random_scalar();
+        let initiator = Initiator::start(setting.clone(), &x_a);
+        let (reply, key_b) = respond(&setting, &x_b, initiator.hello()).unwrap();
+        let key_a = initiator.finish(&reply).unwrap();
+        assert_eq!(key_a.fingerprint(), key_b.fingerprint());
+
+        let sealed = key_a.seal(b"verdict").unwrap();
+        assert_eq!(key_b.open(&sealed).unwrap(), b"verdict");
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(key_b.open(&tampered).is_err());
+        assert!(key_b.open(&sealed[..4]).is_err());
+        assert!(!format!("{:?}", key_a).contains(&format!("{:?}", key_a.key)));
+    }
+
+    #[test]
+    fn test_handshake_rejects_foreign_proof() {
+        let setting = ristretto::setting();
+        let x = setting.group.random_scalar();
+        let initiator = Initiator::start(setting.clone(), &x);
+
+        //Чужой постоянный ключ без знания его x
+        let mut forged = initiator.hello().clone();
+        forged.static_key = setting.group.encode(&setting.h);
+        assert!(respond(&setting, &x, &forged).is_err());
+
+        //Доказательство из одной сессии не подходит к другому одноразовому ключу
+        let other = Initiator::start(setting.clone(), &x);
+        let mut replayed = other.hello().clone();
+        replayed.proof = initiator.hello().proof.clone();
+        assert!(respond(&setting, &x, &replayed).is_err());
+
+        let (mut reply, _) = respond(&setting, &x, initiator.hello()).unwrap();
+        reply.ephemeral = other.hello().ephemeral.clone();
+        assert!(initiator.finish(&reply).is_err());
+    }
+}
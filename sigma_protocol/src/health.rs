@@ -0,0 +1,88 @@
+//////////////////////////////////////////////////////////////
+// Самопроверка генератора случайных чисел при запуске. Вся
+// стойкость протокола держится на случайных секретах и
+// одноразовых ключах, поэтому сломанный источник энтропии (нули,
+// залипший байт, сильный перекос) нужно заметить до первого
+// доказательства. Тесты грубые: они ловят отказ, а не слабость.
+// Монобитный тест - FIPS 140-2: в 20000 битах число единиц
+// лежит в (9725, 10275). Тест повторов - SP 800-90B, 4.4.1:
+// при полной энтропии 8 бит на байт серия из REPETITION_CUTOFF
+// одинаковых байт случается с вероятностью около 2^-40.
+//////////////////////////////////////////////////////////////
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+const MONOBIT_BITS: u32 = 20_000;
+const MONOBIT_RANGE: (u32, u32) = (9725, 10275);
+const REPETITION_CUTOFF: u32 = 6;
+
+//Что делать, если генератор не прошел самопроверку
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RngCheck {
+    //Не запускаться
+    #[default]
+    Refuse,
+    //Запуститься с предупреждением в журнале
+    Warn,
+}
+
+//Результаты тестов на одной выборке
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthReport {
+    pub ones: u32,
+    pub longest_run: u32,
+}
+
+pub fn self_test<R: RngCore>(rng: &mut R) -> Result<HealthReport, String> {
+    let mut sample = vec![0u8; (MONOBIT_BITS / 8) as usize];
+    rng.try_fill_bytes(&mut sample)
+        .map_err(|e| format!("Генератор не выдал энтропию: {}", e))?;
+    let report = HealthReport {
+        ones: sample.iter().map(|b| b.count_ones()).sum(),
+        longest_run: longest_run(&sample),
+    };
+    let (lo, hi) = MONOBIT_RANGE;
+    if report.ones <= lo || report.ones >= hi {
+        return Err(format!(
+            "Монобитный тест: {} единиц из {} бит, допустимо от {} до {}",
+            report.ones, MONOBIT_BITS, lo, hi
+        ));
+    }
+    if report.longest_run >= REPETITION_CUTOFF {
+        return Err(format!(
+            "Тест повторов: {} одинаковых байт подряд",
+            report.longest_run
+        ));
+    }
+    Ok(report)
+}
+
+//Длина самой длинной серии одинаковых байт
+fn longest_run(sample: &[u8]) -> u32 {
+    sample
+        .chunk_by(|a, b| a == b)
+        .map(|run| run.len() as u32)
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+    use rand::rngs::mock::StepRng;
+
+    #[test]
+    fn test_self_test() {
+        let report = self_test(&mut OsRng).unwrap();
+        assert!(report.longest_run < REPETITION_CUTOFF);
+
+        //Нули, единицы и счетчик с шагом в один байт
+        assert!(self_test(&mut StepRng::new(0, 0)).is_err());
+        assert!(self_test(&mut StepRng::new(u64::MAX, 0)).is_err());
+        assert!(self_test(&mut StepRng::new(0, 1)).is_err());
+        assert_eq!(longest_run(&[1, 2, 2, 2, 3, 3]), 3);
+    }
+}
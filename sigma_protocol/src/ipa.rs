@@ -0,0 +1,323 @@
+//////////////////////////////////////////////////////////////
+// Аргумент скалярного произведения (как в Bulletproofs): сжатое
+// доказательство того, что P = g^a * h^b * u^<a, b> для векторов
+// a, b длины n. Каждый раунд вдвое укорачивает векторы и добавляет
+// к доказательству два элемента L, R, поэтому вместо 2n чисел
+// передается 2*log2(n) элементов и два числа. Испытания раундов
+// получаются хэшированием (Фиат-Шамир).
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::fiat_shamir::absorb;
+use crate::group::{Group, Setting};
+use crate::math;
+use crate::message::{decimal, decimal_vec};
+
+//Домены хэшей: вывод образующих и испытания раундов
+const BASE_DOMAIN: &str = "sigma_protocol ipa base";
+const ROUND_DOMAIN: &str = "sigma_protocol ipa round";
+
+//Векторы образующих g_1..g_n, h_1..h_n и отдельная u
+#[derive(Debug, Clone)]
+pub struct Generators<G: Group> {
+    pub g: Vec<G::Element>,
+    pub h: Vec<G::Element>,
+    pub u: G::Element,
+}
+
+//n-я по счетчику образующая с неизвестным логарифмом
+fn derive<G: Group>(group: &G, label: u8, i: u32) -> G::Element {
+    (0u32..)
+        .map(|counter| {
+            let mut seed = BASE_DOMAIN.as_bytes().to_vec();
+            seed.extend(group.describe());
+            seed.push(label);
+            seed.extend(i.to_be_bytes());
+            seed.extend(counter.to_be_bytes());
+            group.hash_to_element(&seed)
+        })
+        .find(|e| group.contains(e) && *e != group.identity())
+        .expect("хэш в группу с бесконечным счетчиком")
+}
+
+impl<G: Group> Generators<G> {
+    pub fn derive(setting: &Setting<G>, n: usize) -> Self {
+        let group = &setting.group;
+        Generators {
+            g: (0..n as u32).map(|i| derive(group, b'g', i)).collect(),
+            h: (0..n as u32).map(|i| derive(group, b'h', i)).collect(),
+            u: derive(group, b'u', 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpaProof {
+    #[serde(with = "decimal_vec")]
+    pub l: Vec<BigUint>,
+    #[serde(with = "decimal_vec")]
+    pub r: Vec<BigUint>,
+    #[serde(with = "decimal")]
+    pub a: BigUint,
+    #[serde(with = "decimal")]
+    pub b: BigUint,
+}
+
+impl IpaProof {
+    //Число передаваемых значений: 2*log2(n) элементов и два скаляра
+    pub fn size(&self) -> usize {
+        self.l.len() + self.r.len() + 2
+    }
+}
+
+pub fn inner_product(a: &[BigUint], b: &[BigUint], order: &BigUint) -> BigUint {
+    a.iter()
+        .zip(b)
+        .fold(BigUint::ZERO, |acc, (x, y)| (acc + x * y) % order)
+}
+
+//П bases_i^exponents_i
+fn multi_exp<G: Group>(group: &G, bases: &[G::Element], exponents: &[BigUint]) -> G::Element {
+    bases
+        .iter()
+        .zip(exponents)
+        .fold(group.identity(), |acc, (b, e)| {
+            group.mul(&acc, &group.exp(b, e))
+        })
+}
+
+//P = g^a * h^b * u^<a, b>
+pub fn commit<G: Group>(
+    setting: &Setting<G>,
+    gens: &Generators<G>,
+    a: &[BigUint],
+    b: &[BigUint],
+) -> G::Element {
+    let group = &setting.group;
+    let c = inner_product(a, b, &setting.order());
+    group.mul(
+        &group.mul(&multi_exp(group, &gens.g, a), &multi_exp(group, &gens.h, b)),
+        &group.exp(&gens.u, &c),
+    )
+}
+
+//Испытание раунда по предыдущему испытанию и L, R; ноль пропускается
+fn round_challenge<G: Group>(
+    setting: &Setting<G>,
+    previous: &BigUint,
+    l: &BigUint,
+    r: &BigUint,
+) -> (BigUint, BigUint) {
+    let order = setting.order();
+    for counter in 0u32.. {
+        let mut hasher = Sha256::new();
+        absorb(&mut hasher, ROUND_DOMAIN.as_bytes());
+        absorb(&mut hasher, &setting.group.describe());
+        for x in [previous, l, r] {
+            absorb(&mut hasher, &x.to_bytes_be());
+        }
+        absorb(&mut hasher, &counter.to_be_bytes());
+        let x = BigUint::from_bytes_be(&hasher.finalize()) % &order;
+        if let Ok(inverse) = math::modular_inverse_euclidean(&x, &order) {
+            return (x, inverse);
+        }
+    }
+    unreachable!("бесконечный счетчик")
+}
+
+//lo^x * hi^y покомпонентно
+fn fold_bases<G: Group>(
+    group: &G,
+    lo: &[G::Element],
+    hi: &[G::Element],
+    x: &BigUint,
+    y: &BigUint,
+) -> Vec<G::Element> {
+    lo.iter()
+        .zip(hi)
+        .map(|(l, h)| group.mul(&group.exp(l, x), &group.exp(h, y)))
+        .collect()
+}
+
+//lo*x + hi*y покомпонентно
+fn fold_scalars(
+    lo: &[BigUint],
+    hi: &[BigUint],
+    x: &BigUint,
+    y: &BigUint,
+    order: &BigUint,
+) -> Vec<BigUint> {
+    lo.iter()
+        .zip(hi)
+        .map(|(l, h)| (l * x + h * y) % order)
+        .collect()
+}
+
+//Доказательство для P = commit(a, b); длина векторов - степень двойки
+pub fn prove<G: Group>(
+    setting: &Setting<G>,
+    gens: &Generators<G>,
+    a: &[BigUint],
+    b: &[BigUint],
+) -> Result<IpaProof, String> {
+    let n = a.len();
+    if n == 0 || !n.is_power_of_two() || b.len() != n || gens.g.len() != n || gens.h.len() != n {
+        return Err("Длины векторов должны совпадать и быть степенью двойки".to_string());
+    }
+    let group = &setting.group;
+    let order = setting.order();
+    let (mut a, mut b) = (a.to_vec(), b.to_vec());
+    let (mut g, mut h) = (gens.g.clone(), gens.h.clone());
+    let mut previous = group.encode(&commit(setting, gens, &a, &b));
+    let (mut ls, mut rs) = (Vec::new(), Vec::new());
+
+    while a.len() > 1 {
+        let half = a.len() / 2;
+        let (a_lo, a_hi) = a.split_at(half);
+        let (b_lo, b_hi) = b.split_at(half);
+        let (g_lo, g_hi) = g.split_at(half);
+        let (h_lo, h_hi) = h.split_at(half);
+
+        //L = g_hi^a_lo * h_lo^b_hi * u^<a_lo, b_hi>, R симметрично
+        let l = group.mul(
+            &group.mul(&multi_exp(group, g_hi, a_lo), &multi_exp(group, h_lo, b_hi)),
+            &group.exp(&gens.u, &inner_product(a_lo, b_hi, &order)),
+        );
+        let r = group.mul(
+            &group.mul(&multi_exp(group, g_lo, a_hi), &multi_exp(group, h_hi, b_lo)),
+            &group.exp(&gens.u, &inner_product(a_hi, b_lo, &order)),
+        );
+        let (l, r) = (group.encode(&l), group.encode(&r));
+        let (x, x_inv) = round_challenge(setting, &previous, &l, &r);
+
+        //a' = a_lo*x + a_hi/x, b' = b_lo/x + b_hi*x
+        let next_a = fold_scalars(a_lo, a_hi, &x, &x_inv, &order);
+        let next_b = fold_scalars(b_lo, b_hi, &x_inv, &x, &order);
+        //g' = g_lo^(1/x) * g_hi^x, h' = h_lo^x * h_hi^(1/x)
+        let next_g = fold_bases(group, g_lo, g_hi, &x_inv, &x);
+        let next_h = fold_bases(group, h_lo, h_hi, &x, &x_inv);
+        (a, b, g, h) = (next_a, next_b, next_g, next_h);
+        previous = x;
+        ls.push(l);
+        rs.push(r);
+    }
+    Ok(IpaProof {
+        l: ls,
+        r: rs,
+        a: a.remove(0),
+        b: b.remove(0),
+    })
+}
+
+//Свертка образующих и P по испытаниям раундов, затем одна проверка
+pub fn verify<G: Group>(
+    setting: &Setting<G>,
+    gens: &Generators<G>,
+    p: &G::Element,
+    proof: &IpaProof,
+) -> bool {
+    let group = &setting.group;
+    let order = setting.order();
+    let n = gens.g.len();
+    if !n.is_power_of_two()
+        || gens.h.len() != n
+        || proof.l.len() != n.trailing_zeros() as usize
+        || proof.r.len() != proof.l.len()
+        || proof.a >= order
+        || proof.b >= order
+    {
+        return false;
+    }
+    let (mut g, mut h) = (gens.g.clone(), gens.h.clone());
+    let mut p = p.clone();
+    let mut previous = group.encode(&p);
+    for (l_enc, r_enc) in proof.l.iter().zip(&proof.r) {
+        let (Some(l), Some(r)) = (group.decode(l_enc), group.decode(r_enc)) else {
+            return false;
+        };
+        let (x, x_inv) = round_challenge(setting, &previous, l_enc, r_enc);
+        let half = g.len() / 2;
+        let (g_lo, g_hi) = g.split_at(half);
+        let (h_lo, h_hi) = h.split_at(half);
+        let next_g = fold_bases(group, g_lo, g_hi, &x_inv, &x);
+        let next_h = fold_bases(group, h_lo, h_hi, &x, &x_inv);
+        (g, h) = (next_g, next_h);
+        //P' = L^(x^2) * P * R^(x^-2)
+        let x2 = &x * &x % &order;
+        let x2_inv = &x_inv * &x_inv % &order;
+        p = group.mul(&group.mul(&group.exp(&l, &x2), &p), &group.exp(&r, &x2_inv));
+        previous = x;
+    }
+    let ab = &proof.a * &proof.b % &order;
+    let expected = group.mul(
+        &group.mul(&group.exp(&g[0], &proof.a), &group.exp(&h[0], &proof.b)),
+        &group.exp(&gens.u, &ab),
+    );
+    p == expected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::toy_params;
+    use crate::ristretto;
+
+    fn scalars(values: &[u32]) -> Vec<BigUint> {
+        values.iter().map(|&v| BigUint::from(v)).collect()
+    }
+
+    #[test]
+    fn test_inner_product_argument() {
+        let setting = ristretto::setting();
+        let gens = Generators::derive(&setting, 8);
+        let a = scalars(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let b = scalars(&[8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(
+            inner_product(&a, &b, &setting.order()),
+            BigUint::from(120u32)
+        );
+        let p = commit(&setting, &gens, &a, &b);
+        let proof = prove(&setting, &gens, &a, &b).unwrap();
+        assert_eq!(proof.l.len(), 3);
+        assert_eq!(proof.size(), 8);
+        assert!(verify(&setting, &gens, &p, &proof));
+
+        //Другое скалярное произведение при тех же a, b не проходит
+        let wrong = setting.group.mul(&p, &gens.u);
+        assert!(!verify(&setting, &gens, &wrong, &proof));
+        let mut forged = proof.clone();
+        forged.a += 1u32;
+        assert!(!verify(&setting, &gens, &p, &forged));
+        let json = serde_json::to_string(&proof).unwrap();
+        let restored: IpaProof = serde_json::from_str(&json).unwrap();
+        assert!(verify(&setting, &gens, &p, &restored));
+    }
+
+    #[test]
+    fn test_small_group_and_lengths() {
+        let setting = toy_params().setting();
+        let gens = Generators::derive(&setting, 4);
+        let (a, b) = (scalars(&[3, 1, 4, 1]), scalars(&[5, 9, 2, 6]));
+        let proof = prove(&setting, &gens, &a, &b).unwrap();
+        assert!(verify(
+            &setting,
+            &gens,
+            &commit(&setting, &gens, &a, &b),
+            &proof
+        ));
+
+        assert!(prove(&setting, &gens, &a[..3], &b[..3]).is_err());
+        let single = Generators::derive(&setting, 1);
+        let proof = prove(&setting, &single, &a[..1], &b[..1]).unwrap();
+        assert!(proof.l.is_empty());
+        assert!(verify(
+            &setting,
+            &single,
+            &commit(&setting, &single, &a[..1], &b[..1]),
+            &proof
+        ));
+    }
+}
@@ -0,0 +1,146 @@
+//////////////////////////////////////////////////////////////
+// Доказательство с нулевым разглашением изоморфизма графов
+// (Голдрайх-Микали-Вигдерсон): доказывающий знает перестановку pi,
+// переводящую G0 в G1, и в каждом раунде показывает случайную
+// копию H одного из графов. По биту испытания b он раскрывает
+// изоморфизм G_b -> H, но никогда оба сразу. Обманщик угадывает
+// бит с вероятностью 1/2 в каждом раунде.
+//////////////////////////////////////////////////////////////
+
+use crate::graph::{Graph, Permutation};
+use crate::sigma::SigmaProtocol;
+
+//Открытая пара графов
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphPair {
+    pub g0: Graph,
+    pub g1: Graph,
+}
+
+impl GraphPair {
+    pub fn get(&self, bit: bool) -> &Graph {
+        if bit { &self.g1 } else { &self.g0 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GraphIsomorphism;
+
+impl SigmaProtocol for GraphIsomorphism {
+    type Statement = GraphPair;
+    type Witness = Permutation;
+    type Nonce = Permutation;
+    type Commitment = Graph;
+    type Challenge = bool;
+    type Response = Permutation;
+
+    //H = sigma(G1) для случайной sigma
+    fn commit(&self, pair: &GraphPair, _pi: &Permutation) -> Option<(Permutation, Graph)> {
+        let sigma = Permutation::random(pair.g1.vertices());
+        let h = pair.g1.permute(&sigma)?;
+        Some((sigma, h))
+    }
+
+    fn challenge(&self) -> bool {
+        rand::random()
+    }
+
+    //b = 1: sigma; b = 0: сначала pi, затем sigma
+    fn respond(&self, pi: &Permutation, sigma: Permutation, bit: &bool) -> Permutation {
+        if *bit { sigma } else { pi.then(&sigma) }
+    }
+
+    //H == rho(G_b)
+    fn verify(&self, pair: &GraphPair, h: &Graph, bit: &bool, rho: &Permutation) -> bool {
+        Permutation::from_vec(rho.as_slice().to_vec()).is_some()
+            && pair.get(*bit).permute(rho).as_ref() == Some(h)
+    }
+}
+
+//Доказывающий: случайный G0 и его переименование G1 = pi(G0)
+#[derive(Debug, Clone)]
+pub struct GiProver {
+    pair: GraphPair,
+    pi: Permutation,
+}
+
+impl GiProver {
+    pub fn new(g0: Graph, pi: Permutation) -> Option<Self> {
+        let g1 = g0.permute(&pi)?;
+        Some(GiProver {
+            pair: GraphPair { g0, g1 },
+            pi,
+        })
+    }
+
+    pub fn random(vertices: usize, density: f64) -> Self {
+        GiProver::new(
+            Graph::random(vertices, density),
+            Permutation::random(vertices),
+        )
+        .expect("размеры графа и перестановки совпадают")
+    }
+
+    pub fn pair(&self) -> &GraphPair {
+        &self.pair
+    }
+
+    pub fn witness(&self) -> &Permutation {
+        &self.pi
+    }
+
+    pub fn commit(&self) -> (Permutation, Graph) {
+        GraphIsomorphism
+            .commit(&self.pair, &self.pi)
+            .expect("перестановка подходит к графу")
+    }
+
+    pub fn respond(&self, sigma: Permutation, bit: bool) -> Permutation {
+        GraphIsomorphism.respond(&self.pi, sigma, &bit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sigma;
+
+    #[test]
+    fn test_honest_prover_accepted() {
+        let prover = GiProver::random(8, 0.5);
+        for bit in [false, true] {
+            let (sigma, h) = prover.commit();
+            let rho = prover.respond(sigma, bit);
+            assert!(GraphIsomorphism.verify(prover.pair(), &h, &bit, &rho));
+        }
+        assert_eq!(
+            sigma::execute(&GraphIsomorphism, prover.pair(), prover.witness()),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_cheater_answers_one_bit() {
+        //Треугольник и путь не изоморфны
+        let pair = GraphPair {
+            g0: Graph::new(3, [(0, 1), (1, 2), (0, 2)]),
+            g1: Graph::new(3, [(0, 1), (1, 2)]),
+        };
+        //Обманщик готовит копию G1 и может ответить только на b = 1
+        let sigma = Permutation::random(3);
+        let h = pair.g1.permute(&sigma).unwrap();
+        assert!(GraphIsomorphism.verify(&pair, &h, &true, &sigma));
+        for rho in [
+            vec![0, 1, 2],
+            vec![0, 2, 1],
+            vec![1, 0, 2],
+            vec![1, 2, 0],
+            vec![2, 0, 1],
+            vec![2, 1, 0],
+        ] {
+            let rho = Permutation::from_vec(rho).unwrap();
+            assert!(!GraphIsomorphism.verify(&pair, &h, &false, &rho));
+        }
+        assert!(!GraphIsomorphism.verify(&pair, &h, &true, &Permutation::identity(4)));
+    }
+}
@@ -0,0 +1,97 @@
+//////////////////////////////////////////////////////////////
+// Вывод ключей из пароля. Argon2id растягивает пароль с солью в
+// 32-байтовый ключ: перебор паролей дорог по времени и памяти.
+// HKDF-SHA256 разворачивает ключ в показатели по модулю q, по одному
+// на метку. Одна и та же фраза с той же солью дает тот же секрет на
+// любом устройстве; соль не секретна, но без нее секрет не вывести.
+//////////////////////////////////////////////////////////////
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use hkdf::Hkdf;
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+pub const SALT_LEN: usize = 16;
+//Лишние байты HKDF перед приведением по модулю q делают смещение
+//распределения пренебрежимым (меньше 2^-128)
+const WIDE_EXTRA: usize = 16;
+
+//Стоимость Argon2id: память в КиБ, число проходов и потоков.
+//По умолчанию - рекомендация OWASP (19 МиБ, 2 прохода)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            m_cost: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+        }
+    }
+}
+
+pub fn random_salt() -> [u8; SALT_LEN] {
+    rand::random()
+}
+
+//Argon2id(пароль, соль) -> 32 байта
+pub fn stretch(password: &str, salt: &[u8], params: KdfParams) -> Result<[u8; 32], String> {
+    let argon_params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(32))
+        .map_err(|e| format!("Некорректные параметры Argon2id: {}", e))?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, argon_params)
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+//Показатель из [0, order) для метки label: HKDF-Expand на длину q
+//плюс WIDE_EXTRA байт и остаток от деления на q
+pub fn expand_scalar(key: &[u8; 32], label: &[u8], order: &BigUint) -> BigUint {
+    let mut wide = vec![0u8; order.bits().div_ceil(8) as usize + WIDE_EXTRA];
+    Hkdf::<Sha256>::from_prk(key)
+        .expect("32 байта - допустимый PRK для HKDF-SHA256")
+        .expand(label, &mut wide)
+        .expect("длина вывода HKDF далеко от предела 255 * 32 байта");
+    BigUint::from_bytes_be(&wide) % order
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    //Дешевые параметры, чтобы тесты не тратили 19 МиБ и секунды
+    pub(crate) const CHEAP: KdfParams = KdfParams {
+        m_cost: 64,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    #[test]
+    fn test_stretch_and_expand() {
+        let salt = [7u8; SALT_LEN];
+        let key = stretch("correct horse", &salt, CHEAP).unwrap();
+        assert_eq!(stretch("correct horse", &salt, CHEAP).unwrap(), key);
+        assert_ne!(
+            stretch("correct horse", &[8u8; SALT_LEN], CHEAP).unwrap(),
+            key
+        );
+        assert_ne!(stretch("wrong horse", &salt, CHEAP).unwrap(), key);
+        assert!(stretch("x", b"short", CHEAP).is_err());
+
+        let order = BigUint::from(11u32);
+        let alpha = expand_scalar(&key, b"alpha", &order);
+        assert!(alpha < order);
+        let big = BigUint::from(1u32) << 255u32;
+        assert_ne!(
+            expand_scalar(&key, b"alpha", &big),
+            expand_scalar(&key, b"beta", &big)
+        );
+    }
+}
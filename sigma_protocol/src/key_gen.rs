@@ -1,45 +1,511 @@
-use num_bigint::{BigInt, BigUint, RandBigInt, ToBigInt, ToBigUint};
-use num_traits::{FromPrimitive, One, Zero};
+use std::fmt;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 
+use num_bigint::{BigUint, RandBigInt};
+use num_integer::Integer;
+use num_traits::{CheckedSub, FromPrimitive, One, ToPrimitive, Zero};
+use rand::rngs::{OsRng, StdRng};
+use rand::{CryptoRng, RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::backend;
 use crate::math;
 
-const RANDOM_SIZE: u64 = 64;
+//Наименьшие допустимые длины простого модуля и секрета в битах
+pub const MIN_PRIME_BITS: u64 = 32;
+pub const MIN_SECRET_BITS: u64 = 64;
+//Раунды Миллера-Рабина при поиске простых по умолчанию и наибольшее число
+pub const DEFAULT_MR_ROUNDS: u8 = 8;
+pub const MAX_MR_ROUNDS: u8 = 64;
 
-//Генерация случайного простого числа
-pub async fn gen_random_prime() -> BigUint {
-    let mut rng = rand::thread_rng();
-    let mut res = rng.gen_biguint(RANDOM_SIZE);
-    if &res % BigUint::from_u8(2).unwrap() == BigUint::zero() {
-        res += BigUint::one();
+//Пробное деление на нечетные простые меньше SIEVE_LIMIT отсекает
+//большую часть составных кандидатов до теста Миллера-Рабина
+const SIEVE_LIMIT: u32 = 1 << 13;
+//Число нечетных кандидатов в одном окне решета
+const SIEVE_WINDOW: usize = 1 << 12;
+//Период, с которым сторож поиска проверяет срок и отмену
+const WATCH_INTERVAL: Duration = Duration::from_millis(10);
+
+static SMALL_PRIMES: LazyLock<Vec<u32>> = LazyLock::new(|| {
+    let mut composite = vec![false; SIEVE_LIMIT as usize];
+    (3..SIEVE_LIMIT)
+        .step_by(2)
+        .filter(|&p| {
+            if composite[p as usize] {
+                return false;
+            }
+            (p * p..SIEVE_LIMIT)
+                .step_by(2 * p as usize)
+                .for_each(|m| composite[m as usize] = true);
+            true
+        })
+        .collect()
+});
+
+//n делится на нечетное простое меньше SIEVE_LIMIT, отличное от самого n
+pub fn has_small_factor(n: &BigUint) -> bool {
+    SMALL_PRIMES
+        .iter()
+        .any(|&p| (n % p).is_zero() && *n != BigUint::from(p))
+}
+
+//Решето по окну кандидатов start, start + 2, ... (start нечетное): false,
+//если кандидат (а при safe еще и 2 * кандидат + 1) делится на малое простое.
+//Остаток start по каждому простому считается один раз на все окно
+fn sieve_window(start: &BigUint, safe: bool) -> Vec<bool> {
+    let mut alive = vec![true; SIEVE_WINDOW];
+    //Малые кандидаты могли бы совпасть с самими простыми решета
+    if *start <= BigUint::from(SIEVE_LIMIT) {
+        return alive;
+    }
+    for &p in SMALL_PRIMES.iter() {
+        let p = p as u64;
+        let r = (start % p).to_u64().unwrap_or_default();
+        //Индекс i кандидата с остатком f: i = (f - r) / 2 (mod p),
+        //где (p + 1) / 2 - обратный к 2 по модулю p
+        let inv2 = p.div_ceil(2);
+        //При остатке (p - 1) / 2 на p делится 2 * кандидат + 1
+        let forbidden = [Some(0), safe.then_some((p - 1) / 2)];
+        for f in forbidden.into_iter().flatten() {
+            let mut i = ((f + p - r) % p * inv2 % p) as usize;
+            while i < SIEVE_WINDOW {
+                alive[i] = false;
+                i += p as usize;
+            }
+        }
+    }
+    alive
+}
+
+//Первый кандидат окна, прошедший решето и проверку is_prime
+fn search_window(
+    start: &BigUint,
+    safe: bool,
+    is_prime: impl Fn(&BigUint) -> bool,
+) -> Option<BigUint> {
+    sieve_window(start, safe)
+        .iter()
+        .enumerate()
+        .filter(|(_, alive)| **alive)
+        .map(|(i, _)| start + 2 * i)
+        .find(|n| is_prime(n))
+}
+
+//Источник случайности секретов: функции без суффикса _with берут
+//OsRng, варианты с _with - переданный вызывающим генератор
+pub trait SecureRng: CryptoRng + RngCore {}
+
+impl<R: CryptoRng + RngCore> SecureRng for R {}
+
+//Пределы поиска простого: deadline ограничивает время, stop отменяет
+//поиск извне. Без них поиск идет до результата
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchLimits<'a> {
+    pub deadline: Option<Duration>,
+    pub stop: Option<&'a AtomicBool>,
+}
+
+impl SearchLimits<'_> {
+    //Причина остановки поиска, начатого в started, если пора остановиться
+    fn exceeded(&self, started: Instant) -> Option<SearchError> {
+        if self.stop.is_some_and(|stop| stop.load(Ordering::Relaxed)) {
+            return Some(SearchError::Cancelled);
+        }
+        self.deadline
+            .filter(|&deadline| started.elapsed() >= deadline)
+            .map(SearchError::Timeout)
     }
+}
+
+//Поиск остановлен раньше, чем нашел простое
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchError {
+    Timeout(Duration),
+    Cancelled,
+}
 
-    while !is_prime_miller_rabin(&res, 8) {
-        res += BigUint::from_u8(2).unwrap();
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::Timeout(deadline) => write!(
+                f,
+                "Поиск простого не уложился в {:.1} с",
+                deadline.as_secs_f64()
+            ),
+            SearchError::Cancelled => write!(f, "Поиск простого отменен"),
+        }
     }
-    res.to_biguint().unwrap()
 }
 
-pub async fn random_biguint_mod(module: &BigUint) -> BigUint {
-    let mut rng = rand::thread_rng();
-    rng.gen_biguint(RANDOM_SIZE) % module
+impl std::error::Error for SearchError {}
+
+//Сколько раундов Миллера-Рабина со случайными основаниями прошло число
+//и оценка ошибки: составное проходит раунд с вероятностью не больше 1/4
+//(Рабин, 1980), поэтому после k раундов она не больше 2^-2k. Для
+//случайных кандидатов оценка сильно завышена, но другой гарантии нет
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Primality {
+    pub mr_rounds: u8,
+    pub error_bits: u32,
+}
+
+impl Primality {
+    pub fn new(mr_rounds: u8) -> Self {
+        Primality {
+            mr_rounds,
+            error_bits: 2 * mr_rounds as u32,
+        }
+    }
+}
+
+impl fmt::Display for Primality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} раундов Миллера-Рабина, вероятность принять составное не больше 2^-{}",
+            self.mr_rounds, self.error_bits
+        )
+    }
+}
+
+//Генерация случайного простого числа длиной ровно bits бит
+pub fn gen_random_prime(bits: u64) -> BigUint {
+    gen_random_prime_with(&mut OsRng, bits)
+}
+
+pub fn gen_random_prime_with<R: SecureRng>(rng: &mut R, bits: u64) -> BigUint {
+    gen_random_prime_limited(rng, bits, DEFAULT_MR_ROUNDS, SearchLimits::default())
+        .expect("поиск без пределов не останавливается")
 }
 
-pub async fn generated_element(module: &BigUint) -> Result<BigUint, String> {
+//Каждый кандидат - новое равномерное нечетное число длины bits: простое
+//выбирается равномерно среди простых этой длины. Перебор n, n + 2, ...
+//от случайного начала чаще выдавал бы простые после длинных промежутков
+pub fn gen_random_prime_limited<R: SecureRng>(
+    rng: &mut R,
+    bits: u64,
+    rounds: u8,
+    limits: SearchLimits,
+) -> Result<BigUint, SearchError> {
+    race(rng, limits, |rng, _| {
+        let candidate = random_odd_with(rng, bits);
+        (!has_small_factor(&candidate) && is_prime_miller_rabin(&candidate, rounds))
+            .then_some(candidate)
+    })
+}
+
+//Равномерное нечетное число длиной ровно bits бит
+pub fn random_odd_with<R: SecureRng>(rng: &mut R, bits: u64) -> BigUint {
+    let mut n = rng.gen_biguint(bits);
+    n.set_bit(bits - 1, true);
+    n.set_bit(0, true);
+    n
+}
+
+//Поиск на всех ядрах: каждый поток перебирает окна от своих случайных
+//начал со своим генератором, засеянным из rng. Первый найденный результат
+//останавливает остальных - они прекращают проверять кандидатов окна.
+//Так же их останавливает сторож, когда истек срок или поиск отменен
+fn race<R: SecureRng, T: Send>(
+    rng: &mut R,
+    limits: SearchLimits,
+    attempt: impl Fn(&mut StdRng, &AtomicBool) -> Option<T> + Sync,
+) -> Result<T, SearchError> {
+    let started = Instant::now();
+    if let Some(stopped) = limits.exceeded(started) {
+        return Err(stopped);
+    }
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+    let rngs: Vec<StdRng> = (0..workers)
+        .map(|_| StdRng::from_rng(&mut *rng).expect("генератор не выдал зерно"))
+        .collect();
+    let cancelled = AtomicBool::new(false);
+    let (cancelled, attempt) = (&cancelled, &attempt);
+    thread::scope(|scope| {
+        let watcher = (limits.deadline.is_some() || limits.stop.is_some()).then(|| {
+            scope.spawn(move || {
+                while !cancelled.load(Ordering::Relaxed) {
+                    let stopped = limits.exceeded(started);
+                    if stopped.is_some() {
+                        cancelled.store(true, Ordering::Relaxed);
+                        return stopped;
+                    }
+                    thread::sleep(WATCH_INTERVAL);
+                }
+                None
+            })
+        });
+        let handles: Vec<_> = rngs
+            .into_iter()
+            .map(|mut rng| {
+                scope.spawn(move || {
+                    while !cancelled.load(Ordering::Relaxed) {
+                        if let Some(found) = attempt(&mut rng, cancelled) {
+                            cancelled.store(true, Ordering::Relaxed);
+                            return Some(found);
+                        }
+                    }
+                    None
+                })
+            })
+            .collect();
+        let found = handles
+            .into_iter()
+            .filter_map(|handle| handle.join().expect("поток поиска простого упал"))
+            .next();
+        let stopped = watcher.and_then(|watcher| watcher.join().expect("сторож поиска упал"));
+        //Потоки без результата выходят, только если их остановил сторож
+        found.ok_or_else(|| stopped.expect("поиск остановлен без результата"))
+    })
+}
+
+//Наименьшее простое больше n
+pub fn next_prime(n: &BigUint) -> BigUint {
+    if *n < BigUint::from(2u32) {
+        return BigUint::from(2u32);
+    }
+    let mut start = (n + 1u32) | BigUint::one();
     loop {
-        let mut rng = rand::thread_rng();
-        let element = rng.gen_biguint(RANDOM_SIZE);
-        let res = match math::mod_pow_big(&element, &BigInt::from_u8(2).unwrap(), module) {
-            Some(result) => result,
-            None => return Err("Error in mod_pow_big".to_string()),
+        if let Some(p) = search_window(&start, false, |n| is_prime_miller_rabin(n, 8)) {
+            return p;
+        }
+        start += 2 * SIEVE_WINDOW;
+    }
+}
+
+//Наибольшее простое меньше n; для n <= 2 его нет
+pub fn prev_prime(n: &BigUint) -> Option<BigUint> {
+    if *n <= BigUint::from(3u32) {
+        return (*n == BigUint::from(3u32)).then(|| BigUint::from(2u32));
+    }
+    let mut candidate = (n - 1u32) | BigUint::one();
+    if candidate == *n {
+        candidate -= 2u32;
+    }
+    while has_small_factor(&candidate) || !is_prime_miller_rabin(&candidate, 8) {
+        candidate -= 2u32;
+    }
+    Some(candidate)
+}
+
+//Ход поиска безопасного простого суммарно по всем потокам: просмотренные
+//окна решета, кандидаты, дошедшие до теста Миллера-Рабина, выполненные
+//раунды теста и время с начала поиска
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchProgress {
+    pub windows: u64,
+    pub tested: u64,
+    pub mr_rounds: u64,
+    pub elapsed: Duration,
+}
+
+//Ожидаемое число кандидатов q, прошедших решето, до безопасного простого
+//длиной bits. По гипотезе Харди-Литтлвуда доля простых Софи Жермен около
+//N равна 2 C_2 / ln(N)^2, а решето оставляет долю
+//1/2 * prod (1 - 2/p) по нечетным p < SIEVE_LIMIT, не трогая их
+pub fn expected_safe_prime_tests(bits: u64) -> f64 {
+    const TWIN_PRIME_CONSTANT: f64 = 0.660_161_815_846_869_6;
+    let survivors = SMALL_PRIMES
+        .iter()
+        .fold(0.5, |acc, &p| acc * (1.0 - 2.0 / p as f64));
+    let ln_q = (bits - 1) as f64 * std::f64::consts::LN_2;
+    ln_q * ln_q * survivors / (2.0 * TWIN_PRIME_CONSTANT)
+}
+
+//Генерация безопасного простого p = 2q + 1 длиной bits (q - простое
+//Софи Жермен); возвращает (p, q). Рекомендуемый источник параметров
+//группы: GroupParams::generate строит группу на этой паре
+pub fn gen_safe_prime(bits: u64) -> (BigUint, BigUint) {
+    gen_safe_prime_with(&mut OsRng, bits)
+}
+
+pub fn gen_safe_prime_with<R: SecureRng>(rng: &mut R, bits: u64) -> (BigUint, BigUint) {
+    gen_safe_prime_reporting(rng, bits, |_| {})
+}
+
+//report вызывается после каждого окна из потока, который его просмотрел
+pub fn gen_safe_prime_reporting<R: SecureRng>(
+    rng: &mut R,
+    bits: u64,
+    report: impl Fn(SearchProgress) + Sync,
+) -> (BigUint, BigUint) {
+    gen_safe_prime_limited(
+        rng,
+        bits,
+        DEFAULT_MR_ROUNDS,
+        SearchLimits::default(),
+        report,
+    )
+    .expect("поиск без пределов не останавливается")
+}
+
+pub fn gen_safe_prime_limited<R: SecureRng>(
+    rng: &mut R,
+    bits: u64,
+    rounds: u8,
+    limits: SearchLimits,
+    report: impl Fn(SearchProgress) + Sync,
+) -> Result<(BigUint, BigUint), SearchError> {
+    assert!(bits >= 3, "безопасное простое короче 3 бит");
+    let started = Instant::now();
+    let (windows, tested, mr_rounds) = (AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0));
+    //Безопасные простые редки, поэтому здесь кандидаты идут окнами решета
+    //от случайного начала, а не по одному, как в gen_random_prime
+    let q = race(rng, limits, |rng, cancelled| {
+        //q нечетное со старшим битом, чтобы p имело ровно bits бит
+        let start = random_odd_with(rng, bits - 1);
+        //Решето по простому 3 отбрасывает и q = 1 (mod 3), при котором 3 | p
+        let found = search_window(&start, true, |q| {
+            if cancelled.load(Ordering::Relaxed) {
+                return false;
+            }
+            tested.fetch_add(1, Ordering::Relaxed);
+            miller_rabin(q, rounds, &mr_rounds)
+                && miller_rabin(&(q * 2u32 + 1u32), rounds, &mr_rounds)
+        });
+        report(SearchProgress {
+            windows: windows.fetch_add(1, Ordering::Relaxed) + 1,
+            tested: tested.load(Ordering::Relaxed),
+            mr_rounds: mr_rounds.load(Ordering::Relaxed),
+            elapsed: started.elapsed(),
+        });
+        //Окно могло выйти за bits - 1 бит
+        found.filter(|q| q.bits() == bits - 1)
+    })?;
+    Ok((&q * 2u32 + 1u32, q))
+}
+
+//////////////////////////////////////////////////////////////
+// Сильные простые (Гордон, 1984): у p - 1 большой простой делитель r,
+// у r - 1 - большой простой делитель t, у p + 1 - большой простой
+// делитель s. Модуль RSA из таких простых не раскладывается методами
+// p - 1 Полларда и p + 1 Уильямса.
+//////////////////////////////////////////////////////////////
+
+//Вид простых множителей модуля RSA
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrimeKind {
+    //Безопасное простое p = 2q + 1
+    #[default]
+    Safe,
+    //Сильное простое по алгоритму Гордона
+    Strong,
+}
+
+//Простое длиной ровно bits бит выбранного вида
+pub fn gen_modulus_prime(bits: u64, kind: PrimeKind) -> BigUint {
+    match kind {
+        PrimeKind::Safe => gen_safe_prime(bits).0,
+        PrimeKind::Strong => gen_strong_prime(bits),
+    }
+}
+
+pub fn gen_strong_prime(bits: u64) -> BigUint {
+    gen_strong_prime_with(&mut OsRng, bits)
+}
+
+//s и t длиной 3/8 от p оставляют для p = p0 + 2jrs около bits/4 бит j
+pub fn gen_strong_prime_with<R: SecureRng>(rng: &mut R, bits: u64) -> BigUint {
+    assert!(
+        bits >= MIN_PRIME_BITS,
+        "сильное простое короче {} бит",
+        MIN_PRIME_BITS
+    );
+    let is_prime = |n: &BigUint| !has_small_factor(n) && is_prime_miller_rabin(n, 8);
+    let lo = BigUint::one() << (bits - 1);
+    let hi = BigUint::one() << bits;
+    loop {
+        let s = gen_random_prime_with(rng, bits * 3 / 8);
+        let t = gen_random_prime_with(rng, bits * 3 / 8);
+        //Первое простое вида r = 2it + 1
+        let mut r = &t * 2u32 + 1u32;
+        while !is_prime(&r) {
+            r += &t * 2u32;
+        }
+        if r == s {
+            continue;
+        }
+        //p0 = 2 (s^(r - 2) mod r) s - 1: p0 = 1 (mod r), p0 = -1 (mod s)
+        let p0 = backend::mod_pow(&s, &(&r - 2u32), &r) * &s * 2u32 - 1u32;
+        let step = &r * &s * 2u32;
+        //j из [j_min, j_max], при которых p имеет ровно bits бит;
+        //перебор начинается со случайного j
+        let j_min = lo.checked_sub(&p0).unwrap_or_default().div_ceil(&step);
+        let Some(j_max) = hi.checked_sub(&(&p0 + 1u32)).map(|d| d / &step) else {
+            continue;
         };
-        if res == BigUint::one() {
+        if j_max < j_min {
             continue;
         }
-        return Ok(res);
+        let mut p = &p0 + random_biguint_range_with(rng, &j_min, &(j_max + 1u32)) * &step;
+        while p < hi {
+            if is_prime(&p) {
+                return p;
+            }
+            p += &step;
+        }
     }
 }
 
-fn is_prime_miller_rabin(n: &BigUint, k: u8) -> bool {
+//Равномерно из [0, module)
+pub fn random_biguint_mod(module: &BigUint) -> BigUint {
+    random_biguint_mod_with(&mut OsRng, module)
+}
+
+//Выборка с отклонением: число из bits(module - 1) случайных бит принимается,
+//только если оно меньше module, иначе выбирается заново (не чаще чем в
+//половине случаев). Остаток от деления длинного случайного числа на module
+//смещен к малым значениям
+pub fn random_biguint_mod_with<R: SecureRng>(rng: &mut R, module: &BigUint) -> BigUint {
+    assert!(!module.is_zero(), "пустой диапазон [0, 0)");
+    let bits = (module - 1u32).bits();
+    loop {
+        let x = rng.gen_biguint(bits);
+        if x < *module {
+            return x;
+        }
+    }
+}
+
+//Равномерно из [lo, hi)
+pub fn random_biguint_range_with<R: SecureRng>(rng: &mut R, lo: &BigUint, hi: &BigUint) -> BigUint {
+    assert!(lo < hi, "пустой диапазон [{}, {})", lo, hi);
+    lo + random_biguint_mod_with(rng, &(hi - lo))
+}
+
+//Равномерно из [0, min(module, 2^bits)): короткий секрет в большой группе
+pub fn random_biguint_bits(module: &BigUint, bits: u64) -> BigUint {
+    random_biguint_bits_with(&mut OsRng, module, bits)
+}
+
+pub fn random_biguint_bits_with<R: SecureRng>(rng: &mut R, module: &BigUint, bits: u64) -> BigUint {
+    random_biguint_mod_with(rng, module.min(&(BigUint::one() << bits)))
+}
+
+//Образующая подгруппы порядка q для безопасного простого module = 2q + 1
+pub fn generated_element(module: &BigUint) -> Result<BigUint, String> {
+    let error = || format!("{} не является безопасным простым", module);
+    let q = module.checked_sub(&BigUint::one()).ok_or_else(error)? >> 1;
+    math::find_generator(module, &q, &[BigUint::from(2u32), q.clone()]).ok_or_else(error)
+}
+
+//Ниже этой границы первые 13 простых оснований дают детерминированный
+//ответ (Соренсон и Вебстер, 2015): 3 317 044 064 679 887 385 961 981
+const DETERMINISTIC_BOUND: &str = "3317044064679887385961981";
+const DETERMINISTIC_BASES: [u32; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+
+//Тест Миллера-Рабина: для n ниже DETERMINISTIC_BOUND точный и воспроизводимый,
+//для больших n - k случайных оснований
+pub fn is_prime_miller_rabin(n: &BigUint, k: u8) -> bool {
+    miller_rabin(n, k, &AtomicU64::new(0))
+}
+
+//То же со счетчиком выполненных раундов
+fn miller_rabin(n: &BigUint, k: u8, rounds: &AtomicU64) -> bool {
     if n <= &BigUint::one() {
         return false;
     }
@@ -49,44 +515,259 @@ fn is_prime_miller_rabin(n: &BigUint, k: u8) -> bool {
     if n % BigUint::from_u8(2).unwrap() == BigUint::zero() {
         return false;
     }
-    let mut t: BigInt = (n - BigUint::one()).to_bigint().unwrap();
-    let mut s = 0;
-    while &t % 2 == BigInt::zero() {
-        t = t / 2;
-        s += 1;
+    let n_1 = n - BigUint::one();
+    let s = n_1.trailing_zeros().unwrap_or(0) as u32;
+    let t = &n_1 >> s;
+    let bound = BigUint::parse_bytes(DETERMINISTIC_BOUND.as_bytes(), 10).unwrap();
+    if n < &bound {
+        return DETERMINISTIC_BASES
+            .iter()
+            .map(|&a| BigUint::from(a))
+            .filter(|a| a < n)
+            .all(|a| {
+                rounds.fetch_add(1, Ordering::Relaxed);
+                !backend::is_witness(n, &a, &t, s)
+            });
     }
-    'A: for _ in 0..k {
-        let mut rng = rand::thread_rng();
+    let mut rng = rand::thread_rng();
+    (0..k).all(|_| {
         let a = rng.gen_biguint_range(
             &BigUint::from_u8(2).unwrap(),
             &(n - BigUint::from_u8(2).unwrap()),
         );
-        let mut x = match math::mod_pow_big(&a, &t, n) {
-            Some(x) => x,
-            None => {
-                eprintln!("Error in mod_pow");
-                continue 'A;
-            }
-        };
-        if x == BigUint::one() || x == n - BigUint::one() {
-            continue 'A;
-        }
-        for _ in 0..s - 1 {
-            x = match math::mod_pow_big(&x, &BigInt::from_i8(2).unwrap(), n) {
-                Some(x) => x,
-                None => {
-                    eprintln!("Error in mod_pow");
-                    continue 'A;
-                }
-            };
-            if x == BigUint::one() {
-                return false;
+        rounds.fetch_add(1, Ordering::Relaxed);
+        !backend::is_witness(n, &a, &t, s)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::ToBigInt;
+
+    #[test]
+    fn test_deterministic_miller_rabin() {
+        //Сильные псевдопростые по основаниям 2..23 и числа Кармайкла
+        let composites = [
+            561u64,
+            2_047,
+            1_373_653,
+            3_215_031_751,
+            3_825_123_056_546_413_051,
+        ];
+        for n in composites {
+            //Ноль случайных раундов: решают только фиксированные основания
+            assert!(!is_prime_miller_rabin(&BigUint::from(n), 0), "n = {}", n);
+        }
+        let primes = [5u64, 41, 43, 1_000_000_007, 18_446_744_073_709_551_557];
+        for n in primes {
+            assert!(is_prime_miller_rabin(&BigUint::from(n), 0), "n = {}", n);
+        }
+        //Граница: 2^81 - 1 (= 2 417 851 639 229 258 349 412 351) составное
+        assert!(!is_prime_miller_rabin(
+            &((BigUint::one() << 81u32) - 1u32),
+            0
+        ));
+    }
+
+    #[test]
+    fn test_sieve_window_matches_trial_division() {
+        let start = BigUint::from(10u64.pow(18) + 1);
+        for safe in [false, true] {
+            let alive = sieve_window(&start, safe);
+            for (i, alive) in alive.into_iter().enumerate() {
+                let n = &start + 2 * i;
+                let sieved = has_small_factor(&n) || safe && has_small_factor(&(&n * 2u32 + 1u32));
+                assert_eq!(alive, !sieved, "n = {}", n);
             }
-            if x == n - BigUint::one() {
-                continue 'A;
+        }
+        //Само малое простое не отсеивается
+        assert!(!has_small_factor(&BigUint::from(8191u32)));
+        assert!(has_small_factor(&BigUint::from(8191u32 * 8191)));
+    }
+
+    #[test]
+    fn test_next_and_prev_prime() {
+        let big = |n: u64| BigUint::from(n);
+        assert_eq!(next_prime(&big(0)), big(2));
+        assert_eq!(next_prime(&big(2)), big(3));
+        assert_eq!(next_prime(&big(13)), big(17));
+        assert_eq!(next_prime(&big(8190)), big(8191));
+        assert_eq!(prev_prime(&big(2)), None);
+        assert_eq!(prev_prime(&big(3)), Some(big(2)));
+        assert_eq!(prev_prime(&big(4)), Some(big(3)));
+        assert_eq!(prev_prime(&big(17)), Some(big(13)));
+        //Соседние простые 10^18: 10^18 - 11 и 10^18 + 3
+        let n = big(10u64.pow(18));
+        assert_eq!(next_prime(&n), &n + 3u32);
+        assert_eq!(prev_prime(&n), Some(&n - 11u32));
+        //Простые около 2^89 - 1 выше детерминированной границы
+        let m = (BigUint::one() << 89u32) - 1u32;
+        assert_eq!(next_prime(&prev_prime(&m).unwrap()), m);
+    }
+
+    #[test]
+    fn test_random_bases_above_bound() {
+        //2^89 - 1 и 2^127 - 1 - простые Мерсенна выше границы
+        for e in [89u32, 127] {
+            assert!(is_prime_miller_rabin(&((BigUint::one() << e) - 1u32), 8));
+        }
+        assert!(!is_prime_miller_rabin(
+            &((BigUint::one() << 101u32) - 1u32),
+            8
+        ));
+        let (p, q) = gen_safe_prime(32);
+        assert!(is_prime_miller_rabin(&p, 0) && is_prime_miller_rabin(&q, 0));
+        let g = generated_element(&p).unwrap();
+        assert!(
+            math::mod_pow_big(&g, &q.to_bigint().unwrap(), &p)
+                .unwrap()
+                .is_one()
+        );
+    }
+
+    #[test]
+    fn test_sized_randomness() {
+        for bits in [MIN_PRIME_BITS, 65] {
+            let p = gen_random_prime(bits);
+            assert_eq!(p.bits(), bits);
+            assert!(is_prime_miller_rabin(&p, 8));
+        }
+        let order = BigUint::one() << 256u32;
+        assert!(random_biguint_bits(&order, MIN_SECRET_BITS).bits() <= MIN_SECRET_BITS);
+        //Граница 2^bits не расширяет группу
+        assert!(random_biguint_bits(&BigUint::from(11u32), MIN_SECRET_BITS) < BigUint::from(11u32));
+    }
+
+    #[test]
+    fn test_race_returns_first_success() {
+        //Каждая попытка удачна с вероятностью 1/16; проигравшие потоки
+        //останавливаются, и race возвращает ровно один результат
+        let found = race(&mut OsRng, SearchLimits::default(), |rng, _| {
+            let x = rng.next_u32();
+            (x % 16 == 0).then_some(x)
+        })
+        .unwrap();
+        assert_eq!(found % 16, 0);
+        let (p, q) = gen_safe_prime(48);
+        assert_eq!((p.bits(), &p), (48, &(&q * 2u32 + 1u32)));
+    }
+
+    #[test]
+    fn test_safe_prime_progress() {
+        let last = std::sync::Mutex::new(SearchProgress::default());
+        let (p, q) = gen_safe_prime_reporting(&mut OsRng, 64, |progress| {
+            let mut last = last.lock().unwrap();
+            if progress.windows > last.windows {
+                *last = progress;
             }
+        });
+        assert!(is_prime_miller_rabin(&p, 8) && is_prime_miller_rabin(&q, 8));
+        let last = last.into_inner().unwrap();
+        assert!(last.windows >= 1 && last.tested >= 1);
+        assert!(last.mr_rounds >= last.tested);
+        //Оценка растет как квадрат длины
+        let (small, large) = (
+            expected_safe_prime_tests(1024),
+            expected_safe_prime_tests(2048),
+        );
+        assert!(small > 100.0 && (large / small - 4.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_configurable_rounds() {
+        assert_eq!(Primality::new(DEFAULT_MR_ROUNDS).error_bits, 16);
+        //Найденные q и p прошли каждое все 32 раунда; числа меньше
+        //DETERMINISTIC_BOUND проверялись бы фиксированными основаниями
+        let most = AtomicU64::new(0);
+        let (p, _) =
+            gen_safe_prime_limited(&mut OsRng, 128, 32, SearchLimits::default(), |progress| {
+                most.fetch_max(progress.mr_rounds, Ordering::Relaxed);
+            })
+            .unwrap();
+        assert_eq!(p.bits(), 128);
+        assert!(most.into_inner() >= 64);
+    }
+
+    #[test]
+    fn test_rejection_sampling_is_uniform() {
+        //6 не степень двойки: остаток от 3-битного числа дал бы 0 и 1
+        //вдвое чаще остальных
+        let mut counts = [0u32; 6];
+        for _ in 0..6000 {
+            counts[random_biguint_mod_with(&mut OsRng, &BigUint::from(6u32))
+                .to_usize()
+                .unwrap()] += 1;
         }
-        return false;
+        assert!(
+            counts.iter().all(|&n| (800..1200).contains(&n)),
+            "{:?}",
+            counts
+        );
+        let (lo, hi) = (BigUint::from(10u32), BigUint::from(12u32));
+        assert!(
+            (lo.clone()..hi.clone()).contains(&random_biguint_range_with(&mut OsRng, &lo, &hi))
+        );
+        assert_eq!(random_odd_with(&mut OsRng, 3).bits(), 3);
+    }
+
+    #[test]
+    fn test_search_limits() {
+        let stop = AtomicBool::new(true);
+        let cancelled = SearchLimits {
+            deadline: None,
+            stop: Some(&stop),
+        };
+        assert_eq!(
+            gen_random_prime_limited(&mut OsRng, 4096, DEFAULT_MR_ROUNDS, cancelled),
+            Err(SearchError::Cancelled)
+        );
+
+        //Безопасное простое в 1024 бита за 50 мс не найти
+        let deadline = Duration::from_millis(50);
+        let started = Instant::now();
+        let timed = SearchLimits {
+            deadline: Some(deadline),
+            stop: None,
+        };
+        assert_eq!(
+            gen_safe_prime_limited(&mut OsRng, 1024, DEFAULT_MR_ROUNDS, timed, |_| {}),
+            Err(SearchError::Timeout(deadline))
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        stop.store(false, Ordering::Relaxed);
+        let generous = SearchLimits {
+            deadline: Some(Duration::from_secs(60)),
+            stop: Some(&stop),
+        };
+        assert_eq!(
+            gen_random_prime_limited(&mut OsRng, 64, DEFAULT_MR_ROUNDS, generous)
+                .unwrap()
+                .bits(),
+            64
+        );
+    }
+
+    #[test]
+    fn test_strong_prime() {
+        let p = gen_strong_prime(64);
+        assert_eq!(p.bits(), 64);
+        assert!(is_prime_miller_rabin(&p, 8));
+        //У p - 1 есть простой делитель r не короче 24 бит, у r - 1 тоже;
+        //наибольший делитель p + 1 не короче 24 бит. Наибольшим делителем
+        //p - 1 может оказаться не r, поэтому проверяются все
+        let factors = |n: BigUint| crate::attack::factorize(&n).unwrap();
+        let largest = |n: BigUint| factors(n).pop().unwrap();
+        assert!(largest(&p + 1u32).bits() >= 24);
+        assert!(
+            factors(&p - 1u32)
+                .into_iter()
+                .any(|r| r.bits() >= 24 && largest(r - 1u32).bits() >= 24)
+        );
+        assert_eq!(
+            gen_modulus_prime(MIN_PRIME_BITS, PrimeKind::Strong).bits(),
+            MIN_PRIME_BITS
+        );
     }
-    true
 }
@@ -0,0 +1,188 @@
+//////////////////////////////////////////////////////////////
+// Долговременная пара ключей доказывающего: секрет (alpha, beta) и
+// открытый ключ u = g^alpha * h^beta (u = g^x для Шнорра). Пара
+// сохраняется в JSON-файл, чтобы ключ переживал перезапуск сервера.
+// Файл содержит секрет в открытом виде и создается с правами 0600.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::Write;
+
+use crate::encoding;
+use crate::group::{Group, Setting};
+use crate::kdf::{self, KdfParams};
+use crate::message::decimal;
+use crate::protocol::{Key, ProofKind, Prover, PublicParams};
+
+//Метки HKDF для показателей, выводимых из парольной фразы
+const ALPHA_LABEL: &[u8] = b"sigma_protocol keypair alpha";
+const BETA_LABEL: &[u8] = b"sigma_protocol keypair beta";
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keypair {
+    kind: ProofKind,
+    #[serde(with = "decimal")]
+    alpha: BigUint,
+    #[serde(with = "decimal", default)]
+    beta: BigUint,
+    //Открытый ключ в кодировке группы
+    #[serde(with = "decimal")]
+    public: BigUint,
+    //Соль в hex, если секрет выведен из парольной фразы
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    salt: Option<String>,
+}
+
+//Секрет в журнал не попадает
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Keypair({:?}, {})", self.kind, self.public)
+    }
+}
+
+impl Keypair {
+    //Случайная пара Окамото в группе вычетов
+    pub fn generate(params: &PublicParams) -> Self {
+        Keypair::generate_in(&params.setting(), ProofKind::Okamoto)
+    }
+
+    pub fn generate_in<G: Group>(setting: &Setting<G>, kind: ProofKind) -> Self {
+        let secret = Key::random(&setting.order());
+        Keypair::from_secret(setting, kind, secret)
+    }
+
+    //Для Шнорра beta секрета игнорируется
+    pub fn from_secret<G: Group>(setting: &Setting<G>, kind: ProofKind, secret: Key) -> Self {
+        let prover = Prover::in_setting(setting.clone(), kind, secret);
+        Keypair {
+            kind,
+            alpha: prover.secret().alpha.clone(),
+            beta: prover.secret().beta.clone(),
+            public: setting.group.encode(prover.public_key()),
+            salt: None,
+        }
+    }
+
+    //Секрет из парольной фразы и соли: та же пара на любом устройстве
+    pub fn from_passphrase<G: Group>(
+        setting: &Setting<G>,
+        kind: ProofKind,
+        passphrase: &str,
+        salt: &[u8],
+        params: KdfParams,
+    ) -> Result<Self, String> {
+        let key = kdf::stretch(passphrase, salt, params)?;
+        let order = setting.order();
+        let secret = Key::new(
+            kdf::expand_scalar(&key, ALPHA_LABEL, &order),
+            kdf::expand_scalar(&key, BETA_LABEL, &order),
+        );
+        Ok(Keypair {
+            salt: Some(encoding::to_hex(salt)),
+            ..Keypair::from_secret(setting, kind, secret)
+        })
+    }
+
+    pub fn kind(&self) -> ProofKind {
+        self.kind
+    }
+
+    pub fn public(&self) -> &BigUint {
+        &self.public
+    }
+
+    pub fn salt(&self) -> Option<&str> {
+        self.salt.as_deref()
+    }
+
+    pub fn secret(&self) -> Key {
+        Key::new(self.alpha.clone(), self.beta.clone())
+    }
+
+    //Доказывающий с этим ключом; ключ из другой группы или с другими
+    //образующими дал бы другой открытый ключ
+    pub fn prover<G: Group>(&self, setting: Setting<G>) -> Result<Prover<G>, String> {
+        if self.alpha >= setting.order() || self.beta >= setting.order() {
+            return Err("Секрет ключа вне группы".to_string());
+        }
+        let prover = Prover::in_setting(setting, self.kind, self.secret());
+        match prover.setting().group.encode(prover.public_key()) == self.public {
+            true => Ok(prover),
+            false => Err("Ключ создан для другой группы".to_string()),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        write_private(path, &json)
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        serde_json::from_str(&json).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+//Запись файла с секретом: читать его может только владелец
+pub(crate) fn write_private(path: &str, contents: &str) -> Result<(), String> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path).map_err(|e| format!("{}: {}", path, e))?;
+    file.write_all(contents.as_bytes())
+        .map_err(|e| format!("{}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kdf::tests::CHEAP;
+    use crate::protocol::toy_params;
+    use crate::ristretto;
+
+    #[test]
+    fn test_keypair_roundtrip() {
+        let path = std::env::temp_dir().join(format!("keypair-{}.json", rand::random::<u64>()));
+        let path = path.to_str().unwrap();
+        let keypair = Keypair::generate(&toy_params());
+        keypair.save(path).unwrap();
+        let loaded = Keypair::load(path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(loaded, keypair);
+
+        let prover = loaded.prover(toy_params().setting()).unwrap();
+        assert_eq!(prover.secret(), &keypair.secret());
+        assert!(Keypair::load("/nonexistent/keypair.json").is_err());
+    }
+
+    #[test]
+    fn test_keypair_bound_to_group() {
+        let setting = ristretto::setting();
+        let keypair = Keypair::generate_in(&setting, ProofKind::Schnorr);
+        assert_eq!(keypair.secret().beta, BigUint::ZERO);
+        assert!(keypair.prover(setting).is_ok());
+        assert!(keypair.prover(toy_params().setting()).is_err());
+    }
+
+    #[test]
+    fn test_keypair_from_passphrase() {
+        let setting = ristretto::setting();
+        let salt = [3u8; kdf::SALT_LEN];
+        let derive = |phrase: &str, salt: &[u8]| {
+            Keypair::from_passphrase(&setting, ProofKind::Okamoto, phrase, salt, CHEAP).unwrap()
+        };
+        let keypair = derive("correct horse", &salt);
+        assert_eq!(derive("correct horse", &salt), keypair);
+        assert_ne!(
+            derive("correct horse", &[4u8; kdf::SALT_LEN]).public(),
+            keypair.public()
+        );
+        assert_ne!(derive("wrong horse", &salt).public(), keypair.public());
+        assert_eq!(keypair.salt(), Some("03".repeat(kdf::SALT_LEN).as_str()));
+        assert!(keypair.prover(setting.clone()).is_ok());
+    }
+}
@@ -0,0 +1,139 @@
+//////////////////////////////////////////////////////////////
+// Файл ключа, зашифрованный паролем. Ключ шифрования выводится из
+// пароля и случайной соли функцией Argon2id, пара ключей в JSON
+// шифруется AES-256-GCM. Открытый ключ хранится рядом в открытом
+// виде и входит в проверяемые данные шифра: подменить его незаметно
+// нельзя, а узнать, чей это файл, можно без пароля.
+//////////////////////////////////////////////////////////////
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key as CipherKey, Nonce};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::encoding::{from_hex, to_hex};
+use crate::kdf::{self, KdfParams};
+use crate::keypair::{self, Keypair};
+use crate::message::decimal;
+
+const NONCE_LEN: usize = 12;
+//Шифр записывается в файл: файлы без него зашифрованы прежним шифром
+const CIPHER: &str = "aes-256-gcm";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    #[serde(default)]
+    cipher: String,
+    kdf: KdfParams,
+    #[serde(with = "decimal")]
+    public: BigUint,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl Keystore {
+    pub fn seal(keypair: &Keypair, password: &str) -> Result<Self, String> {
+        Keystore::seal_with(keypair, password, KdfParams::default())
+    }
+
+    pub fn seal_with(keypair: &Keypair, password: &str, params: KdfParams) -> Result<Self, String> {
+        let salt = kdf::random_salt();
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let key = kdf::stretch(password, &salt, params)?;
+        let plaintext = serde_json::to_vec(keypair).map_err(|e| e.to_string())?;
+        let public = keypair.public().clone();
+        let ciphertext = Aes256Gcm::new(CipherKey::<Aes256Gcm>::from_slice(&key))
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &public.to_bytes_be(),
+                },
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(Keystore {
+            cipher: CIPHER.to_string(),
+            kdf: params,
+            public,
+            salt: to_hex(&salt),
+            nonce: to_hex(&nonce),
+            ciphertext: to_hex(&ciphertext),
+        })
+    }
+
+    //Открытый ключ доступен без пароля
+    pub fn public(&self) -> &BigUint {
+        &self.public
+    }
+
+    pub fn open(&self, password: &str) -> Result<Keypair, String> {
+        if self.cipher != CIPHER {
+            return Err(format!(
+                "Шифр файла ключа {:?} не поддерживается, нужен {}: создайте файл заново",
+                self.cipher, CIPHER
+            ));
+        }
+        let nonce = from_hex(&self.nonce)?;
+        if nonce.len() != NONCE_LEN {
+            return Err("Некорректный nonce файла ключа".to_string());
+        }
+        let key = kdf::stretch(password, &from_hex(&self.salt)?, self.kdf)?;
+        let plaintext = Aes256Gcm::new(CipherKey::<Aes256Gcm>::from_slice(&key))
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &from_hex(&self.ciphertext)?,
+                    aad: &self.public.to_bytes_be(),
+                },
+            )
+            .map_err(|_| "Неверный пароль или поврежденный файл ключа".to_string())?;
+        let keypair: Keypair = serde_json::from_slice(&plaintext).map_err(|e| e.to_string())?;
+        match *keypair.public() == self.public {
+            true => Ok(keypair),
+            false => Err("Открытый ключ файла не совпадает с зашифрованным".to_string()),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        keypair::write_private(path, &json)
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        serde_json::from_str(&json).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kdf::tests::CHEAP;
+    use crate::protocol::toy_params;
+
+    #[test]
+    fn test_keystore_roundtrip() {
+        let params = toy_params();
+        let keypair = Keypair::generate(&params);
+        let sealed = Keystore::seal_with(&keypair, "correct horse", CHEAP).unwrap();
+        assert_eq!(sealed.public(), keypair.public());
+        assert_eq!(sealed.open("correct horse").unwrap(), keypair);
+        assert!(sealed.open("wrong horse").is_err());
+
+        //Открытый ключ защищен шифром от подмены
+        let mut swapped = sealed.clone();
+        swapped.public += 1u32;
+        assert!(swapped.open("correct horse").is_err());
+
+        //Файл без поля шифра создан прежней версией
+        let mut legacy = sealed.clone();
+        legacy.cipher.clear();
+        assert!(legacy.open("correct horse").is_err());
+
+        let json = serde_json::to_string(&sealed).unwrap();
+        assert!(!json.contains("alpha"));
+        assert_eq!(serde_json::from_str::<Keystore>(&json).unwrap(), sealed);
+    }
+}
@@ -0,0 +1,93 @@
+//! Идентификационный сигма-протокол Окамото: доказательство знания пары
+//! (alpha, beta) такой, что u = g^alpha * h^beta mod p.
+//!
+//! Библиотеку можно встроить без веб-интерфейса:
+//!
+//! ```
+//! use num_bigint::BigUint;
+//! use sigma_protocol::{Key, Prover, PublicParams, Verifier};
+//!
+//! let params = PublicParams {
+//!     p: BigUint::from(23u32),
+//!     q: BigUint::from(11u32),
+//!     g: BigUint::from(4u32),
+//!     h: BigUint::from(9u32),
+//! };
+//! let prover = Prover::new(params.clone(), Key::new(5u32.into(), 2u32.into())).unwrap();
+//! let verifier = Verifier::new(params);
+//!
+//! let commitment = prover.commit().unwrap();
+//! let c = verifier.challenge();
+//! let proof = prover.respond(commitment, &c);
+//! assert!(verifier.verify(&proof));
+//! ```
+
+pub mod aggregate;
+pub mod attack;
+pub mod auth;
+pub mod backend;
+pub mod batch;
+pub mod bip340;
+pub mod blind;
+pub mod certificate;
+pub mod challenge;
+pub mod commitment;
+pub mod config;
+pub mod consensus;
+pub mod credential;
+pub mod dkg;
+pub mod dleq;
+pub mod elgamal;
+pub mod encoding;
+#[cfg(feature = "server")]
+pub mod events;
+pub mod extractor;
+pub mod feldman;
+pub mod ffs;
+pub mod fiat_shamir;
+pub mod forgery;
+pub mod graph;
+pub mod group;
+pub mod handshake;
+pub mod health;
+pub mod ipa;
+pub mod isomorphism;
+pub mod kdf;
+pub mod key_gen;
+pub mod keypair;
+pub mod keystore;
+pub mod math;
+pub mod message;
+pub mod modint;
+pub mod modp;
+pub mod musig;
+pub mod nonce;
+pub mod oracle;
+pub mod params;
+#[cfg(feature = "server")]
+pub mod peer;
+pub mod pool;
+pub mod protocol;
+pub mod ring;
+pub mod ristretto;
+pub mod schnorr;
+pub mod secp256k1;
+#[cfg(feature = "server")]
+pub mod sessions;
+pub mod sigma;
+pub mod strength;
+pub mod threshold;
+pub mod timing;
+#[cfg(feature = "server")]
+pub mod transcript;
+#[cfg(feature = "server")]
+pub mod transport;
+pub mod vrf;
+pub mod wire;
+
+pub use elgamal::{Ciphertext, ElGamal, PlaintextKnowledge};
+pub use group::{Group, ModGroup, Setting};
+pub use message::ProtocolMessage;
+pub use protocol::{Key, Okamoto, Proof, ProofKind, Prover, PublicParams, Verifier};
+pub use schnorr::Schnorr;
+pub use sigma::SigmaProtocol;
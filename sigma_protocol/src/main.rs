@@ -1,56 +1,315 @@
 use axum::{
-    Router,
-    extract::State,
-    http::StatusCode,
-    response::Html,
+    Json, Router,
+    body::Bytes,
+    extract::ws::{Message as AxumMessage, WebSocket},
+    extract::{Path, Query, State, WebSocketUpgrade},
+    http::{HeaderMap, StatusCode, header},
     response::sse::{Event, KeepAlive, Sse},
+    response::{Html, Response},
     routing::{get, post},
 };
-use num_bigint::{BigInt, BigUint, ToBigInt};
+use num_bigint::BigUint;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
 use std::net::SocketAddr;
-use tokio::sync::broadcast;
-use tokio_stream::StreamExt;
-use tokio_stream::wrappers::{BroadcastStream, errors::BroadcastStreamRecvError};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
 use tracing::{info, warn};
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use std::time::Duration;
 
-mod config;
-mod key_gen;
-mod math;
-
-use config::Config;
+use sigma_protocol::attack;
+use sigma_protocol::auth::{self, Registry};
+use sigma_protocol::bip340;
+use sigma_protocol::blind::{
+    self, BlindCommitRequest, BlindCommitment, BlindResponse, BlindSignRequest, BlindSignature,
+    BlindSigner,
+};
+use sigma_protocol::certificate::{CertificateKind, PrimeCertificate};
+use sigma_protocol::challenge::{self, Beacon, BeaconRound, ChallengeProvider, SealedChallenge};
+use sigma_protocol::commitment::{self, EqualityStatement, EqualityWitness, Generators, Opening};
+use sigma_protocol::config::{self, ChallengeMode, Config, GroupKind};
+use sigma_protocol::credential::{self, Issuer, SetPredicate};
+use sigma_protocol::dkg::{self, DkgParty};
+use sigma_protocol::dleq::{DleqProver, DleqVerifier};
+use sigma_protocol::elgamal::{ElGamal, PlaintextKnowledge};
+use sigma_protocol::encoding::{self, HexBig, to_hex};
+use sigma_protocol::events::{
+    self, EventBus, EventSink, FileSink, NullSink, Numbered, ProtocolEvent, SseSink,
+};
+use sigma_protocol::extractor;
+use sigma_protocol::ffs::{Ffs, FfsParams, FfsProver};
+use sigma_protocol::forgery::{self, ForgeryStats};
+use sigma_protocol::handshake::{self, Hello, Initiator};
+use sigma_protocol::health::{self, RngCheck};
+use sigma_protocol::ipa;
+use sigma_protocol::isomorphism::{GiProver, GraphIsomorphism};
+use sigma_protocol::kdf::KdfParams;
+use sigma_protocol::key_gen::{Primality, SearchLimits, SearchProgress};
+use sigma_protocol::keypair::Keypair;
+use sigma_protocol::keystore::Keystore;
+use sigma_protocol::math;
+use sigma_protocol::message::decimal;
+use sigma_protocol::musig::{self, Coordinator};
+use sigma_protocol::params::{self, GroupParams, ParamsFile};
+use sigma_protocol::peer::{self, ForwardedEvent, PeerClient, PeerSink};
+use sigma_protocol::pool::ParamsPool;
+use sigma_protocol::protocol::{Decided, Session, Verification};
+use sigma_protocol::ring::{self, RingSignature};
+use sigma_protocol::schnorr::Schnorr;
+use sigma_protocol::sessions::{
+    self as sessions, ExchangeError, SessionInfo, SessionRegistry, SessionStatus,
+};
+use sigma_protocol::sigma::SigmaProtocol;
+use sigma_protocol::strength::Strength;
+use sigma_protocol::threshold::{
+    self, CommitRequest, Participant, Party, PartyCommitment, PartyResponse, RespondRequest,
+    ThresholdProver,
+};
+use sigma_protocol::timing::{SizeReport, Stats, Timeline};
+use sigma_protocol::transcript::{Transcript, TranscriptSink};
+use sigma_protocol::transport::{
+    ChannelTransport, HTTP_TRANSPORT_PATH, HttpTransport, StdioTransport, Transport,
+    WS_TRANSPORT_PATH, WebSocketTransport,
+};
+use sigma_protocol::vrf::{self, VrfProof};
+use sigma_protocol::{
+    Group, Key, ModGroup, Proof, ProofKind, ProtocolMessage, Prover, PublicParams, Setting,
+    Verifier, consensus, fiat_shamir, group, key_gen, modp, ristretto, secp256k1,
+};
 
+//Учебные параметры (--toy-params): подгруппа порядка 11 в Z_23^*, h
+//выводится из g, испытание интерактивного режима всегда C
+const P: u8 = 23;
 const Q: u8 = 11;
-const G: u8 = 2;
-const H: u8 = 3;
+const G: u8 = 4;
 const C: u8 = 4;
-const K1: u8 = 5;
-const K2: u8 = 2;
-const T1: u8 = 3;
-const T2: u8 = 7;
+//Протокол Фейге-Фиата-Шамира: число секретов (бит испытания) и длина модуля
+const FFS_SECRETS: usize = 5;
+const FFS_MODULUS_BITS: u64 = 256;
+//Переменная окружения с паролем зашифрованного файла ключа
+const KEY_PASSWORD_VAR: &str = "SIGMA_KEY_PASSWORD";
+//Переменная окружения с парольной фразой, из которой выводится ключ
+const KEY_PASSPHRASE_VAR: &str = "SIGMA_KEY_PASSPHRASE";
+//Период отчета о ходе поиска безопасного простого, в окнах решета
+const PRIME_PROGRESS_WINDOWS: u64 = 4;
+//Длина p для /start/primes по умолчанию и наибольшая допустимая
+const PRIMES_DEFAULT_BITS: u64 = 1024;
+const MAX_PRIMES_BITS: u64 = 4096;
+//Число хранимых сессий, емкость журнала каждой и ожидание ее первого клиента
+const MAX_SESSIONS: usize = 64;
+const SESSION_EVENTS: usize = 100;
+const SESSION_WAIT: Duration = Duration::from_secs(30);
+//Время на пересылку последних событий второму серверу перед выходом роли
+const FORWARD_GRACE: Duration = Duration::from_millis(300);
+//Эль-Гамаль: открытый текст берется из [0, ELGAMAL_MAX_PLAINTEXT) и расшифровывается перебором
+const ELGAMAL_MAX_PLAINTEXT: u64 = 1000;
+//Удостоверение: атрибуты Павла; раскрывается страна, возраст доказывается
+//принадлежностью к CREDENTIAL_AGES, номер паспорта и уровень доступа скрыты
+const CREDENTIAL_ATTRIBUTES: [&str; 4] = ["возраст", "страна", "номер паспорта", "уровень доступа"];
+const CREDENTIAL_DISCLOSED: usize = 1;
+const CREDENTIAL_AGE: usize = 0;
+const CREDENTIAL_AGES: std::ops::RangeInclusive<u32> = 18..=30;
+//Совместная подпись: число участников, если в конфигурации нет других серверов
+const MUSIG_LOCAL_SIGNERS: usize = 3;
+const MUSIG_MESSAGE: &str = "Совместное решение участников";
+//Аргумент скалярного произведения: длина векторов (степень двойки)
+const IPA_LENGTH: usize = 16;
+//Сообщение, которое подписывается вслепую
+const BLIND_MESSAGE: &str = "Электронная монета номиналом 1";
+//Кольцевая подпись: размер кольца и подписываемое сообщение
+const RING_SIZE: usize = 4;
+const RING_MESSAGE: &str = "Сообщение от одного из участников кольца";
+//Изоморфизм графов: число вершин и вероятность ребра
+const GI_VERTICES: usize = 6;
+const GI_DENSITY: f64 = 0.5;
+//Число попыток обмана по умолчанию и предел для одного запроса
+const FORGERY_ATTEMPTS: u64 = 1000;
+const MAX_FORGERY_ATTEMPTS: u64 = 1_000_000;
 // const PATH: &str = "config_p.json";
 
+//Вычисляет body с setting группы kind: для групп вычетов это modular
+//(параметры сервера), для кривых - их стандартные параметры. Тело
+//компилируется отдельно для каждого типа группы
+macro_rules! with_setting {
+    ($kind:expr, $modular:expr, |$setting:ident| $body:expr) => {
+        match $kind {
+            GroupKind::Modular
+            | GroupKind::Modp2048
+            | GroupKind::Modp3072
+            | GroupKind::Modp4096 => {
+                let $setting = $modular;
+                $body
+            }
+            GroupKind::Ristretto255 => {
+                let $setting = ristretto::setting();
+                $body
+            }
+            GroupKind::Secp256k1 => {
+                let $setting = secp256k1::setting();
+                $body
+            }
+        }
+    };
+}
+
 #[derive(Parser)]
+#[command(subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Путь до конфигурации сервера
-    #[arg(short, long)]
-    config_path: String,
+    #[arg(short, long, required = true)]
+    config_path: Option<String>,
+
+    /// Запуск одной стороны протокола вместо веб-интерфейса
+    #[arg(long, value_enum)]
+    role: Option<Role>,
+
+    /// Транспорт между доказывающим и проверяющим
+    #[arg(long, value_enum, default_value_t = TransportKind::Http)]
+    transport: TransportKind,
+
+    /// Число утверждений, доказываемых одновременно с общим испытанием
+    #[arg(long, default_value_t = 1)]
+    statements: usize,
+
+    /// Группа протокола вместо указанной в конфигурации (например, modp2048)
+    #[arg(long, value_enum)]
+    group: Option<GroupKind>,
+
+    /// Длина генерируемого безопасного простого p вместо prime_bits из конфигурации
+    #[arg(long)]
+    bits: Option<u64>,
+
+    /// Учебные параметры (p = 23, q = 11) и фиксированное испытание для демонстрации
+    #[arg(long)]
+    toy_params: bool,
+
+    /// Длина секрета доказывающего в битах вместо secret_bits из конфигурации
+    #[arg(long)]
+    secret_bits: Option<u64>,
+
+    /// Секретный ключ доказывающего в hex (например, ключ secp256k1); по умолчанию случайный
+    #[arg(long)]
+    secret_key: Option<String>,
+
+    /// Число участников при раздаче долей секрета (--role deal)
+    #[arg(long, default_value_t = 3)]
+    parties: usize,
+
+    /// Число участников, достаточное для доказательства (--role deal)
+    #[arg(long, default_value_t = 2)]
+    threshold: usize,
+
+    /// Подписываемое сообщение в hex (--role sign)
+    #[arg(long, default_value = "")]
+    message: String,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Генерация параметров группы в файл без запуска сервера
+    GenParams {
+        /// Длина безопасного простого p
+        #[arg(long, default_value_t = 2048)]
+        bits: u64,
+
+        /// Файл для параметров (JSON)
+        #[arg(long)]
+        out: String,
+
+        /// Доказуемо простое p с сертификатом указанного вида
+        #[arg(long, value_enum)]
+        certificate: Option<CertificateKind>,
+
+        /// Раунды Миллера-Рабина для p и q без сертификата
+        #[arg(long, default_value_t = key_gen::DEFAULT_MR_ROUNDS)]
+        rounds: u8,
+    },
+}
+
+//Параметры запуска одной стороны протокола
+struct RoleOptions {
+    role: Role,
+    transport: TransportKind,
+    statements: usize,
+    secret: Option<BigUint>,
+    parties: usize,
+    threshold: usize,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Role {
+    Prover,
+    Verifier,
+    /// Обе стороны в одном процессе через канал в памяти
+    Local,
+    /// Неинтерактивное доказательство (Фиат-Шамир) в stdout
+    Prove,
+    /// Раздача долей секрета пороговому доказывающему в stdout
+    Deal,
+    /// Подпись Шнорра BIP-340 (secp256k1) сообщения --message в stdout
+    Sign,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum TransportKind {
+    Http,
+    Ws,
+    Stdio,
 }
 
 #[derive(Debug, Clone)]
 struct AppState {
     config: Config,
-    q: BigUint,
-    g: BigUint,
-    h: BigUint,
-    tx: broadcast::Sender<String>,
+    group: GroupKind,
+    params: PublicParams,
+    //Группа вычетов с предвычисленными степенями g и h
+    setting: Setting<ModGroup>,
+    //Сертификат простоты сгенерированного p
+    certificate: Option<PrimeCertificate>,
+    //Оценка ошибки теста простоты, если p и q простые с высокой вероятностью
+    primality: Option<Primality>,
+    //Стойкость параметров группы протокола и предупреждения о слабостях
+    strength: Strength,
+    events: EventBus,
+    peer: PeerClient,
+    inbox: mpsc::Sender<ProtocolMessage>,
+    stats: Arc<Mutex<Stats>>,
+    transcript: Arc<Mutex<Transcript>>,
+    party: Option<Arc<Mutex<Party>>>,
+    forgery: Arc<Mutex<ForgeryStats>>,
+    signer: Arc<Mutex<BlindSigner>>,
+    cosigner: Arc<Mutex<musig::Signer>>,
+    vrf_key: BigUint,
+    dkg: Arc<Mutex<DkgParty>>,
+    static_key: BigUint,
+    auth: Arc<Mutex<Registry>>,
+    //Долговременный ключ доказывающего из key_file
+    keypair: Option<Keypair>,
+    //Заранее сгенерированные параметры для /start/primes
+    pool: Option<Arc<ParamsPool>>,
+    sessions: Arc<SessionRegistry>,
 }
 
 impl AppState {
-    async fn new(config_path: String) -> Self {
-        let config = match Config::load(&config_path) {
+    async fn new(
+        config_path: String,
+        group: Option<GroupKind>,
+        bits: (Option<u64>, Option<u64>),
+        toy: bool,
+        inbox: mpsc::Sender<ProtocolMessage>,
+    ) -> Self {
+        let config = Config::load(&config_path)
+            .map_err(|e| e.to_string())
+            .and_then(|config| config.with_toy_params(toy).with_bits(bits.0, bits.1));
+        let config = match config {
             Ok(config) => config,
             Err(e) => {
                 eprintln!("Failed to load config: {}", e);
@@ -58,84 +317,508 @@ impl AppState {
             }
         };
 
-        let (tx, _) = broadcast::channel::<String>(100);
+        //Секреты и параметры нельзя выбирать сломанным генератором
+        match (
+            health::self_test(&mut rand::rngs::OsRng),
+            config.get_rng_check(),
+        ) {
+            (Ok(report), _) => info!(
+                "Самопроверка генератора пройдена: {} единиц, серия повторов {}",
+                report.ones, report.longest_run
+            ),
+            (Err(e), RngCheck::Refuse) => {
+                eprintln!("RNG self-test failed: {}", e);
+                std::process::exit(1);
+            }
+            (Err(e), RngCheck::Warn) => {
+                warn!(
+                    "!!! Генератор случайных чисел не прошел самопроверку: {} !!!",
+                    e
+                )
+            }
+        }
+
+        let mut events = EventBus::new(100);
+        if let Some(path) = config.get_event_log() {
+            match FileSink::open(path).await {
+                Ok(sink) => events.attach(sink),
+                Err(e) => {
+                    eprintln!("Failed to open event log {}: {}", path, e);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        let transcript = Arc::new(Mutex::new(Transcript::new()));
+        events.attach(TranscriptSink::new(transcript.clone()));
+
+        let group = group.unwrap_or(config.get_group());
+        //Файл параметров проверяется целиком при загрузке; группа MODP важнее
+        let file = config
+            .get_params_file()
+            .filter(|_| group.modp_bits().is_none())
+            .map(|path| {
+                info!("Загрузка параметров группы из {}", path);
+                ParamsFile::load(path).unwrap_or_else(|e| {
+                    eprintln!("Failed to load params: {}", e);
+                    std::process::exit(1);
+                })
+            });
+        let mut certificate = file.as_ref().and_then(|file| file.certificate.clone());
+        let mut primality = file.as_ref().and_then(|file| file.primality);
+        //Стандартные группы MODP и параметры из файла не требуют генерации
+        //простого числа
+        let preset = group
+            .modp_bits()
+            .and_then(modp::params)
+            .or(file.map(|file| file.params.public_params()));
+        let params = match &preset {
+            Some(params) => params.clone(),
+            None if config.get_toy_params() => {
+                warn!(
+                    "Учебные параметры: p = {}, q = {} - только для демонстрации",
+                    P, Q
+                );
+                Setting::derive(
+                    ModGroup::new(BigUint::from(P), BigUint::from(Q)),
+                    BigUint::from(G),
+                )
+                .params()
+            }
+            None => match (config.get_prime_bits(), config.get_provable_primes()) {
+                (bits, true) => {
+                    info!("Генерация доказуемо простого p длиной {} бит", bits);
+                    let (params, cert) =
+                        GroupParams::generate_provable(bits, config.get_certificate());
+                    certificate = Some(cert);
+                    params.public_params()
+                }
+                (bits, false) => {
+                    info!(
+                        "Генерация безопасного простого длиной {} бит; gen-params и \
+                         params_file избавляют от нее при запуске",
+                        bits
+                    );
+                    let rounds = config.get_mr_rounds();
+                    let limits = SearchLimits {
+                        deadline: config.get_prime_timeout(),
+                        stop: None,
+                    };
+                    let progress = prime_progress(&events, bits);
+                    let params = GroupParams::generate_limited(bits, rounds, limits, progress)
+                        .unwrap_or_else(|e| {
+                            eprintln!("Failed to generate params: {}", e);
+                            std::process::exit(1);
+                        });
+                    primality = Some(Primality::new(rounds));
+                    params.public_params()
+                }
+            },
+        };
+        //Вырожденные параметры дают бессмысленные доказательства. Простота
+        //модулей RFC 3526 известна, для сертифицированного p она доказана,
+        //файл параметров уже проверен - у них проверяются только образующие
+        let checked = match (&preset, &certificate) {
+            (Some(_), _) => params::validate_generators(&params),
+            (None, Some(cert)) => cert
+                .verify()
+                .and_then(|_| params::validate_generators(&params)),
+            (None, None) => params::validate(&params),
+        };
+        if let Err(e) = checked {
+            eprintln!("Некорректные параметры группы: {}", e);
+            std::process::exit(1);
+        }
+        if let Some(primality) = primality {
+            info!("Простота p и q: {}", primality);
+        }
+
+        //Таблицы нужны только группам вычетов; для кривых хватает параметров
+        let setting = match group {
+            GroupKind::Modular
+            | GroupKind::Modp2048
+            | GroupKind::Modp3072
+            | GroupKind::Modp4096 => params.setting().precompute(),
+            GroupKind::Ristretto255 | GroupKind::Secp256k1 => params.setting(),
+        };
+
+        let threshold = config.get_min_security_bits();
+        let strength = match group {
+            GroupKind::Modular
+            | GroupKind::Modp2048
+            | GroupKind::Modp3072
+            | GroupKind::Modp4096 => Strength::modular(&setting, threshold),
+            GroupKind::Ristretto255 => Strength::curve(&ristretto::setting(), threshold),
+            GroupKind::Secp256k1 => Strength::curve(&secp256k1::setting(), threshold),
+        };
+        info!(
+            "Стойкость параметров группы: около {} бит",
+            strength.security_bits
+        );
+        for warning in &strength.warnings {
+            warn!("Небезопасные параметры: {}", warning);
+        }
+
+        let party = config
+            .get_threshold()
+            .map(|threshold| Arc::new(Mutex::new(Party::new(threshold.share.clone()))));
+
+        //Ключ слепой подписи живет, пока работает сервер
+        let order = with_setting!(group, setting.clone(), |setting| setting.order());
+        let signer = Arc::new(Mutex::new(BlindSigner::random(&order)));
+        let cosigner = Arc::new(Mutex::new(musig::Signer::random(&order)));
+        let vrf_key = key_gen::random_biguint_mod(&order);
+        //Постоянный ключ сервера для обмена ключами со вторым сервером
+        let static_key = key_gen::random_biguint_mod(&order);
+
+        //Постоянный ключ доказывающего, если задан key_salt или key_file
+        let keypair = with_setting!(group, setting.clone(), |setting| {
+            load_keypair(&config, &setting)
+        });
+        let keypair = keypair.unwrap_or_else(|e| {
+            eprintln!("Failed to load key: {}", e);
+            std::process::exit(1);
+        });
+
+        //Администраторы из конфигурации; без них служебные эндпоинты закрыты
+        let auth = with_setting!(group, setting.clone(), |setting| {
+            admin_registry(&config, &setting)
+        });
+        let auth = auth.unwrap_or_else(|e| {
+            eprintln!("Invalid admin key: {}", e);
+            std::process::exit(1);
+        });
+
+        let pool = config.get_params_pool().map(|pool| {
+            info!(
+                "Фоновая генерация пула из {} параметров длиной {} бит",
+                pool.size, pool.bits
+            );
+            ParamsPool::spawn(pool.bits, config.get_mr_rounds(), pool.size)
+        });
 
-        let module = key_gen::gen_random_prime().await;
+        let peer = PeerClient::new(
+            config.get_name().to_string(),
+            config.get_second_server().clone(),
+            config.get_retry().clone(),
+            config.get_codec(),
+        );
 
-        let state = AppState {
+        let sessions = Arc::new(SessionRegistry::new(
+            MAX_SESSIONS,
+            config.get_session_timeout(),
+        ));
+        AppState {
             config,
-            q: BigUint::from(Q), //module.clone(),
-            g: BigUint::from(G),
-            // match key_gen::generated_element(&module).await {
-            //     Ok(g) => g,
-            //     Err(e) => {
-            //         eprintln!("Failed to generate element: {}", e);
-            //         std::process::exit(1);
-            //     }
-            // },
-            h: BigUint::from(H),
-            // match key_gen::generated_element(&module).await {
-            //     Ok(h) => h,
-            //     Err(e) => {
-            //         eprintln!("Failed to generate element: {}", e);
-            //         std::process::exit(1);
-            //     }
-            // },
-            tx,
+            group,
+            params,
+            setting,
+            certificate,
+            primality,
+            strength,
+            events,
+            peer,
+            inbox,
+            stats: Arc::new(Mutex::new(Stats::default())),
+            transcript,
+            party,
+            forgery: Arc::new(Mutex::new(ForgeryStats::default())),
+            signer,
+            cosigner,
+            vrf_key,
+            dkg: Arc::new(Mutex::new(DkgParty::new())),
+            static_key,
+            auth: Arc::new(Mutex::new(auth)),
+            keypair,
+            pool,
+            sessions,
+        }
+    }
+
+    //Постоянный ключ используется в своей группе; в остальных группах
+    //демонстраций ключ по-прежнему новый в каждой сессии
+    fn prover<G: Group>(&self, setting: Setting<G>) -> Prover<G> {
+        let kind = self.config.get_protocol();
+        let stored = self
+            .keypair
+            .as_ref()
+            .and_then(|keypair| keypair.prover(setting.clone()).ok());
+        let prover = match (stored, self.config.get_secret_bits()) {
+            (Some(prover), _) => prover,
+            (None, Some(bits)) => {
+                let secret = Key::random_bits(&setting.order(), bits);
+                Prover::in_setting(setting, kind, secret)
+            }
+            (None, None) => Prover::random_in(setting, kind),
         };
-        state
+        prover
+            .with_nonces(self.config.get_nonces())
+            .with_hash(self.config.get_hash())
+            .with_committed_challenge(self.config.get_challenge_commitment())
     }
 
-    async fn get_challenge(&self) -> BigUint {
-        let c = BigUint::from(C); //key_gen::random_biguint_mod(&self.q).await;
-        let _ = self.tx.send(format!(
-            "Виктор: Привет, я Виктор. Докажи что ты знаешь секретный ключ, твое испытание: {}",
-            c
-        ));
+    fn verifier<G: Group>(&self, setting: Setting<G>) -> Verifier<G> {
+        let verifier = Verifier::in_setting(setting, self.config.get_protocol())
+            .with_hash(self.config.get_hash())
+            .with_committed_challenge(self.config.get_challenge_commitment());
+        match self.config.get_challenge_space() {
+            Some(space) => verifier.with_challenge_space(space),
+            None => verifier,
+        }
+    }
+
+    //Стойкость сессии; фиксированное испытание повторением не усиливается
+    fn soundness<G: Group>(&self, setting: &Setting<G>) -> ProtocolEvent {
+        let rounds = self.config.get_rounds();
+        let bits = match self.config.get_challenge() {
+            ChallengeMode::Interactive if self.config.get_toy_params() => 0.0,
+            _ => self.verifier(setting.clone()).soundness_bits(rounds),
+        };
+        ProtocolEvent::Soundness { rounds, bits }
+    }
+
+    //Публикация времени шагов сессии и учет в статистике по размеру модуля
+    fn record_timings(&self, bits: u64, timeline: &Timeline) {
+        self.events.publish(ProtocolEvent::Timings {
+            steps: timeline.steps().to_vec(),
+        });
+        self.stats.lock().unwrap().record(bits, timeline);
+    }
+
+    async fn get_challenge<G: Group>(
+        &self,
+        setting: &Setting<G>,
+        u: &BigUint,
+        ut: &BigUint,
+    ) -> Result<BigUint, String> {
+        let c = match self.config.get_challenge() {
+            ChallengeMode::Interactive if self.config.get_toy_params() => BigUint::from(C),
+            ChallengeMode::Interactive | ChallengeMode::Random => {
+                self.verifier(setting.clone()).challenge()
+            }
+            ChallengeMode::FiatShamir => {
+                fiat_shamir::challenge_with(self.config.get_hash(), setting, u, ut)
+            }
+            ChallengeMode::Beacon => {
+                let space = self.verifier(setting.clone()).challenge_space().clone();
+                let beacon = Beacon::new(self.config.get_beacon());
+                let issued = beacon.issue(&space, u, ut).await?;
+                if let Some(BeaconRound { round, randomness }) = issued.round {
+                    self.events
+                        .publish(ProtocolEvent::BeaconRound { round, randomness });
+                }
+                issued.c
+            }
+        };
+        self.events
+            .publish(ProtocolEvent::Challenge { c: c.clone() });
         tokio::time::sleep(Duration::from_millis(500)).await;
         info!("V сгенерировал с");
-        c
+        Ok(c)
     }
-}
 
-#[derive(Debug, Clone)]
-struct Key {
-    alpha: BigUint,
-    beta: BigUint,
+    //Испытание, выбранное и зафиксированное до коммитмента доказывающего
+    async fn seal_challenge<G: Group>(
+        &self,
+        setting: &Setting<G>,
+    ) -> Result<SealedChallenge, String> {
+        let c = match self.config.get_challenge() {
+            ChallengeMode::Interactive if self.config.get_toy_params() => BigUint::from(C),
+            ChallengeMode::Interactive | ChallengeMode::Random => {
+                self.verifier(setting.clone()).challenge()
+            }
+            ChallengeMode::FiatShamir | ChallengeMode::Beacon => {
+                return Err(
+                    "Испытание, зависящее от u_t, нельзя зафиксировать до коммитмента".to_string(),
+                );
+            }
+        };
+        let sealed = SealedChallenge::seal(c);
+        self.events.publish(ProtocolEvent::ChallengeCommitted {
+            digest: sealed.digest(),
+        });
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        Ok(sealed)
+    }
+
+    //Раскрытие испытания; доказывающий сверяет его с коммитментом
+    async fn reveal_challenge(&self, sealed: &SealedChallenge) -> Result<BigUint, String> {
+        let opened = challenge::open_challenge(&sealed.digest(), &sealed.c, &sealed.salt);
+        self.events.publish(ProtocolEvent::ChallengeRevealed {
+            c: sealed.c.clone(),
+            salt: sealed.salt.clone(),
+            opened,
+        });
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        match opened {
+            true => Ok(sealed.c.clone()),
+            false => Err("Раскрытое испытание не совпадает с коммитментом".to_string()),
+        }
+    }
 }
 
-impl Key {
-    fn new(alpha: BigUint, beta: BigUint) -> Self {
-        Key { alpha, beta }
+//Реестр с администраторами из конфигурации
+fn admin_registry<G: Group>(config: &Config, setting: &Setting<G>) -> Result<Registry, String> {
+    let mut registry = Registry::new();
+    for admin in config.get_admins() {
+        registry.register_admin(setting, admin)?;
+    }
+    if config.get_admins().is_empty() {
+        warn!("Администраторы не заданы: служебные эндпоинты и регистрация закрыты");
     }
+    Ok(registry)
 }
 
-async fn compute_u(key: &Key, g: &BigUint, h: &BigUint, q: &BigUint) -> BigUint {
-    let a = match key.alpha.to_bigint() {
-        Some(a) => a,
-        None => {
-            warn!("Failed to convert alpha to bigint");
-            std::process::exit(1);
-        }
-    };
-    let b = match key.beta.to_bigint() {
-        Some(b) => b,
-        None => {
-            warn!("Failed to convert beta to bigint");
-            std::process::exit(1);
-        }
+//Ключ из парольной фразы и key_salt, из key_file или новый, сохраненный
+//в key_file. Зашифрованный файл отпирается паролем при запуске
+fn load_keypair<G: Group>(
+    config: &Config,
+    setting: &Setting<G>,
+) -> Result<Option<Keypair>, String> {
+    let kind = config.get_protocol();
+    if let Some(salt) = config.get_key_salt() {
+        let salt = encoding::from_hex(salt)?;
+        let phrase = ask_secret(KEY_PASSPHRASE_VAR, "Парольная фраза доказывающего: ")?;
+        let keypair =
+            Keypair::from_passphrase(setting, kind, &phrase, &salt, KdfParams::default())?;
+        info!("Ключ доказывающего выведен из парольной фразы");
+        return Ok(Some(keypair));
+    }
+    let Some(path) = config.get_key_file() else {
+        return Ok(None);
     };
+    let password = || ask_secret(KEY_PASSWORD_VAR, "Пароль ключа доказывающего: ");
+    if std::path::Path::new(path).exists() {
+        let keypair = match Keystore::load(path) {
+            Ok(keystore) => keystore.open(&password()?)?,
+            Err(_) => Keypair::load(path)?,
+        };
+        keypair.prover(setting.clone())?;
+        info!("Ключ доказывающего загружен из {}", path);
+        return Ok(Some(keypair));
+    }
+    let keypair = Keypair::generate_in(setting, kind);
+    match config.get_encrypt_key() {
+        true => Keystore::seal(&keypair, &password()?)?.save(path)?,
+        false => keypair.save(path)?,
+    }
+    info!("Новый ключ доказывающего сохранен в {}", path);
+    Ok(Some(keypair))
+}
 
-    (math::mod_pow_big(g, &a, &q).unwrap() * math::mod_pow_big(h, &b, &q).unwrap()) % q
+//Секрет из переменной окружения или, если ее нет, с терминала без эха
+fn ask_secret(var: &str, prompt: &str) -> Result<String, String> {
+    match std::env::var(var) {
+        Ok(secret) => Ok(secret),
+        Err(_) => rpassword::prompt_password(prompt).map_err(|e| e.to_string()),
+    }
 }
 
 #[tokio::main]
 async fn main() {
     let cli = Args::parse();
-    tracing_subscriber::fmt::init();
+    if let Some(Command::GenParams {
+        bits,
+        out,
+        certificate,
+        rounds,
+    }) = cli.command
+    {
+        tracing_subscriber::fmt::init();
+        return gen_params(bits, &out, certificate, rounds);
+    }
+    let Some(config_path) = cli.config_path else {
+        unreachable!("clap требует --config-path без подкоманды");
+    };
+    if cli.transport == TransportKind::Stdio
+        || matches!(cli.role, Some(Role::Prove | Role::Deal | Role::Sign))
+    {
+        // stdout занят сообщениями протокола, доказательством или долями
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .init();
+    } else {
+        tracing_subscriber::fmt::init();
+    }
+
+    let (inbox_tx, inbox_rx) = mpsc::channel(16);
+    let state = AppState::new(
+        config_path,
+        cli.group,
+        (cli.bits, cli.secret_bits),
+        cli.toy_params,
+        inbox_tx,
+    )
+    .await;
 
-    let state = AppState::new(cli.config_path).await;
+    let Some(role) = cli.role else {
+        return serve(state).await;
+    };
+    //Подпись BIP-340 всегда в secp256k1, независимо от группы конфигурации
+    if let Role::Sign = role {
+        return sign_bip340(cli.secret_key.as_deref(), &cli.message);
+    }
+    let secret = cli.secret_key.map(|hex| {
+        let hex = hex.trim_start_matches("0x");
+        BigUint::parse_bytes(hex.as_bytes(), 16).unwrap_or_else(|| {
+            eprintln!("Invalid secret key: expected hex");
+            std::process::exit(1);
+        })
+    });
+    let options = RoleOptions {
+        role,
+        transport: cli.transport,
+        statements: cli.statements,
+        secret,
+        parties: cli.parties,
+        threshold: cli.threshold,
+    };
+    with_setting!(state.group, state.setting.clone(), |setting| {
+        run_role(options, setting, state, inbox_rx).await
+    })
+}
+
+//Подкоманда gen-params: параметры генерируются один раз и не задерживают
+//запуск сервера
+fn gen_params(bits: u64, out: &str, certificate: Option<CertificateKind>, rounds: u8) {
+    if let Err(e) = config::check_bits("bits", bits, key_gen::MIN_PRIME_BITS) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    if !(1..=key_gen::MAX_MR_ROUNDS).contains(&rounds) {
+        eprintln!(
+            "rounds = {}: допустимо от 1 до {}",
+            rounds,
+            key_gen::MAX_MR_ROUNDS
+        );
+        std::process::exit(1);
+    }
+    let file = match certificate {
+        Some(kind) => {
+            info!("Генерация доказуемо простого p длиной {} бит", bits);
+            ParamsFile::generate_provable(bits, kind)
+        }
+        None => {
+            info!("Генерация безопасного простого длиной {} бит", bits);
+            info!("{}", Primality::new(rounds));
+            ParamsFile::generate(bits, rounds, |progress| {
+                if progress.windows % PRIME_PROGRESS_WINDOWS == 0 {
+                    info!(
+                        "Поиск простого: {} окон решета, {} кандидатов проверено",
+                        progress.windows, progress.tested
+                    );
+                }
+            })
+        }
+    };
+    if let Err(e) = file.save(out) {
+        eprintln!("Failed to write params: {}", e);
+        std::process::exit(1);
+    }
+    info!("Параметры записаны в {}", out);
+}
 
+async fn serve(state: AppState) {
     let addr: SocketAddr = state.config.get_address().parse().unwrap();
 
     // Start server
@@ -143,15 +826,318 @@ async fn main() {
     let app = Router::new()
         .route("/", get(root_handler))
         .route("/start", post(start_handler))
+        .route("/start/dleq", post(start_dleq_handler))
+        .route("/start/ffs", post(start_ffs_handler))
+        .route("/start/isomorphism", post(start_isomorphism_handler))
+        .route("/start/elgamal", post(start_elgamal_handler))
+        .route("/start/ring", post(start_ring_handler))
+        .route("/start/blind", post(start_blind_handler))
+        .route("/start/credential", post(start_credential_handler))
+        .route("/start/ipa", post(start_ipa_handler))
+        .route("/start/musig", post(start_musig_handler))
+        .route("/start/dkg", post(start_dkg_handler))
+        .route("/start/handshake", post(start_handshake_handler))
+        .route("/start/simulator", post(start_simulator_handler))
+        .route("/start/forgery", post(start_forgery_handler))
+        .route("/start/extractor", post(start_extractor_handler))
+        .route("/start/attack", post(start_attack_handler))
+        .route("/start/bsgs", post(start_bsgs_handler))
+        .route("/start/primes", post(start_primes_handler))
         .route("/logs", get(logs_handler))
+        .route("/sessions", post(create_session_handler))
+        .route("/sessions/{id}", get(session_handler))
+        .route("/sessions/{id}/logs", get(session_logs_handler))
+        .route(sessions::COMMIT_PATH, post(external_commit_handler))
+        .route(sessions::CHALLENGE_PATH, post(external_challenge_handler))
+        .route(sessions::RESPONSE_PATH, post(external_response_handler))
+        .route("/ws", get(ws_handler))
+        .route("/ws/logs", get(ws_logs_handler))
+        .route("/peer/message", post(peer_message_handler))
+        .route(peer::PEER_EVENT_PATH, post(peer_event_handler))
+        .route(HTTP_TRANSPORT_PATH, post(transport_message_handler))
+        .route(WS_TRANSPORT_PATH, get(ws_transport_handler))
+        .route("/commitment/equality", post(commitment_equality_handler))
+        .route("/commitment/opening", post(commitment_opening_handler))
+        .route("/verify", post(verify_handler))
+        .route("/verify/aggregate", post(verify_aggregate_handler))
+        .route("/verify/fiat_shamir", post(verify_fiat_shamir_handler))
+        .route("/verify/consensus", post(verify_consensus_handler))
+        .route("/verify/bip340", post(verify_bip340_handler))
+        .route("/verify/ring", post(verify_ring_handler))
+        .route("/verify/blind", post(verify_blind_handler))
+        .route("/vrf", post(vrf_handler))
+        .route("/verify/vrf", post(verify_vrf_handler))
+        .route("/verify_batch", post(verify_batch_handler))
+        .route("/stats", get(stats_handler))
+        .route("/params", get(params_handler))
+        .route("/transcript", get(transcript_handler))
+        .route("/forgery", get(forgery_handler))
+        .route(blind::COMMIT_PATH, post(blind_commit_handler))
+        .route(blind::SIGN_PATH, post(blind_sign_handler))
+        .route(handshake::HANDSHAKE_PATH, post(handshake_handler))
+        .route(auth::REGISTER_PATH, post(auth_register_handler))
+        .route(auth::LOGIN_PATH, post(auth_login_handler))
+        .route(auth::RESPOND_PATH, post(auth_respond_handler))
+        .route(dkg::COMMIT_PATH, post(dkg_commit_handler))
+        .route(dkg::COMMITMENTS_PATH, post(dkg_commitments_handler))
+        .route(dkg::DISTRIBUTE_PATH, post(dkg_distribute_handler))
+        .route(dkg::SHARE_PATH, post(dkg_share_handler))
+        .route(dkg::FINISH_PATH, post(dkg_finish_handler))
+        .route(musig::COMMIT_PATH, post(musig_commit_handler))
+        .route(musig::REVEAL_PATH, post(musig_reveal_handler))
+        .route(musig::SIGN_PATH, post(musig_sign_handler))
+        .route(threshold::COMMIT_PATH, post(threshold_commit_handler))
+        .route(threshold::RESPOND_PATH, post(threshold_respond_handler))
         .with_state(state);
 
+    info!("Listening on {}", addr);
     if let Err(e) = axum::serve(listener, app).await {
         eprintln!("Server error: {}", e);
         std::process::exit(1);
     }
+}
 
-    info!("Listening on {}", addr);
+//Запуск одной стороны протокола через выбранный транспорт
+async fn run_role<G: Group + 'static>(
+    options: RoleOptions,
+    setting: Setting<G>,
+    mut state: AppState,
+    inbox: mpsc::Receiver<ProtocolMessage>,
+) {
+    let RoleOptions {
+        role,
+        transport,
+        statements,
+        secret,
+        parties,
+        threshold,
+    } = options;
+    //По HTTP оба сервера пересылают друг другу свои события, и журнал
+    //каждого показывает обмен целиком; иначе события некому читать
+    match transport {
+        TransportKind::Http => state.events.attach(PeerSink::new(state.peer.clone())),
+        _ => state.events.attach(NullSink),
+    }
+    let mut provers = Vec::with_capacity(statements);
+    //Заданный ключ x доказывается первым утверждением как (x, 0)
+    if let Some(x) = secret {
+        let secret = Key::new(x % setting.order(), BigUint::ZERO);
+        let prover = Prover::in_setting(setting.clone(), state.config.get_protocol(), secret)
+            .with_nonces(state.config.get_nonces())
+            .with_hash(state.config.get_hash())
+            .with_committed_challenge(state.config.get_challenge_commitment());
+        let public_key = setting.group.serialize(prover.public_key());
+        info!("Публичный ключ: {}", to_hex(&public_key));
+        provers.push(prover);
+    }
+    while provers.len() < statements.max(1) {
+        provers.push(state.prover(setting.clone()));
+    }
+    let count = provers.len();
+    let rounds = state.config.get_rounds();
+    let verifier = state.verifier(setting.clone());
+    let mut timeline = Timeline::new();
+    //Роли по транспорту всегда используют случайные испытания проверяющего
+    let soundness = ProtocolEvent::Soundness {
+        rounds,
+        bits: verifier.soundness_bits(rounds),
+    };
+
+    //Пороговый доказывающий доказывает единственное утверждение своим ключом
+    if state.config.get_threshold().is_some() && statements > 1 {
+        eprintln!("Threshold prover supports a single statement");
+        std::process::exit(1);
+    }
+    let threshold_prover = state.config.get_threshold().map(|config| {
+        let mut participants: Vec<Participant> = state
+            .party
+            .iter()
+            .cloned()
+            .map(Participant::Local)
+            .collect();
+        participants.extend(config.peers.iter().cloned().map(Participant::remote));
+        ThresholdProver::new(
+            setting.clone(),
+            state.config.get_protocol(),
+            config.threshold,
+            participants,
+        )
+    });
+    let threshold_prover = threshold_prover.as_ref();
+    let events = state.events.clone();
+
+    let result = match (role, transport) {
+        (Role::Sign, _) => unreachable!("подпись BIP-340 выполняется до выбора группы"),
+        (Role::Deal, _) => {
+            //Заданный ключ уже стоит первым утверждением
+            return deal(&setting, provers[0].secret(), threshold, parties);
+        }
+        // stdout получает доказательство, которое можно проверить позже
+        (Role::Prove, _) => {
+            let Some(proof) = provers[0].prove_non_interactive() else {
+                eprintln!("Failed to compute proof");
+                std::process::exit(1);
+            };
+            match proof.to_json() {
+                Ok(json) => println!("{}", json),
+                Err(e) => {
+                    eprintln!("Failed to encode proof: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        (Role::Local, _) => {
+            let (mut prover_side, mut verifier_side) = ChannelTransport::pair();
+            let verifier = tokio::spawn(async move {
+                verifier
+                    .run_rounds(count, rounds, &mut verifier_side, &mut Timeline::new())
+                    .await
+            });
+            let result =
+                Prover::run_rounds(&provers, rounds, &mut prover_side, &mut timeline).await;
+            match verifier.await {
+                Ok(Err(e)) => Err(e),
+                Err(e) => Err(e.to_string()),
+                Ok(Ok(_)) => result,
+            }
+        }
+        (Role::Prover, TransportKind::Stdio) => {
+            prove(
+                &provers,
+                threshold_prover,
+                rounds,
+                &mut StdioTransport::stdio(),
+                &mut timeline,
+            )
+            .await
+        }
+        (Role::Verifier, TransportKind::Stdio) => {
+            verifier
+                .run_rounds(count, rounds, &mut StdioTransport::stdio(), &mut timeline)
+                .await
+        }
+        (Role::Prover, TransportKind::Ws) => {
+            let url = format!(
+                "ws://{}{}",
+                state.config.get_second_server().get(),
+                WS_TRANSPORT_PATH
+            );
+            match WebSocketTransport::connect(&url).await {
+                Ok(mut ws) => {
+                    prove(&provers, threshold_prover, rounds, &mut ws, &mut timeline).await
+                }
+                Err(e) => Err(e),
+            }
+        }
+        // Проверяющий по WebSocket принимает подключения на WS_TRANSPORT_PATH
+        (Role::Verifier, TransportKind::Ws) => return serve(state).await,
+        (role, TransportKind::Http) => {
+            let mut http = HttpTransport::new(state.peer.clone(), inbox, state.events.clone());
+            tokio::spawn(serve(state));
+            match role {
+                Role::Prover => {
+                    prove(&provers, threshold_prover, rounds, &mut http, &mut timeline).await
+                }
+                _ => {
+                    verifier
+                        .run_rounds(count, rounds, &mut http, &mut timeline)
+                        .await
+                }
+            }
+        }
+    };
+
+    let timings = ProtocolEvent::Timings {
+        steps: timeline.steps().to_vec(),
+    };
+    info!("{}", timings.narration());
+    info!("{}", soundness.narration());
+    if transport == TransportKind::Http {
+        events.publish(timings);
+        events.publish(soundness);
+        //Последние события успевают уйти второму серверу до выхода
+        tokio::time::sleep(FORWARD_GRACE).await;
+    }
+
+    match result {
+        Ok(true) => info!("Знание секретного ключа подтверждено"),
+        Ok(false) => {
+            warn!("Знание секретного ключа не подтверждено");
+            std::process::exit(2);
+        }
+        Err(e) => {
+            eprintln!("Protocol error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+//Обычный или пороговый доказывающий
+async fn prove<G: Group, T: Transport>(
+    provers: &[Prover<G>],
+    threshold: Option<&ThresholdProver<G>>,
+    rounds: u32,
+    transport: &mut T,
+    timeline: &mut Timeline,
+) -> Result<bool, String> {
+    match threshold {
+        Some(threshold) => threshold.run_rounds(rounds, transport, timeline).await,
+        None => Prover::run_rounds(provers, rounds, transport, timeline).await,
+    }
+}
+
+//Доли секрета для конфигураций участников и публичный ключ u в stdout
+fn deal<G: Group>(setting: &Setting<G>, secret: &Key, threshold: usize, parties: usize) {
+    let shares = match threshold::split(secret, threshold, parties, &setting.order()) {
+        Ok(shares) => shares,
+        Err(e) => {
+            eprintln!("Failed to split secret: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let u = setting
+        .group
+        .encode(&setting.compute_u(&secret.alpha, &secret.beta));
+    let output = serde_json::json!({
+        "u": u.to_string(),
+        "threshold": threshold,
+        "shares": shares,
+    });
+    println!("{}", output);
+}
+
+#[derive(Debug, Serialize)]
+struct Bip340Signature {
+    public_key: String,
+    message: String,
+    signature: String,
+}
+
+//Без ключа подписывает случайным; aux - свежая случайность, как советует BIP-340
+fn sign_bip340(secret_key: Option<&str>, message: &str) {
+    let fail = |e: String| -> ! {
+        eprintln!("Failed to sign: {}", e);
+        std::process::exit(1);
+    };
+    let secret = match secret_key {
+        Some(hex) => encoding::from_hex_array(hex).unwrap_or_else(|e| fail(e)),
+        None => loop {
+            let secret = rand::random::<[u8; 32]>();
+            if bip340::public_key(&secret).is_ok() {
+                break secret;
+            }
+        },
+    };
+    let message = encoding::from_hex(message).unwrap_or_else(|e| fail(e));
+    let public_key = bip340::public_key(&secret).unwrap_or_else(|e| fail(e));
+    let signature = bip340::sign(&secret, &message, &rand::random()).unwrap_or_else(|e| fail(e));
+    let report = Bip340Signature {
+        public_key: to_hex(&public_key),
+        message: to_hex(&message),
+        signature: to_hex(&signature),
+    };
+    println!("{}", serde_json::to_string(&report).unwrap());
 }
 
 async fn root_handler() -> Html<&'static str> {
@@ -160,162 +1146,2757 @@ async fn root_handler() -> Html<&'static str> {
 
 async fn start_handler(State(state): State<AppState>) -> StatusCode {
     info!("Получен запрос на запуск задач");
+    wait_for_subscribers(&state).await;
 
-    let tx = state.tx.clone();
-    while tx.receiver_count() == 0 {
-        warn!("Receivers count equal 0. Wait");
-        tokio::time::sleep(Duration::from_millis(500)).await;
-    }
-
-    tokio::spawn(async move {
-        start_proof(state, tx).await;
-    });
+    tokio::spawn(run_proof(state));
 
     StatusCode::ACCEPTED
 }
 
-async fn logs_handler(
+//Доказательство в группе из конфигурации
+async fn run_proof(state: AppState) -> Result<bool, String> {
+    with_setting!(state.group, state.setting.clone(), |setting| {
+        start_proof(setting, state).await
+    })
+}
+
+//Новая сессия: протокол начнется, когда к журналу сессии подключится клиент
+async fn create_session_handler(
     State(state): State<AppState>,
-) -> Sse<impl futures_core::Stream<Item = Result<Event, axum::Error>>> {
-    let stream = BroadcastStream::new(state.tx.subscribe()).map(|res| match res {
-        Ok(msg) => Ok(Event::default().data(msg)),
-        Err(BroadcastStreamRecvError::Lagged(skipped)) => {
-            Ok(Event::default().data(format!("⚠️ Пропущено {} сообщений", skipped)))
-        }
-    });
+) -> Result<(StatusCode, Json<SessionInfo>), (StatusCode, String)> {
+    let (id, events) = state
+        .sessions
+        .create(SESSION_EVENTS)
+        .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e))?;
+    info!("Создана сессия {}", id);
+    let info = SessionInfo {
+        id: id.clone(),
+        status: SessionStatus::Created,
+        age_ms: 0,
+    };
+    tokio::spawn(run_session(state, id, events));
+    Ok((StatusCode::CREATED, Json(info)))
+}
 
-    Sse::new(stream).keep_alive(KeepAlive::default())
+async fn run_session(mut state: AppState, id: String, events: EventBus) {
+    state.events = events;
+    let deadline = tokio::time::Instant::now() + SESSION_WAIT;
+    while state.events.live_subscribers() == 0 && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let sessions = state.sessions.clone();
+    sessions.set_status(&id, SessionStatus::Running);
+    let status = match run_proof(state).await {
+        Ok(accepted) => SessionStatus::Finished { accepted },
+        Err(reason) => SessionStatus::Failed { reason },
+    };
+    info!("Сессия {} завершена: {:?}", id, status);
+    sessions.set_status(&id, status);
 }
 
-async fn start_proof(appstate: AppState, tx: broadcast::Sender<String>) {
-    info!("Начинаем проверку");
-    let q = &appstate.q;
+async fn session_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<SessionInfo>, StatusCode> {
+    state
+        .sessions
+        .info(&id)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
 
-    let secret_key = Key::new(
-        // BigUint::from(K1),
-        // BigUint::from(K2),
-        key_gen::random_biguint_mod(&q).await,
-        key_gen::random_biguint_mod(&q).await,
-    );
-    info!("P Сгенерировал альфа и бета");
-    let u = compute_u(&secret_key, &appstate.g, &appstate.h, &appstate.q).await;
+//Коммитмент внешнего доказывающего: публичный ключ u и u_t
+#[derive(Debug, Deserialize)]
+struct ExternalCommitment {
+    u: HexBig,
+    ut: HexBig,
+}
 
-    info!("P Вычислил публичный ключ");
+#[derive(Debug, Deserialize)]
+struct ExternalSession {
+    session: String,
+}
 
-    let g = &appstate.g;
-    let h = &appstate.h;
+#[derive(Debug, Deserialize)]
+struct ExternalResponse {
+    session: String,
+    alpha: HexBig,
+    #[serde(default)]
+    beta: HexBig,
+}
 
-    let _ = tx
-        .send(format!(
-            "Сервер: Правила сервера: \n\t q = {} \n\t g = {} \n\t h = {}",
-            q, g, h
-        ))
-        .inspect_err(|e| warn!("Error log stream: {}", e));
-    tokio::time::sleep(Duration::from_millis(500)).await;
+#[derive(Debug, Serialize)]
+struct ExternalChallenge {
+    session: String,
+    c: HexBig,
+}
 
-    let keyt = Key::new(
-        // BigUint::from(T1),
-        // BigUint::from(T2),
-        key_gen::random_biguint_mod(&q).await,
-        key_gen::random_biguint_mod(&q).await,
-    );
+#[derive(Debug, Serialize)]
+struct ExternalVerdict {
+    session: String,
+    accepted: bool,
+}
 
-    info!("P Сгенерировал альфа_t и бета_t");
-    let ut = compute_u(&keyt, g, h, q).await;
-    let _ = tx.send(format!(
-        "Павел: Привет, я Павел! И я знаю секретный ключ! \n\t Вот мой публичный ключ(u): {} \n\t И дополнительный ключ для доказательства (u_t): {}",
-        u, ut
-    )).inspect_err(|e| warn!("Error log stream: {}", e));
-    tokio::time::sleep(Duration::from_millis(500)).await;
-    let _ = tx
-        .send(format!(
-            "Сервер: Виктор не получит следующее сообщение: \n\t Секретный ключ Павла: ({}, {})",
-            secret_key.alpha, secret_key.beta
-        ))
-        .inspect_err(|e| warn!("Error log stream: {}", e));
-    tokio::time::sleep(Duration::from_millis(500)).await;
-    let _ = tx
-        .send(format!(
-            "Сервер: Виктор не получит следующее сообщение: \n\t Дополнительный ключ: ({}, {})",
-            keyt.alpha, keyt.beta
-        ))
-        .inspect_err(|e| warn!("Error log stream: {}", e));
-    tokio::time::sleep(Duration::from_millis(500)).await;
+fn exchange_status(e: ExchangeError) -> (StatusCode, String) {
+    let status = match e {
+        ExchangeError::Unknown => StatusCode::NOT_FOUND,
+        ExchangeError::Expired => StatusCode::GONE,
+        ExchangeError::OutOfOrder(_) => StatusCode::CONFLICT,
+        ExchangeError::Invalid(_) => StatusCode::BAD_REQUEST,
+    };
+    (status, e.to_string())
+}
 
-    info!("P Вычислил u_t");
-    let c = appstate.get_challenge().await;
+//Проверяющий для внешнего доказывающего работает в группе вычетов
+fn external_group(state: &AppState) -> Result<(), (StatusCode, String)> {
+    match state.group {
+        GroupKind::Modular | GroupKind::Modp2048 | GroupKind::Modp3072 | GroupKind::Modp4096 => {
+            Ok(())
+        }
+        GroupKind::Ristretto255 | GroupKind::Secp256k1 => Err((
+            StatusCode::NOT_IMPLEMENTED,
+            "Внешний доказывающий поддерживается только в группах вычетов".to_string(),
+        )),
+    }
+}
 
-    info!("P Получил испытание!");
+//Коммитмент внешнего доказывающего открывает новую сессию
+fn external_commit(
+    state: &AppState,
+    u: BigUint,
+    ut: BigUint,
+) -> Result<(String, EventBus), (StatusCode, String)> {
+    external_group(state)?;
+    for (name, x) in [("u", &u), ("u_t", &ut)] {
+        params::validate_element(&state.params, name, x)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+    let session = state
+        .verifier(state.setting.clone())
+        .session()
+        .receive_commitment(ProtocolMessage::Commitment {
+            u: u.clone(),
+            ut: ut.clone(),
+        })
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let (id, events) = state
+        .sessions
+        .create_external(SESSION_EVENTS, session)
+        .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e))?;
+    info!("Внешний доказывающий открыл сессию {}", id);
+    events.publish(ProtocolEvent::Commitment { u, ut });
+    Ok((id, events))
+}
 
-    let keyz = Key::new(
-        (keyt.alpha + secret_key.alpha * &c) % q,
-        (keyt.beta + secret_key.beta * &c) % q,
-    );
+//Испытание всегда случайно: фиксированное или вычисленное из коммитмента
+//позволило бы внешнему доказывающему подготовить ответ без секрета
+fn external_challenge(state: &AppState, id: &str) -> Result<BigUint, (StatusCode, String)> {
+    external_group(state)?;
+    let verifier = state.verifier(state.setting.clone());
+    let c = state
+        .sessions
+        .challenge(id, || verifier.challenge())
+        .map_err(exchange_status)?;
+    if let Some(events) = state.sessions.events(id) {
+        events.publish(ProtocolEvent::Challenge { c: c.clone() });
+    }
+    Ok(c)
+}
 
-    info!("P Вычислил альфа_z и бета_z");
+fn external_respond(
+    state: &AppState,
+    id: &str,
+    message: ProtocolMessage,
+) -> Result<bool, (StatusCode, String)> {
+    external_group(state)?;
+    let decided = state
+        .sessions
+        .respond(id, message)
+        .map_err(exchange_status)?;
+    info!(
+        "Сессия {}: внешний доказывающий {}",
+        id,
+        match decided.accepted {
+            true => "подтвердил знание ключа",
+            false => "не подтвердил знание ключа",
+        }
+    );
+    if let Some(events) = state.sessions.events(id) {
+        events.publish(ProtocolEvent::Verdict {
+            uz: decided.uz,
+            utuc: decided.utuc,
+            accepted: decided.accepted,
+        });
+    }
+    Ok(decided.accepted)
+}
 
-    let _ = tx
-        .send(format!(
-            "Павел: Я успешно вычислил \n\t a_z = {} \n\t b_z = {}",
-            keyz.alpha, keyz.beta
-        ))
-        .inspect_err(|e| warn!("Error log stream: {}", e));
-    tokio::time::sleep(Duration::from_millis(500)).await;
-    send_proof(keyz, u, ut, c.to_bigint().unwrap(), appstate.clone(), tx).await;
+async fn external_commit_handler(
+    State(state): State<AppState>,
+    Json(request): Json<ExternalCommitment>,
+) -> Result<(StatusCode, Json<SessionInfo>), (StatusCode, String)> {
+    let (id, _) = external_commit(&state, request.u.into(), request.ut.into())?;
+    let info = state.sessions.info(&id).ok_or((
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Сессия вытеснена".to_string(),
+    ))?;
+    Ok((StatusCode::CREATED, Json(info)))
 }
 
-async fn send_proof(
-    key: Key,
-    u: BigUint,
-    ut: BigUint,
-    c: BigInt,
-    appstate: AppState,
-    tx: broadcast::Sender<String>,
-) {
-    let uz = compute_u(&key, &appstate.g, &appstate.h, &appstate.q).await;
-    info!("V вычислил u_z");
-    let _ = tx
-        .send(format!("Виктор: Я успешно вычислил u_z = {}", uz))
-        .inspect_err(|e| warn!("Error log stream: {}", e));
-    tokio::time::sleep(Duration::from_millis(500)).await;
+async fn external_challenge_handler(
+    State(state): State<AppState>,
+    Json(request): Json<ExternalSession>,
+) -> Result<Json<ExternalChallenge>, (StatusCode, String)> {
+    let c = external_challenge(&state, &request.session)?;
+    Ok(Json(ExternalChallenge {
+        session: request.session,
+        c: c.into(),
+    }))
+}
 
-    let uc = match math::mod_pow_big(&u, &c, &appstate.q) {
-        Some(u) => u,
-        None => {
-            let _ = tx
-                .send("Задача завершена с ошибкой!".to_string())
-                .inspect_err(|e| warn!("Error log stream: {}", e));
-            return;
-        }
+async fn external_response_handler(
+    State(state): State<AppState>,
+    Json(request): Json<ExternalResponse>,
+) -> Result<Json<ExternalVerdict>, (StatusCode, String)> {
+    let message = ProtocolMessage::Response {
+        alpha: request.alpha.into(),
+        beta: request.beta.into(),
     };
-    let _ = tx
-        .send(format!("Виктор: Я успешно вычислил u^c = {}", uc))
-        .inspect_err(|e| warn!("Error log stream: {}", e));
-    tokio::time::sleep(Duration::from_millis(500)).await;
+    let accepted = external_respond(&state, &request.session, message)?;
+    Ok(Json(ExternalVerdict {
+        session: request.session,
+        accepted,
+    }))
+}
 
-    let utuc = ut * uc % &appstate.q;
-    info!("V вычислил u_t * u^c");
+//Кадр двустороннего WebSocket: событие журнала или сообщение проверяющего
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum WsFrame {
+    Event { event: ProtocolEvent },
+    Message { message: ProtocolMessage },
+}
 
-    let _ = tx
-        .send(format!("Виктор: Я успешно вычислил u_t * u^c = {}", utuc))
-        .inspect_err(|e| warn!("Error log stream: {}", e));
-    tokio::time::sleep(Duration::from_millis(500)).await;
+//Двусторонний WebSocket: общий журнал и события своей сессии приходят
+//клиенту, а клиент в роли доказывающего присылает коммитменты и ответы
+async fn ws_handler(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| ws_session(state, socket))
+}
 
-    if uz == utuc {
-        info!("V подтверлил знание");
-        let _ = tx
-            .send(format!(
-                "Виктор: {} = {} \n\t Павел, вы знаете секретный ключ!",
-                uz, utuc
-            ))
-            .inspect_err(|e| warn!("Error log stream: {}", e));
-    } else {
-        info!("V отверг знание");
-        let _ = tx
-            .send(format!(
-                "Виктор: {} != {} \n\t Павел, вы не знаете секретный ключ!",
-                uz, utuc
-            ))
-            .inspect_err(|e| warn!("Error log stream: {}", e));
+async fn ws_session(state: AppState, mut socket: WebSocket) {
+    let mut events = state.events.subscribe();
+    //Сессия доказывающего на этом соединении и ее журнал
+    let mut session: Option<(String, broadcast::Receiver<Numbered>)> = None;
+    loop {
+        let frame = tokio::select! {
+            event = events.recv() => match event {
+                Ok(numbered) => WsFrame::Event {
+                    event: numbered.event,
+                },
+                Err(broadcast::error::RecvError::Lagged(skipped)) => WsFrame::Event {
+                    event: ProtocolEvent::Lagged { skipped },
+                },
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            Some(event) = session_event(&mut session) => WsFrame::Event { event },
+            incoming = socket.recv() => {
+                let message = match incoming {
+                    Some(Ok(AxumMessage::Text(text))) => ProtocolMessage::from_json(&text),
+                    Some(Ok(AxumMessage::Binary(data))) => ProtocolMessage::from_bytes(&data),
+                    Some(Ok(AxumMessage::Close(_))) | Some(Err(_)) | None => return,
+                    Some(Ok(_)) => continue,
+                };
+                let reply = message
+                    .map_err(|e| (StatusCode::BAD_REQUEST, e))
+                    .and_then(|message| ws_exchange(&state, &mut session, message));
+                WsFrame::Message {
+                    message: reply.unwrap_or_else(|(_, reason)| ProtocolMessage::Abort { reason }),
+                }
+            }
+        };
+        let Ok(text) = serde_json::to_string(&frame) else {
+            continue;
+        };
+        if socket.send(AxumMessage::Text(text.into())).await.is_err() {
+            return;
+        }
+    }
+}
+
+//Следующее событие сессии; без сессии не завершается никогда
+async fn session_event(
+    session: &mut Option<(String, broadcast::Receiver<Numbered>)>,
+) -> Option<ProtocolEvent> {
+    match session {
+        Some((_, events)) => events.recv().await.ok().map(|numbered| numbered.event),
+        None => std::future::pending().await,
+    }
+}
+
+//Шаг доказывающего по WebSocket: на коммитмент приходит испытание,
+//на ответ - решение проверяющего
+fn ws_exchange(
+    state: &AppState,
+    session: &mut Option<(String, broadcast::Receiver<Numbered>)>,
+    message: ProtocolMessage,
+) -> Result<ProtocolMessage, (StatusCode, String)> {
+    match message {
+        ProtocolMessage::Commitment { u, ut } => {
+            let (id, events) = external_commit(state, u, ut)?;
+            *session = Some((id.clone(), events.subscribe()));
+            let c = external_challenge(state, &id)?;
+            Ok(ProtocolMessage::Challenge { c })
+        }
+        ProtocolMessage::Response { .. } => {
+            let Some((id, _)) = session.as_ref() else {
+                return Err((StatusCode::CONFLICT, "Ответ до коммитмента".to_string()));
+            };
+            let accepted = external_respond(state, id, message)?;
+            Ok(ProtocolMessage::Verdict { accepted })
+        }
+        other => Err((
+            StatusCode::BAD_REQUEST,
+            format!("Неожиданное сообщение: {}", other.describe()),
+        )),
+    }
+}
+
+//Журнал одной сессии
+async fn session_logs_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Sse<impl futures_core::Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    let events = state.sessions.events(&id).ok_or(StatusCode::NOT_FOUND)?;
+    let (tx, rx) = mpsc::channel(16);
+    let sink = SseSink::new(tx);
+    match last_event_id(&headers) {
+        Some(last_id) => tokio::spawn(events.resume(last_id, sink)),
+        None => tokio::spawn(events::pump(events.subscribe(), sink)),
+    };
+    Ok(Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::default()))
+}
+
+//Демонстрация протокола Чаума-Педерсена
+async fn start_dleq_handler(State(state): State<AppState>) -> StatusCode {
+    info!("Получен запрос на запуск DLEQ");
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move {
+        start_dleq(state).await;
+    });
+
+    StatusCode::ACCEPTED
+}
+
+//Демонстрация протокола Фейге-Фиата-Шамира
+async fn start_ffs_handler(State(state): State<AppState>) -> StatusCode {
+    info!("Получен запрос на запуск FFS");
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move {
+        start_ffs(state).await;
+    });
+
+    StatusCode::ACCEPTED
+}
+
+//Демонстрация доказательства изоморфизма графов
+async fn start_isomorphism_handler(State(state): State<AppState>) -> StatusCode {
+    info!("Получен запрос на запуск изоморфизма графов");
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move {
+        start_isomorphism(state).await;
+    });
+
+    StatusCode::ACCEPTED
+}
+
+//Шифрование Эль-Гамаля с доказательством знания открытого текста
+async fn start_elgamal_handler(State(state): State<AppState>) -> StatusCode {
+    info!("Получен запрос на запуск Эль-Гамаля");
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move {
+        with_setting!(state.group, state.setting.clone(), |setting| {
+            start_elgamal(setting, state).await
+        })
+    });
+
+    StatusCode::ACCEPTED
+}
+
+//Кольцевая подпись случайного участника кольца
+async fn start_ring_handler(State(state): State<AppState>) -> StatusCode {
+    info!("Получен запрос на кольцевую подпись");
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move {
+        with_setting!(state.group, state.setting.clone(), |setting| {
+            start_ring(setting, state).await
+        })
+    });
+
+    StatusCode::ACCEPTED
+}
+
+//Слепая подпись: сервер подписывает монету пользователя, не видя ее
+async fn start_blind_handler(State(state): State<AppState>) -> StatusCode {
+    info!("Получен запрос на слепую подпись");
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move {
+        with_setting!(state.group, state.setting.clone(), |setting| {
+            start_blind(setting, state).await
+        })
+    });
+
+    StatusCode::ACCEPTED
+}
+
+//Выдача удостоверения и предъявление с выборочным раскрытием
+async fn start_credential_handler(State(state): State<AppState>) -> StatusCode {
+    info!("Получен запрос на демонстрацию удостоверения");
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move {
+        with_setting!(state.group, state.setting.clone(), |setting| {
+            start_credential(setting, state).await
+        })
+    });
+
+    StatusCode::ACCEPTED
+}
+
+//Генерация общего ключа с серверами из конфигурации
+async fn start_handshake_handler(State(state): State<AppState>) -> StatusCode {
+    info!("Получен запрос на обмен ключами со вторым сервером");
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move {
+        with_setting!(state.group, state.setting.clone(), |setting| {
+            start_handshake(setting, state).await
+        })
+    });
+
+    StatusCode::ACCEPTED
+}
+
+async fn start_dkg_handler(State(state): State<AppState>) -> StatusCode {
+    info!("Получен запрос на распределенную генерацию ключа");
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move {
+        with_setting!(state.group, state.setting.clone(), |setting| {
+            start_dkg(setting, state).await
+        })
+    });
+
+    StatusCode::ACCEPTED
+}
+
+//Совместная подпись с серверами из конфигурации
+async fn start_musig_handler(State(state): State<AppState>) -> StatusCode {
+    info!("Получен запрос на совместную подпись");
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move {
+        with_setting!(state.group, state.setting.clone(), |setting| {
+            start_musig(setting, state).await
+        })
+    });
+
+    StatusCode::ACCEPTED
+}
+
+//Сжатое доказательство скалярного произведения
+async fn start_ipa_handler(State(state): State<AppState>) -> StatusCode {
+    info!("Получен запрос на аргумент скалярного произведения");
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move {
+        with_setting!(state.group, state.setting.clone(), |setting| {
+            start_ipa(setting, state).await
+        })
+    });
+
+    StatusCode::ACCEPTED
+}
+
+//Честный прогон и симулятор без секрета рядом
+async fn start_simulator_handler(State(state): State<AppState>) -> StatusCode {
+    info!("Получен запрос на запуск симулятора");
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move {
+        with_setting!(state.group, state.setting.clone(), |setting| {
+            start_simulation(setting, state).await
+        })
+    });
+
+    StatusCode::ACCEPTED
+}
+
+//Извлечение секрета отматыванием доказывающего
+async fn start_extractor_handler(State(state): State<AppState>) -> StatusCode {
+    info!("Получен запрос на запуск экстрактора");
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move {
+        with_setting!(state.group, state.setting.clone(), |setting| {
+            start_extraction(setting, state).await
+        })
+    });
+
+    StatusCode::ACCEPTED
+}
+
+//Атака на параметры группы вычетов; для кривых p - 1 не связано с порядком
+async fn start_attack_handler(State(state): State<AppState>) -> StatusCode {
+    info!("Получен запрос на запуск атаки на параметры");
+    match state.group {
+        GroupKind::Modular | GroupKind::Modp2048 | GroupKind::Modp3072 | GroupKind::Modp4096 => {}
+        GroupKind::Ristretto255 | GroupKind::Secp256k1 => return StatusCode::BAD_REQUEST,
+    }
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move { start_attack(state.setting.clone(), state).await });
+
+    StatusCode::ACCEPTED
+}
+
+//Восстановление секрета шагами младенца и великана
+async fn start_bsgs_handler(State(state): State<AppState>) -> StatusCode {
+    info!("Получен запрос на запуск атаки BSGS");
+    match state.group {
+        GroupKind::Modular | GroupKind::Modp2048 | GroupKind::Modp3072 | GroupKind::Modp4096 => {}
+        GroupKind::Ristretto255 | GroupKind::Secp256k1 => return StatusCode::BAD_REQUEST,
+    }
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move { start_bsgs(state.setting.clone(), state).await });
+
+    StatusCode::ACCEPTED
+}
+
+#[derive(Deserialize)]
+struct PrimesQuery {
+    bits: Option<u64>,
+}
+
+//Генерация параметров настоящего размера с ходом поиска в журнале;
+//параметры самого сервера не меняются
+async fn start_primes_handler(
+    State(state): State<AppState>,
+    Query(query): Query<PrimesQuery>,
+) -> StatusCode {
+    info!("Получен запрос на генерацию параметров");
+    let pool_bits = state.pool.as_ref().map(|pool| pool.bits());
+    let bits = query.bits.or(pool_bits).unwrap_or(PRIMES_DEFAULT_BITS);
+    if !(key_gen::MIN_PRIME_BITS..=MAX_PRIMES_BITS).contains(&bits) {
+        return StatusCode::BAD_REQUEST;
+    }
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move { start_primes(bits, state).await });
+
+    StatusCode::ACCEPTED
+}
+
+#[derive(Deserialize)]
+struct ForgeryQuery {
+    attempts: Option<u64>,
+}
+
+//Серия попыток обмана без секрета
+async fn start_forgery_handler(
+    State(state): State<AppState>,
+    Query(query): Query<ForgeryQuery>,
+) -> StatusCode {
+    info!("Получен запрос на запуск обманщика");
+    let attempts = query.attempts.unwrap_or(FORGERY_ATTEMPTS);
+    if attempts == 0 || attempts > MAX_FORGERY_ATTEMPTS {
+        return StatusCode::BAD_REQUEST;
+    }
+    wait_for_subscribers(&state).await;
+
+    tokio::spawn(async move {
+        with_setting!(state.group, state.setting.clone(), |setting| {
+            start_forgery(setting, attempts, state).await
+        })
+    });
+
+    StatusCode::ACCEPTED
+}
+
+async fn wait_for_subscribers(state: &AppState) {
+    while state.events.live_subscribers() == 0 {
+        warn!("Receivers count equal 0. Wait");
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+//Номер последнего полученного события из заголовка переподключения SSE
+fn last_event_id(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+}
+
+async fn logs_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Sse<impl futures_core::Stream<Item = Result<Event, Infallible>>> {
+    let (tx, rx) = mpsc::channel(16);
+    let mut sink = SseSink::new(tx);
+    //Переподключившийся клиент получает пропущенные события из буфера
+    if let Some(last_id) = last_event_id(&headers) {
+        tokio::spawn(state.events.resume(last_id, sink));
+        return Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::default());
+    }
+    //Предупреждение о слабых параметрах получает каждый новый клиент
+    if state.strength.is_weak() {
+        let warning = ProtocolEvent::WeakParams {
+            security_bits: state.strength.security_bits,
+            warnings: state.strength.warnings.clone(),
+        };
+        let _ = sink.emit(&warning).await;
+    }
+    tokio::spawn(events::pump(state.events.subscribe(), sink));
+    Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::default())
+}
+
+//Журнал событий по WebSocket
+async fn ws_logs_handler(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| events::pump(state.events.subscribe(), socket))
+}
+
+fn peer_sender(headers: &HeaderMap) -> String {
+    headers
+        .get(peer::PEER_NAME_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("Второй сервер")
+        .to_string()
+}
+
+//Декодирование сообщения второго сервера по Content-Type;
+//зашифрованное тело сначала открывается ключом сессии
+fn decode_peer_body(
+    state: &AppState,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<ProtocolMessage, String> {
+    let opened;
+    let body = match headers.contains_key(peer::PEER_SEALED_HEADER) {
+        true => {
+            let key = state
+                .peer
+                .session_key()
+                .ok_or("Зашифрованное сообщение до обмена ключами")?;
+            opened = key.open(body)?;
+            &opened[..]
+        }
+        false => body,
+    };
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if is_json {
+        std::str::from_utf8(body)
+            .map_err(|e| e.to_string())
+            .and_then(ProtocolMessage::from_json)
+    } else {
+        ProtocolMessage::from_bytes(body)
+    }
+}
+
+async fn peer_message_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    let from = peer_sender(&headers);
+    match decode_peer_body(&state, &headers, &body) {
+        Ok(message) => {
+            info!("Получено сообщение от второго сервера {}", from);
+            state
+                .events
+                .publish(ProtocolEvent::PeerMessage { from, message });
+            StatusCode::ACCEPTED
+        }
+        Err(e) => {
+            warn!("Некорректное сообщение от {}: {}", from, e);
+            StatusCode::BAD_REQUEST
+        }
+    }
+}
+
+//События журнала второго сервера в раздельном режиме
+async fn peer_event_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(event): Json<ForwardedEvent>,
+) -> StatusCode {
+    state.events.publish(ProtocolEvent::PeerEvent {
+        from: peer_sender(&headers),
+        narration: event.narration,
+    });
+    StatusCode::ACCEPTED
+}
+
+//Входящие сообщения HTTP-транспорта
+async fn transport_message_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    let from = peer_sender(&headers);
+    let message = match decode_peer_body(&state, &headers, &body) {
+        Ok(message) => message,
+        Err(e) => {
+            warn!("Некорректное сообщение от {}: {}", from, e);
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+    state.events.publish(ProtocolEvent::PeerMessage {
+        from,
+        message: message.clone(),
+    });
+    match state.inbox.send(message).await {
+        Ok(()) => StatusCode::ACCEPTED,
+        Err(_) => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+//Проверяющий для доказывающих, подключающихся по WebSocket
+async fn ws_transport_handler(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |mut socket| async move {
+        info!("Подключение доказывающего по WebSocket");
+        with_setting!(state.group, state.setting.clone(), |setting| {
+            verify_remote(&state, setting, &mut socket).await
+        })
+    })
+}
+
+async fn verify_remote<G: Group, T: Transport>(
+    state: &AppState,
+    setting: Setting<G>,
+    transport: &mut T,
+) {
+    let bits = setting.group.modulus().bits();
+    let mut timeline = Timeline::new();
+    let result = state
+        .verifier(setting)
+        .run_rounds(1, state.config.get_rounds(), transport, &mut timeline)
+        .await;
+    let event = match result {
+        Ok(accepted) => ProtocolEvent::RemoteVerdict { accepted },
+        Err(reason) => ProtocolEvent::RemoteError { reason },
+    };
+    state.events.publish(event);
+    state.record_timings(bits, &timeline);
+}
+
+#[derive(Debug, Deserialize)]
+struct EqualityRequest {
+    value: HexBig,
+    g2: Option<HexBig>,
+    h2: Option<HexBig>,
+}
+
+#[derive(Debug, Serialize)]
+struct EqualityReport {
+    c1: HexBig,
+    c2: HexBig,
+    t1: HexBig,
+    t2: HexBig,
+    challenge: HexBig,
+    z: HexBig,
+    z1: HexBig,
+    z2: HexBig,
+    valid: bool,
+}
+
+//Элемент подгруппы порядка q, переданный через API
+fn parse_element(
+    params: &PublicParams,
+    name: &str,
+    value: HexBig,
+) -> Result<BigUint, (StatusCode, String)> {
+    let x = value.into();
+    params::validate_element(params, name, &x).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    Ok(x)
+}
+
+//Демонстрация доказательства равенства значений двух коммитментов Педерсена
+async fn commitment_equality_handler(
+    State(state): State<AppState>,
+    Json(request): Json<EqualityRequest>,
+) -> Result<Json<EqualityReport>, (StatusCode, String)> {
+    let modulus = &state.params.p;
+    let order = &state.params.q;
+    let value = request.value.0 % order;
+    let gens1 = Generators::new(state.params.g.clone(), state.params.h.clone());
+    let gens2 = Generators::new(
+        match request.g2 {
+            Some(g2) => parse_element(&state.params, "g2", g2)?,
+            None => state.params.g.clone(),
+        },
+        match request.h2 {
+            Some(h2) => parse_element(&state.params, "h2", h2)?,
+            None => state.params.h.clone(),
+        },
+    );
+
+    let internal = || {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Ошибка вычисления коммитмента".to_string(),
+        )
+    };
+    let witness = EqualityWitness {
+        value,
+        r1: key_gen::random_biguint_mod(order),
+        r2: key_gen::random_biguint_mod(order),
+    };
+    let statement = EqualityStatement {
+        c1: commitment::commit(&gens1, &witness.value, &witness.r1, modulus)
+            .ok_or_else(internal)?,
+        c2: commitment::commit(&gens2, &witness.value, &witness.r2, modulus)
+            .ok_or_else(internal)?,
+        gens1,
+        gens2,
+    };
+
+    let (nonce, com) =
+        commitment::equality_commit(&statement, modulus, order).ok_or_else(internal)?;
+    let challenge = key_gen::random_biguint_mod(order);
+    let response = commitment::equality_respond(&witness, &nonce, &challenge, order);
+    let valid = commitment::equality_verify(&statement, &com, &challenge, &response, modulus);
+    info!("Проверка равенства коммитментов: {}", valid);
+
+    Ok(Json(EqualityReport {
+        c1: statement.c1.into(),
+        c2: statement.c2.into(),
+        t1: com.t1.into(),
+        t2: com.t2.into(),
+        challenge: challenge.into(),
+        z: response.z.into(),
+        z1: response.z1.into(),
+        z2: response.z2.into(),
+        valid,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct OpeningRequest {
+    value: HexBig,
+    blinding: Option<HexBig>,
+    commitment: Option<HexBig>,
+}
+
+#[derive(Debug, Serialize)]
+struct OpeningReport {
+    commitment: HexBig,
+    ut: HexBig,
+    challenge: HexBig,
+    alpha: HexBig,
+    beta: HexBig,
+    valid: bool,
+}
+
+//Доказательство знания раскрытия коммитмента C = g^value * h^blinding без его раскрытия
+async fn commitment_opening_handler(
+    State(state): State<AppState>,
+    Json(request): Json<OpeningRequest>,
+) -> Result<Json<OpeningReport>, (StatusCode, String)> {
+    let params = &state.params;
+    let order = &params.order();
+    let value = request.value.0 % order;
+    let opening = match request.blinding {
+        Some(blinding) => Opening::new(value, blinding.0 % order),
+        None => Opening::random(value, order),
+    };
+    let internal = || {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Ошибка вычисления коммитмента".to_string(),
+        )
+    };
+    let gens = params.generators();
+    let com = match request.commitment {
+        Some(com) => {
+            let com = parse_element(params, "commitment", com)?;
+            if !commitment::open(&gens, &com, &opening, &params.p) {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "Коммитмент не раскрывается указанными значениями".to_string(),
+                ));
+            }
+            com
+        }
+        None => commitment::commit(&gens, &opening.value, &opening.blinding, &params.p)
+            .ok_or_else(internal)?,
+    };
+
+    //Раскрытие (value, blinding) - это секрет Окамото для u = C
+    let prover = Prover::new(params.clone(), Key::new(opening.value, opening.blinding))
+        .ok_or_else(internal)?;
+    let verifier = Verifier::new(params.clone());
+    let commitment = prover.commit().ok_or_else(internal)?;
+    let c = verifier.challenge();
+    let proof = prover.respond(commitment, &c);
+    let valid = proof.u == com && verifier.verify(&proof);
+    info!("Доказательство знания раскрытия коммитмента: {}", valid);
+
+    Ok(Json(OpeningReport {
+        commitment: com.into(),
+        ut: proof.ut.into(),
+        challenge: proof.c.into(),
+        alpha: proof.alpha.into(),
+        beta: proof.beta.into(),
+        valid,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct AggregateRequest {
+    proofs: Vec<Proof>,
+}
+
+#[derive(Debug, Serialize)]
+struct AggregateReport {
+    count: usize,
+    valid: bool,
+    invalid: Vec<usize>,
+}
+
+//Агрегированная проверка - та же пакетная проверка, что и /verify_batch
+async fn verify_aggregate_handler(
+    state: State<AppState>,
+    request: Json<AggregateRequest>,
+) -> Result<Json<AggregateReport>, (StatusCode, String)> {
+    verify_batch_handler(state, request).await
+}
+
+//Пакетная проверка случайной линейной комбинацией; неверные доказательства ищутся делением пополам
+async fn verify_batch_handler(
+    State(state): State<AppState>,
+    Json(request): Json<AggregateRequest>,
+) -> Result<Json<AggregateReport>, (StatusCode, String)> {
+    match state.group {
+        GroupKind::Modular | GroupKind::Modp2048 | GroupKind::Modp3072 | GroupKind::Modp4096 => {}
+        GroupKind::Ristretto255 | GroupKind::Secp256k1 => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Пакетная проверка только в группе вычетов".to_string(),
+            ));
+        }
+    }
+    let verifier = state.verifier(state.setting.clone());
+    let valid = verifier.verify_batch(&request.proofs);
+    info!(
+        "Пакетная проверка {} доказательств: {}",
+        request.proofs.len(),
+        valid
+    );
+    let invalid = if valid {
+        Vec::new()
+    } else {
+        verifier.find_invalid(&request.proofs)
+    };
+    Ok(Json(AggregateReport {
+        count: request.proofs.len(),
+        valid,
+        invalid,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+struct VerifyReport {
+    valid: bool,
+}
+
+//Неинтерактивное доказательство для POST /verify; без c испытание
+//вычисляется из стенограммы
+#[derive(Debug, Deserialize)]
+struct VerifyRequest {
+    u: HexBig,
+    ut: HexBig,
+    c: Option<HexBig>,
+    alpha: HexBig,
+    #[serde(default)]
+    beta: HexBig,
+    //Отпечаток параметров из /params; без него подразумеваются параметры сервера
+    params: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct VerifyResult {
+    valid: bool,
+    group: GroupKind,
+    protocol: ProofKind,
+    params: String,
+    c: HexBig,
+    checks: Verification,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'static str>,
+}
+
+//Проверка неинтерактивного доказательства с отчетом по шагам
+async fn verify_handler(
+    State(state): State<AppState>,
+    Json(request): Json<VerifyRequest>,
+) -> Result<Json<VerifyResult>, (StatusCode, String)> {
+    let result = with_setting!(state.group, state.setting.clone(), |setting| {
+        verify_proof(&setting, &state, request)
+    })?;
+    info!("Проверка доказательства по запросу: {}", result.valid);
+    Ok(Json(result))
+}
+
+fn verify_proof<G: Group>(
+    setting: &Setting<G>,
+    state: &AppState,
+    request: VerifyRequest,
+) -> Result<VerifyResult, (StatusCode, String)> {
+    let fingerprint = fiat_shamir::params_fingerprint(setting);
+    if let Some(params) = &request.params
+        && !params.eq_ignore_ascii_case(&fingerprint)
+    {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!(
+                "Доказательство построено для других параметров: {}, у сервера {}",
+                params, fingerprint
+            ),
+        ));
+    }
+    let verifier = state.verifier(setting.clone());
+    let (u, ut) = (request.u.into(), request.ut.into());
+    let c = match request.c {
+        Some(c) => c.into(),
+        None => fiat_shamir::challenge_with(state.config.get_hash(), setting, &u, &ut),
+    };
+    let proof = Proof {
+        u,
+        ut,
+        c,
+        alpha: request.alpha.into(),
+        beta: request.beta.into(),
+    };
+    let checks = verifier.inspect_non_interactive(&proof);
+    Ok(VerifyResult {
+        valid: checks.valid(),
+        group: state.group,
+        protocol: verifier.kind(),
+        params: fingerprint,
+        c: proof.c.into(),
+        checks,
+        reason: checks.failure(),
+    })
+}
+
+//Проверка неинтерактивного доказательства, полученного заранее
+async fn verify_fiat_shamir_handler(
+    State(state): State<AppState>,
+    Json(proof): Json<Proof>,
+) -> Json<VerifyReport> {
+    let valid = with_setting!(state.group, state.setting.clone(), |setting| {
+        state.verifier(setting).verify_non_interactive(&proof)
+    });
+    info!("Проверка неинтерактивного доказательства: {}", valid);
+    Json(VerifyReport { valid })
+}
+
+#[derive(Debug, Deserialize)]
+struct Bip340Request {
+    public_key: String,
+    message: String,
+    signature: String,
+}
+
+//Проверка подписи BIP-340 (например, из кошелька Bitcoin)
+async fn verify_bip340_handler(
+    Json(request): Json<Bip340Request>,
+) -> Result<Json<VerifyReport>, (StatusCode, String)> {
+    let bad = |e: String| (StatusCode::BAD_REQUEST, e);
+    let public_key = encoding::from_hex_array(&request.public_key).map_err(bad)?;
+    let message = encoding::from_hex(&request.message).map_err(bad)?;
+    let signature = encoding::from_hex_array(&request.signature).map_err(bad)?;
+    let valid = bip340::verify(&public_key, &message, &signature);
+    info!("Проверка подписи BIP-340: {}", valid);
+    Ok(Json(VerifyReport { valid }))
+}
+
+#[derive(Debug, Deserialize)]
+struct RingRequest {
+    message: String,
+    signature: RingSignature,
+}
+
+//Проверка кольцевой подписи в группе сервера
+async fn verify_ring_handler(
+    State(state): State<AppState>,
+    Json(request): Json<RingRequest>,
+) -> Json<VerifyReport> {
+    let message = request.message.as_bytes();
+    let valid = with_setting!(state.group, state.setting.clone(), |setting| {
+        ring::verify(&setting, message, &request.signature)
+    });
+    info!("Проверка кольцевой подписи: {}", valid);
+    Json(VerifyReport { valid })
+}
+
+#[derive(Debug, Deserialize)]
+struct VrfRequest {
+    input: String,
+}
+
+//Выход VRF вместе с ключом и доказательством; тот же вид принимает проверка
+#[derive(Debug, Serialize, Deserialize)]
+struct VrfEvaluation {
+    #[serde(with = "decimal")]
+    public_key: BigUint,
+    input: String,
+    #[serde(with = "decimal")]
+    output: BigUint,
+    proof: VrfProof,
+}
+
+fn vrf_evaluate<G: Group>(setting: &Setting<G>, x: &BigUint, input: String) -> VrfEvaluation {
+    let group = &setting.group;
+    let (output, proof) = vrf::prove(setting, x, input.as_bytes());
+    VrfEvaluation {
+        public_key: group.encode(&group.exp(&setting.g, x)),
+        input,
+        output,
+        proof,
+    }
+}
+
+//Выход VRF сервера для входа; ключ живет, пока работает сервер
+async fn vrf_handler(
+    State(state): State<AppState>,
+    Json(request): Json<VrfRequest>,
+) -> Json<VrfEvaluation> {
+    let (x, input) = (&state.vrf_key, request.input);
+    let evaluation = with_setting!(state.group, state.setting.clone(), |setting| {
+        vrf_evaluate(&setting, x, input)
+    });
+    Json(evaluation)
+}
+
+//Проверка выхода VRF по открытому ключу
+async fn verify_vrf_handler(
+    State(state): State<AppState>,
+    Json(request): Json<VrfEvaluation>,
+) -> Json<VerifyReport> {
+    let (y, input, output, proof) = (
+        &request.public_key,
+        request.input.as_bytes(),
+        &request.output,
+        &request.proof,
+    );
+    let valid = with_setting!(state.group, state.setting.clone(), |setting| {
+        vrf::verify(&setting, y, input, output, proof)
+    });
+    info!("Проверка выхода VRF: {}", valid);
+    Json(VerifyReport { valid })
+}
+
+#[derive(Debug, Serialize)]
+struct ConsensusReport {
+    valid: bool,
+    quorum: usize,
+    verdicts: BTreeMap<String, Option<bool>>,
+}
+
+//Доказательство рассылается проверяющим из конфигурации и принимается
+//кворумом; решения проверяющих попадают в журнал и стенограмму
+async fn verify_consensus_handler(
+    State(state): State<AppState>,
+    Json(proof): Json<Proof>,
+) -> Result<Json<ConsensusReport>, (StatusCode, String)> {
+    let verifiers = state.config.get_verifiers();
+    if verifiers.is_empty() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "В конфигурации не заданы проверяющие".to_string(),
+        ));
+    }
+    let events = &state.events;
+    let announcement = with_setting!(state.group, state.setting.clone(), |setting| {
+        announce(&setting)
+    });
+    events.publish(announcement);
+    events.publish(ProtocolEvent::Commitment {
+        u: proof.u.clone(),
+        ut: proof.ut.clone(),
+    });
+    events.publish(ProtocolEvent::Challenge { c: proof.c.clone() });
+    events.publish(ProtocolEvent::Response {
+        alpha: proof.alpha.clone(),
+        beta: proof.beta.clone(),
+    });
+
+    let decision = consensus::verify(
+        &reqwest::Client::new(),
+        verifiers,
+        state.config.get_quorum(),
+        &proof,
+    )
+    .await;
+    for vote in &decision.votes {
+        events.publish(ProtocolEvent::VerifierVerdict {
+            verifier: vote.verifier.clone(),
+            accepted: vote.accepted,
+        });
+    }
+    let approvals = decision
+        .votes
+        .iter()
+        .filter(|vote| vote.accepted == Some(true))
+        .count();
+    events.publish(ProtocolEvent::Consensus {
+        approvals,
+        verifiers: decision.votes.len(),
+        quorum: decision.quorum,
+        accepted: decision.accepted,
+    });
+    info!(
+        "Проверка кворумом: {} из {}, итог {}",
+        approvals,
+        decision.votes.len(),
+        decision.accepted
+    );
+    Ok(Json(ConsensusReport {
+        valid: decision.accepted,
+        quorum: decision.quorum,
+        verdicts: decision
+            .votes
+            .into_iter()
+            .map(|vote| (vote.verifier, vote.accepted))
+            .collect(),
+    }))
+}
+
+//Статистика задержек шагов по размерам параметров (бит модуля)
+async fn stats_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<BTreeMap<u64, SizeReport>>, (StatusCode, String)> {
+    authorize(&state, &headers)?;
+    Ok(Json(state.stats.lock().unwrap().report()))
+}
+
+//Служебные эндпоинты открыты только администраторам из конфигурации
+//по токену, полученному входом с доказательством знания ключа
+fn authorize(state: &AppState, headers: &HeaderMap) -> Result<String, (StatusCode, String)> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or((StatusCode::UNAUTHORIZED, "Нужен токен доступа".to_string()))?;
+    let mut registry = state.auth.lock().unwrap();
+    let username = registry.authorize(token).ok_or((
+        StatusCode::UNAUTHORIZED,
+        "Токен недействителен или просрочен".to_string(),
+    ))?;
+    match registry.is_admin(&username) {
+        true => Ok(username),
+        false => Err((
+            StatusCode::FORBIDDEN,
+            format!("Пользователь {} не администратор", username),
+        )),
+    }
+}
+
+//Регистрация открытого ключа u = g^x пользователя администратором
+async fn auth_register_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<auth::RegisterRequest>,
+) -> Result<Json<()>, (StatusCode, String)> {
+    let admin = authorize(&state, &headers)?;
+    let mut registry = state.auth.lock().unwrap();
+    let registered = with_setting!(state.group, state.setting.clone(), |setting| {
+        registry.register(&setting, &request)
+    });
+    if registered.is_ok() {
+        info!(
+            "Администратор {} зарегистрировал пользователя {}",
+            admin, request.username
+        );
+    }
+    registered.map(Json).map_err(|e| (StatusCode::CONFLICT, e))
+}
+
+//Вход: коммитмент пользователя и испытание сервера
+async fn auth_login_handler(
+    State(state): State<AppState>,
+    Json(request): Json<auth::LoginRequest>,
+) -> Result<Json<auth::LoginChallenge>, (StatusCode, String)> {
+    fn login<G: Group>(
+        state: &AppState,
+        setting: Setting<G>,
+        request: &auth::LoginRequest,
+    ) -> Result<auth::LoginChallenge, String> {
+        let c = Schnorr::in_setting(setting.clone()).challenge();
+        state.auth.lock().unwrap().login(&setting, request, c)
+    }
+    let challenge = with_setting!(state.group, state.setting.clone(), |setting| {
+        login(&state, setting, &request)
+    });
+    challenge
+        .map(Json)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, e))
+}
+
+//Вход: ответ пользователя; токен выдается только при принятом доказательстве
+async fn auth_respond_handler(
+    State(state): State<AppState>,
+    Json(response): Json<auth::LoginResponse>,
+) -> Result<Json<auth::AccessToken>, (StatusCode, String)> {
+    let mut registry = state.auth.lock().unwrap();
+    let token = with_setting!(state.group, state.setting.clone(), |setting| {
+        registry.respond(&setting, &response)
+    });
+    match &token {
+        Ok(_) => info!("Вход выполнен, выдан токен доступа"),
+        Err(e) => warn!("{}", e),
+    }
+    token.map(Json).map_err(|e| (StatusCode::UNAUTHORIZED, e))
+}
+
+//Участник порогового доказывающего: коммитмент своей доли
+async fn threshold_commit_handler(
+    State(state): State<AppState>,
+    Json(request): Json<CommitRequest>,
+) -> Result<Json<PartyCommitment>, (StatusCode, String)> {
+    let party = Participant::Local(state.party.clone().ok_or((
+        StatusCode::NOT_FOUND,
+        "У сервера нет доли секрета".to_string(),
+    ))?);
+    let commitment = with_setting!(state.group, state.setting.clone(), |setting| {
+        party.commit(&setting, &request).await
+    });
+    commitment
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+//Ответ доли на испытание координатора
+async fn threshold_respond_handler(
+    State(state): State<AppState>,
+    Json(request): Json<RespondRequest>,
+) -> Result<Json<PartyResponse>, (StatusCode, String)> {
+    let party = Participant::Local(state.party.clone().ok_or((
+        StatusCode::NOT_FOUND,
+        "У сервера нет доли секрета".to_string(),
+    ))?);
+    let order = with_setting!(state.group, state.setting.clone(), |setting| {
+        setting.order()
+    });
+    party
+        .respond(&request, &order)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+//Отвечающая сторона обмена ключами: проверка доказательства и свой ответ
+async fn handshake_handler(
+    State(state): State<AppState>,
+    Json(hello): Json<Hello>,
+) -> Result<Json<Hello>, (StatusCode, String)> {
+    let x = &state.static_key;
+    let responded = with_setting!(state.group, state.setting.clone(), |setting| {
+        handshake::respond(&setting, x, &hello)
+    });
+    let events = &state.events;
+    events.publish(ProtocolEvent::HandshakeReceived {
+        static_key: hello.static_key,
+        ephemeral: hello.ephemeral,
+        valid: responded.is_ok(),
+    });
+    let (reply, key) = responded.map_err(|e| {
+        warn!("{}", e);
+        (StatusCode::FORBIDDEN, e)
+    })?;
+    events.publish(ProtocolEvent::HandshakeSent {
+        static_key: reply.static_key.clone(),
+        ephemeral: reply.ephemeral.clone(),
+    });
+    events.publish(ProtocolEvent::HandshakeEstablished {
+        fingerprint: key.fingerprint(),
+    });
+    state.peer.set_session_key(key);
+    Ok(Json(reply))
+}
+
+//Участник генерации ключа: коммитменты своего многочлена
+async fn dkg_commit_handler(
+    State(state): State<AppState>,
+    Json(request): Json<dkg::CommitRequest>,
+) -> Result<Json<dkg::PartyCommitments>, (StatusCode, String)> {
+    let party = dkg::Participant::Local(state.dkg.clone());
+    let commitments = with_setting!(state.group, state.setting.clone(), |setting| {
+        party.commit(&setting, &request).await
+    });
+    commitments
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+//Участник генерации ключа: коммитменты всех участников
+async fn dkg_commitments_handler(
+    State(state): State<AppState>,
+    Json(request): Json<dkg::CommitmentsRequest>,
+) -> Result<Json<()>, (StatusCode, String)> {
+    let mut party = state.dkg.lock().unwrap();
+    let accepted = with_setting!(state.group, state.setting.clone(), |setting| {
+        party.accept_commitments(&setting, &request)
+    });
+    accepted.map(Json).map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+//Участник генерации ключа: рассылка долей остальным серверам
+async fn dkg_distribute_handler(
+    State(state): State<AppState>,
+    Json(request): Json<dkg::DistributeRequest>,
+) -> Result<Json<()>, (StatusCode, String)> {
+    let order = with_setting!(state.group, state.setting.clone(), |setting| {
+        setting.order()
+    });
+    dkg::distribute(&state.dkg, &order, &request)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+//Участник генерации ключа: доля от другого участника
+async fn dkg_share_handler(
+    State(state): State<AppState>,
+    Json(request): Json<dkg::ShareRequest>,
+) -> Result<Json<()>, (StatusCode, String)> {
+    let received = {
+        let mut party = state.dkg.lock().unwrap();
+        with_setting!(state.group, state.setting.clone(), |setting| {
+            party.receive(&setting, &request)
+        })
+    };
+    state.events.publish(ProtocolEvent::DkgShareReceived {
+        from: request.from,
+        to: request.share.index,
+        valid: received.is_ok(),
+    });
+    received.map(Json).map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+//Участник генерации ключа: итоговая доля и общий ключ
+async fn dkg_finish_handler(
+    State(state): State<AppState>,
+    Json(request): Json<dkg::FinishRequest>,
+) -> Result<Json<dkg::DkgResult>, (StatusCode, String)> {
+    let mut party = state.dkg.lock().unwrap();
+    let result = with_setting!(state.group, state.setting.clone(), |setting| {
+        party.finish(&setting, &request)
+    });
+    result.map(Json).map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+//Участник MuSig: хэш одноразового R_i
+async fn musig_commit_handler(
+    State(state): State<AppState>,
+    Json(request): Json<musig::CommitRequest>,
+) -> Result<Json<musig::NonceCommitment>, (StatusCode, String)> {
+    let signer = musig::Participant::Local(state.cosigner.clone());
+    let commitment = with_setting!(state.group, state.setting.clone(), |setting| {
+        signer.commit(&setting, &request).await
+    });
+    commitment
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+//Участник MuSig: R_i после получения хэшей всех участников
+async fn musig_reveal_handler(
+    State(state): State<AppState>,
+    Json(request): Json<musig::RevealRequest>,
+) -> Result<Json<musig::NonceReveal>, (StatusCode, String)> {
+    let mut signer = state.cosigner.lock().unwrap();
+    let reveal = with_setting!(state.group, state.setting.clone(), |setting| {
+        signer.reveal(&setting, &request)
+    });
+    reveal.map(Json).map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+//Участник MuSig: частичная подпись, сессия закрывается
+async fn musig_sign_handler(
+    State(state): State<AppState>,
+    Json(request): Json<musig::SignRequest>,
+) -> Result<Json<musig::PartialSignature>, (StatusCode, String)> {
+    let mut signer = state.cosigner.lock().unwrap();
+    let partial = with_setting!(state.group, state.setting.clone(), |setting| {
+        signer.sign(&setting, &request)
+    });
+    partial.map(Json).map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+//Слепая подпись: R = g^k для новой сессии
+async fn blind_commit_handler(
+    State(state): State<AppState>,
+    Json(request): Json<BlindCommitRequest>,
+) -> Result<Json<BlindCommitment>, (StatusCode, String)> {
+    let commitment = with_setting!(state.group, state.setting.clone(), |setting| {
+        blind_commit(&setting, &state, &request)
+    });
+    commitment
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+fn blind_commit<G: Group>(
+    setting: &Setting<G>,
+    state: &AppState,
+    request: &BlindCommitRequest,
+) -> Result<BlindCommitment, String> {
+    let k = setting.group.random_scalar();
+    let commitment = state.signer.lock().unwrap().commit(setting, request, k)?;
+    state.events.publish(ProtocolEvent::BlindCommitment {
+        session: request.session.clone(),
+        r: commitment.r.clone(),
+    });
+    Ok(commitment)
+}
+
+//Слепая подпись: ответ на ослепленное испытание, сессия закрывается
+async fn blind_sign_handler(
+    State(state): State<AppState>,
+    Json(request): Json<BlindSignRequest>,
+) -> Result<Json<BlindResponse>, (StatusCode, String)> {
+    let order = with_setting!(state.group, state.setting.clone(), |setting| {
+        setting.order()
+    });
+    blind_sign(&state, &request, &order)
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
+fn blind_sign(
+    state: &AppState,
+    request: &BlindSignRequest,
+    order: &BigUint,
+) -> Result<BlindResponse, String> {
+    let events = &state.events;
+    events.publish(ProtocolEvent::BlindChallenge {
+        session: request.session.clone(),
+        c: request.c.clone(),
+    });
+    let response = state.signer.lock().unwrap().sign(request, order)?;
+    events.publish(ProtocolEvent::BlindResponse {
+        session: request.session.clone(),
+        s: response.s.clone(),
+    });
+    Ok(response)
+}
+
+#[derive(Debug, Deserialize)]
+struct BlindVerifyRequest {
+    #[serde(with = "decimal")]
+    public_key: BigUint,
+    message: String,
+    signature: BlindSignature,
+}
+
+//Проверка снятой с ослепления подписи
+async fn verify_blind_handler(
+    State(state): State<AppState>,
+    Json(request): Json<BlindVerifyRequest>,
+) -> Json<VerifyReport> {
+    let (y, message, signature) = (
+        &request.public_key,
+        request.message.as_bytes(),
+        &request.signature,
+    );
+    let valid = with_setting!(state.group, state.setting.clone(), |setting| {
+        blind::verify(&setting, y, message, signature)
+    });
+    info!("Проверка слепой подписи: {}", valid);
+    Json(VerifyReport { valid })
+}
+
+//Итоги последней серии попыток обмана
+async fn forgery_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ForgeryStats>, (StatusCode, String)> {
+    authorize(&state, &headers)?;
+    Ok(Json(state.forgery.lock().unwrap().clone()))
+}
+
+//Стенограмма последнего доказательства
+async fn transcript_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Transcript>, (StatusCode, String)> {
+    authorize(&state, &headers)?;
+    Ok(Json(state.transcript.lock().unwrap().clone()))
+}
+
+//Параметры группы; h_derived позволяет убедиться, что h получена из g
+//хэшированием в группу с доменом h_domain и log_g(h) никому не известен
+#[derive(Debug, Serialize)]
+struct ParamsReport {
+    group: GroupKind,
+    modulus: String,
+    order: String,
+    g: String,
+    h: String,
+    h_domain: &'static str,
+    h_derived: bool,
+    //Ссылка на эти параметры в POST /verify
+    fingerprint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    certificate: Option<PrimeCertificate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    primality: Option<Primality>,
+    strength: Strength,
+}
+
+fn params_report<G: Group>(
+    kind: GroupKind,
+    setting: &Setting<G>,
+    strength: Strength,
+) -> ParamsReport {
+    let group = &setting.group;
+    ParamsReport {
+        group: kind,
+        modulus: group.modulus().to_string(),
+        order: group.order().to_string(),
+        g: group.encode(&setting.g).to_string(),
+        h: group.encode(&setting.h).to_string(),
+        h_domain: group::H_DOMAIN,
+        h_derived: setting.h_is_derived(),
+        fingerprint: fiat_shamir::params_fingerprint(setting),
+        certificate: None,
+        primality: None,
+        strength,
+    }
+}
+
+async fn params_handler(State(state): State<AppState>) -> Json<ParamsReport> {
+    Json(match state.group {
+        GroupKind::Modular | GroupKind::Modp2048 | GroupKind::Modp3072 | GroupKind::Modp4096 => {
+            ParamsReport {
+                certificate: state.certificate.clone(),
+                primality: state.primality,
+                ..params_report(state.group, &state.setting, state.strength.clone())
+            }
+        }
+        GroupKind::Ristretto255 => {
+            params_report(state.group, &ristretto::setting(), state.strength.clone())
+        }
+        GroupKind::Secp256k1 => {
+            params_report(state.group, &secp256k1::setting(), state.strength.clone())
+        }
+    })
+}
+
+//Объявление открытых параметров группы
+fn announce<G: Group>(setting: &Setting<G>) -> ProtocolEvent {
+    let group = &setting.group;
+    ProtocolEvent::ParamsAnnounced {
+        p: group.modulus(),
+        q: group.order(),
+        g: group.encode(&setting.g),
+        h: group.encode(&setting.h),
+    }
+}
+
+async fn start_proof<G: Group>(setting: Setting<G>, appstate: AppState) -> Result<bool, String> {
+    info!("Начинаем проверку");
+    let group = &setting.group;
+    let events = &appstate.events;
+    let rounds = appstate.config.get_rounds();
+    let mut timeline = Timeline::new();
+
+    timeline.start_compute();
+    let prover = appstate.prover(setting.clone());
+    timeline.stop_compute();
+    info!("P Сгенерировал альфа и бета и вычислил публичный ключ");
+
+    events.publish(announce(&setting));
+    timeline.mark("params");
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let mut verdict = ProtocolMessage::Verdict { accepted: true };
+    for round in 1..=rounds {
+        if rounds > 1 {
+            events.publish(ProtocolEvent::Round { round, rounds });
+        }
+        let session = match proof_round(&appstate, &prover, round, &mut timeline).await {
+            Ok(session) => session,
+            Err(e) => {
+                abort(&appstate, e.clone()).await;
+                return Err(e);
+            }
+        };
+        send_proof(session.decision(), events).await;
+        timeline.mark("verdict");
+        verdict = session.verdict();
+        if !session.decision().accepted {
+            break;
+        }
+    }
+    events.publish(appstate.soundness(&setting));
+    appstate.record_timings(group.modulus().bits(), &timeline);
+    notify_peer(&appstate, &verdict).await;
+    Ok(matches!(
+        verdict,
+        ProtocolMessage::Verdict { accepted: true }
+    ))
+}
+
+//Один раунд: коммитмент, испытание, ответ и вычисления проверяющего
+async fn proof_round<G: Group>(
+    appstate: &AppState,
+    prover: &Prover<G>,
+    round: u32,
+    timeline: &mut Timeline,
+) -> Result<Session<Decided, G>, String> {
+    let events = &appstate.events;
+    let setting = prover.setting();
+    let session = appstate.verifier(setting.clone()).session();
+
+    let sealed = match appstate.config.get_challenge_commitment() {
+        true => Some(appstate.seal_challenge(setting).await?),
+        false => None,
+    };
+    timeline.start_compute();
+    let commitment = prover.commit().ok_or("Ошибка вычисления u_t")?;
+    timeline.stop_compute();
+    info!("P Сгенерировал альфа_t и бета_t");
+
+    events.publish(ProtocolEvent::Commitment {
+        u: commitment.u.clone(),
+        ut: commitment.ut.clone(),
+    });
+    timeline.mark("commitment");
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    if round == 1 {
+        events.publish(ProtocolEvent::SecretDisclosed {
+            alpha: prover.secret().alpha.clone(),
+            beta: prover.secret().beta.clone(),
+        });
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    events.publish(ProtocolEvent::NonceDisclosed {
+        alpha: commitment.nonce().alpha.clone(),
+        beta: commitment.nonce().beta.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    info!("P Вычислил u_t");
+    timeline.start_compute();
+    let session = session.receive_commitment(commitment.message())?;
+    timeline.stop_compute();
+    let c = match &sealed {
+        Some(sealed) => appstate.reveal_challenge(sealed).await?,
+        None => {
+            appstate
+                .get_challenge(setting, &commitment.u, &commitment.ut)
+                .await?
+        }
+    };
+    let (session, _) = session.issue_challenge(c.clone());
+    timeline.mark("challenge");
+
+    info!("P Получил испытание!");
+
+    timeline.start_compute();
+    let proof = prover.respond(commitment, &c);
+    timeline.stop_compute();
+    info!("P Вычислил альфа_z и бета_z");
+
+    events.publish(ProtocolEvent::Response {
+        alpha: proof.alpha.clone(),
+        beta: proof.beta.clone(),
+    });
+    timeline.mark("response");
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    timeline.start_compute();
+    let session = session.receive_response(proof.response())?;
+    timeline.stop_compute();
+    Ok(session)
+}
+
+//Симулятор выбирает c и ответ до коммитмента, поэтому обходится без
+//секрета; его шаги публикуются между шагами честного доказывающего
+async fn start_simulation<G: Group>(setting: Setting<G>, appstate: AppState) {
+    info!("Начинаем симуляцию");
+    let events = &appstate.events;
+    let prover = appstate.prover(setting.clone());
+    let verifier = appstate.verifier(setting.clone());
+
+    events.publish(announce(&setting));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let Some(commitment) = prover.commit() else {
+        return abort(&appstate, "Ошибка вычисления u_t".to_string()).await;
+    };
+    events.publish(ProtocolEvent::Commitment {
+        u: commitment.u.clone(),
+        ut: commitment.ut.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    //Симулятору доступен только публичный ключ
+    let simulated = verifier.simulate(prover.public_key());
+    events.publish(ProtocolEvent::SimulatorChoice {
+        c: simulated.c.clone(),
+        alpha: simulated.alpha.clone(),
+        beta: simulated.beta.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let session = match verifier.session().receive_commitment(commitment.message()) {
+        Ok(session) => session,
+        Err(e) => return abort(&appstate, e).await,
+    };
+    let c = match appstate
+        .get_challenge(&setting, &commitment.u, &commitment.ut)
+        .await
+    {
+        Ok(c) => c,
+        Err(e) => return abort(&appstate, e).await,
+    };
+    let (session, _) = session.issue_challenge(c.clone());
+
+    events.publish(ProtocolEvent::SimulatorCommitment {
+        ut: simulated.ut.clone(),
+        accepted: verifier.verify(&simulated),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let proof = prover.respond(commitment, &c);
+    events.publish(ProtocolEvent::Response {
+        alpha: proof.alpha.clone(),
+        beta: proof.beta.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let session = match session.receive_response(proof.response()) {
+        Ok(session) => session,
+        Err(e) => return abort(&appstate, e).await,
+    };
+    let decided = session.decision();
+    events.publish(ProtocolEvent::Verdict {
+        uz: decided.uz.clone(),
+        utuc: decided.utuc.clone(),
+        accepted: decided.accepted,
+    });
+}
+
+//Два ответа на один коммитмент: после первого ответа состояние
+//доказывающего восстанавливается из снимка
+async fn start_extraction<G: Group>(setting: Setting<G>, appstate: AppState) {
+    info!("Начинаем извлечение секрета");
+    let events = &appstate.events;
+    let prover = appstate.prover(setting.clone());
+    let verifier = appstate.verifier(setting.clone());
+
+    events.publish(announce(&setting));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let Some(commitment) = prover.commit() else {
+        return abort(&appstate, "Ошибка вычисления u_t".to_string()).await;
+    };
+    let snapshot = commitment.rewind_for_extraction();
+    events.publish(ProtocolEvent::Commitment {
+        u: commitment.u.clone(),
+        ut: commitment.ut.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let mut proofs = Vec::new();
+    let mut c = verifier.challenge();
+    for commitment in [commitment, snapshot] {
+        if !proofs.is_empty() {
+            events.publish(ProtocolEvent::Rewind);
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            c = match extractor::second_challenge(&verifier, &c) {
+                Ok(c) => c,
+                Err(e) => return abort(&appstate, e).await,
+            };
+        }
+        events.publish(ProtocolEvent::Challenge { c: c.clone() });
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let proof = prover.respond(commitment, &c);
+        events.publish(ProtocolEvent::Response {
+            alpha: proof.alpha.clone(),
+            beta: proof.beta.clone(),
+        });
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        proofs.push(proof);
+    }
+
+    match extractor::extract(&setting.order(), &proofs[0], &proofs[1]) {
+        Ok(witness) => events.publish(ProtocolEvent::Extracted {
+            matches: &witness == prover.secret(),
+            alpha: witness.alpha,
+            beta: witness.beta,
+        }),
+        Err(e) => abort(&appstate, e).await,
+    }
+}
+
+//Обманщик знает только публичный ключ; промежуточные итоги
+//публикуются десять раз за серию
+async fn start_attack(setting: Setting<ModGroup>, appstate: AppState) {
+    info!("Начинаем атаку на параметры");
+    let events = &appstate.events;
+    let prover = appstate.prover(setting.clone());
+
+    events.publish(announce(&setting));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let n = setting.group.modulus() - 1u32;
+    let Some(factors) = attack::factorize(&n) else {
+        return abort(&appstate, format!("Не удалось разложить {}", n)).await;
+    };
+    events.publish(ProtocolEvent::Factored { n, factors });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let Some(key) = attack::recover_key(&setting, prover.public_key()) else {
+        events.publish(ProtocolEvent::AttackInfeasible {
+            bits: setting.order().bits(),
+        });
+        return;
+    };
+    publish_forged(setting, &prover, key, &appstate);
+}
+
+//Обманщик доказывает знание найденного секрета для чужого открытого ключа
+fn publish_forged(
+    setting: Setting<ModGroup>,
+    prover: &Prover<ModGroup>,
+    key: Key,
+    appstate: &AppState,
+) {
+    let verifier = appstate.verifier(setting.clone());
+    let forged = Prover::in_setting(setting, prover.kind(), key.clone())
+        .with_hash(appstate.config.get_hash());
+    let accepted = match forged.prove_non_interactive() {
+        Some(proof) => {
+            forged.public_key() == prover.public_key() && verifier.verify_non_interactive(&proof)
+        }
+        None => false,
+    };
+    appstate.events.publish(ProtocolEvent::Attacked {
+        alpha: key.alpha,
+        beta: key.beta,
+        accepted,
+    });
+}
+
+async fn start_bsgs(setting: Setting<ModGroup>, appstate: AppState) {
+    info!("Начинаем атаку BSGS");
+    let events = appstate.events.clone();
+    let prover = appstate.prover(setting.clone());
+
+    events.publish(announce(&setting));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let order = setting.order();
+    if order.bits() > attack::MAX_BSGS_BITS {
+        events.publish(ProtocolEvent::AttackInfeasible { bits: order.bits() });
+        return;
+    }
+    //Таблица и шаги великана считаются вне потоков рантайма
+    let (g, u, p) = (
+        setting.g.clone(),
+        prover.public_key().clone(),
+        setting.group.modulus(),
+    );
+    let found = tokio::task::spawn_blocking(move || {
+        math::bsgs(&g, &u, &p, &order, |step, steps| {
+            if step % steps.div_ceil(10) == 0 || step == steps {
+                events.publish(ProtocolEvent::BsgsProgress { step, steps });
+            }
+        })
+    })
+    .await;
+    match found {
+        Ok(Some(alpha)) => {
+            publish_forged(setting, &prover, Key::new(alpha, BigUint::ZERO), &appstate)
+        }
+        Ok(None) => abort(&appstate, "Логарифм u не найден".to_string()).await,
+        Err(e) => abort(&appstate, e.to_string()).await,
+    }
+}
+
+//Отчет о поиске простого каждые PRIME_PROGRESS_WINDOWS окон: событие и строка журнала
+fn prime_progress(events: &EventBus, bits: u64) -> impl Fn(SearchProgress) + Sync + '_ {
+    move |progress| {
+        if progress.windows % PRIME_PROGRESS_WINDOWS != 0 {
+            return;
+        }
+        info!(
+            "Поиск простого: {} окон решета, {} кандидатов проверено",
+            progress.windows, progress.tested
+        );
+        events.publish(ProtocolEvent::PrimeProgress {
+            bits,
+            windows: progress.windows,
+            tested: progress.tested,
+            mr_rounds: progress.mr_rounds,
+            expected: key_gen::expected_safe_prime_tests(bits),
+            elapsed_ms: progress.elapsed.as_millis() as u64,
+        });
+    }
+}
+
+async fn start_primes(bits: u64, appstate: AppState) {
+    //Параметры нужной длины из пула выдаются сразу
+    let pooled = appstate
+        .pool
+        .as_ref()
+        .filter(|pool| pool.bits() == bits)
+        .and_then(|pool| pool.take());
+    let generated = match pooled {
+        Some(params) => {
+            info!("Параметры длиной {} бит взяты из пула", bits);
+            Ok(params)
+        }
+        None => {
+            info!("Начинаем генерацию параметров длиной {} бит", bits);
+            let events = appstate.events.clone();
+            let rounds = appstate.config.get_mr_rounds();
+            let limits = SearchLimits {
+                deadline: appstate.config.get_prime_timeout(),
+                stop: None,
+            };
+            //Поиск занимает все ядра, поэтому идет вне потоков рантайма
+            tokio::task::spawn_blocking(move || {
+                GroupParams::generate_limited(bits, rounds, limits, prime_progress(&events, bits))
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|generated| generated.map_err(|e| e.to_string()))
+        }
+    };
+    match generated {
+        Ok(params) => appstate.events.publish(ProtocolEvent::ParamsAnnounced {
+            p: params.p,
+            q: params.q,
+            g: params.g,
+            h: params.h,
+        }),
+        Err(e) => abort(&appstate, e).await,
+    }
+}
+
+async fn start_forgery<G: Group>(setting: Setting<G>, attempts: u64, appstate: AppState) {
+    info!("Начинаем попытки обмана");
+    let events = &appstate.events;
+    let verifier = appstate.verifier(setting.clone());
+    let u = appstate.prover(setting.clone()).public_key().clone();
+    let mut stats = ForgeryStats::new(verifier.challenge_space().clone());
+
+    events.publish(announce(&setting));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let step = attempts.div_ceil(10);
+    for i in 1..=attempts {
+        stats.record(forgery::attempt(&verifier, &u));
+        if i % step == 0 || i == attempts {
+            events.publish(ProtocolEvent::Forgery {
+                attempts: stats.attempts,
+                accepted: stats.accepted,
+                rate: stats.rate,
+                expected: stats.expected,
+            });
+            *appstate.forgery.lock().unwrap() = stats.clone();
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    }
+}
+
+async fn start_dleq(appstate: AppState) {
+    info!("Начинаем проверку DLEQ");
+    let params = &appstate.params;
+    let events = &appstate.events;
+    let verifier = DleqVerifier::new(params.clone());
+    let mut timeline = Timeline::new();
+
+    timeline.start_compute();
+    let prover = match DleqProver::random(params.clone()) {
+        Ok(prover) => prover,
+        Err(e) => return abort(&appstate, format!("Ошибка вычисления u1, u2: {}", e)).await,
+    };
+    let Some((r, commitment)) = prover.commit() else {
+        return abort(&appstate, "Ошибка вычисления t1, t2".to_string()).await;
+    };
+    timeline.stop_compute();
+
+    events.publish(ProtocolEvent::ParamsAnnounced {
+        p: params.p.clone(),
+        q: params.q.clone(),
+        g: params.g.clone(),
+        h: params.h.clone(),
+    });
+    timeline.mark("params");
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let (statement, com) = match DleqVerifier::receive_commitment(commitment) {
+        Ok(parts) => parts,
+        Err(e) => return abort(&appstate, e).await,
+    };
+    events.publish(ProtocolEvent::DleqCommitment {
+        u1: statement.u1.clone(),
+        u2: statement.u2.clone(),
+        t1: com.t1.clone(),
+        t2: com.t2.clone(),
+    });
+    timeline.mark("commitment");
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let c = verifier.challenge();
+    events.publish(ProtocolEvent::Challenge { c: c.clone() });
+    timeline.mark("challenge");
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    timeline.start_compute();
+    let response = prover.respond(r, &c);
+    timeline.stop_compute();
+    if let ProtocolMessage::DleqResponse { z } = &response {
+        events.publish(ProtocolEvent::DleqResponse { z: z.clone() });
+    }
+    timeline.mark("response");
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    timeline.start_compute();
+    let accepted = match verifier.verify(&statement, &com, &c, response) {
+        Ok(accepted) => accepted,
+        Err(e) => return abort(&appstate, e).await,
+    };
+    timeline.stop_compute();
+    events.publish(ProtocolEvent::DleqVerdict { accepted });
+    timeline.mark("verdict");
+    appstate.record_timings(params.p.bits(), &timeline);
+    notify_peer(&appstate, &ProtocolMessage::Verdict { accepted }).await;
+}
+
+//Раунды FFS с общими ключами; каждый раунд снижает вероятность обмана в 2^k раз
+async fn start_ffs(appstate: AppState) {
+    info!("Начинаем проверку FFS");
+    let events = &appstate.events;
+    let rounds = appstate.config.get_rounds();
+    let mut timeline = Timeline::new();
+
+    timeline.start_compute();
+    let protocol = Ffs::new(
+        FfsParams::generate(FFS_MODULUS_BITS, appstate.config.get_ffs_primes()),
+        FFS_SECRETS,
+    );
+    let prover = FfsProver::random(protocol.clone());
+    timeline.stop_compute();
+
+    events.publish(ProtocolEvent::FfsKeys {
+        n: protocol.params().n.clone(),
+        keys: prover.keys().to_vec(),
+    });
+    timeline.mark("params");
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let keys = prover.keys().to_vec();
+    let mut accepted = true;
+    for round in 1..=rounds {
+        if rounds > 1 {
+            events.publish(ProtocolEvent::Round { round, rounds });
+        }
+        timeline.start_compute();
+        let (r, x) = prover.commit();
+        timeline.stop_compute();
+        events.publish(ProtocolEvent::FfsCommitment { x: x.clone() });
+        timeline.mark("commitment");
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        let bits = protocol.challenge();
+        events.publish(ProtocolEvent::FfsChallenge { bits: bits.clone() });
+        timeline.mark("challenge");
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        timeline.start_compute();
+        let y = prover.respond(r, &bits);
+        timeline.stop_compute();
+        events.publish(ProtocolEvent::FfsResponse { y: y.clone() });
+        timeline.mark("response");
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        timeline.start_compute();
+        accepted = protocol.verify(&keys, &x, &bits, &y);
+        let (left, right) = protocol.sides(&keys, &x, &bits, &y);
+        timeline.stop_compute();
+        events.publish(ProtocolEvent::Verdict {
+            uz: left,
+            utuc: right,
+            accepted,
+        });
+        timeline.mark("verdict");
+        if !accepted {
+            break;
+        }
+    }
+    events.publish(ProtocolEvent::Soundness {
+        rounds,
+        bits: (FFS_SECRETS as u32 * rounds) as f64,
+    });
+    appstate.record_timings(protocol.params().n.bits(), &timeline);
+    notify_peer(&appstate, &ProtocolMessage::Verdict { accepted }).await;
+}
+
+//Виктор публикует ключ, Павел шифрует число и доказывает, что знает его
+async fn start_elgamal<G: Group>(setting: Setting<G>, appstate: AppState) {
+    info!("Начинаем шифрование Эль-Гамаля");
+    let group = &setting.group;
+    let events = &appstate.events;
+    let elgamal = ElGamal::in_setting(setting.clone());
+
+    events.publish(announce(&setting));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let (x, y) = elgamal.keygen();
+    events.publish(ProtocolEvent::ElGamalKey {
+        y: group.encode(&y),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let m = BigUint::from(rand::random::<u64>() % ELGAMAL_MAX_PLAINTEXT);
+    let (ciphertext, plaintext) = elgamal.encrypt(&y, &m);
+    events.publish(ProtocolEvent::ElGamalCiphertext {
+        c1: group.encode(&ciphertext.c1),
+        c2: group.encode(&ciphertext.c2),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let protocol = PlaintextKnowledge::new(elgamal.clone(), y);
+    let Some((nonce, t)) = protocol.commit(&ciphertext, &plaintext) else {
+        return abort(&appstate, "Ошибка вычисления коммитмента".to_string()).await;
+    };
+    events.publish(ProtocolEvent::ElGamalCommitment {
+        t1: group.encode(&t.c1),
+        t2: group.encode(&t.c2),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let c = protocol.challenge();
+    events.publish(ProtocolEvent::Challenge { c: c.clone() });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let z = protocol.respond(&plaintext, nonce, &c);
+    events.publish(ProtocolEvent::ElGamalResponse {
+        zm: z.zm.clone(),
+        zr: z.zr.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let accepted = protocol.verify(&ciphertext, &t, &c, &z);
+    events.publish(ProtocolEvent::ElGamalVerdict { accepted });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    match elgamal.decrypt_small(&x, &ciphertext, ELGAMAL_MAX_PLAINTEXT) {
+        Some(m) => events.publish(ProtocolEvent::ElGamalDecrypted { m }),
+        None => events.publish(ProtocolEvent::Error {
+            reason: "Открытый текст вне диапазона перебора".to_string(),
+        }),
+    }
+    notify_peer(&appstate, &ProtocolMessage::Verdict { accepted }).await;
+}
+
+//Сервер выдает удостоверение, Павел предъявляет его Виктору
+async fn start_credential<G: Group>(setting: Setting<G>, appstate: AppState) {
+    info!("Начинаем демонстрацию удостоверения");
+    let events = &appstate.events;
+
+    events.publish(announce(&setting));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let values: Vec<BigUint> = {
+        let mut rng = rand::thread_rng();
+        vec![
+            BigUint::from(rng.gen_range(CREDENTIAL_AGES)),
+            BigUint::from(643u32),
+            BigUint::from(rng.gen_range(100_000u32..1_000_000)),
+            BigUint::from(rng.gen_range(1u32..=3)),
+        ]
+    };
+    events.publish(ProtocolEvent::CredentialAttributes {
+        names: CREDENTIAL_ATTRIBUTES
+            .iter()
+            .map(|n| n.to_string())
+            .collect(),
+        values: values.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let issuer = Issuer::random(setting.clone(), CREDENTIAL_ATTRIBUTES.len());
+    let credential = match issuer.issue(values) {
+        Ok(credential) => credential,
+        Err(e) => return abort(&appstate, e).await,
+    };
+    events.publish(ProtocolEvent::CredentialIssued {
+        commitment: credential.commitment.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let predicate = SetPredicate {
+        index: CREDENTIAL_AGE,
+        values: CREDENTIAL_AGES.map(BigUint::from).collect(),
+    };
+    let y = issuer.public_key();
+    let presentation = match credential::present(
+        &setting,
+        &y,
+        &credential,
+        &[CREDENTIAL_DISCLOSED],
+        predicate,
+    ) {
+        Ok(presentation) => presentation,
+        Err(e) => return abort(&appstate, e).await,
+    };
+    events.publish(ProtocolEvent::CredentialPresented {
+        presentation: presentation.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let accepted = credential::verify(&setting, &y, CREDENTIAL_ATTRIBUTES.len(), &presentation);
+    events.publish(ProtocolEvent::CredentialVerdict { accepted });
+    notify_peer(&appstate, &ProtocolMessage::Verdict { accepted }).await;
+}
+
+//Этот сервер - инициатор; итог отправляется второму серверу уже зашифрованным
+async fn start_handshake<G: Group>(setting: Setting<G>, appstate: AppState) {
+    info!("Начинаем обмен ключами со вторым сервером");
+    let events = &appstate.events;
+
+    events.publish(announce(&setting));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let initiator = Initiator::start(setting, &appstate.static_key);
+    let hello = initiator.hello();
+    events.publish(ProtocolEvent::HandshakeSent {
+        static_key: hello.static_key.clone(),
+        ephemeral: hello.ephemeral.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let (reply, key) = match initiator.connect(appstate.peer.address()).await {
+        Ok(established) => established,
+        Err(e) => return abort(&appstate, e).await,
+    };
+    events.publish(ProtocolEvent::HandshakeReceived {
+        static_key: reply.static_key,
+        ephemeral: reply.ephemeral,
+        valid: true,
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    events.publish(ProtocolEvent::HandshakeEstablished {
+        fingerprint: key.fingerprint(),
+    });
+    appstate.peer.set_session_key(key);
+    notify_peer(&appstate, &ProtocolMessage::Verdict { accepted: true }).await;
+}
+
+//Этот сервер - участник 1 и координатор; доли идут напрямую между серверами
+async fn start_dkg<G: Group>(setting: Setting<G>, appstate: AppState) {
+    info!("Начинаем распределенную генерацию ключа");
+    let events = &appstate.events;
+
+    events.publish(announce(&setting));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let peers = appstate.config.get_peers();
+    let mut roster = vec![appstate.config.get_own_address().clone()];
+    roster.extend(peers.iter().cloned());
+    let mut participants = vec![dkg::Participant::Local(appstate.dkg.clone())];
+    participants.extend(peers.into_iter().map(dkg::Participant::remote));
+    let parties = participants.len();
+    let threshold = consensus::majority(parties);
+    let session = format!("{:032x}", rand::random::<u128>());
+    events.publish(ProtocolEvent::DkgStarted { parties, threshold });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let mut commitments = Vec::new();
+    for (i, participant) in participants.iter().enumerate() {
+        let request = dkg::CommitRequest {
+            session: session.clone(),
+            index: i as u32 + 1,
+            threshold,
+            parties,
+        };
+        match participant.commit(&setting, &request).await {
+            Ok(party) => {
+                events.publish(ProtocolEvent::DkgCommitments {
+                    index: party.index,
+                    commitments: party.commitments.clone(),
+                });
+                commitments.push(party);
+            }
+            Err(e) => return abort(&appstate, e).await,
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    let request = dkg::CommitmentsRequest {
+        session: session.clone(),
+        parties: commitments,
+    };
+    for participant in &participants {
+        if let Err(e) = participant.accept_commitments(&setting, &request).await {
+            return abort(&appstate, e).await;
+        }
+    }
+    let request = dkg::DistributeRequest {
+        session: session.clone(),
+        roster,
+    };
+    for participant in &participants {
+        if let Err(e) = participant.distribute(&setting.order(), &request).await {
+            return abort(&appstate, e).await;
+        }
+    }
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let request = dkg::FinishRequest { session };
+    let mut results = Vec::new();
+    for participant in &participants {
+        match participant.finish(&setting, &request).await {
+            Ok(result) => {
+                events.publish(ProtocolEvent::DkgShareKey {
+                    index: result.index,
+                    verification_key: result.verification_key.clone(),
+                });
+                results.push(result);
+            }
+            Err(e) => return abort(&appstate, e).await,
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    //Все получили один Y, и его дают ключи проверки первых t участников
+    let public_key = results[0].public_key.clone();
+    let keys: Vec<(u32, BigUint)> = results[..threshold]
+        .iter()
+        .map(|r| (r.index, r.verification_key.clone()))
+        .collect();
+    let combined = dkg::combine(&setting, &keys).map(|y| setting.group.encode(&y));
+    let accepted = results.iter().all(|r| r.public_key == public_key)
+        && combined.as_ref() == Some(&public_key);
+    events.publish(ProtocolEvent::DkgVerdict {
+        public_key,
+        accepted,
+    });
+    notify_peer(&appstate, &ProtocolMessage::Verdict { accepted }).await;
+}
+
+//Этот сервер координирует подпись; без других серверов в конфигурации
+//остальные участники работают в этом же процессе
+async fn start_musig<G: Group>(setting: Setting<G>, appstate: AppState) {
+    info!("Начинаем совместную подпись");
+    let events = &appstate.events;
+
+    events.publish(announce(&setting));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let mut participants = vec![musig::Participant::Local(appstate.cosigner.clone())];
+    let cosigners = appstate.config.get_cosigners();
+    participants.extend(cosigners.iter().cloned().map(musig::Participant::remote));
+    if cosigners.is_empty() {
+        for _ in 1..MUSIG_LOCAL_SIGNERS {
+            let signer = musig::Signer::random(&setting.order());
+            participants.push(musig::Participant::Local(Arc::new(Mutex::new(signer))));
+        }
+    }
+
+    let signing = match Coordinator::new(setting.clone(), participants)
+        .sign(MUSIG_MESSAGE)
+        .await
+    {
+        Ok(signing) => signing,
+        Err(e) => return abort(&appstate, e).await,
+    };
+    events.publish(ProtocolEvent::MuSigKeys {
+        keys: signing.keys,
+        aggregate: signing.aggregate.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    events.publish(ProtocolEvent::MuSigCommitments {
+        commitments: signing.commitments,
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    events.publish(ProtocolEvent::MuSigNonces {
+        nonces: signing.nonces,
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    events.publish(ProtocolEvent::MuSigPartials {
+        partials: signing.partials,
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    events.publish(ProtocolEvent::MuSigSignature {
+        message: MUSIG_MESSAGE.to_string(),
+        r: signing.signature.r.clone(),
+        s: signing.signature.s.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let accepted = musig::verify(
+        &setting,
+        &signing.aggregate,
+        MUSIG_MESSAGE.as_bytes(),
+        &signing.signature,
+    );
+    events.publish(ProtocolEvent::MuSigVerdict { accepted });
+    notify_peer(&appstate, &ProtocolMessage::Verdict { accepted }).await;
+}
+
+//Павел сворачивает векторы за log2(n) раундов, Виктор проверяет одно равенство
+async fn start_ipa<G: Group>(setting: Setting<G>, appstate: AppState) {
+    info!("Начинаем аргумент скалярного произведения");
+    let events = &appstate.events;
+
+    events.publish(announce(&setting));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let gens = ipa::Generators::derive(&setting, IPA_LENGTH);
+    let (mut a, mut b) = (Vec::new(), Vec::new());
+    for _ in 0..IPA_LENGTH {
+        a.push(setting.group.random_scalar());
+        b.push(setting.group.random_scalar());
+    }
+    let p = ipa::commit(&setting, &gens, &a, &b);
+    events.publish(ProtocolEvent::IpaStatement {
+        n: IPA_LENGTH,
+        p: setting.group.encode(&p),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let proof = match ipa::prove(&setting, &gens, &a, &b) {
+        Ok(proof) => proof,
+        Err(e) => return abort(&appstate, e).await,
+    };
+    for (round, (l, r)) in proof.l.iter().zip(&proof.r).enumerate() {
+        events.publish(ProtocolEvent::IpaRound {
+            round: round + 1,
+            l: l.clone(),
+            r: r.clone(),
+        });
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    events.publish(ProtocolEvent::IpaFinal {
+        a: proof.a.clone(),
+        b: proof.b.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let accepted = ipa::verify(&setting, &gens, &p, &proof);
+    events.publish(ProtocolEvent::IpaVerdict {
+        accepted,
+        size: proof.size(),
+        naive: 2 * IPA_LENGTH,
+    });
+    notify_peer(&appstate, &ProtocolMessage::Verdict { accepted }).await;
+}
+
+//Павел получает подпись Виктора на монету, которую Виктор не видит
+async fn start_blind<G: Group>(setting: Setting<G>, appstate: AppState) {
+    info!("Начинаем слепую подпись");
+    let events = &appstate.events;
+
+    events.publish(announce(&setting));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let session = format!("{:016x}", rand::random::<u64>());
+    let request = BlindCommitRequest {
+        session: session.clone(),
+    };
+    let commitment = match blind_commit(&setting, &appstate, &request) {
+        Ok(commitment) => commitment,
+        Err(e) => return abort(&appstate, e).await,
+    };
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let message = BLIND_MESSAGE.as_bytes();
+    let blinding = match blind::blind(&setting, &commitment, message) {
+        Ok(blinding) => blinding,
+        Err(e) => return abort(&appstate, e).await,
+    };
+    let request = BlindSignRequest {
+        session,
+        c: blinding.challenge().clone(),
+    };
+    let response = match blind_sign(&appstate, &request, &setting.order()) {
+        Ok(response) => response,
+        Err(e) => return abort(&appstate, e).await,
+    };
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let signature = match blind::unblind(&setting, &blinding, &response) {
+        Ok(signature) => signature,
+        Err(e) => return abort(&appstate, e).await,
+    };
+    events.publish(ProtocolEvent::BlindSignature {
+        r: signature.r.clone(),
+        c: blinding.blinded().1.clone(),
+        s: signature.s.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let accepted = blind::verify(&setting, &commitment.public_key, message, &signature);
+    events.publish(ProtocolEvent::BlindVerdict { accepted });
+    notify_peer(&appstate, &ProtocolMessage::Verdict { accepted }).await;
+}
+
+//Участники кольца получают ключи, один из них подписывает сообщение
+async fn start_ring<G: Group>(setting: Setting<G>, appstate: AppState) {
+    info!("Начинаем кольцевую подпись");
+    let group = &setting.group;
+    let events = &appstate.events;
+
+    events.publish(announce(&setting));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let mut secrets = Vec::with_capacity(RING_SIZE);
+    let mut keys = Vec::with_capacity(RING_SIZE);
+    for _ in 0..RING_SIZE {
+        let x = group.random_scalar();
+        keys.push(group.exp(&setting.g, &x));
+        secrets.push(x);
+    }
+    let index = rand::random::<u64>() as usize % RING_SIZE;
+    events.publish(ProtocolEvent::RingSigner {
+        index,
+        size: RING_SIZE,
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let message = RING_MESSAGE.as_bytes();
+    let signature = match ring::sign(&setting, &keys, index, &secrets[index], message) {
+        Ok(signature) => signature,
+        Err(e) => return abort(&appstate, e).await,
+    };
+    events.publish(ProtocolEvent::RingSigned {
+        message: RING_MESSAGE.to_string(),
+        signature: signature.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let accepted = ring::verify(&setting, message, &signature);
+    events.publish(ProtocolEvent::RingVerdict { accepted });
+    notify_peer(&appstate, &ProtocolMessage::Verdict { accepted }).await;
+}
+
+//Раунды изоморфизма графов; каждый раунд дает один бит стойкости
+async fn start_isomorphism(appstate: AppState) {
+    info!("Начинаем проверку изоморфизма графов");
+    let events = &appstate.events;
+    let rounds = appstate.config.get_rounds();
+
+    let prover = GiProver::random(GI_VERTICES, GI_DENSITY);
+    let pair = prover.pair().clone();
+    events.publish(ProtocolEvent::GiGraphs {
+        g0: pair.g0.clone(),
+        g1: pair.g1.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let mut accepted = true;
+    for round in 1..=rounds {
+        if rounds > 1 {
+            events.publish(ProtocolEvent::Round { round, rounds });
+        }
+        let (sigma, h) = prover.commit();
+        events.publish(ProtocolEvent::GiCommitment { h: h.clone() });
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        let bit = GraphIsomorphism.challenge();
+        events.publish(ProtocolEvent::GiChallenge { bit });
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        let rho = prover.respond(sigma, bit);
+        events.publish(ProtocolEvent::GiResponse {
+            permutation: rho.clone(),
+        });
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        accepted = GraphIsomorphism.verify(&pair, &h, &bit, &rho);
+        events.publish(ProtocolEvent::GiVerdict { accepted });
+        if !accepted {
+            break;
+        }
+    }
+    events.publish(ProtocolEvent::Soundness {
+        rounds,
+        bits: rounds as f64,
+    });
+    notify_peer(&appstate, &ProtocolMessage::Verdict { accepted }).await;
+}
+
+//Озвучивание вычислений проверяющего и вердикта
+async fn send_proof(decided: &Decided, events: &EventBus) {
+    info!("V вычислил u_z");
+    events.publish(ProtocolEvent::Computed {
+        name: "u_z".to_string(),
+        value: decided.uz.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    events.publish(ProtocolEvent::Computed {
+        name: "u^c".to_string(),
+        value: decided.uc.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    info!("V вычислил u_t * u^c");
+    events.publish(ProtocolEvent::Computed {
+        name: "u_t * u^c".to_string(),
+        value: decided.utuc.clone(),
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    if decided.accepted {
+        info!("V подтверлил знание");
+    } else {
+        info!("V отверг знание");
+    }
+    events.publish(ProtocolEvent::Verdict {
+        uz: decided.uz.clone(),
+        utuc: decided.utuc.clone(),
+        accepted: decided.accepted,
+    });
+}
+
+async fn abort(appstate: &AppState, reason: String) {
+    warn!("{}", reason);
+    appstate.events.publish(ProtocolEvent::Error {
+        reason: reason.clone(),
+    });
+    notify_peer(appstate, &ProtocolMessage::Abort { reason }).await;
+}
+
+//Пересылка итогового сообщения второму серверу
+async fn notify_peer(appstate: &AppState, message: &ProtocolMessage) {
+    if let Err(error) = appstate
+        .peer
+        .send("/peer/message", message, &appstate.events)
+        .await
+    {
+        warn!("{}", error);
+        appstate.events.publish(ProtocolEvent::PeerFailed { error });
     }
 }
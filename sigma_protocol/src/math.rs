@@ -1,87 +1,530 @@
+use std::collections::HashMap;
+use std::fmt;
+
 use num_bigint::{BigInt, BigUint};
 use num_integer::Integer;
-use num_traits::{One, Zero};
+use num_traits::{CheckedSub, One, ToPrimitive, Zero};
+
+use crate::backend;
 
+//Бинарный алгоритм Штейна: только сдвиги и вычитания на месте, без
+//деления и новых выделений памяти на каждом шаге
 pub fn gcd_big(a: &BigUint, b: &BigUint) -> BigUint {
-    if b == &BigUint::zero() {
-        a.clone()
-    } else {
-        gcd_big(&b, &(a % b))
+    let (Some(za), Some(zb)) = (a.trailing_zeros(), b.trailing_zeros()) else {
+        //Один из аргументов - ноль
+        return a | b;
+    };
+    let (mut a, mut b) = (a >> za, b >> zb);
+    //Оба нечетны; разность четна и сдвигается до следующего нечетного
+    while a != b {
+        if a > b {
+            std::mem::swap(&mut a, &mut b);
+        }
+        b -= &a;
+        let tz = b.trailing_zeros().unwrap_or(0);
+        b >>= tz;
+    }
+    a << za.min(zb)
+}
+
+//Почему не удалось вычисление по модулю
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MathError {
+    ZeroModulus,
+    //По модулю 1 все вычеты равны нулю, обратных нет
+    TrivialModulus,
+    //Обратного к нулю не существует
+    NoInverse,
+    //gcd(a, m) > 1: a необратим по модулю m
+    NotCoprime { gcd: BigUint },
+    //Число не помещается в заданное число байт
+    IntegerTooLarge { len: usize },
+}
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MathError::ZeroModulus => write!(f, "модуль равен нулю"),
+            MathError::TrivialModulus => write!(f, "модуль равен единице"),
+            MathError::NoInverse => write!(f, "ноль не имеет обратного"),
+            MathError::NotCoprime { gcd } => {
+                write!(f, "число не взаимно просто с модулем, НОД = {}", gcd)
+            }
+            MathError::IntegerTooLarge { len } => {
+                write!(f, "число не помещается в {} байт", len)
+            }
+        }
     }
 }
 
-pub fn mod_pow_big(base: &BigUint, exponent: &BigInt, modulus: &BigUint) -> Option<BigUint> {
+impl std::error::Error for MathError {}
+
+impl From<MathError> for String {
+    fn from(e: MathError) -> String {
+        e.to_string()
+    }
+}
+
+pub fn mod_pow_big(
+    base: &BigUint,
+    exponent: &BigInt,
+    modulus: &BigUint,
+) -> Result<BigUint, MathError> {
     if modulus == &BigUint::zero() {
-        return None;
+        return Err(MathError::ZeroModulus);
     }
 
     if modulus == &BigUint::one() {
-        return Some(BigUint::zero());
+        return Ok(BigUint::zero());
     }
 
     let result = if exponent < &BigInt::zero() {
-        match modular_inverse_euclidean(&base, modulus) {
-            Some(inv_base) => {
-                mod_pow_positive_big(&inv_base, &(-exponent).to_biguint().unwrap(), modulus)
-            }
-            None => return None,
-        }
+        let inv_base = modular_inverse_euclidean(base, modulus)?;
+        mod_pow_positive_big(&inv_base, &(-exponent).to_biguint().unwrap(), modulus)
     } else {
-        mod_pow_positive_big(&base, &exponent.to_biguint().unwrap(), modulus)
+        mod_pow_positive_big(base, &exponent.to_biguint().unwrap(), modulus)
     };
 
-    Some(result)
+    Ok(result)
 }
 
+//Возведение в степень выполняет активный бэкенд (num-bigint или GMP)
 fn mod_pow_positive_big(base: &BigUint, exponent: &BigUint, modulus: &BigUint) -> BigUint {
     if modulus.is_one() {
         return BigUint::zero();
     }
+    backend::mod_pow(base, exponent, modulus)
+}
 
-    let mut result = BigUint::one();
-    let mut base = base % modulus;
-    let mut exponent = exponent.clone();
+//////////////////////////////////////////////////////////////
+// Фиксированное основание: для каждого окна из FIXED_WINDOW бит
+// показателя заранее вычислены base^(d * 2^(FIXED_WINDOW * i)),
+// и степень собирается одним умножением на окно без возведений
+// в квадрат. Таблицы строятся один раз на сессию для g и h.
+//////////////////////////////////////////////////////////////
+
+const FIXED_WINDOW: u64 = 4;
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct FixedBase {
+    base: BigUint,
+    modulus: BigUint,
+    //table[i][d - 1] = base^(d * 2^(FIXED_WINDOW * i)) mod modulus
+    table: Vec<Vec<BigUint>>,
+}
+
+//Таблицы велики, в журнал попадает только основание
+impl std::fmt::Debug for FixedBase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FixedBase")
+            .field("base", &self.base)
+            .field("windows", &self.table.len())
+            .finish()
+    }
+}
 
-    while !exponent.is_zero() {
-        if exponent.is_odd() {
-            result = (result * &base) % modulus;
+impl FixedBase {
+    //Таблицы для показателей до bits бит; None для нулевого модуля
+    pub fn new(base: &BigUint, modulus: &BigUint, bits: u64) -> Option<Self> {
+        if modulus.is_zero() {
+            return None;
+        }
+        let digits = 1usize << FIXED_WINDOW;
+        let windows = bits.div_ceil(FIXED_WINDOW) as usize;
+        let mut table = Vec::with_capacity(windows);
+        let mut step = base % modulus;
+        for _ in 0..windows {
+            let mut row = Vec::with_capacity(digits - 1);
+            row.push(step.clone());
+            for d in 1..digits - 1 {
+                row.push(&row[d - 1] * &step % modulus);
+            }
+            step = &row[digits - 2] * &step % modulus;
+            table.push(row);
         }
-        base = (&base * &base) % modulus;
-        exponent >>= 1;
+        Some(FixedBase {
+            base: base.clone(),
+            modulus: modulus.clone(),
+            table,
+        })
     }
 
-    result
+    pub fn base(&self) -> &BigUint {
+        &self.base
+    }
+
+    //base^exponent mod modulus; длинные показатели считаются без таблиц
+    pub fn pow(&self, exponent: &BigUint) -> BigUint {
+        if exponent.bits() > self.table.len() as u64 * FIXED_WINDOW {
+            return mod_pow_positive_big(&self.base, exponent, &self.modulus);
+        }
+        let mut result = BigUint::one() % &self.modulus;
+        for (i, row) in self.table.iter().enumerate() {
+            let offset = i as u64 * FIXED_WINDOW;
+            let digit = (0..FIXED_WINDOW)
+                .filter(|j| exponent.bit(offset + j))
+                .fold(0usize, |d, j| d | 1 << j);
+            if digit != 0 {
+                result = result * &row[digit - 1] % &self.modulus;
+            }
+        }
+        result
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Редукция Барретта: для фиксированного модуля m заранее
+// вычисляется mu = floor(4^k / m), после чего x mod m для x < m^2
+// находится двумя умножениями и сдвигами вместо деления.
+//
+// Деление в num-bigint и так быстрое: по benches/barrett.rs возведение
+// в степень с Барреттом выигрывает около 10% лишь на модулях от 3072
+// бит, а на 2048 битах проигрывает, поэтому mod_pow_big остается на %.
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Barrett {
+    modulus: BigUint,
+    mu: BigUint,
+    //k - длина модуля в битах
+    k: u64,
+}
+
+impl Barrett {
+    //None для нулевого модуля
+    pub fn new(modulus: &BigUint) -> Option<Self> {
+        if modulus.is_zero() {
+            return None;
+        }
+        let k = modulus.bits();
+        Some(Barrett {
+            mu: (BigUint::one() << (2 * k)) / modulus,
+            modulus: modulus.clone(),
+            k,
+        })
+    }
+
+    pub fn modulus(&self) -> &BigUint {
+        &self.modulus
+    }
+
+    //x mod m; значения не меньше 4^k приводятся обычным делением
+    pub fn reduce(&self, x: &BigUint) -> BigUint {
+        if x.bits() > 2 * self.k {
+            return x % &self.modulus;
+        }
+        //Оценка частного занижена не больше чем на 2
+        let q = ((x >> (self.k - 1)) * &self.mu) >> (self.k + 1);
+        let mut r = x - q * &self.modulus;
+        while r >= self.modulus {
+            r -= &self.modulus;
+        }
+        r
+    }
+
+    pub fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        self.reduce(&(a * b))
+    }
+
+    //base^exponent mod m возведением в квадрат справа налево
+    pub fn pow(&self, base: &BigUint, exponent: &BigUint) -> BigUint {
+        let mut result = BigUint::one() % &self.modulus;
+        let mut base = self.reduce(base);
+        for i in 0..exponent.bits() {
+            if exponent.bit(i) {
+                result = self.mul(&result, &base);
+            }
+            base = self.mul(&base, &base);
+        }
+        result
+    }
 }
 
-fn extended_euclidean(a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt) {
-    if a == &BigInt::zero() {
-        return (b.clone(), BigInt::zero(), BigInt::one());
+//Расширенный алгоритм Евклида: (g, x, y), где g = gcd(a, b) >= 0 и a*x + b*y = g
+pub fn extended_gcd(a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt) {
+    let (mut old_r, mut r) = (a.clone(), b.clone());
+    let (mut old_x, mut x) = (BigInt::one(), BigInt::zero());
+    let (mut old_y, mut y) = (BigInt::zero(), BigInt::one());
+    while !r.is_zero() {
+        let (q, rem) = old_r.div_rem(&r);
+        old_r = std::mem::replace(&mut r, rem);
+        let next_x = &old_x - &q * &x;
+        old_x = std::mem::replace(&mut x, next_x);
+        let next_y = &old_y - &q * &y;
+        old_y = std::mem::replace(&mut y, next_y);
+    }
+    if old_r < BigInt::zero() {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
     }
-    let (gcd, x1, y1) = extended_euclidean(&(b % a), a);
-    let x = y1 - (b / a) * &x1;
-    let y = x1;
-    (gcd, x, y)
 }
 
-pub fn modular_inverse_euclidean(a: &BigUint, m: &BigUint) -> Option<BigUint> {
+pub fn modular_inverse_euclidean(a: &BigUint, m: &BigUint) -> Result<BigUint, MathError> {
+    if m.is_zero() {
+        return Err(MathError::ZeroModulus);
+    }
+    if m.is_one() {
+        return Err(MathError::TrivialModulus);
+    }
+    if a.is_zero() {
+        return Err(MathError::NoInverse);
+    }
     let a = BigInt::from(a.clone());
     let m = BigInt::from(m.clone());
-    if a == BigInt::zero() {
+    let (gcd, x, _) = extended_gcd(&a, &m);
+    if gcd != BigInt::one() {
+        return Err(MathError::NotCoprime {
+            gcd: gcd.magnitude().clone(),
+        });
+    }
+    let mut result = x % &m;
+    if result < BigInt::zero() {
+        result += &m;
+    }
+
+    Ok(result.magnitude().clone())
+}
+
+//g^a * h^b mod modulus за один проход по битам (трюк Шамира) в бэкенде
+pub fn mod_multi_pow(
+    g: &BigUint,
+    a: &BigUint,
+    h: &BigUint,
+    b: &BigUint,
+    modulus: &BigUint,
+) -> Option<BigUint> {
+    if modulus.is_zero() {
         return None;
     }
-    if m == BigInt::one() {
+    Some(backend::mod_multi_pow(g, a, h, b, modulus))
+}
+
+//Символ Якоби (a/n) для нечетного n > 0: 1, -1 или 0, если gcd(a, n) > 1.
+//Для простого n это символ Лежандра: 1 - ненулевой квадратичный вычет
+pub fn jacobi(a: &BigUint, n: &BigUint) -> Option<i8> {
+    if n.is_zero() || n.is_even() {
         return None;
     }
-    let (gcd, x, _) = extended_euclidean(&a, &m);
-    if gcd != BigInt::one() {
+    let mut a = a % n;
+    let mut n = n.clone();
+    let mut sign = 1i8;
+    while !a.is_zero() {
+        //(2/n) = -1 при n = 3, 5 mod 8
+        let twos = a.trailing_zeros().unwrap_or(0);
+        a >>= twos;
+        let n_mod_8 = (&n % 8u32).to_u32_digits().first().copied().unwrap_or(0);
+        if twos % 2 == 1 && (n_mod_8 == 3 || n_mod_8 == 5) {
+            sign = -sign;
+        }
+        //Квадратичный закон взаимности: знак меняется, если оба = 3 mod 4
+        std::mem::swap(&mut a, &mut n);
+        if a.bit(1) && n.bit(1) {
+            sign = -sign;
+        }
+        a %= &n;
+    }
+    Some(if n.is_one() { sign } else { 0 })
+}
+
+//Квадратный корень по простому модулю p (Тонелли-Шенкс): x с x^2 = a mod p
+//или None, если a - невычет. Из двух корней возвращается меньший
+pub fn mod_sqrt(a: &BigUint, p: &BigUint) -> Option<BigUint> {
+    let a = a % p;
+    if p == &BigUint::from(2u32) || a.is_zero() {
+        return Some(a);
+    }
+    if jacobi(&a, p)? != 1 {
         return None;
     }
-    let mut result = x % &m;
-    if result < BigInt::zero() {
-        result += &m;
+    let pow = |base: &BigUint, exp: &BigUint| mod_pow_positive_big(base, exp, p);
+    let root = if p.bit(1) {
+        //p = 3 mod 4: x = a^((p+1)/4)
+        pow(&a, &((p + 1u32) >> 2))
+    } else {
+        //p - 1 = q * 2^s, z - любой невычет
+        let s = (p - 1u32).trailing_zeros().unwrap_or(0);
+        let q = (p - 1u32) >> s;
+        let z = (2u32..)
+            .map(BigUint::from)
+            .find(|z| jacobi(z, p) == Some(-1))
+            .expect("невычет существует для нечетного простого p");
+        let mut m = s;
+        let mut c = pow(&z, &q);
+        let mut t = pow(&a, &q);
+        let mut r = pow(&a, &((&q + 1u32) >> 1));
+        //Инвариант: r^2 = a * t; порядок t уменьшается каждый шаг
+        while !t.is_one() {
+            let mut i = 0;
+            let mut t2i = t.clone();
+            while !t2i.is_one() {
+                t2i = &t2i * &t2i % p;
+                i += 1;
+                if i == m {
+                    return None;
+                }
+            }
+            let b = pow(&c, &(BigUint::one() << (m - i - 1)));
+            m = i;
+            c = &b * &b % p;
+            t = t * &c % p;
+            r = r * b % p;
+        }
+        r
+    };
+    if &root * &root % p != a {
+        return None;
+    }
+    let other = p - &root;
+    Some(root.min(other))
+}
+
+//Образующая подгруппы порядка q в Z_p^* по простым делителям factors
+//числа p - 1: g = x^((p-1)/q) для x = 2, 3, ..., пока порядок g не
+//станет ровно q, то есть g^(q/r) != 1 для каждого простого r | q.
+//None, если q не делит p - 1 или среди factors есть не делитель p - 1
+pub fn find_generator(p: &BigUint, q: &BigUint, factors: &[BigUint]) -> Option<BigUint> {
+    let p_1 = p.checked_sub(&BigUint::one())?;
+    if q.is_zero() || !(&p_1 % q).is_zero() {
+        return None;
+    }
+    if factors
+        .iter()
+        .any(|r| r <= &BigUint::one() || !(&p_1 % r).is_zero())
+    {
+        return None;
+    }
+    let cofactor = &p_1 / q;
+    let q_factors: Vec<&BigUint> = factors.iter().filter(|r| (q % *r).is_zero()).collect();
+    let mut x = BigUint::from(2u32);
+    while x < *p {
+        let g = mod_pow_positive_big(&x, &cofactor, p);
+        let exact = q_factors
+            .iter()
+            .all(|r| !mod_pow_positive_big(&g, &(q / *r), p).is_one());
+        if !g.is_one() && exact {
+            return Some(g);
+        }
+        x += 1u32;
+    }
+    None
+}
+
+//Наибольшее число шагов младенца: таблица g^j должна помещаться в память
+const BSGS_MAX_STEPS: u64 = 1 << 24;
+
+//Дискретный логарифм y по основанию g порядка order методом шагов младенца
+//и великана: y = g^(i*m + j), m = floor(sqrt(order)) + 1. Таблица g^j на m
+//элементов и не больше m умножений на g^-m вместо перебора всех order.
+//progress получает номер шага великана и их общее число. None, если
+//логарифма нет или таблица длиннее BSGS_MAX_STEPS
+pub fn bsgs(
+    g: &BigUint,
+    y: &BigUint,
+    modulus: &BigUint,
+    order: &BigUint,
+    mut progress: impl FnMut(u64, u64),
+) -> Option<BigUint> {
+    if modulus.is_zero() || order.is_zero() {
+        return None;
+    }
+    let m = order.sqrt() + 1u32;
+    let steps = m.to_u64().filter(|&steps| steps <= BSGS_MAX_STEPS)?;
+    let mut table = HashMap::with_capacity(steps as usize);
+    let mut baby = BigUint::one() % modulus;
+    for j in 0..steps {
+        table.entry(baby.clone()).or_insert(j);
+        baby = baby * g % modulus;
+    }
+    //g^-m = g^(order - m mod order)
+    let giant = mod_pow_positive_big(g, &((order - &m % order) % order), modulus);
+    let mut gamma = y % modulus;
+    for i in 0..steps {
+        if let Some(j) = table.get(&gamma) {
+            return Some((&m * i + j) % order);
+        }
+        gamma = gamma * &giant % modulus;
+        progress(i + 1, steps);
+    }
+    None
+}
+
+//////////////////////////////////////////////////////////////
+// Мультиэкспонента: произведение base_i^exp_i mod modulus.
+// Для нескольких оснований - метод Штрауса с общей цепочкой
+// возведений в квадрат. Для многих - метод Пиппенджера: показатели
+// режутся на окна по c бит, основания раскладываются по корзинам
+// по значению окна, и сумма корзин с весами 1..2^c-1 собирается
+// накопительными произведениями. Умножений около N*b/c вместо N*b.
+//////////////////////////////////////////////////////////////
+
+//None для нулевого модуля или разного числа оснований и показателей;
+//метод Штрауса или корзины Пиппенджера выбирает бэкенд
+pub fn multi_exp(bases: &[BigUint], exps: &[BigUint], modulus: &BigUint) -> Option<BigUint> {
+    if modulus.is_zero() || bases.len() != exps.len() {
+        return None;
+    }
+    Some(backend::multi_exp(bases, exps, modulus))
+}
+
+//////////////////////////////////////////////////////////////
+// Сравнение без раннего выхода: время зависит только от длины
+// операндов, а не от позиции первого различия. Для проверок,
+// где сравнивается секрет или значение, подбираемое побайтно.
+//////////////////////////////////////////////////////////////
+
+//Числа сравниваются по всем 64-битным словам, короткое дополняется нулями
+pub fn ct_eq(a: &BigUint, b: &BigUint) -> bool {
+    let (a, b) = (a.to_u64_digits(), b.to_u64_digits());
+    let word = |digits: &[u64], i: usize| digits.get(i).copied().unwrap_or(0);
+    let diff = (0..a.len().max(b.len())).fold(0u64, |acc, i| acc | (word(&a, i) ^ word(&b, i)));
+    std::hint::black_box(diff) == 0
+}
+
+//Байтовые строки (MAC, хэши); длина считается открытой
+pub fn ct_eq_bytes(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
+//////////////////////////////////////////////////////////////
+// Кодирование фиксированной длины (I2OSP/OS2IP из RFC 8017):
+// число дополняется ведущими нулями до длины модуля, так что
+// у каждого значения ровно одно представление в байтах.
+//////////////////////////////////////////////////////////////
+
+//Число байт, достаточное для любого значения меньше n
+pub fn byte_len(n: &BigUint) -> usize {
+    n.bits().div_ceil(8) as usize
+}
+
+//x в виде ровно len байт, старшие впереди
+pub fn i2osp(x: &BigUint, len: usize) -> Result<Vec<u8>, MathError> {
+    if x.is_zero() {
+        return Ok(vec![0u8; len]);
     }
+    let bytes = x.to_bytes_be();
+    if bytes.len() > len {
+        return Err(MathError::IntegerTooLarge { len });
+    }
+    let mut out = vec![0u8; len - bytes.len()];
+    out.extend(bytes);
+    Ok(out)
+}
+
+pub fn os2ip(bytes: &[u8]) -> BigUint {
+    BigUint::from_bytes_be(bytes)
+}
 
-    result.to_biguint()
+//Значение многочлена a_0 + a_1 x + ... + a_k x^k по модулю (схема Горнера)
+pub fn eval_polynomial(coefficients: &[BigUint], x: &BigUint, modulus: &BigUint) -> BigUint {
+    coefficients
+        .iter()
+        .rev()
+        .fold(BigUint::zero(), |acc, a| (acc * x + a) % modulus)
 }
 
 #[cfg(test)]
@@ -89,6 +532,7 @@ mod tests {
     use num_traits::FromPrimitive;
 
     use super::*;
+    use crate::group::Group;
 
     ///////////////////////////////////
     ///          GCD               ///
@@ -219,6 +663,146 @@ mod tests {
         assert_eq!(gcd_big(&a, &b), BigUint::from_u64(3 * 5 * 7).unwrap());
     }
 
+    #[test]
+    fn test_gcd_binary_large_operands() {
+        //Общий множитель со степенью двойки у 2048-битных операндов
+        let common = (BigUint::one() << 37u32) * 1_000_000_007u64;
+        let x = (BigUint::one() << 2000u32) + 12_345u32;
+        let y = (BigUint::one() << 1999u32) * 3u32 + 1u32;
+        let a = &common * &x;
+        let b = &common * &y * 4u32;
+        assert_eq!(gcd_big(&a, &b), &common * x.gcd(&y));
+        assert_eq!(gcd_big(&a, &b), a.gcd(&b));
+        assert_eq!(gcd_big(&a, &a), a);
+    }
+
+    #[test]
+    fn test_gcd_deep_fibonacci() {
+        //Соседние числа Фибоначчи - худший случай: по шагу на каждое число
+        let (mut a, mut b) = (BigUint::zero(), BigUint::one());
+        for _ in 0..10_000 {
+            let next = &a + &b;
+            a = std::mem::replace(&mut b, next);
+        }
+        assert!(b.bits() > 6_000);
+        assert_eq!(gcd_big(&b, &a), BigUint::one());
+        let (g, x, y) = extended_gcd(&BigInt::from(b.clone()), &BigInt::from(a.clone()));
+        assert_eq!(g, BigInt::one());
+        assert_eq!(BigInt::from(b) * x + BigInt::from(a) * y, g);
+    }
+
+    //////////////////////////////////
+    ///       EXTENDED GCD         ///
+    /////////////////////////////////
+    #[test]
+    fn test_extended_gcd_bezout() {
+        let values = [-240i64, -46, -7, -1, 0, 1, 7, 46, 240, 1_000_000_007];
+        for &a in &values {
+            for &b in &values {
+                let (a, b) = (BigInt::from(a), BigInt::from(b));
+                let (g, x, y) = extended_gcd(&a, &b);
+                assert_eq!(&a * &x + &b * &y, g, "a = {}, b = {}", a, b);
+                assert!(g >= BigInt::zero());
+                assert_eq!(g, a.gcd(&b));
+            }
+        }
+        //gcd(240, 46) = 2 = 240 * (-9) + 46 * 47
+        let (g, x, y) = extended_gcd(&BigInt::from(240), &BigInt::from(46));
+        assert_eq!((g, x, y), (2.into(), (-9).into(), 47.into()));
+        assert_eq!(
+            extended_gcd(&BigInt::zero(), &BigInt::zero()),
+            (BigInt::zero(), BigInt::one(), BigInt::zero())
+        );
+    }
+
+    //////////////////////////////////
+    ///         FIXED BASE         ///
+    /////////////////////////////////
+    #[test]
+    fn test_fixed_base_matches_mod_pow() {
+        let m = BigUint::from(1_000_000_007u32);
+        let fixed = FixedBase::new(&BigUint::from(5u32), &m, 30).unwrap();
+        assert_eq!(fixed.base(), &BigUint::from(5u32));
+        //Показатели внутри таблиц, на их границе и длиннее таблиц
+        for e in [
+            0u64,
+            1,
+            15,
+            16,
+            255,
+            65_537,
+            (1 << 30) - 1,
+            1 << 30,
+            u64::MAX,
+        ] {
+            let e = BigUint::from(e);
+            assert_eq!(
+                Ok(fixed.pow(&e)),
+                mod_pow_big(&BigUint::from(5u32), &BigInt::from(e.clone()), &m),
+                "e = {}",
+                e
+            );
+        }
+        assert!(FixedBase::new(&BigUint::from(5u32), &BigUint::zero(), 8).is_none());
+        assert_eq!(
+            FixedBase::new(&BigUint::from(5u32), &BigUint::one(), 8)
+                .unwrap()
+                .pow(&BigUint::from(3u32)),
+            BigUint::zero()
+        );
+        assert!(!format!("{:?}", fixed).contains("table"));
+    }
+
+    //////////////////////////////////
+    ///          BARRETT           ///
+    /////////////////////////////////
+    #[test]
+    fn test_barrett_reduce_matches_rem() {
+        assert!(Barrett::new(&BigUint::zero()).is_none());
+        for m in [1u64, 2, 3, 11, 23, 255, 256, 1_000_000_007, u64::MAX] {
+            let m = BigUint::from(m);
+            let barrett = Barrett::new(&m).unwrap();
+            let square = &m * &m;
+            //Границы и значения вне [0, m^2) тоже приводятся верно
+            let xs = [
+                BigUint::zero(),
+                &m - 1u32,
+                m.clone(),
+                &square - 1u32,
+                square.clone(),
+                &square * &m + 17u32,
+            ];
+            for x in xs {
+                assert_eq!(barrett.reduce(&x), &x % &m, "x = {}, m = {}", x, m);
+            }
+        }
+    }
+
+    #[test]
+    fn test_barrett_pow_matches_mod_pow() {
+        let m = BigUint::parse_bytes(b"123456789012345678901234567891", 10).unwrap();
+        let barrett = Barrett::new(&m).unwrap();
+        assert_eq!(barrett.modulus(), &m);
+        for (base, exp) in [
+            (2u32, 0u32),
+            (0, 5),
+            (3, 1),
+            (123_456, 65_537),
+            (7, 1_000_000),
+        ] {
+            let (base, exp) = (BigUint::from(base), BigUint::from(exp));
+            assert_eq!(
+                Ok(barrett.pow(&base, &exp)),
+                mod_pow_big(&base, &BigInt::from(exp.clone()), &m)
+            );
+        }
+        let one = Barrett::new(&BigUint::one()).unwrap();
+        assert_eq!(
+            one.pow(&BigUint::from(5u32), &BigUint::zero()),
+            BigUint::zero()
+        );
+    }
+
     //////////////////////////////////
     ///          MOD POW           ///
     /////////////////////////////////
@@ -288,7 +872,7 @@ mod tests {
         let exponent = BigInt::from(-2i32);
         let modulus = BigUint::from(10u32);
         let result = mod_pow_big(&base, &exponent, &modulus);
-        assert!(result.is_none());
+        assert!(result.is_err());
     }
 
     #[test]
@@ -353,13 +937,18 @@ mod tests {
         let exponent = BigInt::from(-1i32);
         let modulus = BigUint::from(4u32);
         let result = mod_pow_big(&base, &exponent, &modulus);
-        assert!(result.is_none());
+        assert_eq!(
+            result,
+            Err(MathError::NotCoprime {
+                gcd: BigUint::from(2u32)
+            })
+        );
 
         let base = BigUint::from(6u32);
         let exponent = BigInt::from(-1i32);
         let modulus = BigUint::from(9u32);
         let result = mod_pow_big(&base, &exponent, &modulus);
-        assert!(result.is_none());
+        assert!(result.is_err());
     }
 
     #[test]
@@ -432,17 +1021,17 @@ mod tests {
         let a = BigUint::from(2u32);
         let m = BigUint::from(4u32);
         let result = modular_inverse_euclidean(&a, &m);
-        assert!(result.is_none());
+        assert!(result.is_err());
 
         let a = BigUint::from(6u32);
         let m = BigUint::from(9u32);
         let result = modular_inverse_euclidean(&a, &m);
-        assert!(result.is_none());
+        assert!(result.is_err());
 
         let a = BigUint::from(10u32);
         let m = BigUint::from(15u32);
         let result = modular_inverse_euclidean(&a, &m);
-        assert!(result.is_none());
+        assert!(result.is_err());
     }
 
     #[test]
@@ -477,7 +1066,7 @@ mod tests {
         for i in 1..17 {
             let a = BigUint::from(i as u32);
             let result = modular_inverse_euclidean(&a, &prime);
-            assert!(result.is_some());
+            assert!(result.is_ok());
 
             let inv = result.unwrap();
             let product = (&a * &inv) % &prime;
@@ -492,7 +1081,7 @@ mod tests {
         for &i in &coprime_with_15 {
             let a = BigUint::from(i as u32);
             let result = modular_inverse_euclidean(&a, &m);
-            assert!(result.is_some(), "Обратный должен существовать для {}", i);
+            assert!(result.is_ok(), "Обратный должен существовать для {}", i);
 
             let inv = result.unwrap();
             let product = (&a * &inv) % &m;
@@ -503,11 +1092,7 @@ mod tests {
         for &i in &not_coprime_with_15 {
             let a = BigUint::from(i as u32);
             let result = modular_inverse_euclidean(&a, &m);
-            assert!(
-                result.is_none(),
-                "Обратный не должен существовать для {}",
-                i
-            );
+            assert!(result.is_err(), "Обратный не должен существовать для {}", i);
         }
     }
 
@@ -535,14 +1120,24 @@ mod tests {
 
     #[test]
     fn test_modular_inverse_edge_cases() {
+        //m - 1 = -1 обратно самому себе: (m - 1)^2 = 1 mod m
         let m = BigUint::from(17u32);
         let a = &m - BigUint::one();
         let result = modular_inverse_euclidean(&a, &m);
-        assert!(result.is_none());
+        assert_eq!(result, Ok(a));
+        let result = modular_inverse_euclidean(&BigUint::from(6u32), &BigUint::from(9u32));
+        assert_eq!(
+            result,
+            Err(MathError::NotCoprime {
+                gcd: BigUint::from(3u32)
+            })
+        );
         let a = BigUint::zero();
         let m = BigUint::from(7u32);
         let result = modular_inverse_euclidean(&a, &m);
-        assert!(result.is_none());
+        assert_eq!(result, Err(MathError::NoInverse));
+        let result = modular_inverse_euclidean(&BigUint::one(), &BigUint::zero());
+        assert_eq!(result, Err(MathError::ZeroModulus));
     }
 
     #[test]
@@ -550,12 +1145,12 @@ mod tests {
         let a = BigUint::from(0u32);
         let m = BigUint::one();
         let result = modular_inverse_euclidean(&a, &m);
-        assert!(result.is_none());
+        assert!(result.is_err());
 
         let a = BigUint::from(1u32);
         let m = BigUint::one();
         let result = modular_inverse_euclidean(&a, &m);
-        assert!(result.is_none());
+        assert_eq!(result, Err(MathError::TrivialModulus));
     }
 
     #[test]
@@ -564,7 +1159,7 @@ mod tests {
 
         let a = BigUint::from(0u32);
         let result = modular_inverse_euclidean(&a, &m);
-        assert!(result.is_none());
+        assert!(result.is_err());
 
         let a = BigUint::from(1u32);
         let result = modular_inverse_euclidean(&a, &m).unwrap();
@@ -610,7 +1205,7 @@ mod tests {
 
         let a_not_coprime = BigUint::from(2u32);
         let result = modular_inverse_euclidean(&a_not_coprime, &m);
-        assert!(result.is_none());
+        assert!(result.is_err());
     }
 
     #[test]
@@ -628,13 +1223,277 @@ mod tests {
             let result = modular_inverse_euclidean(&BigUint::from(a), &BigUint::from(m));
             match expected {
                 Some(inv) => {
-                    assert!(result.is_some());
+                    assert!(result.is_ok());
                     assert_eq!(result.unwrap(), BigUint::from(inv));
                 }
                 None => {
-                    assert!(result.is_none());
+                    assert!(result.is_err());
                 }
             }
         }
     }
+
+    //////////////////////////////////
+    ///         MULTI EXP          ///
+    /////////////////////////////////
+    #[test]
+    fn test_bsgs_recovers_exponent() {
+        //p = 2q + 1 с простым q = 1019, g = 4 порядка q
+        let (p, q, g) = (
+            BigUint::from(2039u32),
+            BigUint::from(1019u32),
+            BigUint::from(4u32),
+        );
+        for x in [0u32, 1, 31, 32, 500, 1018] {
+            let y = mod_pow_positive_big(&g, &BigUint::from(x), &p);
+            let mut last = (0, 0);
+            let found = bsgs(&g, &y, &p, &q, |i, steps| last = (i, steps));
+            assert_eq!(found, Some(BigUint::from(x)));
+            //m = 32: x = 32i + j найден на шаге великана i
+            if x >= 32 {
+                assert_eq!(last, (x as u64 / 32, 32));
+            }
+        }
+        //-1 - невычет при p = 3 (mod 4), вне подгруппы порядка q
+        assert_eq!(bsgs(&g, &(&p - 1u32), &p, &q, |_, _| ()), None);
+        let huge = BigUint::one() << 128u32;
+        assert_eq!(bsgs(&g, &g, &p, &huge, |_, _| ()), None);
+    }
+
+    #[test]
+    fn test_ct_eq() {
+        let a = BigUint::from(0x1234_5678_9abc_def0u64) << 100u32;
+        assert!(ct_eq(&a, &a.clone()));
+        assert!(!ct_eq(&a, &(&a + 1u32)));
+        assert!(!ct_eq(&a, &(&a ^ (BigUint::one() << 160u32))));
+        //Разное число слов
+        assert!(!ct_eq(&a, &BigUint::zero()));
+        assert!(ct_eq(&BigUint::zero(), &BigUint::zero()));
+        assert!(ct_eq_bytes(b"tag", b"tag"));
+        assert!(!ct_eq_bytes(b"tag", b"tah"));
+        assert!(!ct_eq_bytes(b"tag", b"tags"));
+    }
+
+    #[test]
+    fn test_i2osp_os2ip() {
+        let x = BigUint::from(0x0102u32);
+        assert_eq!(i2osp(&x, 4), Ok(vec![0, 0, 1, 2]));
+        assert_eq!(i2osp(&BigUint::zero(), 2), Ok(vec![0, 0]));
+        assert_eq!(i2osp(&x, 1), Err(MathError::IntegerTooLarge { len: 1 }));
+        assert_eq!(os2ip(&[0, 0, 1, 2]), x);
+        //Длина по модулю: 2^16 требует трех байт, 2^16 - 1 - двух
+        assert_eq!(byte_len(&BigUint::from(0xffffu32)), 2);
+        assert_eq!(byte_len(&BigUint::from(0x10000u32)), 3);
+
+        //Элементы группы MODP всегда длиной в модуль
+        let group = crate::modp::params(2048).unwrap().setting().group;
+        let small = BigUint::from(5u32);
+        let bytes = group.serialize(&small);
+        assert_eq!(bytes.len(), 256);
+        assert_eq!(group.deserialize(&bytes), Some(small.clone()));
+        assert_eq!(group.deserialize(&[5]), None);
+        assert_eq!(group.decode(&group.encode(&small)), Some(small));
+    }
+
+    #[test]
+    fn test_multi_exp_matches_product() {
+        let m = BigUint::from(1_000_003u32);
+        let bases: Vec<BigUint> = [2u32, 17, 123_456, 999_999]
+            .iter()
+            .map(|&b| BigUint::from(b))
+            .collect();
+        let exps: Vec<BigUint> = [0u32, 5, 65_537, 1_000_000]
+            .iter()
+            .map(|&e| BigUint::from(e))
+            .collect();
+        let expected = bases.iter().zip(&exps).fold(BigUint::one(), |acc, (b, e)| {
+            acc * mod_pow_big(b, &BigInt::from(e.clone()), &m).unwrap() % &m
+        });
+        assert_eq!(multi_exp(&bases, &exps, &m), Some(expected));
+        assert_eq!(multi_exp(&[], &[], &m), Some(BigUint::one()));
+        assert_eq!(multi_exp(&bases, &exps, &BigUint::zero()), None);
+        assert_eq!(multi_exp(&bases, &exps[1..], &m), None);
+    }
+
+    #[test]
+    fn test_mod_multi_pow_matches_two_pows() {
+        let m = BigUint::from(1_000_003u32);
+        let cases = [
+            (2u32, 0u32, 3u32, 0u32),
+            (2, 5, 3, 0),
+            (2, 0, 3, 7),
+            (123_456, 65_537, 999_999, 1_000_000),
+            (1_000_004, 3, 1_000_003, 3),
+        ];
+        for (g, a, h, b) in cases {
+            let (g, a, h, b) = (
+                BigUint::from(g),
+                BigUint::from(a),
+                BigUint::from(h),
+                BigUint::from(b),
+            );
+            let expected = mod_pow_big(&g, &BigInt::from(a.clone()), &m).unwrap()
+                * mod_pow_big(&h, &BigInt::from(b.clone()), &m).unwrap()
+                % &m;
+            assert_eq!(mod_multi_pow(&g, &a, &h, &b, &m), Some(expected));
+        }
+        let one = BigUint::one();
+        assert_eq!(
+            mod_multi_pow(&one, &one, &one, &one, &one),
+            Some(BigUint::zero())
+        );
+        assert_eq!(
+            mod_multi_pow(&one, &one, &one, &one, &BigUint::zero()),
+            None
+        );
+    }
+
+    //////////////////////////////////
+    ///           JACOBI           ///
+    /////////////////////////////////
+    #[test]
+    fn test_jacobi_small_table() {
+        //Строка n = 15 из таблицы символов Якоби для a = 0..15
+        let row = [0, 1, 1, 0, 1, 0, 0, -1, 1, 0, 0, -1, 0, -1, -1];
+        for (a, &expected) in row.iter().enumerate() {
+            let symbol = jacobi(&BigUint::from(a), &BigUint::from(15u32));
+            assert_eq!(symbol, Some(expected), "a = {}", a);
+        }
+        assert_eq!(jacobi(&BigUint::from(5u32), &BigUint::one()), Some(1));
+        assert_eq!(jacobi(&BigUint::from(5u32), &BigUint::from(8u32)), None);
+        assert_eq!(jacobi(&BigUint::from(5u32), &BigUint::zero()), None);
+    }
+
+    #[test]
+    fn test_jacobi_matches_euler_criterion() {
+        //Для простого p: (a/p) = a^((p-1)/2) mod p
+        for p in [3u32, 23, 101, 1_000_003] {
+            let p = BigUint::from(p);
+            let half = BigInt::from((&p - 1u32) >> 1);
+            for a in [1u32, 2, 3, 4, 9, 10, 22, 50, 999_999, 2_000_009] {
+                let a = BigUint::from(a);
+                let euler = mod_pow_big(&a, &half, &p).unwrap();
+                let expected = if euler.is_zero() {
+                    0
+                } else if euler.is_one() {
+                    1
+                } else {
+                    -1
+                };
+                assert_eq!(jacobi(&a, &p), Some(expected), "a = {}, p = {}", a, p);
+            }
+        }
+    }
+
+    //////////////////////////////////
+    ///          MOD SQRT          ///
+    /////////////////////////////////
+    #[test]
+    fn test_mod_sqrt_three_mod_four() {
+        //23 = 3 mod 4: квадраты подгруппы порядка 11
+        let p = BigUint::from(23u32);
+        for x in 1u32..23 {
+            let square = BigUint::from(x * x % 23);
+            let root = mod_sqrt(&square, &p).unwrap();
+            assert_eq!(&root * &root % &p, square);
+            assert!(root <= BigUint::from(11u32));
+        }
+        assert_eq!(mod_sqrt(&BigUint::from(5u32), &p), None);
+        assert_eq!(mod_sqrt(&BigUint::zero(), &p), Some(BigUint::zero()));
+        assert_eq!(
+            mod_sqrt(&BigUint::from(3u32), &BigUint::from(2u32)),
+            Some(BigUint::one())
+        );
+    }
+
+    #[test]
+    fn test_mod_sqrt_tonelli_shanks() {
+        //p - 1 = 2^s * q с разными s: 17 = 2^4 + 1, 97 = 3 * 2^5 + 1, 7681 = 15 * 2^9 + 1
+        //и 2^64 - 2^32 + 1 (s = 32)
+        let primes = [17u64, 97, 7_681, 18_446_744_069_414_584_321];
+        for p in primes {
+            let p = BigUint::from(p);
+            for x in [1u64, 2, 3, 5, 16, 1_000, 123_456_789] {
+                let x = BigUint::from(x) % &p;
+                let square = &x * &x % &p;
+                let root = mod_sqrt(&square, &p).unwrap();
+                assert!(root == x || root == (&p - &x) % &p, "x = {}, p = {}", x, p);
+            }
+            //Невычеты отклоняются
+            let non_residue = (2u32..)
+                .map(BigUint::from)
+                .find(|z| jacobi(z, &p) == Some(-1))
+                .unwrap();
+            assert_eq!(mod_sqrt(&non_residue, &p), None);
+        }
+    }
+
+    //////////////////////////////////
+    ///       FIND GENERATOR       ///
+    /////////////////////////////////
+    #[test]
+    fn test_find_generator_orders() {
+        //p = 23, p - 1 = 2 * 11
+        let p = BigUint::from(23u32);
+        let factors = [BigUint::from(2u32), BigUint::from(11u32)];
+        let order = |g: &BigUint| {
+            (1u32..)
+                .find(|k| mod_pow_positive_big(g, &BigUint::from(*k), &p).is_one())
+                .unwrap()
+        };
+        for q in [2u32, 11, 22] {
+            let g = find_generator(&p, &BigUint::from(q), &factors).unwrap();
+            assert_eq!(order(&g), q, "q = {}", q);
+        }
+        //p = 61, p - 1 = 2^2 * 3 * 5: подгруппа составного порядка 30 и примитивный корень
+        let p = BigUint::from(61u32);
+        let factors = [2u32, 3, 5].map(BigUint::from);
+        let g = find_generator(&p, &BigUint::from(30u32), &factors).unwrap();
+        assert_eq!(
+            mod_pow_positive_big(&g, &BigUint::from(30u32), &p),
+            BigUint::one()
+        );
+        for k in [6u32, 10, 15] {
+            assert!(!mod_pow_positive_big(&g, &BigUint::from(k), &p).is_one());
+        }
+        assert_eq!(
+            find_generator(&p, &BigUint::from(60u32), &factors),
+            Some(BigUint::from(2u32))
+        );
+    }
+
+    #[test]
+    fn test_find_generator_rejects_bad_input() {
+        let p = BigUint::from(23u32);
+        let factors = [BigUint::from(2u32), BigUint::from(11u32)];
+        assert_eq!(find_generator(&p, &BigUint::from(7u32), &factors), None);
+        assert_eq!(find_generator(&p, &BigUint::zero(), &factors), None);
+        assert_eq!(
+            find_generator(&p, &BigUint::from(11u32), &[BigUint::from(3u32)]),
+            None
+        );
+        assert_eq!(find_generator(&BigUint::zero(), &BigUint::one(), &[]), None);
+    }
+
+    //////////////////////////////////
+    ///         POLYNOMIAL         ///
+    /////////////////////////////////
+    #[test]
+    fn test_eval_polynomial() {
+        let m = BigUint::from(11u32);
+        //3 + 2x + 5x^2 в точке 4: 3 + 8 + 80 = 91 = 3 mod 11
+        let coefficients: Vec<BigUint> = [3u32, 2, 5].iter().map(|&a| BigUint::from(a)).collect();
+        assert_eq!(
+            eval_polynomial(&coefficients, &BigUint::from(4u32), &m),
+            BigUint::from(3u32)
+        );
+        assert_eq!(
+            eval_polynomial(&coefficients, &BigUint::zero(), &m),
+            BigUint::from(3u32)
+        );
+        assert_eq!(
+            eval_polynomial(&[], &BigUint::from(4u32), &m),
+            BigUint::zero()
+        );
+    }
 }
@@ -0,0 +1,349 @@
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+//Сообщения, которыми обмениваются доказывающий и проверяющий
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProtocolMessage {
+    Commitment {
+        #[serde(with = "decimal")]
+        u: BigUint,
+        #[serde(with = "decimal")]
+        ut: BigUint,
+    },
+    Challenge {
+        #[serde(with = "decimal")]
+        c: BigUint,
+    },
+    Response {
+        #[serde(with = "decimal")]
+        alpha: BigUint,
+        #[serde(with = "decimal")]
+        beta: BigUint,
+    },
+    Verdict {
+        accepted: bool,
+    },
+    //Коммитмент протокола Чаума-Педерсена: u1 = g^x, u2 = h^x, t1 = g^r, t2 = h^r
+    DleqCommitment {
+        #[serde(with = "decimal")]
+        u1: BigUint,
+        #[serde(with = "decimal")]
+        u2: BigUint,
+        #[serde(with = "decimal")]
+        t1: BigUint,
+        #[serde(with = "decimal")]
+        t2: BigUint,
+    },
+    DleqResponse {
+        #[serde(with = "decimal")]
+        z: BigUint,
+    },
+    Abort {
+        reason: String,
+    },
+    //Испытание, зафиксированное проверяющим до коммитмента: H(s, c)
+    ChallengeCommitment {
+        #[serde(with = "decimal")]
+        digest: BigUint,
+    },
+    //Раскрытие зафиксированного испытания вместе с солью
+    ChallengeReveal {
+        #[serde(with = "decimal")]
+        c: BigUint,
+        #[serde(with = "decimal")]
+        salt: BigUint,
+    },
+}
+
+const TAG_COMMITMENT: u8 = 1;
+const TAG_CHALLENGE: u8 = 2;
+const TAG_RESPONSE: u8 = 3;
+const TAG_VERDICT: u8 = 4;
+const TAG_ABORT: u8 = 5;
+const TAG_DLEQ_COMMITMENT: u8 = 6;
+const TAG_DLEQ_RESPONSE: u8 = 7;
+const TAG_CHALLENGE_COMMITMENT: u8 = 8;
+const TAG_CHALLENGE_REVEAL: u8 = 9;
+
+impl ProtocolMessage {
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| e.to_string())
+    }
+
+    pub fn from_json(data: &str) -> Result<Self, String> {
+        serde_json::from_str(data).map_err(|e| e.to_string())
+    }
+
+    //Двоичный формат: байт типа, затем поля; числа и строки с префиксом длины u32 (big-endian)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self {
+            ProtocolMessage::Commitment { u, ut } => {
+                out.push(TAG_COMMITMENT);
+                put_bytes(&mut out, &u.to_bytes_be());
+                put_bytes(&mut out, &ut.to_bytes_be());
+            }
+            ProtocolMessage::Challenge { c } => {
+                out.push(TAG_CHALLENGE);
+                put_bytes(&mut out, &c.to_bytes_be());
+            }
+            ProtocolMessage::Response { alpha, beta } => {
+                out.push(TAG_RESPONSE);
+                put_bytes(&mut out, &alpha.to_bytes_be());
+                put_bytes(&mut out, &beta.to_bytes_be());
+            }
+            ProtocolMessage::Verdict { accepted } => {
+                out.push(TAG_VERDICT);
+                out.push(*accepted as u8);
+            }
+            ProtocolMessage::DleqCommitment { u1, u2, t1, t2 } => {
+                out.push(TAG_DLEQ_COMMITMENT);
+                for x in [u1, u2, t1, t2] {
+                    put_bytes(&mut out, &x.to_bytes_be());
+                }
+            }
+            ProtocolMessage::DleqResponse { z } => {
+                out.push(TAG_DLEQ_RESPONSE);
+                put_bytes(&mut out, &z.to_bytes_be());
+            }
+            ProtocolMessage::Abort { reason } => {
+                out.push(TAG_ABORT);
+                put_bytes(&mut out, reason.as_bytes());
+            }
+            ProtocolMessage::ChallengeCommitment { digest } => {
+                out.push(TAG_CHALLENGE_COMMITMENT);
+                put_bytes(&mut out, &digest.to_bytes_be());
+            }
+            ProtocolMessage::ChallengeReveal { c, salt } => {
+                out.push(TAG_CHALLENGE_REVEAL);
+                put_bytes(&mut out, &c.to_bytes_be());
+                put_bytes(&mut out, &salt.to_bytes_be());
+            }
+        }
+        out
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { data, pos: 0 };
+        let message = match reader.byte()? {
+            TAG_COMMITMENT => ProtocolMessage::Commitment {
+                u: reader.biguint()?,
+                ut: reader.biguint()?,
+            },
+            TAG_CHALLENGE => ProtocolMessage::Challenge {
+                c: reader.biguint()?,
+            },
+            TAG_RESPONSE => ProtocolMessage::Response {
+                alpha: reader.biguint()?,
+                beta: reader.biguint()?,
+            },
+            TAG_VERDICT => match reader.byte()? {
+                0 => ProtocolMessage::Verdict { accepted: false },
+                1 => ProtocolMessage::Verdict { accepted: true },
+                b => return Err(format!("Некорректное значение вердикта: {}", b)),
+            },
+            TAG_DLEQ_COMMITMENT => ProtocolMessage::DleqCommitment {
+                u1: reader.biguint()?,
+                u2: reader.biguint()?,
+                t1: reader.biguint()?,
+                t2: reader.biguint()?,
+            },
+            TAG_DLEQ_RESPONSE => ProtocolMessage::DleqResponse {
+                z: reader.biguint()?,
+            },
+            TAG_ABORT => ProtocolMessage::Abort {
+                reason: String::from_utf8(reader.bytes()?.to_vec()).map_err(|e| e.to_string())?,
+            },
+            TAG_CHALLENGE_COMMITMENT => ProtocolMessage::ChallengeCommitment {
+                digest: reader.biguint()?,
+            },
+            TAG_CHALLENGE_REVEAL => ProtocolMessage::ChallengeReveal {
+                c: reader.biguint()?,
+                salt: reader.biguint()?,
+            },
+            tag => return Err(format!("Неизвестный тип сообщения: {}", tag)),
+        };
+        if reader.pos != data.len() {
+            return Err("Лишние байты в конце сообщения".to_string());
+        }
+        Ok(message)
+    }
+
+    //Человекочитаемое описание для журнала
+    pub fn describe(&self) -> String {
+        match self {
+            ProtocolMessage::Commitment { u, ut } => {
+                format!("публичный ключ u = {}, коммитмент u_t = {}", u, ut)
+            }
+            ProtocolMessage::Challenge { c } => format!("испытание c = {}", c),
+            ProtocolMessage::Response { alpha, beta } => {
+                format!("ответ a_z = {}, b_z = {}", alpha, beta)
+            }
+            ProtocolMessage::Verdict { accepted: true } => {
+                "вердикт: секретный ключ подтвержден".to_string()
+            }
+            ProtocolMessage::Verdict { accepted: false } => {
+                "вердикт: секретный ключ не подтвержден".to_string()
+            }
+            ProtocolMessage::DleqCommitment { u1, u2, t1, t2 } => format!(
+                "u1 = {}, u2 = {}, коммитмент t1 = {}, t2 = {}",
+                u1, u2, t1, t2
+            ),
+            ProtocolMessage::DleqResponse { z } => format!("ответ z = {}", z),
+            ProtocolMessage::Abort { reason } => format!("протокол прерван: {}", reason),
+            ProtocolMessage::ChallengeCommitment { digest } => {
+                format!("коммитмент испытания H(s, c) = {}", digest)
+            }
+            ProtocolMessage::ChallengeReveal { c, salt } => {
+                format!("раскрытое испытание c = {}, соль s = {}", c, salt)
+            }
+        }
+    }
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    out.extend_from_slice(bytes);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.data.len() - self.pos < n {
+            return Err("Неожиданный конец сообщения".to_string());
+        }
+        let slice = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(slice)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], String> {
+        let len = u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as usize;
+        self.take(len)
+    }
+
+    fn biguint(&mut self) -> Result<BigUint, String> {
+        Ok(BigUint::from_bytes_be(self.bytes()?))
+    }
+}
+
+//Сериализация BigUint в JSON десятичной строкой
+pub mod decimal {
+    use num_bigint::BigUint;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &BigUint, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigUint, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+//Список чисел - массив десятичных строк
+pub mod decimal_vec {
+    use num_bigint::BigUint;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(values: &[BigUint], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(|v| v.to_string()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<BigUint>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| s.parse().map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples() -> Vec<ProtocolMessage> {
+        vec![
+            ProtocolMessage::Commitment {
+                u: BigUint::from(9u32),
+                ut: BigUint::parse_bytes(b"123456789012345678901234567890", 10).unwrap(),
+            },
+            ProtocolMessage::Challenge {
+                c: BigUint::from(0u32),
+            },
+            ProtocolMessage::Response {
+                alpha: BigUint::from(5u32),
+                beta: BigUint::from(70000u32),
+            },
+            ProtocolMessage::Verdict { accepted: true },
+            ProtocolMessage::Verdict { accepted: false },
+            ProtocolMessage::DleqCommitment {
+                u1: BigUint::from(1u32),
+                u2: BigUint::from(2u32),
+                t1: BigUint::from(300u32),
+                t2: BigUint::from(0u32),
+            },
+            ProtocolMessage::DleqResponse {
+                z: BigUint::from(17u32),
+            },
+            ProtocolMessage::Abort {
+                reason: "ошибка".to_string(),
+            },
+            ProtocolMessage::ChallengeCommitment {
+                digest: BigUint::from(u64::MAX),
+            },
+            ProtocolMessage::ChallengeReveal {
+                c: BigUint::from(4u32),
+                salt: BigUint::from(0u32),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        for message in samples() {
+            let bytes = message.to_bytes();
+            assert_eq!(ProtocolMessage::from_bytes(&bytes).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        for message in samples() {
+            let json = message.to_json().unwrap();
+            assert_eq!(ProtocolMessage::from_json(&json).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn test_json_format() {
+        let message = ProtocolMessage::Challenge {
+            c: BigUint::from(4u32),
+        };
+        assert_eq!(
+            message.to_json().unwrap(),
+            r#"{"type":"challenge","c":"4"}"#
+        );
+    }
+
+    #[test]
+    fn test_binary_rejects_malformed() {
+        assert!(ProtocolMessage::from_bytes(&[]).is_err());
+        assert!(ProtocolMessage::from_bytes(&[42]).is_err());
+        assert!(ProtocolMessage::from_bytes(&[TAG_CHALLENGE, 0, 0, 0, 5, 1]).is_err());
+        assert!(ProtocolMessage::from_bytes(&[TAG_VERDICT, 2]).is_err());
+        assert!(ProtocolMessage::from_bytes(&[TAG_VERDICT, 1, 0]).is_err());
+    }
+}
@@ -0,0 +1,175 @@
+//////////////////////////////////////////////////////////////
+// Вычеты по модулю: значение всегда лежит в [0, modulus), модуль
+// общий через Arc. Операция над вычетами с разными модулями -
+// ошибка программы и вызывает панику, как переполнение, вместо
+// тихого результата по чужому модулю.
+//////////////////////////////////////////////////////////////
+
+use std::ops::{Add, Mul, Neg, Sub};
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+use crate::math::{self, MathError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModInt {
+    value: BigUint,
+    modulus: Arc<BigUint>,
+}
+
+impl ModInt {
+    //Модуль должен быть ненулевым
+    pub fn new(value: BigUint, modulus: Arc<BigUint>) -> Self {
+        assert!(!modulus.is_zero(), "Нулевой модуль вычета");
+        ModInt {
+            value: value % &*modulus,
+            modulus,
+        }
+    }
+
+    //Вычет с тем же модулем
+    pub fn lift(&self, value: BigUint) -> Self {
+        ModInt::new(value, self.modulus.clone())
+    }
+
+    pub fn value(&self) -> &BigUint {
+        &self.value
+    }
+
+    pub fn into_value(self) -> BigUint {
+        self.value
+    }
+
+    pub fn modulus(&self) -> &BigUint {
+        &self.modulus
+    }
+
+    pub fn pow(&self, exp: &BigUint) -> Self {
+        self.lift(self.value.modpow(exp, &self.modulus))
+    }
+
+    //Ошибка, если значение не взаимно просто с модулем
+    pub fn inverse(&self) -> Result<Self, MathError> {
+        math::modular_inverse_euclidean(&self.value, &self.modulus).map(|v| self.lift(v))
+    }
+
+    fn check(&self, other: &ModInt) {
+        assert!(
+            Arc::ptr_eq(&self.modulus, &other.modulus) || self.modulus == other.modulus,
+            "Вычеты по разным модулям: {} и {}",
+            self.modulus,
+            other.modulus
+        );
+    }
+}
+
+//Операция для всех сочетаний ссылок и значений; правым операндом может
+//быть и BigUint - он приводится по модулю левого
+macro_rules! impl_op {
+    ($op:ident, $method:ident, |$a:ident, $b:ident, $m:ident| $body:expr) => {
+        impl $op<&ModInt> for &ModInt {
+            type Output = ModInt;
+
+            fn $method(self, other: &ModInt) -> ModInt {
+                self.check(other);
+                let ($a, $b, $m) = (&self.value, &other.value, &*self.modulus);
+                self.lift($body)
+            }
+        }
+
+        impl $op<ModInt> for ModInt {
+            type Output = ModInt;
+
+            fn $method(self, other: ModInt) -> ModInt {
+                (&self).$method(&other)
+            }
+        }
+
+        impl $op<&ModInt> for ModInt {
+            type Output = ModInt;
+
+            fn $method(self, other: &ModInt) -> ModInt {
+                (&self).$method(other)
+            }
+        }
+
+        impl $op<&BigUint> for &ModInt {
+            type Output = ModInt;
+
+            fn $method(self, other: &BigUint) -> ModInt {
+                self.$method(&self.lift(other.clone()))
+            }
+        }
+
+        impl $op<&BigUint> for ModInt {
+            type Output = ModInt;
+
+            fn $method(self, other: &BigUint) -> ModInt {
+                (&self).$method(other)
+            }
+        }
+    };
+}
+
+impl_op!(Add, add, |a, b, _m| a + b);
+impl_op!(Sub, sub, |a, b, m| a + m - b);
+impl_op!(Mul, mul, |a, b, _m| a * b);
+
+impl Neg for &ModInt {
+    type Output = ModInt;
+
+    fn neg(self) -> ModInt {
+        self.lift(&*self.modulus - &self.value)
+    }
+}
+
+impl Neg for ModInt {
+    type Output = ModInt;
+
+    fn neg(self) -> ModInt {
+        -&self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic_mod_q() {
+        let q = Arc::new(BigUint::from(11u32));
+        let x = ModInt::new(BigUint::from(25u32), q.clone());
+        assert_eq!(x.value(), &BigUint::from(3u32));
+        let y = x.lift(BigUint::from(9u32));
+        assert_eq!((&x + &y).into_value(), BigUint::from(1u32));
+        assert_eq!((&x - &y).into_value(), BigUint::from(5u32));
+        assert_eq!((&x * &y).into_value(), BigUint::from(5u32));
+        assert_eq!((-&x).into_value(), BigUint::from(8u32));
+        assert_eq!((-x.lift(BigUint::zero())).into_value(), BigUint::zero());
+        assert_eq!(
+            x.pow(&BigUint::from(10u32)).into_value(),
+            BigUint::from(1u32)
+        );
+        assert_eq!(
+            (x.inverse().unwrap() * &x).into_value(),
+            BigUint::from(1u32)
+        );
+        assert_eq!(
+            x.lift(BigUint::from(22u32)).inverse(),
+            Err(MathError::NoInverse)
+        );
+        //a_z = alpha_t + alpha * c
+        let c = BigUint::from(7u32);
+        assert_eq!((y.clone() + x * &c).into_value(), BigUint::from(8u32));
+    }
+
+    #[test]
+    #[should_panic(expected = "Вычеты по разным модулям")]
+    fn test_mixed_moduli_panic() {
+        let a = ModInt::new(BigUint::from(3u32), Arc::new(BigUint::from(11u32)));
+        let b = ModInt::new(BigUint::from(3u32), Arc::new(BigUint::from(23u32)));
+        let _ = a + b;
+    }
+}
@@ -0,0 +1,100 @@
+//////////////////////////////////////////////////////////////
+// Группы MODP из RFC 3526: безопасные простые p = 2q + 1,
+// образующая g = 2 порождает подгруппу квадратов порядка q.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+
+use crate::group::{ModGroup, Setting};
+use crate::protocol::PublicParams;
+
+//p = 2^2048 - 2^1984 - 1 + 2^64 * ([2^1918 * pi] + 124476)
+const MODP_2048: &str = concat!(
+    "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74",
+    "020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437",
+    "4FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7ED",
+    "EE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF05",
+    "98DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB",
+    "9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3B",
+    "E39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF695581718",
+    "3995497CEA956AE515D2261898FA051015728E5A8AACAA68FFFFFFFFFFFFFFFF",
+);
+
+//p = 2^3072 - 2^3008 - 1 + 2^64 * ([2^2942 * pi] + 1690314)
+const MODP_3072: &str = concat!(
+    "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74",
+    "020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437",
+    "4FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7ED",
+    "EE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF05",
+    "98DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB",
+    "9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3B",
+    "E39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF695581718",
+    "3995497CEA956AE515D2261898FA051015728E5A8AAAC42DAD33170D04507A33",
+    "A85521ABDF1CBA64ECFB850458DBEF0A8AEA71575D060C7DB3970F85A6E1E4C7",
+    "ABF5AE8CDB0933D71E8C94E04A25619DCEE3D2261AD2EE6BF12FFA06D98A0864",
+    "D87602733EC86A64521F2B18177B200CBBE117577A615D6C770988C0BAD946E2",
+    "08E24FA074E5AB3143DB5BFCE0FD108E4B82D120A93AD2CAFFFFFFFFFFFFFFFF",
+);
+
+//p = 2^4096 - 2^4032 - 1 + 2^64 * ([2^3966 * pi] + 240904)
+const MODP_4096: &str = concat!(
+    "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74",
+    "020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437",
+    "4FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7ED",
+    "EE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF05",
+    "98DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB",
+    "9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3B",
+    "E39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF695581718",
+    "3995497CEA956AE515D2261898FA051015728E5A8AAAC42DAD33170D04507A33",
+    "A85521ABDF1CBA64ECFB850458DBEF0A8AEA71575D060C7DB3970F85A6E1E4C7",
+    "ABF5AE8CDB0933D71E8C94E04A25619DCEE3D2261AD2EE6BF12FFA06D98A0864",
+    "D87602733EC86A64521F2B18177B200CBBE117577A615D6C770988C0BAD946E2",
+    "08E24FA074E5AB3143DB5BFCE0FD108E4B82D120A92108011A723C12A787E6D7",
+    "88719A10BDBA5B2699C327186AF4E23C1A946834B6150BDA2583E9CA2AD44CE8",
+    "DBBBC2DB04DE8EF92E8EFC141FBECAA6287C59474E6BC05D99B2964FA090C3A2",
+    "233BA186515BE7ED1F612970CEE2D7AFB81BDD762170481CD0069127D5B05AA9",
+    "93B4EA988D8FDDC186FFB7DC90A6C08F4DF435C934063199FFFFFFFFFFFFFFFF",
+);
+
+//Параметры для p длиной 2048, 3072 или 4096 бит
+pub fn params(bits: u32) -> Option<PublicParams> {
+    let hex = match bits {
+        2048 => MODP_2048,
+        3072 => MODP_3072,
+        4096 => MODP_4096,
+        _ => return None,
+    };
+    let p = BigUint::parse_bytes(hex.as_bytes(), 16)?;
+    let q = (&p - 1u32) / 2u32;
+    //h выводится из g = 2 хэшированием в подгруппу квадратов
+    Some(Setting::derive(ModGroup::new(p, q), BigUint::from(2u32)).params())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::Group;
+    use crate::protocol::{Key, Prover, Verifier};
+
+    #[test]
+    fn test_generators_in_subgroup() {
+        let params = params(2048).unwrap();
+        assert_eq!(params.p.bits(), 2048);
+        assert_eq!(params.p, &params.q * 2u32 + 1u32);
+        let group = params.setting().group;
+        assert_eq!(group.exp(&params.g, &params.q), group.identity());
+        assert_eq!(group.exp(&params.h, &params.q), group.identity());
+        assert!(params.h > group.identity());
+        assert!(params.setting().h_is_derived());
+        assert!(super::params(1024).is_none());
+    }
+
+    #[test]
+    fn test_proof_over_modp2048() {
+        let params = params(2048).unwrap();
+        let secret = Key::random(&params.order());
+        let prover = Prover::new(params.clone(), secret).unwrap();
+        let proof = prover.prove_non_interactive().unwrap();
+        assert!(Verifier::new(params).verify_non_interactive(&proof));
+    }
+}
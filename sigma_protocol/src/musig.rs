@@ -0,0 +1,541 @@
+//////////////////////////////////////////////////////////////
+// Совместная подпись Шнорра по схеме MuSig: n участников подписывают
+// одно сообщение, и подпись проверяется одним агрегированным ключом
+// X = П X_i^a_i, где a_i = H(L, X_i), L - список всех ключей.
+// Коэффициенты a_i не дают участнику подобрать свой ключ так, чтобы
+// подчинить себе агрегированный.
+//
+// Раунды: сначала участники обмениваются хэшами t_i = H(R_i) своих
+// одноразовых R_i = g^r_i, затем раскрывают R_i, затем отвечают
+// s_i = r_i + c * a_i * x_i, где c = H(X, R, m), R = П R_i.
+// Подпись (R, s = Σ s_i) проверяется как обычная: g^s = R * X^c.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+#[cfg(feature = "server")]
+use std::sync::{Arc, Mutex};
+
+use crate::fiat_shamir::absorb;
+use crate::group::{Group, Setting};
+use crate::key_gen;
+use crate::message::{decimal, decimal_vec};
+#[cfg(feature = "server")]
+use crate::{config::Address, threshold};
+
+pub const COMMIT_PATH: &str = "/musig/commit";
+pub const REVEAL_PATH: &str = "/musig/reveal";
+pub const SIGN_PATH: &str = "/musig/sign";
+
+//Домены хэшей: коэффициенты ключей, хэши R_i и испытание подписи
+const KEY_DOMAIN: &str = "sigma_protocol musig key";
+const NONCE_DOMAIN: &str = "sigma_protocol musig nonce";
+const SIGN_DOMAIN: &str = "sigma_protocol musig sign";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CommitRequest {
+    pub session: String,
+}
+
+//Ключ участника и хэш t_i = H(R_i)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NonceCommitment {
+    #[serde(with = "decimal")]
+    pub public_key: BigUint,
+    #[serde(with = "decimal")]
+    pub t: BigUint,
+}
+
+//Хэши всех участников в порядке их ключей
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RevealRequest {
+    pub session: String,
+    #[serde(with = "decimal_vec")]
+    pub commitments: Vec<BigUint>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NonceReveal {
+    #[serde(with = "decimal")]
+    pub r: BigUint,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SignRequest {
+    pub session: String,
+    #[serde(with = "decimal_vec")]
+    pub keys: Vec<BigUint>,
+    #[serde(with = "decimal_vec")]
+    pub nonces: Vec<BigUint>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PartialSignature {
+    #[serde(with = "decimal")]
+    pub s: BigUint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MuSigSignature {
+    #[serde(with = "decimal")]
+    pub r: BigUint,
+    #[serde(with = "decimal")]
+    pub s: BigUint,
+}
+
+//Полный SHA-256 без приведения по модулю q
+fn digest<G: Group>(setting: &Setting<G>, domain: &str, parts: &[&[u8]]) -> BigUint {
+    let group = &setting.group;
+    let mut hasher = Sha256::new();
+    absorb(&mut hasher, domain.as_bytes());
+    absorb(&mut hasher, &group.describe());
+    absorb(&mut hasher, &group.serialize(&setting.g));
+    for part in parts {
+        absorb(&mut hasher, part);
+    }
+    BigUint::from_bytes_be(&hasher.finalize())
+}
+
+//a_i = H(X_1, ..., X_n, X_i)
+pub fn key_coefficient<G: Group>(setting: &Setting<G>, keys: &[BigUint], key: &BigUint) -> BigUint {
+    let encoded: Vec<Vec<u8>> = keys.iter().chain([key]).map(|k| k.to_bytes_be()).collect();
+    let parts: Vec<&[u8]> = encoded.iter().map(|k| k.as_slice()).collect();
+    digest(setting, KEY_DOMAIN, &parts) % setting.order()
+}
+
+//X = П X_i^a_i; None, если ключ вне группы
+pub fn aggregate_key<G: Group>(setting: &Setting<G>, keys: &[BigUint]) -> Option<G::Element> {
+    let group = &setting.group;
+    keys.iter().try_fold(group.identity(), |acc, key| {
+        let x = group.decode(key)?;
+        let a = key_coefficient(setting, keys, key);
+        Some(group.mul(&acc, &group.exp(&x, &a)))
+    })
+}
+
+//t_i = H(R_i); не приводится по модулю q, чтобы в малых группах
+//хэши разных R не совпадали
+pub fn nonce_commitment<G: Group>(setting: &Setting<G>, r: &BigUint) -> BigUint {
+    digest(setting, NONCE_DOMAIN, &[&r.to_bytes_be()])
+}
+
+//c = H(X, R, m)
+fn challenge<G: Group>(
+    setting: &Setting<G>,
+    aggregate: &BigUint,
+    r: &BigUint,
+    message: &[u8],
+) -> BigUint {
+    digest(
+        setting,
+        SIGN_DOMAIN,
+        &[&aggregate.to_bytes_be(), &r.to_bytes_be(), message],
+    ) % setting.order()
+}
+
+//R = П R_i
+fn joint_nonce<G: Group>(setting: &Setting<G>, nonces: &[BigUint]) -> Option<G::Element> {
+    let group = &setting.group;
+    nonces.iter().try_fold(group.identity(), |acc, r| {
+        Some(group.mul(&acc, &group.decode(r)?))
+    })
+}
+
+//g^s == R * X^c
+pub fn verify<G: Group>(
+    setting: &Setting<G>,
+    aggregate: &BigUint,
+    message: &[u8],
+    signature: &MuSigSignature,
+) -> bool {
+    let group = &setting.group;
+    let (Some(x), Some(r)) = (group.decode(aggregate), group.decode(&signature.r)) else {
+        return false;
+    };
+    if signature.s >= setting.order() {
+        return false;
+    }
+    let c = challenge(setting, aggregate, &signature.r, message);
+    group.exp(&setting.g, &signature.s) == group.mul(&r, &group.exp(&x, &c))
+}
+
+//////////////////////////////////////////////////////////////
+// Участник: ключ x и одноразовые r открытых сессий
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+struct Session {
+    r: BigUint,
+    //Хэши всех участников; известны после раскрытия
+    commitments: Option<Vec<BigUint>>,
+}
+
+#[derive(Debug)]
+pub struct Signer {
+    x: BigUint,
+    pending: HashMap<String, Session>,
+}
+
+impl Signer {
+    pub fn new(x: BigUint) -> Self {
+        Signer {
+            x,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn random(order: &BigUint) -> Self {
+        Signer::new(key_gen::random_biguint_mod(order))
+    }
+
+    pub fn public_key<G: Group>(&self, setting: &Setting<G>) -> G::Element {
+        setting.group.exp(&setting.g, &self.x)
+    }
+
+    //Первый раунд: только хэш R_i, сам R_i пока скрыт
+    pub fn commit<G: Group>(
+        &mut self,
+        setting: &Setting<G>,
+        request: &CommitRequest,
+        r: BigUint,
+    ) -> Result<NonceCommitment, String> {
+        if self.pending.contains_key(&request.session) {
+            return Err(format!("Сессия {} уже открыта", request.session));
+        }
+        let group = &setting.group;
+        let nonce = group.encode(&group.exp(&setting.g, &r));
+        let commitment = NonceCommitment {
+            public_key: group.encode(&self.public_key(setting)),
+            t: nonce_commitment(setting, &nonce),
+        };
+        self.pending.insert(
+            request.session.clone(),
+            Session {
+                r,
+                commitments: None,
+            },
+        );
+        Ok(commitment)
+    }
+
+    //Второй раунд: R_i раскрывается после получения хэшей всех участников
+    pub fn reveal<G: Group>(
+        &mut self,
+        setting: &Setting<G>,
+        request: &RevealRequest,
+    ) -> Result<NonceReveal, String> {
+        let session = self
+            .pending
+            .get_mut(&request.session)
+            .ok_or_else(|| format!("Нет открытой сессии {}", request.session))?;
+        if session.commitments.is_some() {
+            return Err(format!("R уже раскрыт в сессии {}", request.session));
+        }
+        let group = &setting.group;
+        let r = group.encode(&group.exp(&setting.g, &session.r));
+        if !request.commitments.contains(&nonce_commitment(setting, &r)) {
+            return Err("Среди хэшей нет хэша этого участника".to_string());
+        }
+        session.commitments = Some(request.commitments.clone());
+        Ok(NonceReveal { r })
+    }
+
+    //Третий раунд: s_i = r_i + c * a_i * x_i; сессия закрывается
+    pub fn sign<G: Group>(
+        &mut self,
+        setting: &Setting<G>,
+        request: &SignRequest,
+    ) -> Result<PartialSignature, String> {
+        let session = self
+            .pending
+            .remove(&request.session)
+            .ok_or_else(|| format!("Нет открытой сессии {}", request.session))?;
+        let commitments = session
+            .commitments
+            .ok_or_else(|| format!("R не раскрыт в сессии {}", request.session))?;
+        let n = commitments.len();
+        if request.keys.len() != n || request.nonces.len() != n {
+            return Err("Число ключей, R и хэшей не совпадает".to_string());
+        }
+        //R_j должны совпасть с хэшами, присланными до раскрытия
+        let opened = request
+            .nonces
+            .iter()
+            .zip(&commitments)
+            .all(|(r, t)| nonce_commitment(setting, r) == *t);
+        if !opened {
+            return Err("Раскрытый R не совпадает с хэшем первого раунда".to_string());
+        }
+        let group = &setting.group;
+        let own = group.encode(&self.public_key(setting));
+        let index = request
+            .keys
+            .iter()
+            .position(|key| *key == own)
+            .ok_or("Ключ участника не входит в список")?;
+        if request.nonces[index] != group.encode(&group.exp(&setting.g, &session.r)) {
+            return Err("R участника подменен".to_string());
+        }
+
+        let order = setting.order();
+        let aggregate = aggregate_key(setting, &request.keys).ok_or("Ключ вне группы")?;
+        let r = joint_nonce(setting, &request.nonces).ok_or("R вне группы")?;
+        let c = challenge(
+            setting,
+            &group.encode(&aggregate),
+            &group.encode(&r),
+            request.message.as_bytes(),
+        );
+        let a = key_coefficient(setting, &request.keys, &own);
+        Ok(PartialSignature {
+            s: (session.r + c * a % &order * &self.x) % &order,
+        })
+    }
+}
+
+//Участник в этом процессе или на другом сервере
+#[cfg(feature = "server")]
+#[derive(Debug, Clone)]
+pub enum Participant {
+    Local(Arc<Mutex<Signer>>),
+    Remote {
+        address: Address,
+        client: reqwest::Client,
+    },
+}
+
+#[cfg(feature = "server")]
+impl Participant {
+    pub fn remote(address: Address) -> Self {
+        Participant::Remote {
+            address,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn commit<G: Group>(
+        &self,
+        setting: &Setting<G>,
+        request: &CommitRequest,
+    ) -> Result<NonceCommitment, String> {
+        match self {
+            Participant::Local(signer) => {
+                let r = setting.group.random_scalar();
+                signer.lock().unwrap().commit(setting, request, r)
+            }
+            Participant::Remote { address, client } => {
+                threshold::post(client, address, COMMIT_PATH, request).await
+            }
+        }
+    }
+
+    pub async fn reveal<G: Group>(
+        &self,
+        setting: &Setting<G>,
+        request: &RevealRequest,
+    ) -> Result<NonceReveal, String> {
+        match self {
+            Participant::Local(signer) => signer.lock().unwrap().reveal(setting, request),
+            Participant::Remote { address, client } => {
+                threshold::post(client, address, REVEAL_PATH, request).await
+            }
+        }
+    }
+
+    pub async fn sign<G: Group>(
+        &self,
+        setting: &Setting<G>,
+        request: &SignRequest,
+    ) -> Result<PartialSignature, String> {
+        match self {
+            Participant::Local(signer) => signer.lock().unwrap().sign(setting, request),
+            Participant::Remote { address, client } => {
+                threshold::post(client, address, SIGN_PATH, request).await
+            }
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Координатор: пересылает сообщения раундов и собирает подпись.
+// Секретов он не знает и может быть любым из участников
+//////////////////////////////////////////////////////////////
+
+//Все значения совместной подписи для журнала
+#[cfg(feature = "server")]
+#[derive(Debug, Clone)]
+pub struct Signing {
+    pub keys: Vec<BigUint>,
+    pub aggregate: BigUint,
+    pub commitments: Vec<BigUint>,
+    pub nonces: Vec<BigUint>,
+    pub partials: Vec<BigUint>,
+    pub signature: MuSigSignature,
+}
+
+#[cfg(feature = "server")]
+#[derive(Debug, Clone)]
+pub struct Coordinator<G: Group> {
+    setting: Setting<G>,
+    participants: Vec<Participant>,
+}
+
+#[cfg(feature = "server")]
+impl<G: Group> Coordinator<G> {
+    pub fn new(setting: Setting<G>, participants: Vec<Participant>) -> Self {
+        Coordinator {
+            setting,
+            participants,
+        }
+    }
+
+    //Подписывают все участники; отказ любого прерывает подпись
+    pub async fn sign(&self, message: &str) -> Result<Signing, String> {
+        if self.participants.is_empty() {
+            return Err("Нет участников подписи".to_string());
+        }
+        let setting = &self.setting;
+        let group = &setting.group;
+        let order = setting.order();
+        let session = format!("{:032x}", rand::random::<u128>());
+
+        let request = CommitRequest {
+            session: session.clone(),
+        };
+        let mut keys = Vec::new();
+        let mut commitments = Vec::new();
+        for participant in &self.participants {
+            let commitment = participant.commit(setting, &request).await?;
+            keys.push(commitment.public_key);
+            commitments.push(commitment.t);
+        }
+        let aggregate = aggregate_key(setting, &keys).ok_or("Ключ участника вне группы")?;
+
+        let request = RevealRequest {
+            session: session.clone(),
+            commitments: commitments.clone(),
+        };
+        let mut nonces = Vec::new();
+        for participant in &self.participants {
+            nonces.push(participant.reveal(setting, &request).await?.r);
+        }
+
+        let request = SignRequest {
+            session,
+            keys: keys.clone(),
+            nonces: nonces.clone(),
+            message: message.to_string(),
+        };
+        let r = joint_nonce(setting, &nonces).ok_or("R участника вне группы")?;
+        let (aggregate, r) = (group.encode(&aggregate), group.encode(&r));
+        let c = challenge(setting, &aggregate, &r, message.as_bytes());
+        let mut partials = Vec::new();
+        for (i, participant) in self.participants.iter().enumerate() {
+            let s = participant.sign(setting, &request).await?.s;
+            //g^s_i == R_i * X_i^(c * a_i) указывает на нечестного участника
+            let (Some(x), Some(r_i)) = (group.decode(&keys[i]), group.decode(&nonces[i])) else {
+                return Err(format!("Участник {} прислал не элемент группы", i + 1));
+            };
+            let e = &c * key_coefficient(setting, &keys, &keys[i]) % &order;
+            if group.exp(&setting.g, &s) != group.mul(&r_i, &group.exp(&x, &e)) {
+                return Err(format!("Частичная подпись участника {} неверна", i + 1));
+            }
+            partials.push(s);
+        }
+        let s = partials.iter().fold(BigUint::ZERO, |acc, s| acc + s) % &order;
+
+        Ok(Signing {
+            keys,
+            aggregate,
+            commitments,
+            nonces,
+            partials,
+            signature: MuSigSignature { r, s },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::toy_params;
+    #[cfg(feature = "server")]
+    use crate::ristretto;
+
+    #[cfg(feature = "server")]
+    fn participants<G: Group>(setting: &Setting<G>, n: usize) -> Vec<Participant> {
+        let mut participants = Vec::new();
+        for _ in 0..n {
+            let signer = Signer::random(&setting.order());
+            participants.push(Participant::Local(Arc::new(Mutex::new(signer))));
+        }
+        participants
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_joint_signature() {
+        let setting = ristretto::setting();
+        let coordinator = Coordinator::new(setting.clone(), participants(&setting, 3));
+        let signing = coordinator.sign("message").await.unwrap();
+        assert_eq!(signing.partials.len(), 3);
+        let aggregate = &signing.aggregate;
+        assert!(verify(&setting, aggregate, b"message", &signing.signature));
+        assert!(!verify(&setting, aggregate, b"other", &signing.signature));
+
+        //Произведение ключей без коэффициентов - не агрегированный ключ
+        let group = &setting.group;
+        let product = signing.keys.iter().fold(group.identity(), |acc, key| {
+            group.mul(&acc, &group.decode(key).unwrap())
+        });
+        assert_ne!(group.encode(&product), *aggregate);
+        assert!(Coordinator::new(setting, vec![]).sign("m").await.is_err());
+    }
+
+    #[test]
+    fn test_signer_rounds() {
+        let setting = toy_params().setting();
+        let mut signer = Signer::new(BigUint::from(3u32));
+        let mut other = Signer::new(BigUint::from(5u32));
+        let commit = CommitRequest {
+            session: "s".to_string(),
+        };
+        let own = signer
+            .commit(&setting, &commit, BigUint::from(2u32))
+            .unwrap();
+        let theirs = other
+            .commit(&setting, &commit, BigUint::from(7u32))
+            .unwrap();
+        assert!(
+            signer
+                .commit(&setting, &commit, BigUint::from(2u32))
+                .is_err()
+        );
+
+        //Без своего хэша участник R не раскрывает
+        let foreign = RevealRequest {
+            session: "s".to_string(),
+            commitments: vec![theirs.t.clone()],
+        };
+        assert!(signer.reveal(&setting, &foreign).is_err());
+        let reveal = RevealRequest {
+            session: "s".to_string(),
+            commitments: vec![own.t.clone(), theirs.t.clone()],
+        };
+        let r = signer.reveal(&setting, &reveal).unwrap().r;
+        assert!(signer.reveal(&setting, &reveal).is_err());
+
+        //R второго участника, не совпадающий с его хэшем, отвергается
+        let sign = SignRequest {
+            session: "s".to_string(),
+            keys: vec![own.public_key.clone(), theirs.public_key.clone()],
+            nonces: vec![r, BigUint::from(4u32)],
+            message: "m".to_string(),
+        };
+        assert!(signer.sign(&setting, &sign).is_err());
+        assert!(signer.sign(&setting, &sign).is_err());
+    }
+}
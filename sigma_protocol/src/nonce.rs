@@ -0,0 +1,128 @@
+//////////////////////////////////////////////////////////////
+// Детерминированные одноразовые ключи по образцу RFC 6979:
+// (alpha_t, beta_t) выводятся через HMAC-DRBG (SHA-256) из секрета
+// и утверждения, поэтому сбой генератора случайных чисел не приводит
+// к повторному использованию одноразового ключа.
+//////////////////////////////////////////////////////////////
+
+use hmac::{Hmac, Mac};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::math;
+use crate::protocol::Key;
+
+type HmacSha256 = Hmac<Sha256>;
+
+//Способ получения одноразового ключа доказывающего
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NonceMode {
+    #[default]
+    Random,
+    //HMAC от секрета и утверждения (RFC 6979)
+    Deterministic,
+}
+
+//HMAC-DRBG из RFC 6979, 3.2; extra - дополнительные данные k' из 3.6
+struct Drbg {
+    k: [u8; 32],
+    v: [u8; 32],
+}
+
+impl Drbg {
+    fn new(seed: &[&[u8]]) -> Self {
+        let mut drbg = Drbg {
+            k: [0u8; 32],
+            v: [1u8; 32],
+        };
+        drbg.reseed(0x00, seed);
+        drbg.reseed(0x01, seed);
+        drbg
+    }
+
+    fn hmac(&self, parts: &[&[u8]]) -> [u8; 32] {
+        let mut mac = HmacSha256::new_from_slice(&self.k).expect("HMAC принимает ключ любой длины");
+        for part in parts {
+            mac.update(part);
+        }
+        mac.finalize().into_bytes().into()
+    }
+
+    //K = HMAC_K(V || byte || seed), V = HMAC_K(V)
+    fn reseed(&mut self, byte: u8, seed: &[&[u8]]) {
+        let byte = [byte];
+        let mut parts: Vec<&[u8]> = vec![&self.v, &byte];
+        parts.extend_from_slice(seed);
+        self.k = self.hmac(&parts);
+        self.v = self.hmac(&[&self.v]);
+    }
+
+    //Первое значение из [1, q) среди первых qlen бит выхода
+    fn next_scalar(&mut self, order: &BigUint) -> BigUint {
+        let qlen = order.bits();
+        loop {
+            let mut t = Vec::new();
+            while (t.len() as u64) * 8 < qlen {
+                self.v = self.hmac(&[&self.v]);
+                t.extend_from_slice(&self.v);
+            }
+            let k = BigUint::from_bytes_be(&t) >> ((t.len() as u64) * 8 - qlen);
+            //Следующее значение берется после обновления состояния
+            self.reseed(0x00, &[]);
+            if k > BigUint::ZERO && k < *order {
+                return k;
+            }
+        }
+    }
+}
+
+//Число длиной ceil(qlen / 8) байт (int2octets)
+fn int2octets(x: &BigUint, order: &BigUint) -> Vec<u8> {
+    math::i2osp(&(x % order), math::byte_len(order)).expect("вычет короче порядка")
+}
+
+//Одноразовый ключ для секрета и сообщения (описания утверждения);
+//при одинаковых аргументах результат одинаков
+pub fn derive(order: &BigUint, secret: &Key, message: &[u8], extra: &[u8]) -> Key {
+    let alpha = int2octets(&secret.alpha, order);
+    let beta = int2octets(&secret.beta, order);
+    let h1 = int2octets(&BigUint::from_bytes_be(&Sha256::digest(message)), order);
+    let mut drbg = Drbg::new(&[&alpha, &beta, &h1, extra]);
+    let alpha_t = drbg.next_scalar(order);
+    let beta_t = drbg.next_scalar(order);
+    Key::new(alpha_t, beta_t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_is_deterministic() {
+        let order = BigUint::parse_bytes(
+            b"FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
+            16,
+        )
+        .unwrap();
+        let secret = Key::new(BigUint::from(5u32), BigUint::from(2u32));
+        let nonce = derive(&order, &secret, b"statement", &[]);
+        assert_eq!(nonce, derive(&order, &secret, b"statement", &[]));
+        assert!(nonce.alpha < order && nonce.beta < order);
+        assert_ne!(nonce.alpha, nonce.beta);
+
+        //Любое изменение секрета, утверждения или доп. данных меняет ключ
+        let other = Key::new(BigUint::from(5u32), BigUint::from(3u32));
+        assert_ne!(nonce, derive(&order, &other, b"statement", &[]));
+        assert_ne!(nonce, derive(&order, &secret, b"statement2", &[]));
+        assert_ne!(nonce, derive(&order, &secret, b"statement", &[1]));
+
+        //Малый порядок: значения всегда в [1, q)
+        let small = BigUint::from(11u32);
+        for x in 0u32..20 {
+            let nonce = derive(&small, &Key::new(x.into(), 0u32.into()), b"", &[]);
+            assert!(nonce.alpha > BigUint::ZERO && nonce.alpha < small);
+        }
+    }
+}
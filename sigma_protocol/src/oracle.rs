@@ -0,0 +1,156 @@
+//////////////////////////////////////////////////////////////
+// Случайный оракул: хэш-функция, через которую выводятся
+// испытания Фиата-Шамира и элементы группы из байтов; expand
+// растягивает хэш до нужной длины, например для вывода ключей.
+// Функция выбирается в конфигурации (hash: sha256, sha3_256 или
+// blake3); все стороны протокола должны использовать одну и ту же.
+//////////////////////////////////////////////////////////////
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sha3::Sha3_256;
+
+pub trait RandomOracle: Clone + Send + Sync {
+    fn input(&mut self, bytes: &[u8]);
+
+    //32 байта выхода; состояние поглощается
+    fn output(self) -> [u8; 32];
+}
+
+impl RandomOracle for Sha256 {
+    fn input(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn output(self) -> [u8; 32] {
+        self.finalize().into()
+    }
+}
+
+impl RandomOracle for Sha3_256 {
+    fn input(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn output(self) -> [u8; 32] {
+        self.finalize().into()
+    }
+}
+
+impl RandomOracle for blake3::Hasher {
+    fn input(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn output(self) -> [u8; 32] {
+        self.finalize().into()
+    }
+}
+
+//Хэш-функция из конфигурации
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashKind {
+    #[default]
+    Sha256,
+    Sha3_256,
+    Blake3,
+}
+
+//Оракул выбранной функции; состояния SHA-3 и BLAKE3 велики, поэтому в куче
+#[derive(Debug, Clone)]
+pub enum Oracle {
+    Sha256(Sha256),
+    Sha3_256(Box<Sha3_256>),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl RandomOracle for Oracle {
+    fn input(&mut self, bytes: &[u8]) {
+        match self {
+            Oracle::Sha256(h) => h.input(bytes),
+            Oracle::Sha3_256(h) => h.input(bytes),
+            Oracle::Blake3(h) => h.input(bytes),
+        }
+    }
+
+    fn output(self) -> [u8; 32] {
+        match self {
+            Oracle::Sha256(h) => h.output(),
+            Oracle::Sha3_256(h) => (*h).output(),
+            Oracle::Blake3(h) => (*h).output(),
+        }
+    }
+}
+
+impl HashKind {
+    pub fn oracle(self) -> Oracle {
+        match self {
+            HashKind::Sha256 => Oracle::Sha256(Sha256::new()),
+            HashKind::Sha3_256 => Oracle::Sha3_256(Box::default()),
+            HashKind::Blake3 => Oracle::Blake3(Box::default()),
+        }
+    }
+
+    pub fn digest(self, bytes: &[u8]) -> [u8; 32] {
+        let mut oracle = self.oracle();
+        oracle.input(bytes);
+        oracle.output()
+    }
+
+    //Растяжение до len байт: блоки H(data, счетчик u32 BE)
+    pub fn expand(self, data: &[u8], len: usize) -> Vec<u8> {
+        let mut out = Vec::with_capacity(len + 32);
+        let mut counter = 0u32;
+        while out.len() < len {
+            let mut oracle = self.oracle();
+            oracle.input(data);
+            oracle.input(&counter.to_be_bytes());
+            out.extend(oracle.output());
+            counter += 1;
+        }
+        out.truncate(len);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::to_hex;
+
+    #[test]
+    fn test_known_digests() {
+        let hex = |bytes: [u8; 32]| to_hex(&bytes);
+        assert_eq!(
+            hex(HashKind::Sha256.digest(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex(HashKind::Sha3_256.digest(b"abc")),
+            "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"
+        );
+        assert_eq!(
+            hex(HashKind::Blake3.digest(b"abc")),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+        let parsed: HashKind = serde_json::from_str("\"sha3_256\"").unwrap();
+        assert_eq!(parsed, HashKind::Sha3_256);
+    }
+
+    #[test]
+    fn test_expand_prefix_and_kinds_differ() {
+        let long = HashKind::Blake3.expand(b"data", 100);
+        assert_eq!(long.len(), 100);
+        assert_eq!(HashKind::Blake3.expand(b"data", 40), long[..40]);
+        //Первый блок - хэш данных со счетчиком 0
+        let mut first = HashKind::Sha256.oracle();
+        first.input(b"data");
+        first.input(&0u32.to_be_bytes());
+        assert_eq!(HashKind::Sha256.expand(b"data", 32), first.output());
+        assert_ne!(
+            HashKind::Sha256.expand(b"data", 32),
+            HashKind::Sha3_256.expand(b"data", 32)
+        );
+    }
+}
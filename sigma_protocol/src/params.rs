@@ -0,0 +1,278 @@
+//////////////////////////////////////////////////////////////
+// Параметры группы Шнорра: безопасное простое p = 2q + 1 и
+// образующие g, h подгруппы квадратов порядка q.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::{BigUint, RandBigInt};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::certificate::{self, CertificateKind, PrimeCertificate};
+use crate::group::{Group, ModGroup, Setting};
+use crate::key_gen::{self, Primality, SearchError, SearchLimits, SearchProgress};
+use crate::message::decimal;
+use crate::protocol::PublicParams;
+
+//Раунды Миллера-Рабина при проверке параметров
+const PRIME_ROUNDS: u8 = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GroupParams {
+    #[serde(with = "decimal")]
+    pub p: BigUint,
+    #[serde(with = "decimal")]
+    pub q: BigUint,
+    #[serde(with = "decimal")]
+    pub g: BigUint,
+    #[serde(with = "decimal")]
+    pub h: BigUint,
+}
+
+impl GroupParams {
+    //Случайные параметры с модулем p длиной bits (bits >= 8)
+    pub fn generate(bits: u64) -> Self {
+        GroupParams::generate_reporting(bits, |_| {})
+    }
+
+    //То же с отчетом о ходе поиска безопасного простого
+    pub fn generate_reporting(bits: u64, report: impl Fn(SearchProgress) + Sync) -> Self {
+        GroupParams::generate_limited(
+            bits,
+            key_gen::DEFAULT_MR_ROUNDS,
+            SearchLimits::default(),
+            report,
+        )
+        .expect("поиск без пределов не останавливается")
+    }
+
+    //То же с заданным числом раундов Миллера-Рабина, сроком и отменой поиска
+    pub fn generate_limited(
+        bits: u64,
+        rounds: u8,
+        limits: SearchLimits,
+        report: impl Fn(SearchProgress) + Sync,
+    ) -> Result<Self, SearchError> {
+        let (p, q) = key_gen::gen_safe_prime_limited(&mut OsRng, bits, rounds, limits, report)?;
+        let g = subgroup_element(&p);
+        let setting = Setting::derive(ModGroup::new(p.clone(), q.clone()), g);
+        Ok(GroupParams {
+            p,
+            q,
+            g: setting.g,
+            h: setting.h,
+        })
+    }
+
+    //То же с доказуемо простым p: сертификат p включает сертификат q
+    pub fn generate_provable(bits: u64, kind: CertificateKind) -> (Self, PrimeCertificate) {
+        let certificate = certificate::gen_provable_safe_prime(bits, kind);
+        let p = certificate.n.clone();
+        let q: BigUint = (&p - 1u32) >> 1;
+        let g = subgroup_element(&p);
+        let setting = Setting::derive(ModGroup::new(p.clone(), q.clone()), g);
+        let params = GroupParams {
+            p,
+            q,
+            g: setting.g,
+            h: setting.h,
+        };
+        (params, certificate)
+    }
+
+    //Параметры протокола: элементы по модулю p, показатели по модулю q
+    pub fn public_params(&self) -> PublicParams {
+        PublicParams {
+            p: self.p.clone(),
+            q: self.q.clone(),
+            g: self.g.clone(),
+            h: self.h.clone(),
+        }
+    }
+}
+
+//Файл параметров: поиск простого нужной длины долог, поэтому параметры
+//можно сгенерировать заранее и не ждать его при каждом запуске сервера
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ParamsFile {
+    #[serde(flatten)]
+    pub params: GroupParams,
+    //Сертификат простоты p, если p сгенерировано доказуемо простым
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<PrimeCertificate>,
+    //Раунды теста и оценка ошибки, если простота p и q вероятностная
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primality: Option<Primality>,
+}
+
+impl ParamsFile {
+    pub fn generate(bits: u64, rounds: u8, report: impl Fn(SearchProgress) + Sync) -> Self {
+        let params = GroupParams::generate_limited(bits, rounds, SearchLimits::default(), report)
+            .expect("поиск без пределов не останавливается");
+        ParamsFile {
+            params,
+            certificate: None,
+            primality: Some(Primality::new(rounds)),
+        }
+    }
+
+    pub fn generate_provable(bits: u64, kind: CertificateKind) -> Self {
+        let (params, certificate) = GroupParams::generate_provable(bits, kind);
+        ParamsFile {
+            params,
+            certificate: Some(certificate),
+            primality: None,
+        }
+    }
+
+    //С сертификатом простота p и q доказана, без него - проверяется тестом
+    pub fn validate(&self) -> Result<(), String> {
+        let params = self.params.public_params();
+        let Some(certificate) = &self.certificate else {
+            return validate(&params);
+        };
+        if certificate.n != params.p {
+            return Err("Сертификат выдан не для p".to_string());
+        }
+        certificate.verify()?;
+        if params.p != &params.q * 2u32 + 1u32 {
+            return Err("p не равно 2q + 1".to_string());
+        }
+        validate_generators(&params)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    //Параметры из файла проверяются: файл мог быть изменен вручную
+    pub fn load(path: &str) -> Result<Self, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let file: ParamsFile =
+            serde_json::from_str(&json).map_err(|e| format!("{}: {}", path, e))?;
+        file.validate().map_err(|e| format!("{}: {}", path, e))?;
+        Ok(file)
+    }
+}
+
+//Проверка параметров: p и q простые, q делит p - 1, g и h - различные
+//элементы порядка q. Вырожденные параметры делают протокол бессмысленным.
+pub fn validate(params: &PublicParams) -> Result<(), String> {
+    let PublicParams { p, q, .. } = params;
+    if !key_gen::is_prime_miller_rabin(p, PRIME_ROUNDS) {
+        return Err(format!("p = {} не является простым", p));
+    }
+    if !key_gen::is_prime_miller_rabin(q, PRIME_ROUNDS) {
+        return Err(format!("q = {} не является простым", q));
+    }
+    validate_generators(params)
+}
+
+//Проверка без теста простоты - для заведомо простых p и q (RFC 3526)
+pub fn validate_generators(params: &PublicParams) -> Result<(), String> {
+    let PublicParams { p, q, g, h } = params;
+    if (p - 1u32) % q != BigUint::ZERO {
+        return Err("q не делит p - 1".to_string());
+    }
+    validate_element(params, "g", g)?;
+    validate_element(params, "h", h)?;
+    if g == h {
+        return Err("g и h совпадают".to_string());
+    }
+    Ok(())
+}
+
+//Элемент лежит в (1, p) и принадлежит подгруппе порядка q
+pub fn validate_element(params: &PublicParams, name: &str, x: &BigUint) -> Result<(), String> {
+    let group = params.setting().group;
+    if *x <= group.identity() || *x >= params.p {
+        return Err(format!("{} = {} вне диапазона (1, p)", name, x));
+    }
+    if !group.contains(x) {
+        return Err(format!("{} = {} не лежит в подгруппе порядка q", name, x));
+    }
+    Ok(())
+}
+
+//Квадрат случайного x: элемент подгруппы порядка q, отличный от 1
+fn subgroup_element(p: &BigUint) -> BigUint {
+    let mut rng = rand::thread_rng();
+    let two = BigUint::from(2u32);
+    loop {
+        let x = rng.gen_biguint_range(&two, &(p - 1u32));
+        let element = &x * &x % p;
+        if element != BigUint::from(1u32) {
+            return element;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::Group;
+
+    #[test]
+    fn test_generated_params() {
+        let params = GroupParams::generate(64);
+        assert_eq!(params.p.bits(), 64);
+        assert_eq!(params.p, &params.q * 2u32 + 1u32);
+        assert!(key_gen::is_prime_miller_rabin(&params.q, 16));
+
+        let group = params.public_params().setting().group;
+        for x in [&params.g, &params.h] {
+            assert_ne!(*x, group.identity());
+            assert_eq!(group.exp(x, &params.q), group.identity());
+        }
+        assert_ne!(params.g, params.h);
+        assert!(params.public_params().setting().h_is_derived());
+        assert_eq!(validate(&params.public_params()), Ok(()));
+    }
+
+    #[test]
+    fn test_params_file_roundtrip() {
+        let path = std::env::temp_dir().join(format!("params-{}.json", rand::random::<u64>()));
+        let path = path.to_str().unwrap();
+        for file in [
+            ParamsFile::generate(64, key_gen::DEFAULT_MR_ROUNDS, |_| {}),
+            ParamsFile::generate_provable(64, CertificateKind::Pocklington),
+        ] {
+            file.save(path).unwrap();
+            assert_eq!(ParamsFile::load(path).unwrap(), file);
+        }
+
+        //Сертификат другого числа и испорченный образующий отвергаются
+        let mut forged = ParamsFile::generate_provable(64, CertificateKind::Pratt);
+        forged.params = GroupParams::generate(64);
+        assert!(forged.validate().is_err());
+        let mut tampered = ParamsFile::generate(64, key_gen::DEFAULT_MR_ROUNDS, |_| {});
+        tampered.params.g = BigUint::from(1u32);
+        tampered.save(path).unwrap();
+        assert!(ParamsFile::load(path).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    fn toy(p: u32, q: u32, g: u32, h: u32) -> PublicParams {
+        PublicParams {
+            p: BigUint::from(p),
+            q: BigUint::from(q),
+            g: BigUint::from(g),
+            h: BigUint::from(h),
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_degenerate() {
+        assert_eq!(validate(&toy(23, 11, 4, 9)), Ok(()));
+        //Составной модуль, составной порядок, q не делит p - 1
+        assert!(validate(&toy(21, 11, 4, 9)).is_err());
+        assert!(validate(&toy(23, 22, 4, 9)).is_err());
+        assert!(validate(&toy(23, 7, 4, 9)).is_err());
+        //g = 1, g вне диапазона, g порядка 22, g = h
+        assert!(validate(&toy(23, 11, 1, 9)).is_err());
+        assert!(validate(&toy(23, 11, 27, 9)).is_err());
+        assert!(validate(&toy(23, 11, 5, 9)).is_err());
+        assert!(validate(&toy(23, 11, 9, 9)).is_err());
+    }
+}
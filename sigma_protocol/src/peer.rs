@@ -0,0 +1,226 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::config::{Address, Codec, RetryPolicy};
+use crate::events::{EventBus, EventSink, ProtocolEvent};
+use crate::handshake::SessionKey;
+use crate::message::ProtocolMessage;
+
+//Заголовок с именем сервера-отправителя
+pub const PEER_NAME_HEADER: &str = "x-peer-name";
+//Заголовок зашифрованного ключом сессии тела
+pub const PEER_SEALED_HEADER: &str = "x-peer-sealed";
+pub const PEER_EVENT_PATH: &str = "/peer/event";
+//Пересылка журнала не должна задерживать протокол
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(1);
+
+//Тело запроса на PEER_EVENT_PATH
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForwardedEvent {
+    pub narration: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct PeerClient {
+    name: String,
+    address: Address,
+    client: reqwest::Client,
+    retry: RetryPolicy,
+    codec: Codec,
+    //Ключ сессии после обмена ключами; общий для всех копий клиента
+    session: Arc<Mutex<Option<SessionKey>>>,
+}
+
+impl PeerClient {
+    pub fn new(name: String, address: Address, retry: RetryPolicy, codec: Codec) -> Self {
+        PeerClient {
+            name,
+            address,
+            client: reqwest::Client::new(),
+            retry,
+            codec,
+            session: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn set_session_key(&self, key: SessionKey) {
+        *self.session.lock().unwrap() = Some(key);
+    }
+
+    pub fn session_key(&self) -> Option<SessionKey> {
+        self.session.lock().unwrap().clone()
+    }
+
+    fn encode(&self, message: &ProtocolMessage) -> Result<(&'static str, Vec<u8>), String> {
+        match self.codec {
+            Codec::Json => Ok(("application/json", message.to_json()?.into_bytes())),
+            Codec::Binary => Ok(("application/octet-stream", message.to_bytes())),
+        }
+    }
+
+    //Отправка сообщения второму серверу с повторами и экспоненциальной задержкой.
+    //Промежуточные ошибки публикуются в журнал событий.
+    pub async fn send(
+        &self,
+        path: &str,
+        message: &ProtocolMessage,
+        events: &EventBus,
+    ) -> Result<(), String> {
+        let url = format!("http://{}{}", self.address.get(), path);
+        let (content_type, body) = self.encode(message)?;
+        let session = self.session_key();
+        let body = match &session {
+            Some(key) => key.seal(&body)?,
+            None => body,
+        };
+        let attempts = self.retry.max_attempts.max(1);
+
+        for attempt in 1..=attempts {
+            let mut request = self
+                .client
+                .post(&url)
+                .header(reqwest::header::CONTENT_TYPE, content_type)
+                .header(PEER_NAME_HEADER, &self.name);
+            if session.is_some() {
+                request = request.header(PEER_SEALED_HEADER, "1");
+            }
+            let request = request.body(body.clone());
+            let err = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) => format!("HTTP {}", response.status()),
+                Err(e) => e.to_string(),
+            };
+
+            if attempt == attempts {
+                return Err(format!(
+                    "Не удалось доставить сообщение на {} за {} попыток: {}",
+                    url, attempts, err
+                ));
+            }
+
+            let delay = self.retry.delay(attempt);
+            warn!("Peer {} attempt {} failed: {}", url, attempt, err);
+            events.publish(ProtocolEvent::PeerRetry {
+                attempt,
+                attempts,
+                error: err,
+                delay_ms: delay.as_millis() as u64,
+            });
+            tokio::time::sleep(delay).await;
+        }
+
+        unreachable!()
+    }
+}
+
+//Пересылка событий своего журнала второму серверу: в раздельном режиме
+//журнал каждого сервера показывает обе стороны протокола
+pub struct PeerSink {
+    peer: PeerClient,
+}
+
+impl PeerSink {
+    pub fn new(peer: PeerClient) -> Self {
+        PeerSink { peer }
+    }
+
+    //Пересланные события и сбои самой пересылки обратно не отправляются
+    fn forwards(event: &ProtocolEvent) -> bool {
+        !matches!(
+            event,
+            ProtocolEvent::PeerEvent { .. }
+                | ProtocolEvent::PeerRetry { .. }
+                | ProtocolEvent::PeerFailed { .. }
+                | ProtocolEvent::Lagged { .. }
+        )
+    }
+}
+
+impl EventSink for PeerSink {
+    async fn emit(&mut self, event: &ProtocolEvent) -> Result<(), String> {
+        if !Self::forwards(event) {
+            return Ok(());
+        }
+        let url = format!("http://{}{}", self.peer.address.get(), PEER_EVENT_PATH);
+        let body = ForwardedEvent {
+            narration: event.narration(),
+        };
+        let sent = self
+            .peer
+            .client
+            .post(&url)
+            .header(PEER_NAME_HEADER, &self.peer.name)
+            .timeout(FORWARD_TIMEOUT)
+            .json(&body)
+            .send()
+            .await;
+        //Недоступный второй сервер не останавливает пересылку следующих событий
+        if let Err(e) = sent {
+            warn!("Failed to forward event to {}: {}", url, e);
+        }
+        Ok(())
+    }
+}
+
+impl RetryPolicy {
+    //Задержка перед повтором номер attempt (с 1): base * 2^(attempt-1), не больше max,
+    //со случайным разбросом в диапазоне [delay/2, delay]
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay_ms
+            .saturating_mul(1u64 << (attempt - 1).min(32))
+            .min(self.max_delay_ms);
+        let jitter = rand::thread_rng().gen_range(exp / 2..=exp);
+        Duration::from_millis(jitter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_grows_and_capped() {
+        let retry = RetryPolicy {
+            max_attempts: 10,
+            base_delay_ms: 100,
+            max_delay_ms: 1000,
+        };
+        for _ in 0..20 {
+            let d1 = retry.delay(1).as_millis();
+            assert!((50..=100).contains(&d1));
+            let d3 = retry.delay(3).as_millis();
+            assert!((200..=400).contains(&d3));
+            let d10 = retry.delay(10).as_millis();
+            assert!((500..=1000).contains(&d10));
+        }
+    }
+
+    #[test]
+    fn test_forwarded_events_do_not_loop() {
+        assert!(!PeerSink::forwards(&ProtocolEvent::PeerEvent {
+            from: "verifier".to_string(),
+            narration: "Виктор".to_string(),
+        }));
+        assert!(!PeerSink::forwards(&ProtocolEvent::PeerFailed {
+            error: "timeout".to_string(),
+        }));
+        assert!(PeerSink::forwards(&ProtocolEvent::Round {
+            round: 1,
+            rounds: 3,
+        }));
+    }
+
+    #[test]
+    fn test_delay_huge_attempt_no_overflow() {
+        let retry = RetryPolicy::default();
+        assert!(retry.delay(u32::MAX).as_millis() <= retry.max_delay_ms as u128);
+    }
+}
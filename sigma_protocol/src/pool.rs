@@ -0,0 +1,110 @@
+//////////////////////////////////////////////////////////////
+// Пул заранее сгенерированных параметров группы. Фоновый поток
+// держит в запасе до size наборов параметров длиной bits и
+// пополняет пул после каждой выдачи, так что запрос новых
+// параметров не ждет поиска безопасного простого. Поток
+// завершается вскоре после удаления пула.
+//////////////////////////////////////////////////////////////
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::thread;
+use std::time::Duration;
+
+use crate::key_gen::SearchLimits;
+use crate::params::GroupParams;
+
+//Как часто полный пул проверяет, не удален ли он
+const IDLE_POLL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub struct ParamsPool {
+    bits: u64,
+    rounds: u8,
+    size: usize,
+    ready: Mutex<VecDeque<GroupParams>>,
+    //Сигнал фоновому потоку: из пула взяли параметры
+    taken: Condvar,
+}
+
+impl ParamsPool {
+    //rounds - раунды Миллера-Рабина при поиске простых
+    pub fn spawn(bits: u64, rounds: u8, size: usize) -> Arc<Self> {
+        let pool = Arc::new(ParamsPool {
+            bits,
+            rounds,
+            size,
+            ready: Mutex::new(VecDeque::with_capacity(size)),
+            taken: Condvar::new(),
+        });
+        let weak = Arc::downgrade(&pool);
+        thread::spawn(move || refill(weak));
+        pool
+    }
+
+    pub fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    pub fn len(&self) -> usize {
+        self.ready.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    //Готовые параметры без ожидания; None, если пул еще не пополнился
+    pub fn take(&self) -> Option<GroupParams> {
+        let params = self.ready.lock().unwrap().pop_front();
+        self.taken.notify_one();
+        params
+    }
+}
+
+//Поиск простого идет без блокировки пула: выдача в это время не ждет
+fn refill(weak: Weak<ParamsPool>) {
+    while let Some(pool) = weak.upgrade() {
+        let ready = pool.ready.lock().unwrap();
+        if ready.len() >= pool.size {
+            let _ = pool.taken.wait_timeout(ready, IDLE_POLL).unwrap();
+            continue;
+        }
+        drop(ready);
+        let params =
+            GroupParams::generate_limited(pool.bits, pool.rounds, SearchLimits::default(), |_| {})
+                .expect("поиск без пределов не останавливается");
+        pool.ready.lock().unwrap().push_back(params);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_gen;
+    use crate::params;
+    use std::time::Instant;
+
+    fn wait_for(pool: &ParamsPool, len: usize) {
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while pool.len() < len {
+            assert!(Instant::now() < deadline, "пул не пополнился");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_pool_refills() {
+        let pool = ParamsPool::spawn(64, key_gen::DEFAULT_MR_ROUNDS, 2);
+        wait_for(&pool, 2);
+        //Полный пул не растет дальше size
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(pool.len(), 2);
+
+        let params = pool.take().unwrap();
+        assert_eq!(params.p.bits(), 64);
+        assert_eq!(params::validate(&params.public_params()), Ok(()));
+        assert_ne!(pool.take().unwrap(), params);
+        wait_for(&pool, 2);
+    }
+}
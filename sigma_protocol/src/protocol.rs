@@ -0,0 +1,1487 @@
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+
+use crate::batch;
+use crate::commitment::{self, Generators};
+use crate::fiat_shamir::{self, ProofTranscript};
+use crate::group::{Group, ModGroup, Setting};
+use crate::key_gen::{self, SecureRng};
+use crate::math;
+use crate::message::{ProtocolMessage, decimal};
+use crate::nonce::{self, NonceMode};
+use crate::oracle::HashKind;
+use crate::schnorr::Schnorr;
+use crate::sigma::SigmaProtocol;
+#[cfg(feature = "server")]
+use crate::{
+    challenge::{self, SealedChallenge},
+    timing::Timeline,
+    transport::Transport,
+};
+
+//Открытые параметры протокола: модуль p, порядок q подгруппы и ее образующие g, h
+#[derive(Debug, Clone)]
+pub struct PublicParams {
+    pub p: BigUint,
+    pub q: BigUint,
+    pub g: BigUint,
+    pub h: BigUint,
+}
+
+impl PublicParams {
+    //g^a * h^b mod p - коммитмент Педерсена к a с ослепляющим множителем b
+    pub fn compute_u(&self, a: &BigUint, b: &BigUint) -> Option<BigUint> {
+        commitment::commit(&self.generators(), a, b, &self.p)
+    }
+
+    pub fn generators(&self) -> Generators {
+        Generators::new(self.g.clone(), self.h.clone())
+    }
+
+    //Порядок подгруппы, по нему приводятся показатели степени
+    pub fn order(&self) -> BigUint {
+        self.q.clone()
+    }
+
+    //Те же параметры как группа вычетов с образующими
+    pub fn setting(&self) -> Setting<ModGroup> {
+        Setting::new(
+            ModGroup::new(self.p.clone(), self.q.clone()),
+            self.g.clone(),
+            self.h.clone(),
+        )
+    }
+}
+
+impl Setting<ModGroup> {
+    pub fn params(&self) -> PublicParams {
+        PublicParams {
+            p: self.group.modulus(),
+            q: self.group.order(),
+            g: self.g.clone(),
+            h: self.h.clone(),
+        }
+    }
+}
+
+//Игрушечные параметры тестов: подгруппа порядка 11 в Z_23^*
+#[cfg(test)]
+pub(crate) fn toy_params() -> PublicParams {
+    PublicParams {
+        p: BigUint::from(23u32),
+        q: BigUint::from(11u32),
+        g: BigUint::from(4u32),
+        h: BigUint::from(9u32),
+    }
+}
+
+//Пара показателей (alpha, beta): секретный ключ или одноразовый ключ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Key {
+    pub alpha: BigUint,
+    pub beta: BigUint,
+}
+
+impl Key {
+    pub fn new(alpha: BigUint, beta: BigUint) -> Self {
+        Key { alpha, beta }
+    }
+
+    pub fn random(order: &BigUint) -> Self {
+        Key::new(
+            key_gen::random_biguint_mod(order),
+            key_gen::random_biguint_mod(order),
+        )
+    }
+
+    pub fn random_with<R: SecureRng>(rng: &mut R, order: &BigUint) -> Self {
+        Key::new(
+            key_gen::random_biguint_mod_with(rng, order),
+            key_gen::random_biguint_mod_with(rng, order),
+        )
+    }
+
+    //Показатели не длиннее bits бит
+    pub fn random_bits(order: &BigUint, bits: u64) -> Self {
+        Key::new(
+            key_gen::random_biguint_bits(order, bits),
+            key_gen::random_biguint_bits(order, bits),
+        )
+    }
+}
+
+//Запись завершенного доказательства (u, u_t, c, a_z, b_z).
+//Доказательство Шнорра для u = g^x записывается с b_z = 0.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Proof {
+    #[serde(with = "decimal")]
+    pub u: BigUint,
+    #[serde(with = "decimal")]
+    pub ut: BigUint,
+    #[serde(with = "decimal")]
+    pub c: BigUint,
+    #[serde(with = "decimal")]
+    pub alpha: BigUint,
+    #[serde(with = "decimal", default)]
+    pub beta: BigUint,
+}
+
+impl Proof {
+    pub fn response(&self) -> ProtocolMessage {
+        ProtocolMessage::Response {
+            alpha: self.alpha.clone(),
+            beta: self.beta.clone(),
+        }
+    }
+
+    //g^a_z * h^b_z == u_t * u^c
+    pub fn verify(&self, params: &PublicParams) -> bool {
+        Okamoto::new(params.clone()).verify(
+            &self.u,
+            &self.ut,
+            &self.c,
+            &Key::new(self.alpha.clone(), self.beta.clone()),
+        )
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Протокол Окамото в терминах SigmaProtocol:
+// утверждение u, свидетель (alpha, beta), коммитмент u_t,
+// ответ (a_z, b_z)
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct Okamoto<G: Group = ModGroup> {
+    setting: Setting<G>,
+}
+
+impl Okamoto {
+    pub fn new(params: PublicParams) -> Self {
+        Okamoto::in_setting(params.setting())
+    }
+}
+
+impl<G: Group> Okamoto<G> {
+    pub fn in_setting(setting: Setting<G>) -> Self {
+        Okamoto { setting }
+    }
+
+    pub fn setting(&self) -> &Setting<G> {
+        &self.setting
+    }
+
+    //u = g^alpha * h^beta
+    pub fn statement(&self, witness: &Key) -> G::Element {
+        self.setting.compute_u(&witness.alpha, &witness.beta)
+    }
+}
+
+impl<G: Group> SigmaProtocol for Okamoto<G> {
+    type Statement = G::Element;
+    type Witness = Key;
+    type Nonce = Key;
+    type Commitment = G::Element;
+    type Challenge = BigUint;
+    type Response = Key;
+
+    fn commit(&self, _statement: &G::Element, _witness: &Key) -> Option<(Key, G::Element)> {
+        let group = &self.setting.group;
+        let nonce = Key::new(group.random_scalar(), group.random_scalar());
+        let ut = self.statement(&nonce);
+        Some((nonce, ut))
+    }
+
+    fn challenge(&self) -> BigUint {
+        self.setting.group.random_scalar()
+    }
+
+    //a_z = alpha_t + alpha*c, b_z = beta_t + beta*c
+    fn respond(&self, witness: &Key, nonce: Key, c: &BigUint) -> Key {
+        let alpha_t = self.setting.scalar(nonce.alpha);
+        let beta_t = alpha_t.lift(nonce.beta);
+        Key::new(
+            (alpha_t.lift(witness.alpha.clone()) * c + alpha_t).into_value(),
+            (beta_t.lift(witness.beta.clone()) * c + beta_t).into_value(),
+        )
+    }
+
+    //g^a_z * h^b_z == u_t * u^c
+    fn verify(&self, u: &G::Element, ut: &G::Element, c: &BigUint, response: &Key) -> bool {
+        let group = &self.setting.group;
+        if !group.contains(u) || !group.contains(ut) {
+            return false;
+        }
+        let left = group.encode(&self.statement(response));
+        math::ct_eq(&left, &group.encode(&group.mul(ut, &group.exp(u, c))))
+    }
+}
+
+//Шаги проверки неинтерактивного доказательства
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Verification {
+    //u и u_t - элементы группы
+    pub elements: bool,
+    //c совпадает с хэшем стенограммы
+    pub challenge: bool,
+    //g^a_z * h^b_z == u_t * u^c
+    pub equation: bool,
+}
+
+impl Verification {
+    pub fn valid(&self) -> bool {
+        self.elements && self.challenge && self.equation
+    }
+
+    //Первый непройденный шаг
+    pub fn failure(&self) -> Option<&'static str> {
+        match (self.elements, self.challenge, self.equation) {
+            (false, _, _) => Some("u или u_t не является элементом группы"),
+            (_, false, _) => Some("испытание не совпадает с хэшем стенограммы"),
+            (_, _, false) => Some("g^a_z * h^b_z != u_t * u^c"),
+            _ => None,
+        }
+    }
+}
+
+//Вид доказываемого утверждения
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofKind {
+    //u = g^alpha * h^beta
+    #[default]
+    Okamoto,
+    //u = g^x, в ответе b_z = 0
+    Schnorr,
+}
+
+#[derive(Debug, Clone)]
+enum Protocol<G: Group> {
+    Okamoto(Okamoto<G>),
+    Schnorr(Schnorr<G>),
+}
+
+impl<G: Group> Protocol<G> {
+    fn new(kind: ProofKind, setting: Setting<G>) -> Self {
+        match kind {
+            ProofKind::Okamoto => Protocol::Okamoto(Okamoto::in_setting(setting)),
+            ProofKind::Schnorr => Protocol::Schnorr(Schnorr::in_setting(setting)),
+        }
+    }
+
+    fn kind(&self) -> ProofKind {
+        match self {
+            Protocol::Okamoto(_) => ProofKind::Okamoto,
+            Protocol::Schnorr(_) => ProofKind::Schnorr,
+        }
+    }
+
+    fn setting(&self) -> &Setting<G> {
+        match self {
+            Protocol::Okamoto(p) => p.setting(),
+            Protocol::Schnorr(p) => p.setting(),
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Состояния сессии проверяющего. Переходы поглощают предыдущее
+// состояние, поэтому нарушить порядок шагов нельзя на этапе компиляции.
+//////////////////////////////////////////////////////////////
+
+pub struct AwaitingCommitment;
+
+pub struct AwaitingChallenge {
+    u: BigUint,
+    ut: BigUint,
+}
+
+pub struct AwaitingResponse {
+    u: BigUint,
+    ut: BigUint,
+    c: BigUint,
+}
+
+//Значения обеих сторон уравнения в закодированном виде
+#[derive(Debug, Clone)]
+pub struct Decided {
+    pub uz: BigUint,
+    pub uc: BigUint,
+    pub utuc: BigUint,
+    pub accepted: bool,
+}
+
+pub struct Session<S, G: Group = ModGroup> {
+    setting: Setting<G>,
+    kind: ProofKind,
+    state: S,
+}
+
+impl Session<AwaitingCommitment> {
+    pub fn new(params: PublicParams) -> Self {
+        Session::with_kind(params, ProofKind::Okamoto)
+    }
+
+    pub fn with_kind(params: PublicParams, kind: ProofKind) -> Self {
+        Session::in_setting(params.setting(), kind)
+    }
+}
+
+impl<G: Group> Session<AwaitingCommitment, G> {
+    pub fn in_setting(setting: Setting<G>, kind: ProofKind) -> Self {
+        Session {
+            setting,
+            kind,
+            state: AwaitingCommitment,
+        }
+    }
+
+    pub fn receive_commitment(
+        self,
+        message: ProtocolMessage,
+    ) -> Result<Session<AwaitingChallenge, G>, String> {
+        match message {
+            ProtocolMessage::Commitment { u, ut } => Ok(Session {
+                setting: self.setting,
+                kind: self.kind,
+                state: AwaitingChallenge { u, ut },
+            }),
+            other => Err(format!(
+                "Ожидался коммитмент, получено: {}",
+                other.describe()
+            )),
+        }
+    }
+}
+
+impl<G: Group> Session<AwaitingChallenge, G> {
+    pub fn issue_challenge(self, c: BigUint) -> (Session<AwaitingResponse, G>, ProtocolMessage) {
+        let message = ProtocolMessage::Challenge { c: c.clone() };
+        let session = Session {
+            setting: self.setting,
+            kind: self.kind,
+            state: AwaitingResponse {
+                u: self.state.u,
+                ut: self.state.ut,
+                c,
+            },
+        };
+        (session, message)
+    }
+}
+
+impl<G: Group> Session<AwaitingResponse, G> {
+    //Проверка g^a_z * h^b_z == u_t * u^c
+    pub fn receive_response(self, message: ProtocolMessage) -> Result<Session<Decided, G>, String> {
+        let ProtocolMessage::Response { alpha, beta } = message else {
+            return Err(format!("Ожидался ответ, получено: {}", message.describe()));
+        };
+        if self.kind == ProofKind::Schnorr && beta != BigUint::ZERO {
+            return Err("В ответе протокола Шнорра b_z должно быть 0".to_string());
+        }
+        let AwaitingResponse { u, ut, c } = self.state;
+        let group = &self.setting.group;
+        let u = group.decode(&u).ok_or("u не является элементом группы")?;
+        let ut = group
+            .decode(&ut)
+            .ok_or("u_t не является элементом группы")?;
+
+        let uz = self.setting.compute_u(&alpha, &beta);
+        let uc = group.exp(&u, &c);
+        let utuc = group.mul(&ut, &uc);
+        let (uz, utuc) = (group.encode(&uz), group.encode(&utuc));
+        let accepted = math::ct_eq(&uz, &utuc);
+
+        let state = Decided {
+            uz,
+            uc: group.encode(&uc),
+            utuc,
+            accepted,
+        };
+        Ok(Session {
+            setting: self.setting,
+            kind: self.kind,
+            state,
+        })
+    }
+}
+
+impl<G: Group> Session<Decided, G> {
+    pub fn decision(&self) -> &Decided {
+        &self.state
+    }
+
+    pub fn verdict(&self) -> ProtocolMessage {
+        ProtocolMessage::Verdict {
+            accepted: self.state.accepted,
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Доказывающий
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct Prover<G: Group = ModGroup> {
+    protocol: Protocol<G>,
+    secret: Key,
+    u: G::Element,
+    nonces: NonceMode,
+    //Хэш-функция испытания неинтерактивного доказательства
+    hash: HashKind,
+    //Ожидать коммитмент испытания до отправки u_t
+    committed_challenge: bool,
+}
+
+//Коммитмент доказывающего вместе с одноразовым ключом; поглощается при ответе
+#[derive(Debug)]
+pub struct ProverCommitment {
+    nonce: Key,
+    pub u: BigUint,
+    pub ut: BigUint,
+}
+
+impl ProverCommitment {
+    pub fn message(&self) -> ProtocolMessage {
+        ProtocolMessage::Commitment {
+            u: self.u.clone(),
+            ut: self.ut.clone(),
+        }
+    }
+
+    //Одноразовый ключ раскрывается только для демонстрации
+    pub fn nonce(&self) -> &Key {
+        &self.nonce
+    }
+
+    //Снимок состояния после коммитмента - только для демонстрации
+    //экстрактора. ОПАСНО: снимок повторно использует одноразовый ключ, и
+    //два ответа на разные испытания по одному коммитменту раскрывают
+    //секрет любому, кто их увидит. В настоящем протоколе не вызывать
+    pub fn rewind_for_extraction(&self) -> ProverCommitment {
+        ProverCommitment {
+            nonce: self.nonce.clone(),
+            u: self.u.clone(),
+            ut: self.ut.clone(),
+        }
+    }
+}
+
+impl Prover {
+    pub fn new(params: PublicParams, secret: Key) -> Option<Self> {
+        Some(Prover::in_setting(
+            params.setting(),
+            ProofKind::Okamoto,
+            secret,
+        ))
+    }
+
+    //Доказывающий Шнорра; секрет хранится как (x, 0)
+    pub fn schnorr(params: PublicParams, x: BigUint) -> Option<Self> {
+        let secret = Key::new(x, BigUint::ZERO);
+        Some(Prover::in_setting(
+            params.setting(),
+            ProofKind::Schnorr,
+            secret,
+        ))
+    }
+
+    pub fn random(params: PublicParams) -> Option<Self> {
+        Prover::random_of_kind(params, ProofKind::Okamoto)
+    }
+
+    pub fn random_of_kind(params: PublicParams, kind: ProofKind) -> Option<Self> {
+        Some(Prover::random_in(params.setting(), kind))
+    }
+}
+
+impl<G: Group> Prover<G> {
+    //Для Шнорра beta секрета игнорируется
+    pub fn in_setting(setting: Setting<G>, kind: ProofKind, secret: Key) -> Self {
+        let protocol = Protocol::new(kind, setting);
+        let (secret, u) = match &protocol {
+            Protocol::Okamoto(p) => {
+                let u = p.statement(&secret);
+                (secret, u)
+            }
+            Protocol::Schnorr(p) => {
+                let u = p.statement(&secret.alpha);
+                (Key::new(secret.alpha, BigUint::ZERO), u)
+            }
+        };
+        Prover {
+            protocol,
+            secret,
+            u,
+            nonces: NonceMode::Random,
+            hash: HashKind::default(),
+            committed_challenge: false,
+        }
+    }
+
+    pub fn with_nonces(mut self, nonces: NonceMode) -> Self {
+        self.nonces = nonces;
+        self
+    }
+
+    pub fn with_hash(mut self, hash: HashKind) -> Self {
+        self.hash = hash;
+        self
+    }
+
+    //Вариант протокола, где проверяющий фиксирует испытание до коммитмента
+    pub fn with_committed_challenge(mut self, committed: bool) -> Self {
+        self.committed_challenge = committed;
+        self
+    }
+
+    pub fn random_in(setting: Setting<G>, kind: ProofKind) -> Self {
+        let secret = Key::new(setting.group.random_scalar(), setting.group.random_scalar());
+        Prover::in_setting(setting, kind, secret)
+    }
+
+    //Секрет из генератора вызывающего
+    pub fn random_in_with<R: SecureRng>(setting: Setting<G>, kind: ProofKind, rng: &mut R) -> Self {
+        let secret = Key::random_with(rng, &setting.order());
+        Prover::in_setting(setting, kind, secret)
+    }
+
+    pub fn kind(&self) -> ProofKind {
+        self.protocol.kind()
+    }
+
+    pub fn setting(&self) -> &Setting<G> {
+        self.protocol.setting()
+    }
+
+    //Публичный ключ u = g^alpha * h^beta (u = g^x для Шнорра)
+    pub fn public_key(&self) -> &G::Element {
+        &self.u
+    }
+
+    pub fn secret(&self) -> &Key {
+        &self.secret
+    }
+
+    //В детерминированном режиме к выводу ключа добавляется случайность:
+    //один коммитмент с двумя разными испытаниями раскрыл бы секрет
+    pub fn commit(&self) -> Option<ProverCommitment> {
+        match self.nonces {
+            NonceMode::Random => self.commit_random(),
+            NonceMode::Deterministic => {
+                Some(self.commit_deterministic(&rand::random::<[u8; 32]>()))
+            }
+        }
+    }
+
+    //Коммитмент с одноразовым ключом из генератора вызывающего
+    pub fn commit_with<R: SecureRng>(&self, rng: &mut R) -> ProverCommitment {
+        match self.nonces {
+            NonceMode::Random => {
+                let mut nonce = Key::random_with(rng, &self.setting().order());
+                if self.kind() == ProofKind::Schnorr {
+                    nonce.beta = BigUint::ZERO;
+                }
+                self.commitment_from(nonce)
+            }
+            NonceMode::Deterministic => {
+                let mut extra = [0u8; 32];
+                rng.fill_bytes(&mut extra);
+                self.commit_deterministic(&extra)
+            }
+        }
+    }
+
+    //Одноразовый ключ из HMAC секрета и утверждения (RFC 6979)
+    fn commit_deterministic(&self, extra: &[u8]) -> ProverCommitment {
+        let setting = self.setting();
+        let group = &setting.group;
+        let mut message = vec![self.kind() as u8];
+        for element in [&setting.g, &setting.h, &self.u] {
+            message.extend(group.serialize(element));
+        }
+        message.extend(group.describe());
+        let mut nonce = nonce::derive(&setting.order(), &self.secret, &message, extra);
+        if self.kind() == ProofKind::Schnorr {
+            nonce.beta = BigUint::ZERO;
+        }
+        self.commitment_from(nonce)
+    }
+
+    //u_t = g^alpha_t * h^beta_t для готового одноразового ключа
+    fn commitment_from(&self, nonce: Key) -> ProverCommitment {
+        let setting = self.setting();
+        let ut = setting.compute_u(&nonce.alpha, &nonce.beta);
+        ProverCommitment {
+            u: setting.group.encode(&self.u),
+            ut: setting.group.encode(&ut),
+            nonce,
+        }
+    }
+
+    fn commit_random(&self) -> Option<ProverCommitment> {
+        let (nonce, ut) = match &self.protocol {
+            Protocol::Okamoto(p) => p.commit(&self.u, &self.secret)?,
+            Protocol::Schnorr(p) => {
+                let (r, ut) = p.commit(&self.u, &self.secret.alpha)?;
+                (Key::new(r, BigUint::ZERO), ut)
+            }
+        };
+        let group = &self.setting().group;
+        Some(ProverCommitment {
+            nonce,
+            u: group.encode(&self.u),
+            ut: group.encode(&ut),
+        })
+    }
+
+    //Ответ на испытание: a_z = alpha_t + alpha*c, b_z = beta_t + beta*c
+    pub fn respond(&self, commitment: ProverCommitment, c: &BigUint) -> Proof {
+        let response = match &self.protocol {
+            Protocol::Okamoto(p) => p.respond(&self.secret, commitment.nonce, c),
+            Protocol::Schnorr(p) => Key::new(
+                p.respond(&self.secret.alpha, commitment.nonce.alpha, c),
+                BigUint::ZERO,
+            ),
+        };
+        Proof {
+            alpha: response.alpha,
+            beta: response.beta,
+            u: commitment.u,
+            ut: commitment.ut,
+            c: c.clone(),
+        }
+    }
+
+    //Неинтерактивное доказательство: испытание берется из хэша коммитмента
+    pub fn prove_non_interactive(&self) -> Option<Proof> {
+        self.prove_in(&mut ProofTranscript::with_hash(
+            fiat_shamir::PROOF_LABEL,
+            self.hash,
+        ))
+    }
+
+    //Доказательство в общей стенограмме: испытание зависит от всего, что
+    //в нее поглощено до него. Детерминированный ключ выводится из состояния
+    //стенограммы: в одном контексте повтор дает то же доказательство, а в
+    //разных контекстах - разные ключи, иначе два ответа раскрыли бы секрет
+    pub fn prove_in(&self, transcript: &mut ProofTranscript) -> Option<Proof> {
+        self.prove_in_with(transcript, &mut OsRng)
+    }
+
+    pub fn prove_in_with<R: SecureRng>(
+        &self,
+        transcript: &mut ProofTranscript,
+        rng: &mut R,
+    ) -> Option<Proof> {
+        let commitment = match self.nonces {
+            NonceMode::Random => self.commit_with(rng),
+            NonceMode::Deterministic => self.commit_deterministic(&transcript.state_digest()),
+        };
+        let c = transcript.proof_challenge(self.setting(), &commitment.u, &commitment.ut);
+        Some(self.respond(commitment, &c))
+    }
+
+    //Интерактивное доказательство через транспорт
+    #[cfg(feature = "server")]
+    pub async fn run<T: Transport>(
+        &self,
+        transport: &mut T,
+        timeline: &mut Timeline,
+    ) -> Result<bool, String> {
+        Prover::run_all(std::slice::from_ref(self), transport, timeline).await
+    }
+
+    //Несколько независимых раундов; сессия прерывается на первом отказе
+    #[cfg(feature = "server")]
+    pub async fn run_rounds<T: Transport>(
+        provers: &[Prover<G>],
+        rounds: u32,
+        transport: &mut T,
+        timeline: &mut Timeline,
+    ) -> Result<bool, String> {
+        for _ in 0..rounds {
+            if !Prover::run_all(provers, transport, timeline).await? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    //Конъюнкция: все коммитменты, одно общее испытание, все ответы
+    #[cfg(feature = "server")]
+    pub async fn run_all<T: Transport>(
+        provers: &[Prover<G>],
+        transport: &mut T,
+        timeline: &mut Timeline,
+    ) -> Result<bool, String> {
+        let committed = provers.first().is_some_and(|p| p.committed_challenge);
+        let digest = match committed {
+            true => Some(receive_challenge_commitment(transport).await?),
+            false => None,
+        };
+        if digest.is_some() {
+            timeline.mark("challenge_commitment");
+        }
+
+        timeline.start_compute();
+        let mut commitments = Vec::with_capacity(provers.len());
+        for prover in provers {
+            commitments.push(prover.commit().ok_or("Ошибка вычисления u_t")?);
+        }
+        timeline.stop_compute();
+        for commitment in &commitments {
+            transport.send(&commitment.message()).await?;
+        }
+        timeline.mark("commitment");
+
+        let c = match (transport.receive().await?, &digest) {
+            (ProtocolMessage::Challenge { c }, None) => c,
+            (ProtocolMessage::ChallengeReveal { c, salt }, Some(digest)) => {
+                if !challenge::open_challenge(digest, &c, &salt) {
+                    let reason = "Раскрытое испытание не совпадает с коммитментом".to_string();
+                    return Err(fail(transport, reason).await);
+                }
+                c
+            }
+            (ProtocolMessage::Abort { reason }, _) => return Err(reason),
+            (other, _) => {
+                let reason = format!("Ожидалось испытание, получено: {}", other.describe());
+                return Err(fail(transport, reason).await);
+            }
+        };
+        timeline.mark("challenge");
+
+        timeline.start_compute();
+        let proofs: Vec<Proof> = provers
+            .iter()
+            .zip(commitments)
+            .map(|(prover, commitment)| prover.respond(commitment, &c))
+            .collect();
+        timeline.stop_compute();
+        for proof in &proofs {
+            transport.send(&proof.response()).await?;
+        }
+        timeline.mark("response");
+
+        let result = match transport.receive().await? {
+            ProtocolMessage::Verdict { accepted } => Ok(accepted),
+            ProtocolMessage::Abort { reason } => Err(reason),
+            other => Err(format!("Ожидался вердикт, получено: {}", other.describe())),
+        };
+        timeline.mark("verdict");
+        result
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Проверяющий
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct Verifier<G: Group = ModGroup> {
+    protocol: Protocol<G>,
+    //Размер пространства испытаний, не больше q
+    space: BigUint,
+    //Фиксировать испытание хэшем с солью до коммитмента доказывающего
+    committed_challenge: bool,
+    //Хэш-функция испытания неинтерактивного доказательства
+    hash: HashKind,
+}
+
+impl Verifier {
+    pub fn new(params: PublicParams) -> Self {
+        Verifier::with_kind(params, ProofKind::Okamoto)
+    }
+
+    pub fn with_kind(params: PublicParams, kind: ProofKind) -> Self {
+        Verifier::in_setting(params.setting(), kind)
+    }
+
+    //Пакетная проверка набора доказательств (для протокола Шнорра b_z должно быть 0)
+    pub fn verify_batch(&self, proofs: &[Proof]) -> bool {
+        if self.kind() == ProofKind::Schnorr && proofs.iter().any(|p| p.beta != BigUint::ZERO) {
+            return false;
+        }
+        batch::verify_batch(&self.setting().params(), proofs)
+    }
+
+    //Индексы неверных доказательств набора
+    pub fn find_invalid(&self, proofs: &[Proof]) -> Vec<usize> {
+        let mut invalid = batch::find_invalid(&self.setting().params(), proofs);
+        if self.kind() == ProofKind::Schnorr {
+            let schnorr = proofs
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| p.beta != BigUint::ZERO);
+            invalid.extend(schnorr.map(|(i, _)| i));
+            invalid.sort_unstable();
+            invalid.dedup();
+        }
+        invalid
+    }
+}
+
+impl<G: Group> Verifier<G> {
+    pub fn in_setting(setting: Setting<G>, kind: ProofKind) -> Self {
+        Verifier {
+            space: setting.order(),
+            protocol: Protocol::new(kind, setting),
+            committed_challenge: false,
+            hash: HashKind::default(),
+        }
+    }
+
+    pub fn with_hash(mut self, hash: HashKind) -> Self {
+        self.hash = hash;
+        self
+    }
+
+    //Испытание выбирается заранее и раскрывается после коммитмента
+    pub fn with_committed_challenge(mut self, committed: bool) -> Self {
+        self.committed_challenge = committed;
+        self
+    }
+
+    //Испытания из [0, space); меньшее пространство ослабляет стойкость
+    pub fn with_challenge_space(mut self, space: BigUint) -> Self {
+        self.space = space.clamp(BigUint::from(1u32), self.setting().order());
+        self
+    }
+
+    pub fn challenge_space(&self) -> &BigUint {
+        &self.space
+    }
+
+    pub fn kind(&self) -> ProofKind {
+        self.protocol.kind()
+    }
+
+    pub fn setting(&self) -> &Setting<G> {
+        self.protocol.setting()
+    }
+
+    pub fn session(&self) -> Session<AwaitingCommitment, G> {
+        Session::in_setting(self.setting().clone(), self.kind())
+    }
+
+    pub fn challenge(&self) -> BigUint {
+        if self.space != self.setting().order() {
+            return key_gen::random_biguint_mod(&self.space);
+        }
+        match &self.protocol {
+            Protocol::Okamoto(p) => p.challenge(),
+            Protocol::Schnorr(p) => p.challenge(),
+        }
+    }
+
+    pub fn verify(&self, proof: &Proof) -> bool {
+        let group = &self.setting().group;
+        let (Some(u), Some(ut)) = (group.decode(&proof.u), group.decode(&proof.ut)) else {
+            return false;
+        };
+        match &self.protocol {
+            Protocol::Okamoto(p) => p.verify(
+                &u,
+                &ut,
+                &proof.c,
+                &Key::new(proof.alpha.clone(), proof.beta.clone()),
+            ),
+            Protocol::Schnorr(p) => {
+                proof.beta == BigUint::ZERO && p.verify(&u, &ut, &proof.c, &proof.alpha)
+            }
+        }
+    }
+
+    //Проверка неинтерактивного доказательства по шагам, для отчета клиенту
+    pub fn inspect_non_interactive(&self, proof: &Proof) -> Verification {
+        let group = &self.setting().group;
+        let mut transcript = ProofTranscript::with_hash(fiat_shamir::PROOF_LABEL, self.hash);
+        Verification {
+            elements: group.decode(&proof.u).is_some() && group.decode(&proof.ut).is_some(),
+            challenge: proof.c == transcript.proof_challenge(self.setting(), &proof.u, &proof.ut),
+            equation: self.verify(proof),
+        }
+    }
+
+    //Проверка неинтерактивного доказательства: c должно совпадать с хэшем
+    pub fn verify_non_interactive(&self, proof: &Proof) -> bool {
+        let mut transcript = ProofTranscript::with_hash(fiat_shamir::PROOF_LABEL, self.hash);
+        self.verify_in(proof, &mut transcript)
+    }
+
+    //Проверка в общей стенограмме; состояние продвигается так же, как у
+    //доказывающего, поэтому доказательства проверяются в том же порядке
+    pub fn verify_in(&self, proof: &Proof, transcript: &mut ProofTranscript) -> bool {
+        proof.c == transcript.proof_challenge(self.setting(), &proof.u, &proof.ut)
+            && self.verify(proof)
+    }
+
+    //Симулятор (нулевое разглашение при честном проверяющем): принимаемая
+    //стенограмма без секрета. Сначала выбираются c и ответ, затем
+    //коммитмент вычисляется обратно: u_t = g^a_z * h^b_z * u^-c
+    pub fn simulate(&self, u: &G::Element) -> Proof {
+        self.simulate_for(u, self.challenge())
+    }
+
+    //Симуляция с заранее выбранным испытанием c
+    pub fn simulate_for(&self, u: &G::Element, c: BigUint) -> Proof {
+        let setting = self.setting();
+        let group = &setting.group;
+        let c = setting.scalar(c);
+        let alpha = group.random_scalar();
+        let beta = match self.kind() {
+            ProofKind::Okamoto => group.random_scalar(),
+            ProofKind::Schnorr => BigUint::ZERO,
+        };
+        let ut = group.mul(
+            &setting.compute_u(&alpha, &beta),
+            &group.exp(u, (-&c).value()),
+        );
+        Proof {
+            u: group.encode(u),
+            ut: group.encode(&ut),
+            c: c.into_value(),
+            alpha,
+            beta,
+        }
+    }
+
+    //Интерактивная проверка через транспорт
+    #[cfg(feature = "server")]
+    pub async fn run<T: Transport>(
+        &self,
+        transport: &mut T,
+        timeline: &mut Timeline,
+    ) -> Result<bool, String> {
+        self.run_all(1, transport, timeline).await
+    }
+
+    //Проверка rounds независимых раундов: принимается, только если пройдены все
+    #[cfg(feature = "server")]
+    pub async fn run_rounds<T: Transport>(
+        &self,
+        count: usize,
+        rounds: u32,
+        transport: &mut T,
+        timeline: &mut Timeline,
+    ) -> Result<bool, String> {
+        for _ in 0..rounds {
+            if !self.run_all(count, transport, timeline).await? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    //Стойкость rounds раундов в битах: обманщик угадывает испытание
+    //с вероятностью 1/|C| в каждом раунде, итого 2^-bits
+    pub fn soundness_bits(&self, rounds: u32) -> f64 {
+        soundness_bits(&self.space, rounds)
+    }
+
+    //Проверка конъюнкции из count утверждений: принимается, только если верны все
+    #[cfg(feature = "server")]
+    pub async fn run_all<T: Transport>(
+        &self,
+        count: usize,
+        transport: &mut T,
+        timeline: &mut Timeline,
+    ) -> Result<bool, String> {
+        //Испытание выбирается до коммитментов, доказывающему уходит только хэш
+        let sealed = match self.committed_challenge {
+            true => {
+                timeline.start_compute();
+                let sealed = SealedChallenge::seal(self.challenge());
+                timeline.stop_compute();
+                transport.send(&sealed.commitment()).await?;
+                timeline.mark("challenge_commitment");
+                Some(sealed)
+            }
+            false => None,
+        };
+
+        let mut sessions = Vec::with_capacity(count);
+        for _ in 0..count {
+            let message = transport.receive().await?;
+            timeline.start_compute();
+            match message {
+                ProtocolMessage::Abort { reason } => return Err(reason),
+                message => match self.session().receive_commitment(message) {
+                    Ok(session) => sessions.push(session),
+                    Err(e) => return Err(fail(transport, e).await),
+                },
+            }
+            timeline.stop_compute();
+        }
+        timeline.mark("commitment");
+
+        timeline.start_compute();
+        let c = match &sealed {
+            Some(sealed) => sealed.c.clone(),
+            None => self.challenge(),
+        };
+        let sessions: Vec<_> = sessions
+            .into_iter()
+            .map(|session| session.issue_challenge(c.clone()).0)
+            .collect();
+        timeline.stop_compute();
+        let message = match &sealed {
+            Some(sealed) => sealed.reveal(),
+            None => ProtocolMessage::Challenge { c },
+        };
+        transport.send(&message).await?;
+        timeline.mark("challenge");
+
+        let mut accepted = true;
+        for session in sessions {
+            let message = transport.receive().await?;
+            timeline.start_compute();
+            let session = match message {
+                ProtocolMessage::Abort { reason } => return Err(reason),
+                message => match session.receive_response(message) {
+                    Ok(session) => session,
+                    Err(e) => return Err(fail(transport, e).await),
+                },
+            };
+            timeline.stop_compute();
+            accepted &= session.decision().accepted;
+        }
+        timeline.mark("response");
+
+        transport
+            .send(&ProtocolMessage::Verdict { accepted })
+            .await?;
+        timeline.mark("verdict");
+        Ok(accepted)
+    }
+}
+
+//-log2 вероятности обмана за rounds раундов с пространством испытаний размера space
+pub fn soundness_bits(space: &BigUint, rounds: u32) -> f64 {
+    if *space <= BigUint::from(1u32) {
+        return 0.0;
+    }
+    //log2 по старшим 53 битам, чтобы не переполнить f64
+    let bits = space.bits();
+    let shift = bits.saturating_sub(53);
+    let top = (space >> shift)
+        .to_string()
+        .parse::<f64>()
+        .unwrap_or(f64::MAX);
+    (top.log2() + shift as f64) * rounds as f64
+}
+
+//Коммитмент испытания, которым открывается вариант с предварительной фиксацией
+#[cfg(feature = "server")]
+async fn receive_challenge_commitment<T: Transport>(transport: &mut T) -> Result<BigUint, String> {
+    match transport.receive().await? {
+        ProtocolMessage::ChallengeCommitment { digest } => Ok(digest),
+        ProtocolMessage::Abort { reason } => Err(reason),
+        other => {
+            let reason = format!(
+                "Ожидался коммитмент испытания, получено: {}",
+                other.describe()
+            );
+            Err(fail(transport, reason).await)
+        }
+    }
+}
+
+//Отправка Abort второй стороне; возвращает причину
+#[cfg(feature = "server")]
+pub(crate) async fn fail<T: Transport>(transport: &mut T, reason: String) -> String {
+    let _ = transport
+        .send(&ProtocolMessage::Abort {
+            reason: reason.clone(),
+        })
+        .await;
+    reason
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(alpha: u32, beta: u32, at: u32, bt: u32, c: u32, cheat: bool) -> Decided {
+        let params = toy_params();
+        let order = 11u32;
+        let u = params
+            .compute_u(&BigUint::from(alpha), &BigUint::from(beta))
+            .unwrap();
+        let ut = params
+            .compute_u(&BigUint::from(at), &BigUint::from(bt))
+            .unwrap();
+
+        let session = Session::new(params)
+            .receive_commitment(ProtocolMessage::Commitment { u, ut })
+            .unwrap();
+        let (session, challenge) = session.issue_challenge(BigUint::from(c));
+        assert_eq!(
+            challenge,
+            ProtocolMessage::Challenge {
+                c: BigUint::from(c)
+            }
+        );
+
+        let az = (at + alpha * c + cheat as u32) % order;
+        let bz = (bt + beta * c) % order;
+        let session = session
+            .receive_response(ProtocolMessage::Response {
+                alpha: BigUint::from(az),
+                beta: BigUint::from(bz),
+            })
+            .unwrap();
+        session.decision().clone()
+    }
+
+    #[test]
+    fn test_honest_prover_accepted() {
+        for c in 0..11 {
+            assert!(run(5, 2, 3, 7, c, false).accepted);
+        }
+    }
+
+    #[test]
+    fn test_wrong_response_rejected() {
+        let decided = run(5, 2, 3, 7, 4, true);
+        assert!(!decided.accepted);
+        assert_ne!(decided.uz, decided.utuc);
+    }
+
+    #[test]
+    fn test_prover_verifier_api() {
+        let prover = Prover::new(
+            toy_params(),
+            Key::new(BigUint::from(5u32), BigUint::from(2u32)),
+        )
+        .unwrap();
+        let verifier = Verifier::new(toy_params());
+
+        let commitment = prover.commit().unwrap();
+        let session = verifier
+            .session()
+            .receive_commitment(commitment.message())
+            .unwrap();
+        let (session, challenge) = session.issue_challenge(verifier.challenge());
+        let ProtocolMessage::Challenge { c } = challenge else {
+            panic!("ожидалось испытание");
+        };
+        let proof = prover.respond(commitment, &c);
+        assert!(verifier.verify(&proof));
+
+        let session = session.receive_response(proof.response()).unwrap();
+        assert!(session.decision().accepted);
+        assert_eq!(&proof.u, prover.public_key());
+    }
+
+    #[test]
+    fn test_schnorr_over_session() {
+        let prover = Prover::schnorr(toy_params(), BigUint::from(7u32)).unwrap();
+        let verifier = Verifier::with_kind(toy_params(), ProofKind::Schnorr);
+
+        let commitment = prover.commit().unwrap();
+        let session = verifier
+            .session()
+            .receive_commitment(commitment.message())
+            .unwrap();
+        let (session, _) = session.issue_challenge(BigUint::from(4u32));
+        let proof = prover.respond(commitment, &BigUint::from(4u32));
+        assert_eq!(proof.beta, BigUint::ZERO);
+        assert!(verifier.verify(&proof));
+        assert!(
+            session
+                .receive_response(proof.response())
+                .unwrap()
+                .decision()
+                .accepted
+        );
+
+        //Доказательство Окамото не принимается как доказательство Шнорра
+        let okamoto = Prover::new(
+            toy_params(),
+            Key::new(BigUint::from(5u32), BigUint::from(2u32)),
+        )
+        .unwrap()
+        .prove_non_interactive()
+        .unwrap();
+        assert!(okamoto.beta == BigUint::ZERO || !verifier.verify(&okamoto));
+    }
+
+    #[test]
+    fn test_soundness_bits() {
+        assert_eq!(soundness_bits(&BigUint::from(2u32), 40), 40.0);
+        assert_eq!(soundness_bits(&BigUint::from(1u32), 40), 0.0);
+        let big = BigUint::from(1u32) << 255u32;
+        assert!((soundness_bits(&big, 2) - 510.0).abs() < 1e-9);
+        assert!((Verifier::new(toy_params()).soundness_bits(3) - 3.0 * 11f64.log2()).abs() < 1e-9);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_rounds_over_channel() {
+        let (mut prover_side, mut verifier_side) = crate::transport::ChannelTransport::pair();
+        let prover = Prover::new(
+            toy_params(),
+            Key::new(BigUint::from(5u32), BigUint::from(2u32)),
+        )
+        .unwrap();
+        let verifier = tokio::spawn(async move {
+            let mut timeline = Timeline::new();
+            let result = Verifier::new(toy_params())
+                .run_rounds(1, 5, &mut verifier_side, &mut timeline)
+                .await;
+            (result, timeline.steps().len())
+        });
+        let result = Prover::run_rounds(&[prover], 5, &mut prover_side, &mut Timeline::new()).await;
+        assert_eq!(result, Ok(true));
+        assert_eq!(verifier.await.unwrap(), (Ok(true), 20));
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_committed_challenge_over_channel() {
+        let (mut prover_side, mut verifier_side) = crate::transport::ChannelTransport::pair();
+        let prover = Prover::new(
+            toy_params(),
+            Key::new(BigUint::from(5u32), BigUint::from(2u32)),
+        )
+        .unwrap()
+        .with_committed_challenge(true);
+        let verifier = tokio::spawn(async move {
+            let mut timeline = Timeline::new();
+            let result = Verifier::new(toy_params())
+                .with_committed_challenge(true)
+                .run_rounds(1, 2, &mut verifier_side, &mut timeline)
+                .await;
+            (result, timeline.steps().len())
+        });
+        let result = Prover::run_rounds(&[prover], 2, &mut prover_side, &mut Timeline::new()).await;
+        assert_eq!(result, Ok(true));
+        assert_eq!(verifier.await.unwrap(), (Ok(true), 10));
+
+        //Проверяющий раскрывает не то испытание, которое зафиксировал
+        let (mut prover_side, mut verifier_side) = crate::transport::ChannelTransport::pair();
+        let prover = Prover::new(
+            toy_params(),
+            Key::new(BigUint::from(5u32), BigUint::from(2u32)),
+        )
+        .unwrap()
+        .with_committed_challenge(true);
+        let cheater = tokio::spawn(async move {
+            let sealed = SealedChallenge::seal(BigUint::from(3u32));
+            verifier_side.send(&sealed.commitment()).await.unwrap();
+            verifier_side.receive().await.unwrap();
+            let reveal = ProtocolMessage::ChallengeReveal {
+                c: BigUint::from(4u32),
+                salt: sealed.salt,
+            };
+            verifier_side.send(&reveal).await.unwrap();
+            verifier_side.receive().await.unwrap()
+        });
+        let result = prover.run(&mut prover_side, &mut Timeline::new()).await;
+        assert!(result.is_err());
+        assert!(matches!(
+            cheater.await.unwrap(),
+            ProtocolMessage::Abort { .. }
+        ));
+    }
+
+    #[test]
+    fn test_out_of_order_message_rejected() {
+        let result = Session::new(toy_params()).receive_commitment(ProtocolMessage::Challenge {
+            c: BigUint::from(1u32),
+        });
+        assert!(result.is_err());
+    }
+
+    //Аддитивная группа Z_11: логарифм тривиален, но протоколу нужна только структура группы
+    #[derive(Debug, Clone)]
+    struct Additive;
+
+    impl Group for Additive {
+        type Element = u64;
+
+        fn identity(&self) -> u64 {
+            0
+        }
+
+        fn mul(&self, a: &u64, b: &u64) -> u64 {
+            (a + b) % 11
+        }
+
+        fn exp(&self, base: &u64, k: &BigUint) -> u64 {
+            let k = (k % 11u32).to_u64_digits().first().copied().unwrap_or(0);
+            base * k % 11
+        }
+
+        fn order(&self) -> BigUint {
+            BigUint::from(11u32)
+        }
+
+        fn modulus(&self) -> BigUint {
+            BigUint::from(11u32)
+        }
+
+        fn contains(&self, element: &u64) -> bool {
+            *element < 11
+        }
+
+        fn serialize(&self, element: &u64) -> Vec<u8> {
+            element.to_be_bytes().to_vec()
+        }
+
+        fn deserialize(&self, bytes: &[u8]) -> Option<u64> {
+            BigUint::from_bytes_be(bytes).try_into().ok()
+        }
+
+        fn element_len(&self) -> usize {
+            8
+        }
+
+        fn describe(&self) -> Vec<u8> {
+            b"Z_11".to_vec()
+        }
+
+        fn hash_to_element(&self, data: &[u8]) -> u64 {
+            data.iter().map(|b| *b as u64).sum::<u64>() % 11
+        }
+    }
+
+    #[test]
+    fn test_generic_over_group() {
+        let setting = Setting::new(Additive, 3, 5);
+        let secret = Key::new(BigUint::from(4u32), BigUint::from(7u32));
+        let prover = Prover::in_setting(setting.clone(), ProofKind::Okamoto, secret);
+        assert_eq!(*prover.public_key(), (3 * 4 + 5 * 7) % 11);
+
+        let verifier = Verifier::in_setting(setting.clone(), ProofKind::Okamoto);
+        let proof = prover.prove_non_interactive().unwrap();
+        assert!(verifier.verify_non_interactive(&proof));
+
+        let schnorr = Prover::random_in(setting.clone(), ProofKind::Schnorr);
+        let commitment = schnorr.commit().unwrap();
+        let proof = schnorr.respond(commitment, &BigUint::from(6u32));
+        assert!(Verifier::in_setting(setting, ProofKind::Schnorr).verify(&proof));
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_generic_rounds_over_channel() {
+        let setting = Setting::new(Additive, 3, 5);
+        let secret = Key::new(BigUint::from(4u32), BigUint::from(7u32));
+        let prover = Prover::in_setting(setting.clone(), ProofKind::Okamoto, secret);
+        let verifier = Verifier::in_setting(setting, ProofKind::Okamoto);
+
+        let (mut prover_side, mut verifier_side) = crate::transport::ChannelTransport::pair();
+        let handle = tokio::spawn(async move {
+            verifier
+                .run_rounds(1, 3, &mut verifier_side, &mut Timeline::new())
+                .await
+        });
+        let result = Prover::run_rounds(&[prover], 3, &mut prover_side, &mut Timeline::new()).await;
+        assert_eq!(result, Ok(true));
+        assert_eq!(handle.await.unwrap(), Ok(true));
+    }
+
+    #[test]
+    fn test_deterministic_nonces() {
+        let secret = Key::new(BigUint::from(5u32), BigUint::from(2u32));
+        let prover = Prover::new(toy_params(), secret.clone())
+            .unwrap()
+            .with_nonces(NonceMode::Deterministic);
+        let verifier = Verifier::new(toy_params());
+
+        //Неинтерактивное доказательство воспроизводится без генератора случайных чисел
+        let proof = prover.prove_non_interactive().unwrap();
+        assert_eq!(prover.prove_non_interactive().unwrap(), proof);
+        assert!(verifier.verify_non_interactive(&proof));
+        let other = Prover::new(
+            toy_params(),
+            Key::new(BigUint::from(6u32), BigUint::from(2u32)),
+        )
+        .unwrap()
+        .with_nonces(NonceMode::Deterministic);
+        assert_ne!(other.prove_non_interactive().unwrap(), proof);
+
+        let schnorr = Prover::schnorr(toy_params(), BigUint::from(7u32))
+            .unwrap()
+            .with_nonces(NonceMode::Deterministic);
+        let proof = schnorr.prove_non_interactive().unwrap();
+        assert_eq!(proof.beta, BigUint::ZERO);
+        assert!(
+            Verifier::with_kind(toy_params(), ProofKind::Schnorr).verify_non_interactive(&proof)
+        );
+
+        //Интерактивный коммитмент проверяется как обычно
+        let commitment = prover.commit().unwrap();
+        let c = verifier.challenge();
+        assert!(verifier.verify(&prover.respond(commitment, &c)));
+    }
+
+    #[test]
+    fn test_simulated_transcript_accepted() {
+        let u = toy_params()
+            .compute_u(&BigUint::from(5u32), &BigUint::from(2u32))
+            .unwrap();
+        for kind in [ProofKind::Okamoto, ProofKind::Schnorr] {
+            let verifier = Verifier::with_kind(toy_params(), kind);
+            for _ in 0..8 {
+                let proof = verifier.simulate(&u);
+                assert_eq!(proof.u, u);
+                assert!(verifier.verify(&proof));
+            }
+        }
+    }
+
+    #[test]
+    fn test_inspect_non_interactive() {
+        let prover = Prover::new(
+            toy_params(),
+            Key::new(BigUint::from(5u32), BigUint::from(2u32)),
+        )
+        .unwrap();
+        let verifier = Verifier::new(toy_params());
+        let proof = prover.prove_non_interactive().unwrap();
+        let verification = verifier.inspect_non_interactive(&proof);
+        assert!(verification.valid());
+        assert_eq!(verification.failure(), None);
+
+        let mut tampered = proof.clone();
+        tampered.alpha = (&proof.alpha + 1u32) % toy_params().order();
+        let verification = verifier.inspect_non_interactive(&tampered);
+        assert!(verification.elements && verification.challenge && !verification.equation);
+        assert_eq!(verification.failure(), Some("g^a_z * h^b_z != u_t * u^c"));
+    }
+
+    #[test]
+    fn test_injected_rng() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        //Одинаковое зерно дает одинаковые секрет и доказательство
+        let setting = crate::ristretto::setting();
+        let prove = |seed: u64| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let prover = Prover::random_in_with(setting.clone(), ProofKind::Okamoto, &mut rng);
+            let proof = prover
+                .prove_in_with(
+                    &mut ProofTranscript::new(fiat_shamir::PROOF_LABEL),
+                    &mut rng,
+                )
+                .unwrap();
+            (prover.secret().clone(), proof)
+        };
+        let (secret, proof) = prove(7);
+        assert_eq!(prove(7), (secret.clone(), proof.clone()));
+        assert_ne!(prove(8).0, secret);
+        let verifier = Verifier::in_setting(setting.clone(), ProofKind::Okamoto);
+        assert!(verifier.verify_non_interactive(&proof));
+
+        let schnorr = Prover::random_in_with(setting, ProofKind::Schnorr, &mut OsRng);
+        let commitment = schnorr.commit_with(&mut StdRng::seed_from_u64(7));
+        assert_eq!(commitment.nonce.beta, BigUint::ZERO);
+    }
+}
@@ -0,0 +1,201 @@
+//////////////////////////////////////////////////////////////
+// Кольцевая подпись: неинтерактивная дизъюнкция протоколов Шнорра
+// (Крамер-Дамгард-Шенмакерс). Подписавший доказывает "я знаю x_j
+// для одного из ключей y_1..y_N", не раскрывая j: для чужих ключей
+// ответы строятся симулятором, а испытания c_i в сумме равны хэшу.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::fiat_shamir::absorb;
+use crate::group::{Group, Setting};
+use crate::message::decimal_vec;
+
+//Домен хэша, отделяющий кольцевые подписи от других доказательств
+const RING_DOMAIN: &str = "sigma_protocol ring";
+
+//Кольцо открытых ключей вместе с испытаниями и ответами для каждого
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RingSignature {
+    #[serde(with = "decimal_vec")]
+    pub ring: Vec<BigUint>,
+    #[serde(with = "decimal_vec")]
+    pub challenges: Vec<BigUint>,
+    #[serde(with = "decimal_vec")]
+    pub responses: Vec<BigUint>,
+}
+
+//c = H(домен, группа, g, кольцо, сообщение, T_1..T_N) mod q
+fn challenge<G: Group>(
+    setting: &Setting<G>,
+    ring: &[BigUint],
+    message: &[u8],
+    commitments: &[G::Element],
+) -> BigUint {
+    let group = &setting.group;
+    let mut hasher = Sha256::new();
+    absorb(&mut hasher, RING_DOMAIN.as_bytes());
+    absorb(&mut hasher, &group.describe());
+    absorb(&mut hasher, &group.serialize(&setting.g));
+    for y in ring {
+        absorb(&mut hasher, &y.to_bytes_be());
+    }
+    absorb(&mut hasher, message);
+    for t in commitments {
+        absorb(&mut hasher, &group.serialize(t));
+    }
+    BigUint::from_bytes_be(&hasher.finalize()) % setting.order()
+}
+
+//T = g^z * y^-c
+fn simulated<G: Group>(
+    setting: &Setting<G>,
+    y: &G::Element,
+    c: &BigUint,
+    z: &BigUint,
+) -> G::Element {
+    let group = &setting.group;
+    let order = setting.order();
+    group.mul(
+        &group.exp(&setting.g, z),
+        &group.exp(y, &((&order - c % &order) % &order)),
+    )
+}
+
+//Подпись участником index, знающим x: ring[index] = g^x
+pub fn sign<G: Group>(
+    setting: &Setting<G>,
+    ring: &[G::Element],
+    index: usize,
+    x: &BigUint,
+    message: &[u8],
+) -> Result<RingSignature, String> {
+    let group = &setting.group;
+    let order = setting.order();
+    let own = ring.get(index).ok_or("Подписывающий не входит в кольцо")?;
+    if group.exp(&setting.g, x) != *own {
+        return Err("Секрет не соответствует ключу подписывающего".to_string());
+    }
+
+    //Чужие ветви симулируются с заранее выбранными испытаниями
+    let mut challenges = vec![BigUint::ZERO; ring.len()];
+    let mut responses = vec![BigUint::ZERO; ring.len()];
+    let mut commitments = Vec::with_capacity(ring.len());
+    let r = group.random_scalar();
+    for (i, y) in ring.iter().enumerate() {
+        if i == index {
+            commitments.push(group.exp(&setting.g, &r));
+            continue;
+        }
+        challenges[i] = group.random_scalar();
+        responses[i] = group.random_scalar();
+        commitments.push(simulated(setting, y, &challenges[i], &responses[i]));
+    }
+
+    //Своя ветвь получает остаток испытания и честный ответ
+    let encoded: Vec<BigUint> = ring.iter().map(|y| group.encode(y)).collect();
+    let c = challenge(setting, &encoded, message, &commitments);
+    let others = challenges.iter().fold(BigUint::ZERO, |acc, ci| acc + ci) % &order;
+    challenges[index] = (c + &order - others) % &order;
+    responses[index] = (r + x * &challenges[index]) % &order;
+
+    Ok(RingSignature {
+        ring: encoded,
+        challenges,
+        responses,
+    })
+}
+
+//Сумма испытаний должна совпасть с хэшем от восстановленных коммитментов
+pub fn verify<G: Group>(setting: &Setting<G>, message: &[u8], signature: &RingSignature) -> bool {
+    let order = setting.order();
+    let n = signature.ring.len();
+    if n == 0 || signature.challenges.len() != n || signature.responses.len() != n {
+        return false;
+    }
+    let scalars = signature.challenges.iter().chain(&signature.responses);
+    if !scalars.into_iter().all(|s| *s < order) {
+        return false;
+    }
+    let Some(ring) = signature
+        .ring
+        .iter()
+        .map(|y| setting.group.decode(y))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return false;
+    };
+    let commitments: Vec<G::Element> = ring
+        .iter()
+        .zip(&signature.challenges)
+        .zip(&signature.responses)
+        .map(|((y, c), z)| simulated(setting, y, c, z))
+        .collect();
+    let sum = signature
+        .challenges
+        .iter()
+        .fold(BigUint::ZERO, |acc, c| acc + c)
+        % &order;
+    sum == challenge(setting, &signature.ring, message, &commitments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::toy_params;
+    use crate::ristretto;
+
+    fn keys<G: Group>(setting: &Setting<G>, n: usize) -> Vec<(BigUint, G::Element)> {
+        let mut keys = Vec::new();
+        for _ in 0..n {
+            let x = setting.group.random_scalar();
+            let y = setting.group.exp(&setting.g, &x);
+            keys.push((x, y));
+        }
+        keys
+    }
+
+    #[test]
+    fn test_ring_sign_verify() {
+        let setting = ristretto::setting();
+        let keys = keys(&setting, 4);
+        let ring: Vec<_> = keys.iter().map(|(_, y)| *y).collect();
+        for (index, (x, _)) in keys.iter().enumerate() {
+            let signature = sign(&setting, &ring, index, x, b"message").unwrap();
+            assert!(verify(&setting, b"message", &signature));
+            assert!(!verify(&setting, b"other message", &signature));
+
+            let json = serde_json::to_string(&signature).unwrap();
+            let restored: RingSignature = serde_json::from_str(&json).unwrap();
+            assert!(verify(&setting, b"message", &restored));
+        }
+
+        //Подмена ключа в кольце или ответа разрушает подпись
+        let mut signature = sign(&setting, &ring, 1, &keys[1].0, b"m").unwrap();
+        signature.responses[0] = (&signature.responses[0] + 1u32) % setting.order();
+        assert!(!verify(&setting, b"m", &signature));
+        let mut signature = sign(&setting, &ring, 1, &keys[1].0, b"m").unwrap();
+        signature.ring.swap(0, 2);
+        assert!(!verify(&setting, b"m", &signature));
+        signature.ring.pop();
+        assert!(!verify(&setting, b"m", &signature));
+    }
+
+    #[test]
+    fn test_sign_requires_member_secret() {
+        let setting = toy_params().setting();
+        let ring = vec![BigUint::from(4u32), BigUint::from(16u32)];
+        assert!(sign(&setting, &ring, 1, &BigUint::from(3u32), b"m").is_err());
+        assert!(sign(&setting, &ring, 2, &BigUint::from(1u32), b"m").is_err());
+        let signature = sign(&setting, &ring, 1, &BigUint::from(2u32), b"m").unwrap();
+        assert!(verify(&setting, b"m", &signature));
+        let empty = RingSignature {
+            ring: vec![],
+            challenges: vec![],
+            responses: vec![],
+        };
+        assert!(!verify(&setting, b"m", &empty));
+    }
+}
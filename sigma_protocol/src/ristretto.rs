@@ -0,0 +1,125 @@
+//////////////////////////////////////////////////////////////
+// Группа Ristretto255 (curve25519-dalek): группа простого порядка
+// l = 2^252 + 27742317777372353535851937790883648493.
+// Элементы передаются как 32-байтовое сжатое представление.
+//////////////////////////////////////////////////////////////
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use num_bigint::BigUint;
+use sha2::Sha512;
+
+use crate::group::{Group, Setting};
+
+const ELEMENT_BYTES: usize = 32;
+
+//l = 2^252 + 27742317777372353535851937790883648493
+const ORDER: &[u8] =
+    b"7237005577332262213973186563042994240857116359379907606001950938285454250989";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Ristretto255;
+
+impl Ristretto255 {
+    //Скаляр по модулю порядка группы
+    fn scalar(&self, k: &BigUint) -> Scalar {
+        let mut bytes = (k % self.order()).to_bytes_le();
+        bytes.resize(ELEMENT_BYTES, 0);
+        Scalar::from_bytes_mod_order(bytes.try_into().unwrap())
+    }
+}
+
+impl Group for Ristretto255 {
+    type Element = RistrettoPoint;
+
+    fn identity(&self) -> RistrettoPoint {
+        RistrettoPoint::identity()
+    }
+
+    fn mul(&self, a: &RistrettoPoint, b: &RistrettoPoint) -> RistrettoPoint {
+        a + b
+    }
+
+    fn exp(&self, base: &RistrettoPoint, k: &BigUint) -> RistrettoPoint {
+        base * self.scalar(k)
+    }
+
+    fn order(&self) -> BigUint {
+        BigUint::parse_bytes(ORDER, 10).unwrap()
+    }
+
+    //p = 2^255 - 19
+    fn modulus(&self) -> BigUint {
+        (BigUint::from(1u32) << 255u32) - 19u32
+    }
+
+    //Любая точка, прошедшая декодирование, лежит в группе
+    fn contains(&self, _element: &RistrettoPoint) -> bool {
+        true
+    }
+
+    fn serialize(&self, element: &RistrettoPoint) -> Vec<u8> {
+        element.compress().to_bytes().to_vec()
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Option<RistrettoPoint> {
+        CompressedRistretto::from_slice(bytes).ok()?.decompress()
+    }
+
+    fn element_len(&self) -> usize {
+        ELEMENT_BYTES
+    }
+
+    fn describe(&self) -> Vec<u8> {
+        b"ristretto255".to_vec()
+    }
+
+    fn hash_to_element(&self, data: &[u8]) -> RistrettoPoint {
+        RistrettoPoint::hash_from_bytes::<Sha512>(data)
+    }
+}
+
+//g - стандартная базовая точка, h выводится из g хэшированием в группу
+pub fn setting() -> Setting<Ristretto255> {
+    Setting::derive(Ristretto255, RISTRETTO_BASEPOINT_POINT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Key, ProofKind, Prover, Verifier};
+
+    #[test]
+    fn test_order_and_encoding() {
+        let s = setting();
+        let group = &s.group;
+        assert_eq!(group.exp(&s.g, &group.order()), group.identity());
+        assert_eq!(group.decode(&group.encode(&s.h)), Some(s.h));
+        //Точка с ведущим нулевым байтом восстанавливается
+        let x = (1u32..)
+            .map(|k| group.exp(&s.g, &BigUint::from(k)))
+            .find(|p| group.serialize(p)[0] == 0)
+            .unwrap();
+        assert_eq!(group.decode(&group.encode(&x)), Some(x));
+        assert_eq!(group.decode(&(BigUint::from(1u32) << 256u32)), None);
+    }
+
+    #[test]
+    fn test_proofs_over_ristretto() {
+        let secret = Key::new(BigUint::from(5u32), BigUint::from(2u32));
+        let prover = Prover::in_setting(setting(), ProofKind::Okamoto, secret);
+        let verifier = Verifier::in_setting(setting(), ProofKind::Okamoto);
+        let proof = prover.prove_non_interactive().unwrap();
+        assert!(verifier.verify_non_interactive(&proof));
+
+        let mut forged = proof.clone();
+        forged.alpha += 1u32;
+        assert!(!verifier.verify(&forged));
+
+        let schnorr = Prover::random_in(setting(), ProofKind::Schnorr);
+        let proof = schnorr.prove_non_interactive().unwrap();
+        assert!(Verifier::in_setting(setting(), ProofKind::Schnorr).verify_non_interactive(&proof));
+    }
+}
@@ -0,0 +1,106 @@
+//////////////////////////////////////////////////////////////
+// Протокол Шнорра: доказательство знания x такого, что u = g^x.
+// В сообщениях протокола записывается как ответ Окамото с b_z = 0.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+
+use crate::group::{Group, ModGroup, Setting};
+use crate::math;
+use crate::protocol::PublicParams;
+use crate::sigma::SigmaProtocol;
+
+#[derive(Debug, Clone)]
+pub struct Schnorr<G: Group = ModGroup> {
+    setting: Setting<G>,
+}
+
+impl Schnorr {
+    pub fn new(params: PublicParams) -> Self {
+        Schnorr::in_setting(params.setting())
+    }
+}
+
+impl<G: Group> Schnorr<G> {
+    pub fn in_setting(setting: Setting<G>) -> Self {
+        Schnorr { setting }
+    }
+
+    pub fn setting(&self) -> &Setting<G> {
+        &self.setting
+    }
+
+    //u = g^x
+    pub fn statement(&self, x: &BigUint) -> G::Element {
+        self.setting.group.exp(&self.setting.g, x)
+    }
+}
+
+impl<G: Group> SigmaProtocol for Schnorr<G> {
+    type Statement = G::Element;
+    type Witness = BigUint;
+    type Nonce = BigUint;
+    type Commitment = G::Element;
+    type Challenge = BigUint;
+    type Response = BigUint;
+
+    fn commit(&self, _statement: &G::Element, _witness: &BigUint) -> Option<(BigUint, G::Element)> {
+        let r = self.setting.group.random_scalar();
+        let ut = self.statement(&r);
+        Some((r, ut))
+    }
+
+    fn challenge(&self) -> BigUint {
+        self.setting.group.random_scalar()
+    }
+
+    //z = r + x*c
+    fn respond(&self, x: &BigUint, r: BigUint, c: &BigUint) -> BigUint {
+        (self.setting.scalar(x.clone()) * c + &r).into_value()
+    }
+
+    //g^z == u_t * u^c
+    fn verify(&self, u: &G::Element, ut: &G::Element, c: &BigUint, z: &BigUint) -> bool {
+        let group = &self.setting.group;
+        if !group.contains(u) || !group.contains(ut) {
+            return false;
+        }
+        let left = group.encode(&self.statement(z));
+        math::ct_eq(&left, &group.encode(&group.mul(ut, &group.exp(u, c))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::toy_params;
+    use crate::sigma;
+
+    // Подгруппа порядка 11 в Z_23^*
+    fn schnorr() -> Schnorr {
+        Schnorr::new(toy_params())
+    }
+
+    #[test]
+    fn test_schnorr_equation() {
+        let protocol = schnorr();
+        let x = BigUint::from(7u32);
+        let u = protocol.statement(&x);
+        let r = BigUint::from(3u32);
+        let ut = protocol.statement(&r);
+        for c in 0..11u32 {
+            let c = BigUint::from(c);
+            let z = protocol.respond(&x, r.clone(), &c);
+            assert!(protocol.verify(&u, &ut, &c, &z));
+            assert!(!protocol.verify(&u, &ut, &c, &((z + 1u32) % 11u32)));
+        }
+    }
+
+    #[test]
+    fn test_execute_schnorr() {
+        let protocol = schnorr();
+        let x = BigUint::from(7u32);
+        let u = protocol.statement(&x);
+        assert_eq!(sigma::execute(&protocol, &u, &x), Some(true));
+    }
+}
@@ -0,0 +1,143 @@
+//////////////////////////////////////////////////////////////
+// Группа точек кривой secp256k1 (Bitcoin, Ethereum).
+// Элементы передаются как сжатые точки SEC1 (33 байта), поэтому
+// публичный ключ Шнорра u = g^x совпадает со сжатым ключом кошелька.
+//////////////////////////////////////////////////////////////
+
+use k256::elliptic_curve::PrimeField;
+use k256::elliptic_curve::group::prime::PrimeCurveAffine;
+use k256::elliptic_curve::hash2curve::{ExpandMsgXmd, GroupDigest};
+use k256::elliptic_curve::sec1::{FromEncodedPoint, ToEncodedPoint};
+use k256::{AffinePoint, EncodedPoint, ProjectivePoint, Scalar, Secp256k1 as Curve};
+use num_bigint::BigUint;
+use sha2::Sha256;
+
+use crate::group::{Group, Setting};
+
+//Домен хэширования в кривую (RFC 9380)
+const H_DST: &[u8] = b"sigma_protocol-secp256k1_XMD:SHA-256_SSWU_RO_";
+
+const ELEMENT_BYTES: usize = 33;
+
+//n = FFFFFFFF FFFFFFFF FFFFFFFF FFFFFFFE BAAEDCE6 AF48A03B BFD25E8C D0364141
+const ORDER: &[u8] = b"FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141";
+
+//p = 2^256 - 2^32 - 977
+const MODULUS: &[u8] = b"FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Secp256k1;
+
+impl Secp256k1 {
+    //Скаляр по модулю порядка группы
+    fn scalar(&self, k: &BigUint) -> Scalar {
+        let bytes = (k % self.order()).to_bytes_be();
+        let mut repr = [0u8; 32];
+        repr[32 - bytes.len()..].copy_from_slice(&bytes);
+        Scalar::from_repr(repr.into()).unwrap_or(Scalar::ZERO)
+    }
+}
+
+impl Group for Secp256k1 {
+    type Element = ProjectivePoint;
+
+    fn identity(&self) -> ProjectivePoint {
+        ProjectivePoint::IDENTITY
+    }
+
+    fn mul(&self, a: &ProjectivePoint, b: &ProjectivePoint) -> ProjectivePoint {
+        a + b
+    }
+
+    fn exp(&self, base: &ProjectivePoint, k: &BigUint) -> ProjectivePoint {
+        base * &self.scalar(k)
+    }
+
+    fn order(&self) -> BigUint {
+        BigUint::parse_bytes(ORDER, 16).unwrap()
+    }
+
+    fn modulus(&self) -> BigUint {
+        BigUint::parse_bytes(MODULUS, 16).unwrap()
+    }
+
+    //Бесконечно удаленная точка не имеет сжатого представления и не допускается
+    fn contains(&self, element: &ProjectivePoint) -> bool {
+        !bool::from(element.to_affine().is_identity())
+    }
+
+    fn serialize(&self, element: &ProjectivePoint) -> Vec<u8> {
+        element
+            .to_affine()
+            .to_encoded_point(true)
+            .as_bytes()
+            .to_vec()
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> Option<ProjectivePoint> {
+        let point = EncodedPoint::from_bytes(bytes).ok()?;
+        let point: Option<AffinePoint> = AffinePoint::from_encoded_point(&point).into();
+        point.map(ProjectivePoint::from)
+    }
+
+    fn element_len(&self) -> usize {
+        ELEMENT_BYTES
+    }
+
+    fn describe(&self) -> Vec<u8> {
+        b"secp256k1".to_vec()
+    }
+
+    fn hash_to_element(&self, data: &[u8]) -> ProjectivePoint {
+        Curve::hash_from_bytes::<ExpandMsgXmd<Sha256>>(&[data], &[H_DST])
+            .expect("hash to curve with a fixed domain")
+    }
+}
+
+//g - стандартная образующая кривой, h выводится из g хэшированием в кривую
+pub fn setting() -> Setting<Secp256k1> {
+    Setting::derive(Secp256k1, ProjectivePoint::GENERATOR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding;
+    use crate::protocol::{Key, ProofKind, Prover, Verifier};
+
+    #[test]
+    fn test_public_key_matches_sec1() {
+        let s = setting();
+        let group = &s.group;
+        //x = 1: публичный ключ - сама образующая, 02 79BE667E...
+        let u = group.exp(&s.g, &BigUint::from(1u32));
+        assert_eq!(
+            encoding::to_hex(&group.serialize(&u)),
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+        );
+        assert_eq!(group.exp(&s.g, &group.order()), group.identity());
+        assert_eq!(group.decode(&group.encode(&s.h)), Some(s.h));
+        assert_eq!(group.decode(&BigUint::ZERO), None);
+    }
+
+    #[test]
+    fn test_proofs_over_secp256k1() {
+        let x = BigUint::parse_bytes(
+            b"1e99423a4ed27608a15a2616a2b0e9e52ced330ac530edcc32c8ffc6a526aedd",
+            16,
+        )
+        .unwrap();
+        let prover = Prover::in_setting(setting(), ProofKind::Schnorr, Key::new(x, BigUint::ZERO));
+        let verifier = Verifier::in_setting(setting(), ProofKind::Schnorr);
+        let proof = prover.prove_non_interactive().unwrap();
+        assert!(verifier.verify_non_interactive(&proof));
+
+        let mut forged = proof.clone();
+        forged.alpha += 1u32;
+        assert!(!verifier.verify(&forged));
+
+        let okamoto = Prover::random_in(setting(), ProofKind::Okamoto);
+        let proof = okamoto.prove_non_interactive().unwrap();
+        assert!(Verifier::in_setting(setting(), ProofKind::Okamoto).verify_non_interactive(&proof));
+    }
+}
@@ -0,0 +1,379 @@
+//////////////////////////////////////////////////////////////
+// Сессии протокола. Каждый запуск получает свой идентификатор,
+// собственную шину событий и состояние, которое можно
+// запросить по идентификатору. Сессия внешнего доказывающего
+// хранит проверяющего между запросами и истекает, если
+// доказывающий молчит дольше timeout. Когда мест нет,
+// вытесняется самая старая завершенная или истекшая сессия.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::encoding;
+use crate::events::EventBus;
+use crate::message::ProtocolMessage;
+use crate::protocol::{AwaitingChallenge, AwaitingResponse, Decided, Session};
+
+//Проверяющий для внешнего доказывающего
+pub const COMMIT_PATH: &str = "/commit";
+pub const CHALLENGE_PATH: &str = "/challenge";
+pub const RESPONSE_PATH: &str = "/response";
+
+//Длина идентификатора сессии в байтах
+const ID_BYTES: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SessionStatus {
+    Created,
+    Running,
+    Finished { accepted: bool },
+    Failed { reason: String },
+}
+
+impl SessionStatus {
+    pub fn is_done(&self) -> bool {
+        matches!(
+            self,
+            SessionStatus::Finished { .. } | SessionStatus::Failed { .. }
+        )
+    }
+}
+
+//Проверяющий внешнего доказывающего между его запросами
+enum Exchange {
+    AwaitingChallenge(Session<AwaitingChallenge>),
+    AwaitingResponse(Session<AwaitingResponse>, BigUint),
+}
+
+impl fmt::Debug for Exchange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Exchange::AwaitingChallenge(_) => write!(f, "AwaitingChallenge"),
+            Exchange::AwaitingResponse(_, c) => write!(f, "AwaitingResponse(c = {})", c),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExchangeError {
+    Unknown,
+    Expired,
+    //Шаг не соответствует состоянию сессии
+    OutOfOrder(String),
+    Invalid(String),
+}
+
+impl fmt::Display for ExchangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExchangeError::Unknown => write!(f, "Сессия не найдена"),
+            ExchangeError::Expired => write!(f, "Сессия истекла"),
+            ExchangeError::OutOfOrder(reason) | ExchangeError::Invalid(reason) => {
+                write!(f, "{}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExchangeError {}
+
+//Состояние сессии для /sessions/{id}
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    #[serde(flatten)]
+    pub status: SessionStatus,
+    pub age_ms: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    status: SessionStatus,
+    events: EventBus,
+    created: Instant,
+    //Время последнего шага внешнего доказывающего
+    touched: Instant,
+    exchange: Option<Exchange>,
+}
+
+impl Entry {
+    fn expired(&self, timeout: Duration) -> bool {
+        self.exchange.is_some() && self.touched.elapsed() > timeout
+    }
+}
+
+#[derive(Debug)]
+pub struct SessionRegistry {
+    capacity: usize,
+    timeout: Duration,
+    sessions: Mutex<HashMap<String, Entry>>,
+}
+
+impl SessionRegistry {
+    pub fn new(capacity: usize, timeout: Duration) -> Self {
+        SessionRegistry {
+            capacity,
+            timeout,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    //Новая сессия со своей шиной событий
+    pub fn create(&self, event_capacity: usize) -> Result<(String, EventBus), String> {
+        self.insert(event_capacity, SessionStatus::Created, None)
+    }
+
+    //Сессия внешнего доказывающего, приславшего коммитмент
+    pub fn create_external(
+        &self,
+        event_capacity: usize,
+        session: Session<AwaitingChallenge>,
+    ) -> Result<(String, EventBus), String> {
+        self.insert(
+            event_capacity,
+            SessionStatus::Running,
+            Some(Exchange::AwaitingChallenge(session)),
+        )
+    }
+
+    fn insert(
+        &self,
+        event_capacity: usize,
+        status: SessionStatus,
+        exchange: Option<Exchange>,
+    ) -> Result<(String, EventBus), String> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.len() >= self.capacity {
+            let oldest = sessions
+                .iter()
+                .filter(|(_, entry)| entry.status.is_done() || entry.expired(self.timeout))
+                .min_by_key(|(_, entry)| entry.created)
+                .map(|(id, _)| id.clone())
+                .ok_or(format!("Все {} сессий еще выполняются", self.capacity))?;
+            sessions.remove(&oldest);
+        }
+        let id = encoding::to_hex(&rand::random::<[u8; ID_BYTES]>());
+        let events = EventBus::new(event_capacity);
+        sessions.insert(
+            id.clone(),
+            Entry {
+                status,
+                events: events.clone(),
+                created: Instant::now(),
+                touched: Instant::now(),
+                exchange,
+            },
+        );
+        Ok((id, events))
+    }
+
+    pub fn info(&self, id: &str) -> Option<SessionInfo> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let entry = sessions.get_mut(id)?;
+        self.expire(entry);
+        Some(SessionInfo {
+            id: id.to_string(),
+            status: entry.status.clone(),
+            age_ms: entry.created.elapsed().as_millis() as u64,
+        })
+    }
+
+    //Истекшая сессия теряет проверяющего и завершается с ошибкой
+    fn expire(&self, entry: &mut Entry) -> bool {
+        if !entry.expired(self.timeout) {
+            return false;
+        }
+        entry.exchange = None;
+        entry.status = SessionStatus::Failed {
+            reason: format!("Нет ответа доказывающего дольше {:?}", self.timeout),
+        };
+        true
+    }
+
+    //Следующий шаг внешнего доказывающего: состояние проверяющего
+    //забирается из сессии, и step возвращает новое вместе с результатом
+    fn advance<T>(
+        &self,
+        id: &str,
+        step: impl FnOnce(Exchange) -> (Option<Exchange>, Result<T, ExchangeError>),
+    ) -> Result<T, ExchangeError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let entry = sessions.get_mut(id).ok_or(ExchangeError::Unknown)?;
+        if self.expire(entry) {
+            return Err(ExchangeError::Expired);
+        }
+        let exchange = entry.exchange.take().ok_or(ExchangeError::OutOfOrder(
+            "Сессия не ждет шагов доказывающего".to_string(),
+        ))?;
+        let (exchange, result) = step(exchange);
+        entry.exchange = exchange;
+        match &result {
+            Ok(_) => entry.touched = Instant::now(),
+            //Сессия без проверяющего больше не продолжится
+            Err(e) if entry.exchange.is_none() => {
+                entry.status = SessionStatus::Failed {
+                    reason: e.to_string(),
+                }
+            }
+            Err(_) => {}
+        }
+        result
+    }
+
+    //Испытание для коммитмента; повторный запрос получает то же испытание
+    pub fn challenge(
+        &self,
+        id: &str,
+        choose: impl FnOnce() -> BigUint,
+    ) -> Result<BigUint, ExchangeError> {
+        self.advance(id, |exchange| match exchange {
+            Exchange::AwaitingChallenge(session) => {
+                let c = choose();
+                let (session, _) = session.issue_challenge(c.clone());
+                (Some(Exchange::AwaitingResponse(session, c.clone())), Ok(c))
+            }
+            Exchange::AwaitingResponse(session, c) => {
+                (Some(Exchange::AwaitingResponse(session, c.clone())), Ok(c))
+            }
+        })
+    }
+
+    //Проверка ответа; после нее сессия завершена, в том числе
+    //при некорректном ответе
+    pub fn respond(&self, id: &str, message: ProtocolMessage) -> Result<Decided, ExchangeError> {
+        let decided = self.advance(id, |exchange| match exchange {
+            Exchange::AwaitingResponse(session, _) => match session.receive_response(message) {
+                Ok(session) => (None, Ok(session.decision().clone())),
+                Err(e) => (None, Err(ExchangeError::Invalid(e))),
+            },
+            other => (
+                Some(other),
+                Err(ExchangeError::OutOfOrder(
+                    "Ответ до получения испытания".to_string(),
+                )),
+            ),
+        })?;
+        self.set_status(
+            id,
+            SessionStatus::Finished {
+                accepted: decided.accepted,
+            },
+        );
+        Ok(decided)
+    }
+
+    pub fn events(&self, id: &str) -> Option<EventBus> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions.get(id).map(|entry| entry.events.clone())
+    }
+
+    pub fn set_status(&self, id: &str, status: SessionStatus) {
+        if let Some(entry) = self.sessions.lock().unwrap().get_mut(id) {
+            entry.status = status;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::toy_params;
+
+    //u = 4^3 и u_t = 4^5 в подгруппе порядка 11 в Z_23^*
+    fn commitment() -> Session<AwaitingChallenge> {
+        Session::new(toy_params())
+            .receive_commitment(ProtocolMessage::Commitment {
+                u: BigUint::from(18u32),
+                ut: BigUint::from(12u32),
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_session_lifecycle() {
+        let registry = SessionRegistry::new(4, Duration::from_secs(60));
+        let (id, _) = registry.create(8).unwrap();
+        assert_eq!(id.len(), 2 * ID_BYTES);
+        assert_eq!(registry.info(&id).unwrap().status, SessionStatus::Created);
+
+        registry.set_status(&id, SessionStatus::Finished { accepted: true });
+        assert_eq!(
+            registry.info(&id).unwrap().status,
+            SessionStatus::Finished { accepted: true }
+        );
+        assert!(registry.info("unknown").is_none());
+    }
+
+    #[test]
+    fn test_external_exchange() {
+        let registry = SessionRegistry::new(4, Duration::from_secs(60));
+        let (id, _) = registry.create_external(8, commitment()).unwrap();
+        let early = ProtocolMessage::Response {
+            alpha: BigUint::ZERO,
+            beta: BigUint::ZERO,
+        };
+        assert!(matches!(
+            registry.respond(&id, early),
+            Err(ExchangeError::OutOfOrder(_))
+        ));
+
+        let c = registry.challenge(&id, || BigUint::from(2u32)).unwrap();
+        //Повторный запрос не меняет испытание
+        assert_eq!(registry.challenge(&id, || BigUint::from(7u32)).unwrap(), c);
+
+        //a_z = 5 + 2 * 3 mod 11 = 0
+        let response = ProtocolMessage::Response {
+            alpha: BigUint::ZERO,
+            beta: BigUint::ZERO,
+        };
+        assert!(registry.respond(&id, response).unwrap().accepted);
+        assert_eq!(
+            registry.info(&id).unwrap().status,
+            SessionStatus::Finished { accepted: true }
+        );
+        assert!(registry.challenge(&id, || c.clone()).is_err());
+    }
+
+    #[test]
+    fn test_silent_prover_expires() {
+        let registry = SessionRegistry::new(1, Duration::from_millis(10));
+        let (id, _) = registry.create_external(8, commitment()).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(
+            registry.challenge(&id, || BigUint::from(2u32)),
+            Err(ExchangeError::Expired)
+        );
+        assert!(matches!(
+            registry.info(&id).unwrap().status,
+            SessionStatus::Failed { .. }
+        ));
+        //Истекшая сессия освобождает место
+        assert!(registry.create(8).is_ok());
+    }
+
+    #[test]
+    fn test_full_registry_evicts_finished_only() {
+        let registry = SessionRegistry::new(2, Duration::from_secs(60));
+        let (first, _) = registry.create(8).unwrap();
+        let (second, _) = registry.create(8).unwrap();
+        registry.set_status(&second, SessionStatus::Running);
+        assert!(registry.create(8).is_err());
+
+        registry.set_status(
+            &first,
+            SessionStatus::Failed {
+                reason: "timeout".to_string(),
+            },
+        );
+        let (third, _) = registry.create(8).unwrap();
+        assert!(registry.info(&first).is_none());
+        assert!(registry.info(&second).is_some());
+        assert!(registry.info(&third).is_some());
+    }
+}
@@ -0,0 +1,223 @@
+//////////////////////////////////////////////////////////////
+// Общий вид трехходового сигма-протокола:
+// коммитмент -> испытание -> ответ -> проверка.
+//////////////////////////////////////////////////////////////
+
+pub trait SigmaProtocol {
+    //Открытое утверждение (публичный ключ)
+    type Statement;
+    //Секрет доказывающего
+    type Witness;
+    //Одноразовое состояние доказывающего между коммитментом и ответом
+    type Nonce;
+    type Commitment;
+    type Challenge;
+    type Response;
+
+    fn commit(
+        &self,
+        statement: &Self::Statement,
+        witness: &Self::Witness,
+    ) -> Option<(Self::Nonce, Self::Commitment)>;
+
+    fn challenge(&self) -> Self::Challenge;
+
+    fn respond(
+        &self,
+        witness: &Self::Witness,
+        nonce: Self::Nonce,
+        challenge: &Self::Challenge,
+    ) -> Self::Response;
+
+    fn verify(
+        &self,
+        statement: &Self::Statement,
+        commitment: &Self::Commitment,
+        challenge: &Self::Challenge,
+        response: &Self::Response,
+    ) -> bool;
+}
+
+//Прогон всех ходов протокола в одном процессе
+pub fn execute<P: SigmaProtocol>(
+    protocol: &P,
+    statement: &P::Statement,
+    witness: &P::Witness,
+) -> Option<bool> {
+    let (nonce, commitment) = protocol.commit(statement, witness)?;
+    let challenge = protocol.challenge();
+    let response = protocol.respond(witness, nonce, &challenge);
+    Some(protocol.verify(statement, &commitment, &challenge, &response))
+}
+
+//////////////////////////////////////////////////////////////
+// Конъюнкция (параллельная композиция): все утверждения
+// доказываются одновременно с одним общим испытанием
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct And<P> {
+    parts: Vec<P>,
+}
+
+impl<P> And<P> {
+    //Каждому утверждению свой экземпляр протокола (например, свои образующие)
+    pub fn new(parts: Vec<P>) -> Self {
+        And { parts }
+    }
+
+    pub fn parts(&self) -> &[P] {
+        &self.parts
+    }
+}
+
+impl<P> SigmaProtocol for And<P>
+where
+    P: SigmaProtocol + Sync,
+    P::Statement: Sync,
+    P::Witness: Sync,
+    P::Nonce: Send,
+    P::Commitment: Send,
+{
+    type Statement = Vec<P::Statement>;
+    type Witness = Vec<P::Witness>;
+    type Nonce = Vec<P::Nonce>;
+    type Commitment = Vec<P::Commitment>;
+    type Challenge = P::Challenge;
+    type Response = Vec<P::Response>;
+
+    fn commit(
+        &self,
+        statements: &Vec<P::Statement>,
+        witnesses: &Vec<P::Witness>,
+    ) -> Option<(Vec<P::Nonce>, Vec<P::Commitment>)> {
+        if statements.len() != self.parts.len() || witnesses.len() != self.parts.len() {
+            return None;
+        }
+        let mut nonces = Vec::with_capacity(self.parts.len());
+        let mut commitments = Vec::with_capacity(self.parts.len());
+        for ((part, statement), witness) in self.parts.iter().zip(statements).zip(witnesses) {
+            let (nonce, commitment) = part.commit(statement, witness)?;
+            nonces.push(nonce);
+            commitments.push(commitment);
+        }
+        Some((nonces, commitments))
+    }
+
+    //Испытание выбирается по первой части (пустая конъюнкция не имеет смысла)
+    fn challenge(&self) -> P::Challenge {
+        self.parts[0].challenge()
+    }
+
+    fn respond(
+        &self,
+        witnesses: &Vec<P::Witness>,
+        nonces: Vec<P::Nonce>,
+        c: &P::Challenge,
+    ) -> Vec<P::Response> {
+        self.parts
+            .iter()
+            .zip(witnesses)
+            .zip(nonces)
+            .map(|((part, witness), nonce)| part.respond(witness, nonce, c))
+            .collect()
+    }
+
+    fn verify(
+        &self,
+        statements: &Vec<P::Statement>,
+        commitments: &Vec<P::Commitment>,
+        c: &P::Challenge,
+        responses: &Vec<P::Response>,
+    ) -> bool {
+        let n = self.parts.len();
+        if n == 0 || statements.len() != n || commitments.len() != n || responses.len() != n {
+            return false;
+        }
+        self.parts
+            .iter()
+            .zip(statements)
+            .zip(commitments)
+            .zip(responses)
+            .all(|(((part, statement), commitment), response)| {
+                part.verify(statement, commitment, c, response)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Key, Okamoto, PublicParams, toy_params};
+    use num_bigint::BigUint;
+
+    // Подгруппа порядка 11 в Z_23^*
+    fn okamoto() -> Okamoto {
+        okamoto_with(4, 9)
+    }
+
+    fn okamoto_with(g: u32, h: u32) -> Okamoto {
+        Okamoto::new(PublicParams {
+            g: BigUint::from(g),
+            h: BigUint::from(h),
+            ..toy_params()
+        })
+    }
+
+    fn key(alpha: u32, beta: u32) -> Key {
+        Key::new(BigUint::from(alpha), BigUint::from(beta))
+    }
+
+    #[test]
+    fn test_execute_okamoto() {
+        let protocol = okamoto();
+        let witness = Key::new(BigUint::from(5u32), BigUint::from(2u32));
+        let statement = protocol.statement(&witness);
+        assert_eq!(execute(&protocol, &statement, &witness), Some(true));
+
+        let wrong = Key::new(BigUint::from(6u32), BigUint::from(2u32));
+        let mut rejected = false;
+        for _ in 0..8 {
+            //При c = 0 неверный свидетель проходит проверку
+            rejected |= execute(&protocol, &statement, &wrong) == Some(false);
+        }
+        assert!(rejected);
+    }
+
+    #[test]
+    fn test_and_shared_challenge() {
+        let and = And::new(vec![okamoto(), okamoto_with(9, 4), okamoto_with(2, 3)]);
+        let witnesses = vec![key(5, 2), key(1, 7), key(3, 3)];
+        let statements: Vec<_> = and
+            .parts()
+            .iter()
+            .zip(&witnesses)
+            .map(|(part, w)| part.statement(w))
+            .collect();
+        let nonces = vec![key(3, 7), key(0, 4), key(8, 1)];
+        let commitments: Vec<_> = and
+            .parts()
+            .iter()
+            .zip(&nonces)
+            .map(|(part, n)| part.statement(n))
+            .collect();
+        let c = BigUint::from(6u32);
+
+        let responses = and.respond(&witnesses, nonces.clone(), &c);
+        assert!(and.verify(&statements, &commitments, &c, &responses));
+
+        //Одно неверное утверждение проваливает всю конъюнкцию
+        let mut forged = responses.clone();
+        forged[1].alpha += 1u32;
+        assert!(!and.verify(&statements, &commitments, &c, &forged));
+        assert!(!and.verify(&statements[..2].to_vec(), &commitments, &c, &responses));
+    }
+
+    #[test]
+    fn test_execute_and() {
+        let and = And::new(vec![okamoto(), okamoto()]);
+        let witnesses = vec![key(5, 2), key(4, 4)];
+        let statements: Vec<_> = witnesses.iter().map(|w| okamoto().statement(w)).collect();
+        assert_eq!(execute(&and, &statements, &witnesses), Some(true));
+    }
+}
@@ -0,0 +1,177 @@
+//////////////////////////////////////////////////////////////
+// Оценка стойкости параметров группы в битах. Логарифм в группе
+// вычетов по модулю p берется общим решетом числового поля (GNFS)
+// за L_p[1/3, 1.923]; логарифм в подгруппе порядка q любой группы -
+// ρ-методом Полларда примерно за sqrt(q) шагов. Стойкость - меньшая
+// из двух оценок. Кроме длины проверяются известные слабые формы:
+// p близко к степени двойки (специальное решето быстрее общего),
+// малые подгруппы при p - 1 != 2q и h, не выведенная из g.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use num_traits::One;
+use serde::Serialize;
+use std::f64::consts::LN_2;
+
+use crate::group::{Group, ModGroup, Setting};
+
+//Рекомендуемый NIST SP 800-57 минимум стойкости до 2030 года
+pub const DEFAULT_MIN_SECURITY_BITS: u32 = 112;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Strength {
+    //Длина модуля p; для кривых решето неприменимо и модуля нет
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modulus_bits: Option<u64>,
+    pub order_bits: u64,
+    pub security_bits: u32,
+    pub warnings: Vec<String>,
+}
+
+impl Strength {
+    //Группа вычетов: решето по p и ρ-метод по q
+    pub fn modular(setting: &Setting<ModGroup>, threshold: u32) -> Self {
+        let p = setting.group.modulus();
+        let q = setting.group.order();
+        let mut warnings = Vec::new();
+        if near_power_of_two(&p) {
+            warnings.push(
+                "p близко к степени двойки: специальное решето числового поля быстрее общего"
+                    .to_string(),
+            );
+        }
+        let cofactor = (&p - 1u32) / &q;
+        if cofactor != BigUint::from(2u32) {
+            warnings.push(format!(
+                "p - 1 = {} * q: у группы есть малые подгруппы, элементы собеседника \
+                 нужно проверять на принадлежность подгруппе порядка q",
+                cofactor
+            ));
+        }
+        let level = gnfs_bits(p.bits()).min(rho_bits(&q));
+        Strength {
+            modulus_bits: Some(p.bits()),
+            ..Strength::finish(setting, level, warnings, threshold)
+        }
+    }
+
+    //Кривая: логарифм берется только ρ-методом
+    pub fn curve<G: Group>(setting: &Setting<G>, threshold: u32) -> Self {
+        let level = rho_bits(&setting.group.order());
+        Strength::finish(setting, level, Vec::new(), threshold)
+    }
+
+    pub fn is_weak(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+
+    fn finish<G: Group>(
+        setting: &Setting<G>,
+        level: u32,
+        mut warnings: Vec<String>,
+        threshold: u32,
+    ) -> Self {
+        if !setting.h_is_derived() {
+            warnings.push(
+                "h не выведена из g хэшированием: log_g(h) может знать тот, кто выбрал параметры"
+                    .to_string(),
+            );
+        }
+        if level < threshold {
+            warnings.insert(
+                0,
+                format!(
+                    "Стойкость около {} бит ниже порога {} бит",
+                    level, threshold
+                ),
+            );
+        }
+        Strength {
+            modulus_bits: None,
+            order_bits: setting.group.order().bits(),
+            security_bits: level,
+            warnings,
+        }
+    }
+}
+
+//Стойкость модуля по таблице NIST SP 800-57 (часть 1, табл. 2)
+const NIST_LEVELS: [(u64, u32); 5] = [
+    (1024, 80),
+    (2048, 112),
+    (3072, 128),
+    (7680, 192),
+    (15360, 256),
+];
+
+//Стойкость к GNFS: от 1024 бит - по таблице NIST, ниже - log2 L_p[1/3, 1.923]
+//с поправкой -4.69, которая на 1024 битах дает те же 80 бит
+pub fn gnfs_bits(modulus_bits: u64) -> u32 {
+    if let Some(&(_, level)) = NIST_LEVELS
+        .iter()
+        .rev()
+        .find(|(len, _)| modulus_bits >= *len)
+    {
+        return level;
+    }
+    let ln_p = modulus_bits as f64 * LN_2;
+    let work = 1.923 * ln_p.cbrt() * ln_p.ln().powf(2.0 / 3.0) - 4.69;
+    (work / LN_2).max(0.0) as u32
+}
+
+//ρ-метод Полларда: около sqrt(q) операций в группе
+pub fn rho_bits(order: &BigUint) -> u32 {
+    (order.bits() / 2) as u32
+}
+
+//|p - 2^k| < 2^(k/2) для ближайшей степени двойки 2^k
+fn near_power_of_two(p: &BigUint) -> bool {
+    let bits = p.bits();
+    let below = BigUint::one() << (bits - 1);
+    let above = BigUint::one() << bits;
+    let distance = (p - &below).min(&above - p);
+    distance.bits() <= bits / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{modp, ristretto};
+
+    #[test]
+    fn test_security_levels() {
+        //Формула ниже 1024 бит продолжает таблицу без скачка
+        assert_eq!(gnfs_bits(2048), 112);
+        assert_eq!(gnfs_bits(4096), 128);
+        assert!((78..=80).contains(&gnfs_bits(1023)));
+        assert!(gnfs_bits(512) < gnfs_bits(1023));
+        assert_eq!(gnfs_bits(5), 0);
+
+        let modp = modp::params(2048).unwrap().setting();
+        let strength = Strength::modular(&modp, DEFAULT_MIN_SECURITY_BITS);
+        assert!(strength.security_bits >= DEFAULT_MIN_SECURITY_BITS);
+        assert_eq!(strength.modulus_bits, Some(2048));
+
+        let curve = Strength::curve(&ristretto::setting(), DEFAULT_MIN_SECURITY_BITS);
+        assert_eq!((curve.security_bits, curve.modulus_bits), (126, None));
+        assert!(!curve.is_weak());
+    }
+
+    #[test]
+    fn test_weak_forms() {
+        //Учебная группа: слабая по длине, h = 9 не выведена из g = 4
+        let toy = Setting::new(
+            ModGroup::new(BigUint::from(23u32), BigUint::from(11u32)),
+            BigUint::from(4u32),
+            BigUint::from(9u32),
+        );
+        let strength = Strength::modular(&toy, DEFAULT_MIN_SECURITY_BITS);
+        assert_eq!(strength.security_bits, 0);
+        assert_eq!(strength.warnings.len(), 2);
+
+        //Простое Мерсенна 2^61 - 1 - крайний случай специальной формы
+        let p = (BigUint::one() << 61u32) - 1u32;
+        assert!(near_power_of_two(&p));
+        assert!(!near_power_of_two(&BigUint::from(0b1011_0101u32)));
+    }
+}
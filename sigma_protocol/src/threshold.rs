@@ -0,0 +1,563 @@
+//////////////////////////////////////////////////////////////
+// Пороговый доказывающий: секрет (alpha, beta) разделен по схеме
+// Шамира между n участниками, и любые t из них совместно вычисляют
+// коммитмент и ответ. Целиком секрет не хранится ни у кого.
+//
+// Участник i с долей (alpha_i, beta_i) выбирает одноразовый ключ
+// (a_i, b_i) и сообщает u_i = g^alpha_i * h^beta_i, u_t,i = g^a_i * h^b_i.
+// Координатор собирает u = П u_i^l_i и u_t = П u_t,i, где l_i -
+// коэффициенты Лагранжа набора участников, а ответы
+// a_z,i = a_i + l_i * alpha_i * c складывает.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::{BigInt, BigUint};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+#[cfg(feature = "server")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "server")]
+use tracing::warn;
+
+use crate::group::{Group, Setting};
+use crate::key_gen;
+use crate::math;
+use crate::message::decimal;
+use crate::protocol::{Key, ProofKind};
+#[cfg(feature = "server")]
+use crate::{
+    config::Address,
+    fiat_shamir,
+    message::ProtocolMessage,
+    protocol::{Proof, fail},
+    timing::Timeline,
+    transport::Transport,
+};
+
+pub const COMMIT_PATH: &str = "/threshold/commit";
+pub const RESPOND_PATH: &str = "/threshold/respond";
+
+//Доля секрета участника с номером index (значение многочлена в точке index)
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Share {
+    pub index: u32,
+    #[serde(with = "decimal")]
+    pub alpha: BigUint,
+    #[serde(with = "decimal")]
+    pub beta: BigUint,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CommitRequest {
+    pub session: String,
+    #[serde(default)]
+    pub kind: ProofKind,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PartyCommitment {
+    pub index: u32,
+    #[serde(with = "decimal")]
+    pub u: BigUint,
+    #[serde(with = "decimal")]
+    pub ut: BigUint,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RespondRequest {
+    pub session: String,
+    #[serde(with = "decimal")]
+    pub c: BigUint,
+    //Номера участников, чьи коммитменты вошли в u_t
+    pub indices: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PartyResponse {
+    pub index: u32,
+    #[serde(with = "decimal")]
+    pub alpha: BigUint,
+    #[serde(with = "decimal")]
+    pub beta: BigUint,
+}
+
+//Разделение секрета: любые threshold из parties долей восстанавливают его
+pub fn split(
+    secret: &Key,
+    threshold: usize,
+    parties: usize,
+    order: &BigUint,
+) -> Result<Vec<Share>, String> {
+    if threshold == 0 || threshold > parties {
+        return Err(format!(
+            "Порог {} должен быть от 1 до числа участников {}",
+            threshold, parties
+        ));
+    }
+    if BigUint::from(parties) >= *order {
+        return Err("Участников больше, чем ненулевых точек в Z_q".to_string());
+    }
+    //Многочлены степени threshold - 1 со свободными членами alpha и beta
+    let mut alpha = vec![secret.alpha.clone() % order];
+    let mut beta = vec![secret.beta.clone() % order];
+    for _ in 1..threshold {
+        alpha.push(key_gen::random_biguint_mod(order));
+        beta.push(key_gen::random_biguint_mod(order));
+    }
+    Ok((1..=parties as u32)
+        .map(|index| {
+            let x = BigUint::from(index);
+            Share {
+                index,
+                alpha: math::eval_polynomial(&alpha, &x, order),
+                beta: math::eval_polynomial(&beta, &x, order),
+            }
+        })
+        .collect())
+}
+
+//Коэффициент Лагранжа l_i = П j / (j - i) по j != i в точке 0
+pub fn lagrange(index: u32, indices: &[u32], order: &BigUint) -> Option<BigUint> {
+    let q = BigInt::from(order.clone());
+    let mut numerator = BigUint::from(1u32);
+    let mut denominator = BigUint::from(1u32);
+    for &j in indices.iter().filter(|&&j| j != index) {
+        numerator = numerator * j % order;
+        let diff = (BigInt::from(j) - BigInt::from(index)) % &q;
+        let diff = ((diff + &q) % &q).to_biguint()?;
+        denominator = denominator * diff % order;
+    }
+    Some(numerator * math::modular_inverse_euclidean(&denominator, order).ok()? % order)
+}
+
+//Восстановление секрета по долям (для проверки раздачи)
+pub fn reconstruct(shares: &[Share], order: &BigUint) -> Option<Key> {
+    let indices: Vec<u32> = shares.iter().map(|s| s.index).collect();
+    let mut secret = Key::new(BigUint::ZERO, BigUint::ZERO);
+    for share in shares {
+        let l = lagrange(share.index, &indices, order)?;
+        secret.alpha = (secret.alpha + &l * &share.alpha) % order;
+        secret.beta = (secret.beta + &l * &share.beta) % order;
+    }
+    Some(secret)
+}
+
+//////////////////////////////////////////////////////////////
+// Участник: хранит свою долю и одноразовые ключи открытых сессий
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug)]
+pub struct Party {
+    share: Share,
+    pending: HashMap<String, Key>,
+}
+
+impl Party {
+    pub fn new(share: Share) -> Self {
+        Party {
+            share,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn index(&self) -> u32 {
+        self.share.index
+    }
+
+    //u_i и u_t,i; одноразовый ключ запоминается до ответа в этой сессии
+    pub fn commit<G: Group>(
+        &mut self,
+        setting: &Setting<G>,
+        request: &CommitRequest,
+        mut nonce: Key,
+    ) -> Result<PartyCommitment, String> {
+        if self.pending.contains_key(&request.session) {
+            return Err(format!("Сессия {} уже открыта", request.session));
+        }
+        if request.kind == ProofKind::Schnorr {
+            nonce.beta = BigUint::ZERO;
+        }
+        let group = &setting.group;
+        let commitment = PartyCommitment {
+            index: self.share.index,
+            u: group.encode(&setting.compute_u(&self.share.alpha, &self.share.beta)),
+            ut: group.encode(&setting.compute_u(&nonce.alpha, &nonce.beta)),
+        };
+        self.pending.insert(request.session.clone(), nonce);
+        Ok(commitment)
+    }
+
+    //Ответ с учетом коэффициента Лагранжа; ключ сессии используется один раз
+    pub fn respond(
+        &mut self,
+        request: &RespondRequest,
+        order: &BigUint,
+    ) -> Result<PartyResponse, String> {
+        if !request.indices.contains(&self.share.index) {
+            return Err("Участник не входит в набор".to_string());
+        }
+        let nonce = self
+            .pending
+            .remove(&request.session)
+            .ok_or_else(|| format!("Нет открытой сессии {}", request.session))?;
+        let l = lagrange(self.share.index, &request.indices, order)
+            .ok_or("Номера участников совпадают по модулю q")?;
+        let c = &request.c % order;
+        Ok(PartyResponse {
+            index: self.share.index,
+            alpha: (nonce.alpha + &l * &self.share.alpha * &c) % order,
+            beta: (nonce.beta + &l * &self.share.beta * &c) % order,
+        })
+    }
+}
+
+//Участник в этом процессе или на другом сервере
+#[cfg(feature = "server")]
+#[derive(Debug, Clone)]
+pub enum Participant {
+    Local(Arc<Mutex<Party>>),
+    Remote {
+        address: Address,
+        client: reqwest::Client,
+    },
+}
+
+#[cfg(feature = "server")]
+impl Participant {
+    pub fn remote(address: Address) -> Self {
+        Participant::Remote {
+            address,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn commit<G: Group>(
+        &self,
+        setting: &Setting<G>,
+        request: &CommitRequest,
+    ) -> Result<PartyCommitment, String> {
+        match self {
+            Participant::Local(party) => {
+                let nonce = Key::random(&setting.order());
+                party.lock().unwrap().commit(setting, request, nonce)
+            }
+            Participant::Remote { address, client } => {
+                post(client, address, COMMIT_PATH, request).await
+            }
+        }
+    }
+
+    pub async fn respond(
+        &self,
+        request: &RespondRequest,
+        order: &BigUint,
+    ) -> Result<PartyResponse, String> {
+        match self {
+            Participant::Local(party) => party.lock().unwrap().respond(request, order),
+            Participant::Remote { address, client } => {
+                post(client, address, RESPOND_PATH, request).await
+            }
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+pub(crate) async fn post<B: Serialize, R: for<'de> Deserialize<'de>>(
+    client: &reqwest::Client,
+    address: &Address,
+    path: &str,
+    body: &B,
+) -> Result<R, String> {
+    let url = format!("http://{}{}", address.get(), path);
+    let response = client
+        .post(&url)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(format!("{}: HTTP {} {}", url, status, text));
+    }
+    response.json().await.map_err(|e| e.to_string())
+}
+
+//////////////////////////////////////////////////////////////
+// Координатор: ведет протокол с проверяющим от имени участников
+//////////////////////////////////////////////////////////////
+
+#[cfg(feature = "server")]
+#[derive(Debug, Clone)]
+pub struct ThresholdProver<G: Group> {
+    setting: Setting<G>,
+    kind: ProofKind,
+    threshold: usize,
+    participants: Vec<Participant>,
+}
+
+//Коммитмент набора участников, ожидающий испытания
+#[cfg(feature = "server")]
+struct JointCommitment<'a> {
+    session: String,
+    members: Vec<&'a Participant>,
+    indices: Vec<u32>,
+    u: BigUint,
+    ut: BigUint,
+}
+
+#[cfg(feature = "server")]
+impl<G: Group> ThresholdProver<G> {
+    pub fn new(
+        setting: Setting<G>,
+        kind: ProofKind,
+        threshold: usize,
+        participants: Vec<Participant>,
+    ) -> Self {
+        ThresholdProver {
+            setting,
+            kind,
+            threshold: threshold.max(1),
+            participants,
+        }
+    }
+
+    //Коммитменты первых threshold ответивших участников
+    async fn commit(&self) -> Result<JointCommitment<'_>, String> {
+        let request = CommitRequest {
+            session: format!("{:032x}", rand::random::<u128>()),
+            kind: self.kind,
+        };
+        let mut members = Vec::new();
+        let mut parts = Vec::new();
+        for participant in &self.participants {
+            if parts.len() == self.threshold {
+                break;
+            }
+            match participant.commit(&self.setting, &request).await {
+                Ok(part) => {
+                    members.push(participant);
+                    parts.push(part);
+                }
+                Err(e) => warn!("Участник недоступен: {}", e),
+            }
+        }
+        if parts.len() < self.threshold {
+            return Err(format!(
+                "Ответили {} участников из необходимых {}",
+                parts.len(),
+                self.threshold
+            ));
+        }
+
+        let group = &self.setting.group;
+        let order = self.setting.order();
+        let indices: Vec<u32> = parts.iter().map(|p| p.index).collect();
+        let mut u = group.identity();
+        let mut ut = group.identity();
+        for part in &parts {
+            let (Some(u_i), Some(ut_i)) = (group.decode(&part.u), group.decode(&part.ut)) else {
+                return Err(format!("Участник {} прислал не элемент группы", part.index));
+            };
+            let l = lagrange(part.index, &indices, &order).ok_or("Номера участников совпадают")?;
+            u = group.mul(&u, &group.exp(&u_i, &l));
+            ut = group.mul(&ut, &ut_i);
+        }
+        Ok(JointCommitment {
+            session: request.session,
+            members,
+            indices,
+            u: group.encode(&u),
+            ut: group.encode(&ut),
+        })
+    }
+
+    //a_z = сумма ответов участников
+    async fn respond(&self, commitment: JointCommitment<'_>, c: &BigUint) -> Result<Proof, String> {
+        let order = self.setting.order();
+        let request = RespondRequest {
+            session: commitment.session,
+            c: c.clone(),
+            indices: commitment.indices,
+        };
+        let mut alpha = BigUint::ZERO;
+        let mut beta = BigUint::ZERO;
+        for member in commitment.members {
+            let part = member.respond(&request, &order).await?;
+            alpha = (alpha + part.alpha) % &order;
+            beta = (beta + part.beta) % &order;
+        }
+        Ok(Proof {
+            u: commitment.u,
+            ut: commitment.ut,
+            c: c.clone(),
+            alpha,
+            beta,
+        })
+    }
+
+    pub async fn prove_non_interactive(&self) -> Result<Proof, String> {
+        let commitment = self.commit().await?;
+        let c = fiat_shamir::challenge_in(&self.setting, &commitment.u, &commitment.ut);
+        self.respond(commitment, &c).await
+    }
+
+    //Интерактивные раунды через транспорт, как у обычного доказывающего
+    pub async fn run_rounds<T: Transport>(
+        &self,
+        rounds: u32,
+        transport: &mut T,
+        timeline: &mut Timeline,
+    ) -> Result<bool, String> {
+        for _ in 0..rounds {
+            if !self.run(transport, timeline).await? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn run<T: Transport>(
+        &self,
+        transport: &mut T,
+        timeline: &mut Timeline,
+    ) -> Result<bool, String> {
+        timeline.start_compute();
+        let commitment = match self.commit().await {
+            Ok(commitment) => commitment,
+            Err(e) => return Err(fail(transport, e).await),
+        };
+        timeline.stop_compute();
+        transport
+            .send(&ProtocolMessage::Commitment {
+                u: commitment.u.clone(),
+                ut: commitment.ut.clone(),
+            })
+            .await?;
+        timeline.mark("commitment");
+
+        let c = match transport.receive().await? {
+            ProtocolMessage::Challenge { c } => c,
+            ProtocolMessage::Abort { reason } => return Err(reason),
+            other => {
+                let reason = format!("Ожидалось испытание, получено: {}", other.describe());
+                return Err(fail(transport, reason).await);
+            }
+        };
+        timeline.mark("challenge");
+
+        timeline.start_compute();
+        let proof = match self.respond(commitment, &c).await {
+            Ok(proof) => proof,
+            Err(e) => return Err(fail(transport, e).await),
+        };
+        timeline.stop_compute();
+        transport.send(&proof.response()).await?;
+        timeline.mark("response");
+
+        let result = match transport.receive().await? {
+            ProtocolMessage::Verdict { accepted } => Ok(accepted),
+            ProtocolMessage::Abort { reason } => Err(reason),
+            other => Err(format!("Ожидался вердикт, получено: {}", other.describe())),
+        };
+        timeline.mark("verdict");
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::toy_params;
+    #[cfg(feature = "server")]
+    use crate::{protocol::Verifier, transport::ChannelTransport};
+
+    #[cfg(feature = "server")]
+    fn parties(secret: &Key) -> Vec<Participant> {
+        split(secret, 2, 3, &toy_params().q)
+            .unwrap()
+            .into_iter()
+            .map(|share| Participant::Local(Arc::new(Mutex::new(Party::new(share)))))
+            .collect()
+    }
+
+    #[test]
+    fn test_split_and_reconstruct() {
+        let q = toy_params().q;
+        let secret = Key::new(BigUint::from(5u32), BigUint::from(2u32));
+        let shares = split(&secret, 3, 5, &q).unwrap();
+        assert_eq!(reconstruct(&shares[..3], &q), Some(secret.clone()));
+        assert_eq!(reconstruct(&shares[2..], &q), Some(secret.clone()));
+        assert!(split(&secret, 4, 3, &q).is_err());
+        assert!(split(&secret, 2, 11, &q).is_err());
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_threshold_proof() {
+        let secret = Key::new(BigUint::from(5u32), BigUint::from(2u32));
+        let u = toy_params()
+            .setting()
+            .compute_u(&secret.alpha, &secret.beta);
+        let mut participants = parties(&secret);
+        //Первый участник недоступен: доказывают второй и третий
+        participants[0] = Participant::remote(serde_json::from_str(r#"{"port":1}"#).unwrap());
+        let prover = ThresholdProver::new(
+            toy_params().setting(),
+            ProofKind::Okamoto,
+            2,
+            participants.clone(),
+        );
+        let proof = prover.prove_non_interactive().await.unwrap();
+        assert_eq!(proof.u, u);
+        assert!(Verifier::new(toy_params()).verify_non_interactive(&proof));
+
+        let (mut prover_side, mut verifier_side) = ChannelTransport::pair();
+        let verifier = tokio::spawn(async move {
+            Verifier::new(toy_params())
+                .run_rounds(1, 2, &mut verifier_side, &mut Timeline::new())
+                .await
+        });
+        let result = prover
+            .run_rounds(2, &mut prover_side, &mut Timeline::new())
+            .await;
+        assert_eq!(result, Ok(true));
+        assert_eq!(verifier.await.unwrap(), Ok(true));
+
+        //Одного участника недостаточно
+        let lone = ThresholdProver::new(
+            toy_params().setting(),
+            ProofKind::Okamoto,
+            2,
+            participants[..2].to_vec(),
+        );
+        assert!(lone.prove_non_interactive().await.is_err());
+    }
+
+    #[test]
+    fn test_party_nonce_single_use() {
+        let setting = toy_params().setting();
+        let share = Share {
+            index: 1,
+            alpha: BigUint::from(3u32),
+            beta: BigUint::from(4u32),
+        };
+        let mut party = Party::new(share);
+        let commit = CommitRequest {
+            session: "s".to_string(),
+            kind: ProofKind::Okamoto,
+        };
+        let nonce = Key::new(BigUint::from(1u32), BigUint::from(2u32));
+        party.commit(&setting, &commit, nonce.clone()).unwrap();
+        assert!(party.commit(&setting, &commit, nonce).is_err());
+        let respond = RespondRequest {
+            session: "s".to_string(),
+            c: BigUint::from(5u32),
+            indices: vec![1],
+        };
+        let response = party.respond(&respond, &setting.order()).unwrap();
+        //Единственный участник: l_1 = 1, a_z = 1 + 3 * 5 mod 11
+        assert_eq!(response.alpha, BigUint::from(5u32));
+        //Повторный ответ с другим испытанием раскрыл бы долю
+        assert!(party.respond(&respond, &setting.order()).is_err());
+    }
+}
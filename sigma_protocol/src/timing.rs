@@ -0,0 +1,188 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+//Время одного сообщения протокола: момент относительно начала сессии,
+//время вычислений перед ним и остальная задержка (сеть, озвучивание)
+#[derive(Debug, Clone, Serialize)]
+pub struct StepTiming {
+    pub step: String,
+    pub at_ms: f64,
+    pub compute_ms: f64,
+    pub delay_ms: f64,
+}
+
+//Монотонная шкала времени одной сессии
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    start: Instant,
+    last: Instant,
+    compute: Duration,
+    compute_started: Option<Instant>,
+    steps: Vec<StepTiming>,
+}
+
+fn ms(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Timeline {
+            start: now,
+            last: now,
+            compute: Duration::ZERO,
+            compute_started: None,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn start_compute(&mut self) {
+        self.compute_started = Some(Instant::now());
+    }
+
+    pub fn stop_compute(&mut self) {
+        if let Some(started) = self.compute_started.take() {
+            self.compute += started.elapsed();
+        }
+    }
+
+    //Отметка сообщения: все время с прошлой отметки, кроме вычислений, считается задержкой
+    pub fn mark(&mut self, step: &str) {
+        self.stop_compute();
+        let now = Instant::now();
+        let elapsed = now - self.last;
+        self.steps.push(StepTiming {
+            step: step.to_string(),
+            at_ms: ms(now - self.start),
+            compute_ms: ms(self.compute),
+            delay_ms: ms(elapsed.saturating_sub(self.compute)),
+        });
+        self.last = now;
+        self.compute = Duration::ZERO;
+    }
+
+    pub fn steps(&self) -> &[StepTiming] {
+        &self.steps
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Накопленная статистика по размерам параметров
+//////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Default)]
+struct StepAccumulator {
+    count: u64,
+    compute_ms: f64,
+    delay_ms: f64,
+    max_compute_ms: f64,
+    max_delay_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepReport {
+    pub count: u64,
+    pub mean_compute_ms: f64,
+    pub mean_delay_ms: f64,
+    pub max_compute_ms: f64,
+    pub max_delay_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SizeReport {
+    pub runs: u64,
+    pub steps: BTreeMap<String, StepReport>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    sizes: BTreeMap<u64, (u64, BTreeMap<String, StepAccumulator>)>,
+}
+
+impl Stats {
+    pub fn record(&mut self, bits: u64, timeline: &Timeline) {
+        let (runs, steps) = self.sizes.entry(bits).or_default();
+        *runs += 1;
+        for step in timeline.steps() {
+            let acc = steps.entry(step.step.clone()).or_default();
+            acc.count += 1;
+            acc.compute_ms += step.compute_ms;
+            acc.delay_ms += step.delay_ms;
+            acc.max_compute_ms = acc.max_compute_ms.max(step.compute_ms);
+            acc.max_delay_ms = acc.max_delay_ms.max(step.delay_ms);
+        }
+    }
+
+    //Отчет: размер модуля в битах -> статистика шагов
+    pub fn report(&self) -> BTreeMap<u64, SizeReport> {
+        self.sizes
+            .iter()
+            .map(|(bits, (runs, steps))| {
+                let steps = steps
+                    .iter()
+                    .map(|(name, acc)| {
+                        let n = acc.count.max(1) as f64;
+                        let report = StepReport {
+                            count: acc.count,
+                            mean_compute_ms: acc.compute_ms / n,
+                            mean_delay_ms: acc.delay_ms / n,
+                            max_compute_ms: acc.max_compute_ms,
+                            max_delay_ms: acc.max_delay_ms,
+                        };
+                        (name.clone(), report)
+                    })
+                    .collect();
+                (*bits, SizeReport { runs: *runs, steps })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeline_separates_compute_and_delay() {
+        let mut timeline = Timeline::new();
+        timeline.start_compute();
+        std::thread::sleep(Duration::from_millis(20));
+        timeline.stop_compute();
+        std::thread::sleep(Duration::from_millis(30));
+        timeline.mark("commitment");
+        timeline.mark("challenge");
+
+        let steps = timeline.steps();
+        assert_eq!(steps.len(), 2);
+        assert!(steps[0].compute_ms >= 20.0);
+        assert!(steps[0].delay_ms >= 30.0);
+        assert!(steps[1].compute_ms < 1.0);
+        assert!(steps[1].at_ms >= steps[0].at_ms);
+    }
+
+    #[test]
+    fn test_stats_aggregates_by_size() {
+        let mut timeline = Timeline::new();
+        timeline.mark("commitment");
+        timeline.mark("verdict");
+
+        let mut stats = Stats::default();
+        stats.record(4, &timeline);
+        stats.record(4, &timeline);
+        stats.record(2048, &timeline);
+
+        let report = stats.report();
+        assert_eq!(report[&4].runs, 2);
+        assert_eq!(report[&4].steps["commitment"].count, 2);
+        assert_eq!(report[&2048].runs, 1);
+        assert_eq!(report[&2048].steps.len(), 2);
+    }
+}
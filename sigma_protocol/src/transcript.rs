@@ -0,0 +1,341 @@
+//////////////////////////////////////////////////////////////
+// Стенограмма доказательства: все сообщения протокола с автором,
+// временем и значениями. Собирается из событий шины и может быть
+// сохранена и загружена как JSON.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+use crate::events::{EventSink, ProtocolEvent};
+use crate::graph::{Graph, Permutation};
+use crate::message::{decimal, decimal_vec};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Actor {
+    Server,
+    Prover,
+    Verifier,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "message", rename_all = "snake_case")]
+pub enum TranscriptMessage {
+    Params {
+        #[serde(with = "decimal")]
+        p: BigUint,
+        #[serde(with = "decimal")]
+        q: BigUint,
+        #[serde(with = "decimal")]
+        g: BigUint,
+        #[serde(with = "decimal")]
+        h: BigUint,
+    },
+    Commitment {
+        #[serde(with = "decimal")]
+        u: BigUint,
+        #[serde(with = "decimal")]
+        ut: BigUint,
+    },
+    Challenge {
+        #[serde(with = "decimal")]
+        c: BigUint,
+    },
+    //Испытание с предварительной фиксацией: H(s, c), затем c и s
+    ChallengeCommitment {
+        #[serde(with = "decimal")]
+        digest: BigUint,
+    },
+    ChallengeReveal {
+        #[serde(with = "decimal")]
+        c: BigUint,
+        #[serde(with = "decimal")]
+        salt: BigUint,
+    },
+    //Раунд маяка: по нему любой пересчитает испытание
+    Beacon {
+        round: u64,
+        randomness: String,
+    },
+    Response {
+        #[serde(with = "decimal")]
+        alpha: BigUint,
+        #[serde(with = "decimal")]
+        beta: BigUint,
+    },
+    Verdict {
+        accepted: bool,
+    },
+    //Решение одного из проверяющих кворума; null - сервер не ответил
+    VerifierVerdict {
+        verifier: String,
+        accepted: Option<bool>,
+    },
+    //Сообщения протокола Фейге-Фиата-Шамира
+    FfsParams {
+        #[serde(with = "decimal")]
+        n: BigUint,
+        #[serde(with = "decimal_vec")]
+        keys: Vec<BigUint>,
+    },
+    FfsCommitment {
+        #[serde(with = "decimal")]
+        x: BigUint,
+    },
+    FfsChallenge {
+        bits: Vec<bool>,
+    },
+    FfsResponse {
+        #[serde(with = "decimal")]
+        y: BigUint,
+    },
+    //Сообщения протокола изоморфизма графов
+    GiGraphs {
+        g0: Graph,
+        g1: Graph,
+    },
+    GiCommitment {
+        h: Graph,
+    },
+    GiChallenge {
+        bit: bool,
+    },
+    GiResponse {
+        permutation: Permutation,
+    },
+}
+
+impl TranscriptMessage {
+    //Сторона, отправляющая сообщение
+    pub fn actor(&self) -> Actor {
+        match self {
+            TranscriptMessage::Params { .. }
+            | TranscriptMessage::FfsParams { .. }
+            | TranscriptMessage::GiGraphs { .. } => Actor::Server,
+            TranscriptMessage::Commitment { .. }
+            | TranscriptMessage::Response { .. }
+            | TranscriptMessage::FfsCommitment { .. }
+            | TranscriptMessage::FfsResponse { .. }
+            | TranscriptMessage::GiCommitment { .. }
+            | TranscriptMessage::GiResponse { .. } => Actor::Prover,
+            TranscriptMessage::Challenge { .. }
+            | TranscriptMessage::Beacon { .. }
+            | TranscriptMessage::ChallengeCommitment { .. }
+            | TranscriptMessage::ChallengeReveal { .. }
+            | TranscriptMessage::Verdict { .. }
+            | TranscriptMessage::VerifierVerdict { .. }
+            | TranscriptMessage::FfsChallenge { .. }
+            | TranscriptMessage::GiChallenge { .. } => Actor::Verifier,
+        }
+    }
+
+    //Сообщение стенограммы, соответствующее событию шины
+    pub fn from_event(event: &ProtocolEvent) -> Option<Self> {
+        Some(match event.clone() {
+            ProtocolEvent::ParamsAnnounced { p, q, g, h } => {
+                TranscriptMessage::Params { p, q, g, h }
+            }
+            ProtocolEvent::Commitment { u, ut } => TranscriptMessage::Commitment { u, ut },
+            ProtocolEvent::Challenge { c } => TranscriptMessage::Challenge { c },
+            ProtocolEvent::ChallengeCommitted { digest } => {
+                TranscriptMessage::ChallengeCommitment { digest }
+            }
+            ProtocolEvent::ChallengeRevealed { c, salt, .. } => {
+                TranscriptMessage::ChallengeReveal { c, salt }
+            }
+            ProtocolEvent::BeaconRound { round, randomness } => {
+                TranscriptMessage::Beacon { round, randomness }
+            }
+            ProtocolEvent::Response { alpha, beta } => TranscriptMessage::Response { alpha, beta },
+            ProtocolEvent::Verdict { accepted, .. } => TranscriptMessage::Verdict { accepted },
+            ProtocolEvent::VerifierVerdict { verifier, accepted } => {
+                TranscriptMessage::VerifierVerdict { verifier, accepted }
+            }
+            //Итог кворума - общий вердикт
+            ProtocolEvent::Consensus { accepted, .. } => TranscriptMessage::Verdict { accepted },
+            ProtocolEvent::FfsKeys { n, keys } => TranscriptMessage::FfsParams { n, keys },
+            ProtocolEvent::FfsCommitment { x } => TranscriptMessage::FfsCommitment { x },
+            ProtocolEvent::FfsChallenge { bits } => TranscriptMessage::FfsChallenge { bits },
+            ProtocolEvent::FfsResponse { y } => TranscriptMessage::FfsResponse { y },
+            ProtocolEvent::GiGraphs { g0, g1 } => TranscriptMessage::GiGraphs { g0, g1 },
+            ProtocolEvent::GiCommitment { h } => TranscriptMessage::GiCommitment { h },
+            ProtocolEvent::GiChallenge { bit } => TranscriptMessage::GiChallenge { bit },
+            ProtocolEvent::GiResponse { permutation } => {
+                TranscriptMessage::GiResponse { permutation }
+            }
+            ProtocolEvent::GiVerdict { accepted } => TranscriptMessage::Verdict { accepted },
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TranscriptEntry {
+    pub actor: Actor,
+    pub time: String,
+    #[serde(flatten)]
+    pub message: TranscriptMessage,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Transcript {
+    entries: Vec<TranscriptEntry>,
+}
+
+impl Transcript {
+    pub fn new() -> Self {
+        Transcript::default()
+    }
+
+    //Запись сообщения с текущим временем
+    pub fn record(&mut self, message: TranscriptMessage) {
+        self.entries.push(TranscriptEntry {
+            actor: message.actor(),
+            time: chrono::Local::now().to_rfc3339(),
+            message,
+        });
+    }
+
+    //Объявление параметров начинает новую стенограмму
+    pub fn observe(&mut self, event: &ProtocolEvent) {
+        let Some(message) = TranscriptMessage::from_event(event) else {
+            return;
+        };
+        if matches!(
+            message,
+            TranscriptMessage::Params { .. }
+                | TranscriptMessage::FfsParams { .. }
+                | TranscriptMessage::GiGraphs { .. }
+        ) {
+            self.entries.clear();
+        }
+        self.record(message);
+    }
+
+    pub fn entries(&self) -> &[TranscriptEntry] {
+        &self.entries
+    }
+}
+
+//Приемник шины, ведущий стенограмму последнего доказательства
+pub struct TranscriptSink(Arc<Mutex<Transcript>>);
+
+impl TranscriptSink {
+    pub fn new(transcript: Arc<Mutex<Transcript>>) -> Self {
+        TranscriptSink(transcript)
+    }
+}
+
+impl EventSink for TranscriptSink {
+    async fn emit(&mut self, event: &ProtocolEvent) -> Result<(), String> {
+        self.0.lock().unwrap().observe(event);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn n(x: u32) -> BigUint {
+        BigUint::from(x)
+    }
+
+    #[test]
+    fn test_observe_and_roundtrip() {
+        let mut transcript = Transcript::new();
+        transcript.observe(&ProtocolEvent::Challenge { c: n(1) });
+        transcript.observe(&ProtocolEvent::ParamsAnnounced {
+            p: n(23),
+            q: n(11),
+            g: n(4),
+            h: n(3),
+        });
+        transcript.observe(&ProtocolEvent::Commitment { u: n(6), ut: n(8) });
+        transcript.observe(&ProtocolEvent::Round {
+            round: 1,
+            rounds: 1,
+        });
+        transcript.observe(&ProtocolEvent::Challenge { c: n(4) });
+        transcript.observe(&ProtocolEvent::Response {
+            alpha: n(2),
+            beta: n(7),
+        });
+        transcript.observe(&ProtocolEvent::Verdict {
+            uz: n(5),
+            utuc: n(5),
+            accepted: true,
+        });
+
+        //Испытание до объявления параметров и служебные события не попадают
+        let actors: Vec<Actor> = transcript.entries().iter().map(|e| e.actor).collect();
+        assert_eq!(
+            actors,
+            [
+                Actor::Server,
+                Actor::Prover,
+                Actor::Verifier,
+                Actor::Prover,
+                Actor::Verifier
+            ]
+        );
+
+        let json = serde_json::to_string(&transcript).unwrap();
+        assert!(json.contains(r#""actor":"verifier","#));
+        assert!(json.contains(r#""message":"challenge","c":"4""#));
+        let restored: Transcript = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, transcript);
+    }
+
+    #[test]
+    fn test_ffs_messages() {
+        let mut transcript = Transcript::new();
+        transcript.observe(&ProtocolEvent::Challenge { c: n(1) });
+        transcript.observe(&ProtocolEvent::FfsKeys {
+            n: n(1081),
+            keys: vec![n(4), n(9)],
+        });
+        transcript.observe(&ProtocolEvent::FfsChallenge {
+            bits: vec![true, false],
+        });
+        assert_eq!(transcript.entries().len(), 2);
+        assert_eq!(transcript.entries()[1].actor, Actor::Verifier);
+
+        let json = serde_json::to_string(&transcript).unwrap();
+        assert!(json.contains(r#""message":"ffs_params","n":"1081","keys":["4","9"]"#));
+        let restored: Transcript = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, transcript);
+    }
+
+    #[test]
+    fn test_gi_messages() {
+        let mut transcript = Transcript::new();
+        let path = Graph::new(3, [(0, 1), (1, 2)]);
+        transcript.observe(&ProtocolEvent::GiGraphs {
+            g0: path.clone(),
+            g1: path.clone(),
+        });
+        transcript.observe(&ProtocolEvent::GiChallenge { bit: true });
+        transcript.observe(&ProtocolEvent::GiResponse {
+            permutation: Permutation::identity(3),
+        });
+        transcript.observe(&ProtocolEvent::GiVerdict { accepted: true });
+        let actors: Vec<Actor> = transcript.entries().iter().map(|e| e.actor).collect();
+        assert_eq!(
+            actors,
+            [
+                Actor::Server,
+                Actor::Verifier,
+                Actor::Prover,
+                Actor::Verifier
+            ]
+        );
+
+        let json = serde_json::to_string(&transcript).unwrap();
+        assert!(json.contains(r#""message":"gi_response","permutation":[0,1,2]"#));
+        let restored: Transcript = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, transcript);
+    }
+}
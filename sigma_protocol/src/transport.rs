@@ -0,0 +1,278 @@
+use axum::extract::ws::{Message as AxumMessage, WebSocket};
+use futures_util::{SinkExt, StreamExt};
+use std::future::Future;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Lines};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::events::EventBus;
+use crate::message::ProtocolMessage;
+use crate::peer::PeerClient;
+
+//Канал обмена сообщениями между доказывающим и проверяющим
+pub trait Transport {
+    fn send(
+        &mut self,
+        message: &ProtocolMessage,
+    ) -> impl Future<Output = Result<(), String>> + Send;
+
+    fn receive(&mut self) -> impl Future<Output = Result<ProtocolMessage, String>> + Send;
+}
+
+//////////////////////////////////////////////////////////////
+// Канал в памяти процесса
+//////////////////////////////////////////////////////////////
+
+pub struct ChannelTransport {
+    tx: mpsc::Sender<ProtocolMessage>,
+    rx: mpsc::Receiver<ProtocolMessage>,
+}
+
+impl ChannelTransport {
+    pub fn pair() -> (Self, Self) {
+        let (tx1, rx1) = mpsc::channel(16);
+        let (tx2, rx2) = mpsc::channel(16);
+        (
+            ChannelTransport { tx: tx1, rx: rx2 },
+            ChannelTransport { tx: tx2, rx: rx1 },
+        )
+    }
+}
+
+impl Transport for ChannelTransport {
+    async fn send(&mut self, message: &ProtocolMessage) -> Result<(), String> {
+        self.tx
+            .send(message.clone())
+            .await
+            .map_err(|_| "Канал закрыт".to_string())
+    }
+
+    async fn receive(&mut self) -> Result<ProtocolMessage, String> {
+        self.rx.recv().await.ok_or("Канал закрыт".to_string())
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// Стандартный ввод/вывод и каналы ОС: одно JSON-сообщение на строку
+//////////////////////////////////////////////////////////////
+
+pub struct StdioTransport<R, W> {
+    lines: Lines<BufReader<R>>,
+    writer: W,
+}
+
+impl<R: AsyncRead + Unpin, W: AsyncWrite + Unpin> StdioTransport<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        StdioTransport {
+            lines: BufReader::new(reader).lines(),
+            writer,
+        }
+    }
+}
+
+impl StdioTransport<tokio::io::Stdin, tokio::io::Stdout> {
+    pub fn stdio() -> Self {
+        StdioTransport::new(tokio::io::stdin(), tokio::io::stdout())
+    }
+}
+
+impl<R, W> Transport for StdioTransport<R, W>
+where
+    R: AsyncRead + Unpin + Send,
+    W: AsyncWrite + Unpin + Send,
+{
+    async fn send(&mut self, message: &ProtocolMessage) -> Result<(), String> {
+        let mut line = message.to_json()?;
+        line.push('\n');
+        self.writer
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+        self.writer.flush().await.map_err(|e| e.to_string())
+    }
+
+    async fn receive(&mut self) -> Result<ProtocolMessage, String> {
+        match self.lines.next_line().await.map_err(|e| e.to_string())? {
+            Some(line) => ProtocolMessage::from_json(&line),
+            None => Err("Поток ввода закрыт".to_string()),
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// HTTP: исходящие сообщения отправляются второму серверу,
+// входящие приходят на /transport/message и попадают во входящую очередь
+//////////////////////////////////////////////////////////////
+
+pub const HTTP_TRANSPORT_PATH: &str = "/transport/message";
+
+pub struct HttpTransport {
+    peer: PeerClient,
+    inbox: mpsc::Receiver<ProtocolMessage>,
+    events: EventBus,
+}
+
+impl HttpTransport {
+    pub fn new(peer: PeerClient, inbox: mpsc::Receiver<ProtocolMessage>, events: EventBus) -> Self {
+        HttpTransport {
+            peer,
+            inbox,
+            events,
+        }
+    }
+}
+
+impl Transport for HttpTransport {
+    async fn send(&mut self, message: &ProtocolMessage) -> Result<(), String> {
+        self.peer
+            .send(HTTP_TRANSPORT_PATH, message, &self.events)
+            .await
+    }
+
+    async fn receive(&mut self) -> Result<ProtocolMessage, String> {
+        self.inbox
+            .recv()
+            .await
+            .ok_or("Входящая очередь закрыта".to_string())
+    }
+}
+
+//////////////////////////////////////////////////////////////
+// WebSocket: клиент (tungstenite) и серверная сторона (axum)
+//////////////////////////////////////////////////////////////
+
+pub const WS_TRANSPORT_PATH: &str = "/ws/transport";
+
+pub struct WebSocketTransport {
+    stream: WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>,
+}
+
+impl WebSocketTransport {
+    pub async fn connect(url: &str) -> Result<Self, String> {
+        let (stream, _) = tokio_tungstenite::connect_async(url)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(WebSocketTransport { stream })
+    }
+}
+
+impl Transport for WebSocketTransport {
+    async fn send(&mut self, message: &ProtocolMessage) -> Result<(), String> {
+        self.stream
+            .send(WsMessage::Binary(message.to_bytes().into()))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn receive(&mut self) -> Result<ProtocolMessage, String> {
+        while let Some(frame) = self.stream.next().await {
+            match frame.map_err(|e| e.to_string())? {
+                WsMessage::Binary(data) => return ProtocolMessage::from_bytes(&data),
+                WsMessage::Text(text) => return ProtocolMessage::from_json(&text),
+                WsMessage::Close(_) => break,
+                _ => continue,
+            }
+        }
+        Err("Соединение WebSocket закрыто".to_string())
+    }
+}
+
+impl Transport for WebSocket {
+    async fn send(&mut self, message: &ProtocolMessage) -> Result<(), String> {
+        SinkExt::send(self, AxumMessage::Binary(message.to_bytes().into()))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn receive(&mut self) -> Result<ProtocolMessage, String> {
+        while let Some(frame) = self.recv().await {
+            match frame.map_err(|e| e.to_string())? {
+                AxumMessage::Binary(data) => return ProtocolMessage::from_bytes(&data),
+                AxumMessage::Text(text) => return ProtocolMessage::from_json(&text),
+                AxumMessage::Close(_) => break,
+                _ => continue,
+            }
+        }
+        Err("Соединение WebSocket закрыто".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Key, Prover, Verifier, toy_params};
+    use crate::timing::Timeline;
+    use num_bigint::BigUint;
+
+    async fn run_pair<P, V>(mut prover: P, mut verifier: V) -> (bool, bool)
+    where
+        P: Transport + Send + 'static,
+        V: Transport + Send + 'static,
+    {
+        let secret = Key::new(BigUint::from(5u32), BigUint::from(2u32));
+        let verifier_side = Verifier::new(toy_params());
+        let verifier = tokio::spawn(async move {
+            verifier_side
+                .run(&mut verifier, &mut Timeline::new())
+                .await
+                .unwrap()
+        });
+        let mut timeline = Timeline::new();
+        let accepted = Prover::new(toy_params(), secret)
+            .unwrap()
+            .run(&mut prover, &mut timeline)
+            .await
+            .unwrap();
+        assert_eq!(timeline.steps().len(), 4);
+        (accepted, verifier.await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_channel_transport() {
+        let (prover, verifier) = ChannelTransport::pair();
+        assert_eq!(run_pair(prover, verifier).await, (true, true));
+    }
+
+    #[tokio::test]
+    async fn test_stdio_transport_over_pipes() {
+        let (a, b) = tokio::io::duplex(1024);
+        let (ar, aw) = tokio::io::split(a);
+        let (br, bw) = tokio::io::split(b);
+        let prover = StdioTransport::new(ar, aw);
+        let verifier = StdioTransport::new(br, bw);
+        assert_eq!(run_pair(prover, verifier).await, (true, true));
+    }
+
+    #[tokio::test]
+    async fn test_conjunction_over_channel() {
+        let (mut prover_side, mut verifier_side) = ChannelTransport::pair();
+        let provers = vec![
+            Prover::new(
+                toy_params(),
+                Key::new(BigUint::from(5u32), BigUint::from(2u32)),
+            )
+            .unwrap(),
+            Prover::new(
+                toy_params(),
+                Key::new(BigUint::from(1u32), BigUint::from(9u32)),
+            )
+            .unwrap(),
+            Prover::schnorr(toy_params(), BigUint::from(7u32)).unwrap(),
+        ];
+        let verifier = tokio::spawn(async move {
+            Verifier::new(toy_params())
+                .run_all(3, &mut verifier_side, &mut Timeline::new())
+                .await
+        });
+        let accepted = Prover::run_all(&provers, &mut prover_side, &mut Timeline::new()).await;
+        assert_eq!(accepted, Ok(true));
+        assert_eq!(verifier.await.unwrap(), Ok(true));
+    }
+
+    #[tokio::test]
+    async fn test_stdio_transport_closed_input() {
+        let mut transport = StdioTransport::new(tokio::io::empty(), tokio::io::sink());
+        assert!(transport.receive().await.is_err());
+    }
+}
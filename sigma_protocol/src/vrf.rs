@@ -0,0 +1,168 @@
+//////////////////////////////////////////////////////////////
+// Проверяемая случайная функция (VRF) на дискретном логарифме,
+// по образцу ECVRF: владелец ключа x вычисляет для входа alpha
+// значение Gamma = H(y, alpha)^x и выход beta = SHA-256(Gamma).
+// Доказательство - неинтерактивный протокол Чаума-Педерсена:
+// log_g(y) = log_H(Gamma). Без x выход нельзя ни предсказать,
+// ни подменить, а с ключом он для каждого входа ровно один.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::fiat_shamir::absorb;
+use crate::group::{Group, Setting};
+use crate::message::decimal;
+use crate::nonce;
+use crate::protocol::Key;
+
+//Домены хэшей: точка входа, испытание и выход
+const POINT_DOMAIN: &str = "sigma_protocol vrf point";
+const CHALLENGE_DOMAIN: &str = "sigma_protocol vrf challenge";
+const OUTPUT_DOMAIN: &str = "sigma_protocol vrf output";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VrfProof {
+    #[serde(with = "decimal")]
+    pub gamma: BigUint,
+    #[serde(with = "decimal")]
+    pub c: BigUint,
+    #[serde(with = "decimal")]
+    pub s: BigUint,
+}
+
+//H(y, alpha): элемент группы с неизвестным логарифмом; единица пропускается
+fn hash_to_point<G: Group>(setting: &Setting<G>, y: &BigUint, input: &[u8]) -> G::Element {
+    let group = &setting.group;
+    (0u32..)
+        .map(|counter| {
+            let mut hasher = Sha256::new();
+            absorb(&mut hasher, POINT_DOMAIN.as_bytes());
+            absorb(&mut hasher, &group.describe());
+            absorb(&mut hasher, &y.to_bytes_be());
+            absorb(&mut hasher, input);
+            absorb(&mut hasher, &counter.to_be_bytes());
+            group.hash_to_element(&hasher.finalize())
+        })
+        .find(|e| *e != group.identity())
+        .expect("хэш в группу с бесконечным счетчиком")
+}
+
+//c = H(g, H, y, Gamma, U, V) mod q
+fn challenge<G: Group>(setting: &Setting<G>, points: [&G::Element; 6]) -> BigUint {
+    let group = &setting.group;
+    let mut hasher = Sha256::new();
+    absorb(&mut hasher, CHALLENGE_DOMAIN.as_bytes());
+    absorb(&mut hasher, &group.describe());
+    for point in points {
+        absorb(&mut hasher, &group.serialize(point));
+    }
+    BigUint::from_bytes_be(&hasher.finalize()) % setting.order()
+}
+
+//beta = SHA-256(Gamma)
+fn output<G: Group>(setting: &Setting<G>, gamma: &G::Element) -> BigUint {
+    let group = &setting.group;
+    let mut hasher = Sha256::new();
+    absorb(&mut hasher, OUTPUT_DOMAIN.as_bytes());
+    absorb(&mut hasher, &group.describe());
+    absorb(&mut hasher, &group.serialize(gamma));
+    BigUint::from_bytes_be(&hasher.finalize())
+}
+
+//Выход и доказательство; одноразовый k выводится из x и входа (RFC 6979)
+pub fn prove<G: Group>(setting: &Setting<G>, x: &BigUint, input: &[u8]) -> (BigUint, VrfProof) {
+    let group = &setting.group;
+    let order = setting.order();
+    let x = x % &order;
+    let y = group.exp(&setting.g, &x);
+    let point = hash_to_point(setting, &group.encode(&y), input);
+    let gamma = group.exp(&point, &x);
+
+    let secret = Key::new(x.clone(), BigUint::ZERO);
+    let k = nonce::derive(&order, &secret, &group.serialize(&point), b"vrf").alpha;
+    let u = group.exp(&setting.g, &k);
+    let v = group.exp(&point, &k);
+    let c = challenge(setting, [&setting.g, &point, &y, &gamma, &u, &v]);
+    let s = (k + &c * x) % &order;
+
+    let proof = VrfProof {
+        gamma: group.encode(&gamma),
+        c,
+        s,
+    };
+    (output(setting, &gamma), proof)
+}
+
+//U = g^s * y^-c, V = H^s * Gamma^-c; испытание должно совпасть
+pub fn verify<G: Group>(
+    setting: &Setting<G>,
+    public_key: &BigUint,
+    input: &[u8],
+    beta: &BigUint,
+    proof: &VrfProof,
+) -> bool {
+    let group = &setting.group;
+    let order = setting.order();
+    let (Some(y), Some(gamma)) = (group.decode(public_key), group.decode(&proof.gamma)) else {
+        return false;
+    };
+    if proof.c >= order || proof.s >= order {
+        return false;
+    }
+    let point = hash_to_point(setting, public_key, input);
+    let minus_c = (&order - &proof.c) % &order;
+    let u = group.mul(&group.exp(&setting.g, &proof.s), &group.exp(&y, &minus_c));
+    let v = group.mul(&group.exp(&point, &proof.s), &group.exp(&gamma, &minus_c));
+    proof.c == challenge(setting, [&setting.g, &point, &y, &gamma, &u, &v])
+        && *beta == output(setting, &gamma)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::toy_params;
+    use crate::ristretto;
+    use crate::secp256k1;
+
+    fn check<G: Group>(setting: Setting<G>) {
+        let x = BigUint::from(123_456_789u32);
+        let y = setting.group.encode(&setting.group.exp(&setting.g, &x));
+        let (beta, proof) = prove(&setting, &x, b"round 1");
+        assert!(verify(&setting, &y, b"round 1", &beta, &proof));
+        //Выход детерминирован и зависит от входа
+        assert_eq!(
+            prove(&setting, &x, b"round 1"),
+            (beta.clone(), proof.clone())
+        );
+        let (other, _) = prove(&setting, &x, b"round 2");
+        assert_ne!(beta, other);
+        assert!(!verify(&setting, &y, b"round 2", &beta, &proof));
+        assert!(!verify(&setting, &y, b"round 1", &other, &proof));
+        let forged = VrfProof {
+            s: (&proof.s + 1u32) % setting.order(),
+            ..proof
+        };
+        assert!(!verify(&setting, &y, b"round 1", &beta, &forged));
+    }
+
+    #[test]
+    fn test_vrf_prove_verify() {
+        check(ristretto::setting());
+        check(secp256k1::setting());
+    }
+
+    #[test]
+    fn test_vrf_wrong_key() {
+        let setting = toy_params().setting();
+        let (beta, proof) = prove(&setting, &BigUint::from(3u32), b"input");
+        let y = setting.group.exp(&setting.g, &BigUint::from(3u32));
+        assert!(verify(&setting, &y, b"input", &beta, &proof));
+        let other = setting.group.exp(&setting.g, &BigUint::from(5u32));
+        assert!(!verify(&setting, &other, b"input", &beta, &proof));
+        assert!(!verify(&setting, &BigUint::ZERO, b"input", &beta, &proof));
+        let json = serde_json::to_string(&proof).unwrap();
+        assert_eq!(serde_json::from_str::<VrfProof>(&json).unwrap(), proof);
+    }
+}
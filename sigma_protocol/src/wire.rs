@@ -0,0 +1,269 @@
+//////////////////////////////////////////////////////////////
+// Версионированный формат доказательств и открытых параметров:
+// CBOR (компактный, числа - байты big-endian) и JSON (для отладки,
+// числа - десятичные строки). CBOR начинается с байта версии, JSON
+// содержит поле version. Новая версия формата добавляется рядом,
+// а прежние продолжают читаться.
+//////////////////////////////////////////////////////////////
+
+use num_bigint::BigUint;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::message::decimal;
+use crate::protocol::{Proof, PublicParams};
+
+pub const VERSION: u8 = 1;
+
+//Представление версии 1
+#[derive(Deserialize, Serialize)]
+struct ProofV1 {
+    #[serde(with = "bytes_be")]
+    u: BigUint,
+    #[serde(with = "bytes_be")]
+    ut: BigUint,
+    #[serde(with = "bytes_be")]
+    c: BigUint,
+    #[serde(with = "bytes_be")]
+    alpha: BigUint,
+    #[serde(with = "bytes_be")]
+    beta: BigUint,
+}
+
+#[derive(Deserialize, Serialize)]
+struct ParamsV1 {
+    #[serde(with = "bytes_be")]
+    p: BigUint,
+    #[serde(with = "bytes_be")]
+    q: BigUint,
+    #[serde(with = "bytes_be")]
+    g: BigUint,
+    #[serde(with = "bytes_be")]
+    h: BigUint,
+}
+
+#[derive(Deserialize, Serialize)]
+struct DecimalParams {
+    #[serde(with = "decimal")]
+    p: BigUint,
+    #[serde(with = "decimal")]
+    q: BigUint,
+    #[serde(with = "decimal")]
+    g: BigUint,
+    #[serde(with = "decimal")]
+    h: BigUint,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Versioned<T> {
+    version: u8,
+    #[serde(flatten)]
+    body: T,
+}
+
+#[derive(Deserialize)]
+struct VersionOnly {
+    version: u8,
+}
+
+impl Proof {
+    pub fn to_cbor(&self) -> Vec<u8> {
+        to_cbor(&ProofV1 {
+            u: self.u.clone(),
+            ut: self.ut.clone(),
+            c: self.c.clone(),
+            alpha: self.alpha.clone(),
+            beta: self.beta.clone(),
+        })
+    }
+
+    pub fn from_cbor(data: &[u8]) -> Result<Self, String> {
+        let ProofV1 {
+            u,
+            ut,
+            c,
+            alpha,
+            beta,
+        } = from_cbor(data)?;
+        Ok(Proof {
+            u,
+            ut,
+            c,
+            alpha,
+            beta,
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        to_json(self)
+    }
+
+    pub fn from_json(data: &str) -> Result<Self, String> {
+        from_json(data)
+    }
+}
+
+impl PublicParams {
+    pub fn to_cbor(&self) -> Vec<u8> {
+        to_cbor(&ParamsV1 {
+            p: self.p.clone(),
+            q: self.q.clone(),
+            g: self.g.clone(),
+            h: self.h.clone(),
+        })
+    }
+
+    pub fn from_cbor(data: &[u8]) -> Result<Self, String> {
+        let ParamsV1 { p, q, g, h } = from_cbor(data)?;
+        Ok(PublicParams { p, q, g, h })
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        to_json(&DecimalParams {
+            p: self.p.clone(),
+            q: self.q.clone(),
+            g: self.g.clone(),
+            h: self.h.clone(),
+        })
+    }
+
+    pub fn from_json(data: &str) -> Result<Self, String> {
+        let DecimalParams { p, q, g, h } = from_json(data)?;
+        Ok(PublicParams { p, q, g, h })
+    }
+}
+
+fn unsupported(version: u8) -> String {
+    format!("Неподдерживаемая версия формата: {}", version)
+}
+
+fn to_cbor<T: Serialize>(body: &T) -> Vec<u8> {
+    let mut out = vec![VERSION];
+    ciborium::into_writer(body, &mut out).expect("CBOR в память не дает ошибок");
+    out
+}
+
+fn from_cbor<T: DeserializeOwned>(data: &[u8]) -> Result<T, String> {
+    let (&version, mut body) = data.split_first().ok_or("Пустое сообщение")?;
+    if version != VERSION {
+        return Err(unsupported(version));
+    }
+    let value = ciborium::from_reader(&mut body).map_err(|e| e.to_string())?;
+    if !body.is_empty() {
+        return Err("Лишние байты в конце сообщения".to_string());
+    }
+    Ok(value)
+}
+
+fn to_json<T: Serialize>(body: &T) -> Result<String, String> {
+    serde_json::to_string(&Versioned {
+        version: VERSION,
+        body,
+    })
+    .map_err(|e| e.to_string())
+}
+
+fn from_json<T: DeserializeOwned>(data: &str) -> Result<T, String> {
+    let VersionOnly { version } = serde_json::from_str(data).map_err(|e| e.to_string())?;
+    if version != VERSION {
+        return Err(unsupported(version));
+    }
+    let Versioned::<T> { body, .. } = serde_json::from_str(data).map_err(|e| e.to_string())?;
+    Ok(body)
+}
+
+//Сериализация BigUint строкой байтов big-endian
+mod bytes_be {
+    use num_bigint::BigUint;
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(value: &BigUint, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&value.to_bytes_be())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigUint, D::Error> {
+        deserializer.deserialize_bytes(BytesVisitor)
+    }
+
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = BigUint;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("строка байтов")
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<BigUint, E> {
+            Ok(BigUint::from_bytes_be(v))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proof() -> Proof {
+        Proof {
+            u: BigUint::from(13u32),
+            ut: BigUint::parse_bytes(b"123456789012345678901234567890", 10).unwrap(),
+            c: BigUint::from(4u32),
+            alpha: BigUint::ZERO,
+            beta: BigUint::from(70000u32),
+        }
+    }
+
+    #[test]
+    fn test_proof_round_trip() {
+        let proof = proof();
+        let cbor = proof.to_cbor();
+        assert_eq!(cbor[0], VERSION);
+        assert_eq!(Proof::from_cbor(&cbor).unwrap(), proof);
+        let json = proof.to_json().unwrap();
+        assert!(json.starts_with(r#"{"version":1,"u":"13","#));
+        assert_eq!(Proof::from_json(&json).unwrap(), proof);
+    }
+
+    #[test]
+    fn test_params_round_trip() {
+        let params = crate::modp::params(2048).unwrap();
+        let cbor = params.to_cbor();
+        //Четыре числа по 256 байт и небольшие накладные расходы
+        assert!(cbor.len() < 4 * 256 + 32);
+        let decoded = PublicParams::from_cbor(&cbor).unwrap();
+        assert_eq!((decoded.p, decoded.h), (params.p.clone(), params.h.clone()));
+        let decoded = PublicParams::from_json(&params.to_json().unwrap()).unwrap();
+        assert_eq!((decoded.q, decoded.g), (params.q, params.g));
+    }
+
+    //Зафиксированная кодировка версии 1 должна читаться всеми будущими версиями
+    #[test]
+    fn test_version_1_fixture() {
+        let cbor = [
+            0x01, 0xa5, 0x61, b'u', 0x41, 13, 0x62, b'u', b't', 0x41, 16, 0x61, b'c', 0x41, 4,
+            0x65, b'a', b'l', b'p', b'h', b'a', 0x41, 8, 0x64, b'b', b'e', b't', b'a', 0x41, 2,
+        ];
+        let proof = Proof::from_cbor(&cbor).unwrap();
+        assert_eq!(proof.to_cbor(), cbor);
+        assert_eq!(
+            proof.to_json().unwrap(),
+            r#"{"version":1,"u":"13","ut":"16","c":"4","alpha":"8","beta":"2"}"#
+        );
+    }
+
+    #[test]
+    fn test_rejects_unknown_version() {
+        let mut cbor = proof().to_cbor();
+        cbor[0] = 2;
+        assert!(Proof::from_cbor(&cbor).is_err());
+        assert!(Proof::from_cbor(&[]).is_err());
+        let mut cbor = proof().to_cbor();
+        cbor.push(0);
+        assert!(Proof::from_cbor(&cbor).is_err());
+        assert!(Proof::from_json(r#"{"version":2,"u":"1","ut":"1","c":"1","alpha":"1"}"#).is_err());
+        assert!(Proof::from_json(r#"{"u":"1","ut":"1","c":"1","alpha":"1"}"#).is_err());
+    }
+}